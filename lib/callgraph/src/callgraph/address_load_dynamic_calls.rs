@@ -0,0 +1,905 @@
+#![allow(clippy::type_complexity)]
+
+// (C) COPYRIGHT 2018 TECHNOLUTION BV, GOUDA NL
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use crate::Context;
+use crate::Invocation;
+use crate::InvocationType;
+use crate::Procedure;
+use crate::TraitImplementation;
+
+use crate::callgraph::address_references;
+use crate::callgraph::address_references::ReferenceData;
+use crate::callgraph::relocated_sections::RelocatedSection;
+use crate::callgraph::vtables;
+use crate::callgraph::InvocationFinder;
+
+use crate::disassembly::Instruction;
+use crate::disassembly::InstructionKind;
+use crate::disassembly::Operand;
+use crate::disassembly::Register;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::callgraph::CompilationInfo;
+use petgraph::stable_graph::NodeIndex;
+use petgraph::stable_graph::StableGraph;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+/// Implementation of `InvocationFinder` that assumes that all functions for which the address
+/// to that function is loaded into a register are called.
+///
+/// Addresses are either loaded directly (e.g. `lea` on x86, `adrp` + `add` on AArch64, `movw` + `movt` on ARM or
+/// `auipc` + `addi` on RISC-V), or read from memory (e.g. a literal pool on ARM, or the global offset table).
+pub struct AddressLoadDynamicInvocationFinder<P, I, F> {
+    /// If `true`, calls through a vtable are resolved to the called slot where possible, see
+    /// `find_precise_invocations_for_procedure`.
+    pub precise_dynamic_dispatch: bool,
+    pub phantom: PhantomData<(P, I, F)>,
+}
+
+impl<P, I, F> Default for AddressLoadDynamicInvocationFinder<P, I, F> {
+    fn default() -> Self {
+        AddressLoadDynamicInvocationFinder {
+            precise_dynamic_dispatch: false,
+            phantom: PhantomData,
+        }
+    }
+}
+
+/// Returns the register `insn` loads an address into, together with that address.
+/// Memory is read from the relocated views in `relocated_sections` where possible.
+fn get_loaded_address<'a>(
+    ctx: &Context,
+    relocated_sections: &[RelocatedSection],
+    insn: &'a Instruction,
+) -> Option<(&'a Register, u64)> {
+    match &insn.kind {
+        InstructionKind::AddressLoad { register, address } => Some((register, *address)),
+        InstructionKind::MemoryLoad { register, address } => {
+            address_references::read_word_at(ctx, relocated_sections, *address).map(|value| (register, value))
+        }
+        _ => None,
+    }
+}
+
+impl<P, I: Default, F: Default> AddressLoadDynamicInvocationFinder<P, I, F> {
+    /// Internal function that returns invocation details for all address loads in `prc`.
+    ///
+    /// In the case an address load references a function, metadata referencing that function is returned.
+    /// In the case it references a vtable, metadata referencing all functions in the table is returned.
+    /// When the reference could not be traced to a function or vtable, nothing is returned for the address load.
+    ///
+    /// ## Return value
+    /// Returns a vector of tuples. If `res` is a return tuple of this function, the fields denote:
+    ///     `res.0`: address of the instruction (completing) the address load
+    ///     `res.1`: address of the called function
+    ///     `res.2`: type of invocation (either `InvocationType::ProcedureReference` or `InvocationType::VTable`
+    ///     `res.3`: the trait implementation of the vtable, if known
+    fn find_dynamic_invocations_for_procedure(
+        &self,
+        graph: &StableGraph<Rc<RefCell<Procedure<P>>>, Rc<RefCell<Invocation<I, F>>>>,
+        proc_index: &HashMap<u64, NodeIndex<u32>>,
+        ctx: &Context,
+        prc: &Procedure<P>,
+        data: &ReferenceData,
+    ) -> Vec<(u64, u64, InvocationType, Option<TraitImplementation>)> {
+        prc.disassembly
+            .iter()
+            .filter_map(|insn| {
+                let (_, mem_location) = get_loaded_address(ctx, data.relocated_sections, insn)?;
+
+                Some(address_references::resolve_address_reference(
+                    insn.address,
+                    mem_location,
+                    graph,
+                    proc_index,
+                    ctx,
+                    prc,
+                    data,
+                ))
+            })
+            .flatten()
+            .collect::<Vec<_>>()
+    }
+
+    /// Internal function that returns invocation details for all address loads in `prc`, with calls through a
+    /// vtable resolved to the called slot.
+    ///
+    /// The registers holding a vtable pointer are tracked (through register moves) from the instruction loading the
+    /// vtable, to a call through the vtable (`InstructionKind::MemoryCall`) in the same procedure. Such a call
+    /// only gets an edge to the function in the called slot, with type `InvocationType::VTableSlot`.
+    /// The tracking is linear, and stops at a call, since the callee may overwrite any register. A vtable for
+    /// which no call is found (e.g. because the vtable pointer is passed to another procedure, or stored in memory)
+    /// gets edges to all functions in the table, like in `find_dynamic_invocations_for_procedure`.
+    ///
+    /// ## Return value
+    /// Returns a vector of tuples. If `res` is a return tuple of this function, the fields denote:
+    ///     `res.0`: address of the instruction (completing) the address load, or of the call for `VTableSlot`
+    ///     `res.1`: address of the called function
+    ///     `res.2`: type of invocation (`InvocationType::ProcedureReference`, `InvocationType::VTable` or
+    ///              `InvocationType::VTableSlot`)
+    ///     `res.3`: the trait implementation of the vtable, if known
+    fn find_precise_invocations_for_procedure(
+        &self,
+        graph: &StableGraph<Rc<RefCell<Procedure<P>>>, Rc<RefCell<Invocation<I, F>>>>,
+        proc_index: &HashMap<u64, NodeIndex<u32>>,
+        ctx: &Context,
+        prc: &Procedure<P>,
+        data: &ReferenceData,
+    ) -> Vec<(u64, u64, InvocationType, Option<TraitImplementation>)> {
+        let word_size = address_references::word_size(ctx) as u64;
+        let mut result = vec![];
+        // Registers holding a vtable pointer, mapped to the address of the vtable and the index in `vtable_loads`
+        let mut vtable_registers: HashMap<Register, (u64, usize)> = HashMap::new();
+        // Edges to all functions of each loaded vtable, with a flag indicating whether a call through it is found
+        let mut vtable_loads: Vec<(Vec<(u64, u64, InvocationType, Option<TraitImplementation>)>, bool)> = vec![];
+
+        prc.disassembly.iter().for_each(|insn| match &insn.kind {
+            InstructionKind::MemoryCall { base, offset } => {
+                if let Some((vtable, load_index)) = vtable_registers.get(base).cloned() {
+                    let slot = (offset / word_size) as usize;
+                    if let Some((target, trait_implementation)) = address_references::get_vtable_slot(
+                        graph,
+                        proc_index,
+                        ctx,
+                        data,
+                        vtable,
+                        slot,
+                    ) {
+                        result.push((insn.address, target, InvocationType::VTableSlot, trait_implementation));
+                        vtable_loads[load_index].1 = true;
+                    }
+                }
+                vtable_registers.clear();
+            }
+            InstructionKind::Call { .. } | InstructionKind::PointerCall { .. } => vtable_registers.clear(),
+            InstructionKind::RegisterMove {
+                destination,
+                source: Operand::Register(source),
+            } => match vtable_registers.get(source).cloned() {
+                Some(vtable) => {
+                    vtable_registers.insert(destination.clone(), vtable);
+                }
+                None => {
+                    vtable_registers.remove(destination);
+                }
+            },
+            InstructionKind::RegisterMove { destination, .. }
+            | InstructionKind::Other {
+                destination: Some(destination),
+            } => {
+                vtable_registers.remove(destination);
+            }
+            InstructionKind::AddressLoad { .. } | InstructionKind::MemoryLoad { .. } => {
+                let (register, mem_location) = match get_loaded_address(ctx, data.relocated_sections, insn) {
+                    Some(loaded) => loaded,
+                    None => return,
+                };
+                let invocations = address_references::resolve_address_reference(
+                    insn.address,
+                    mem_location,
+                    graph,
+                    proc_index,
+                    ctx,
+                    prc,
+                    data,
+                );
+
+                if invocations.iter().any(|invocation| invocation.2 == InvocationType::VTable) {
+                    vtable_registers.insert(register.clone(), (mem_location, vtable_loads.len()));
+                    vtable_loads.push((invocations, false));
+                } else {
+                    vtable_registers.remove(register);
+                    result.extend(invocations);
+                }
+            }
+            _ => (),
+        });
+
+        // Fall back to all functions in the vtable, if no call through the vtable is found
+        vtable_loads
+            .into_iter()
+            .filter(|(_, called)| !called)
+            .for_each(|(invocations, _)| result.extend(invocations));
+
+        result
+    }
+}
+
+impl<P, I: Default, F: Default> InvocationFinder<P, I, F>
+    for AddressLoadDynamicInvocationFinder<P, I, F>
+{
+    /// Adds all dynamic invocations, either by procedure reference, or vtable reference, to the call graph.
+    fn find_invocations(
+        &self,
+        graph: &mut StableGraph<Rc<RefCell<Procedure<P>>>, Rc<RefCell<Invocation<I, F>>>>,
+        proc_index: &mut HashMap<u64, NodeIndex>,
+        _call_index: &mut HashMap<u64, NodeIndex>,
+        ctx: &Context,
+        compilation_info: CompilationInfo,
+    ) {
+        let nodes_indices = graph.node_indices().collect::<Vec<_>>();
+        // Parse the vtables once, instead of for every address load
+        let data = ReferenceData {
+            relocated_sections: compilation_info.relocated_sections,
+            vtables: vtables::get_vtables(ctx),
+        };
+
+        nodes_indices.iter().for_each(|idx| {
+            let prc = graph[*idx].clone();
+            let prc = prc.borrow();
+            let dest_indices = if self.precise_dynamic_dispatch {
+                self.find_precise_invocations_for_procedure(graph, proc_index, ctx, &prc, &data)
+            } else {
+                self.find_dynamic_invocations_for_procedure(graph, proc_index, ctx, &prc, &data)
+            };
+
+            address_references::create_dynamic_edges(
+                graph,
+                proc_index,
+                ctx,
+                *idx,
+                &dest_indices,
+                &compilation_info,
+            );
+        });
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    use crate::callgraph::relocated_sections;
+    use crate::Context;
+    use crate::Crate;
+    use crate::disassembly::get_disassembler;
+
+    use addr2line::demangle;
+    use addr2line::Context as Addr2LineContext;
+
+    use gimli::Dwarf;
+    use gimli::EndianSlice;
+
+    use object::ElfFile;
+    use object::Object;
+    use object::ObjectSection;
+
+    use petgraph::graph::NodeIndex;
+    use petgraph::visit::EdgeRef;
+    use petgraph::Directed;
+
+    use std::collections::hash_map::RandomState;
+    use std::collections::HashMap;
+
+    /// Local helper function to create a Context
+    fn parse<'a>(file_content: &'a [u8]) -> Context<'a> {
+        let elf = ElfFile::parse(&file_content).expect("Failed to parse file content");
+        let endianness = gimli::RunTimeEndian::Little;
+
+        let dwarf = Dwarf::load(|section| -> Result<_, ()> {
+            let data = elf.section_data_by_name(section.name()).unwrap_or(&[]);
+            Ok(EndianSlice::new(data, endianness))
+        }).expect("Failed to load DWARF sections");
+        let file_context = Addr2LineContext::from_dwarf(dwarf.borrow(|section| *section))
+            .expect("Could not construct context from file");
+
+        let disassembler = get_disassembler(&elf).expect("Failed to construct disassembler");
+
+        Context {
+            elf,
+            loader: Some(file_context),
+            dwarf,
+            split_dwarf: HashMap::new(),
+            disassembler: Some(disassembler),
+            object_files: vec![],
+            wasm_module: None,
+        }
+    }
+
+    /// Adds a node for a specific function in the target binary
+    fn add_node(
+        name: &str,
+        demangled_name: &str,
+        graph: &mut StableGraph<Rc<RefCell<Procedure<()>>>, Rc<RefCell<Invocation<(), ()>>>>,
+        proc_index: &mut HashMap<u64, NodeIndex>,
+        ctx: &Context,
+    ) -> NodeIndex {
+        // Get symbol of the procedure
+        let (address, size) = ctx.elf
+            .symbols()
+            .filter(|s| s.name().is_some())
+            .find(|s| {
+                if let Some(dm) = demangle(s.name().unwrap(), gimli::DW_LANG_Rust) {
+                    return dm.starts_with(demangled_name);
+                }
+                false
+            })
+            .map(|s| (s.address(), s.size()))
+            .expect(&format!(
+                "No symbol for function main with expected (demangled) name: '{}'",
+                demangled_name
+            ));
+
+        add_raw_node(name, demangled_name, graph, proc_index, ctx, address, size)
+    }
+
+    /// Add a node to the graph with parameter values
+    fn add_raw_node(
+        name: &str,
+        demangled_name: &str,
+        graph: &mut StableGraph<
+            Rc<RefCell<Procedure<()>>>,
+            Rc<RefCell<Invocation<(), ()>>>,
+            Directed,
+            u32,
+        >,
+        proc_index: &mut HashMap<u64, NodeIndex<u32>, RandomState>,
+        ctx: &Context,
+        address: u64,
+        size: u64,
+    ) -> NodeIndex<u32> {
+        // Get code of the procedure
+        let text_section = ctx.elf
+            .sections()
+            .find(|s| s.name() == Some(".text"))
+            .unwrap();
+        let text_section_address = text_section.address();
+        let text_section_data = text_section.data();
+        let code = &text_section_data[(address - text_section_address) as usize
+                                          ..(address - text_section_address + size) as usize];
+        let node = Procedure {
+            name: name.to_string(),
+            linkage_name: demangled_name.to_string(),
+            linkage_name_demangled: name.to_string(),
+            defining_crate: Crate {
+                name: "crate".to_string(),
+                version: Some("1.2.3".to_string()),
+            },
+            location: None,
+            start_address: address,
+            size,
+            address_ranges: vec![address..address + size],
+            language: None,
+            attributes: (),
+            disassembly: ctx.disassemble(code, address),
+        };
+        let index = graph.add_node(Rc::new(RefCell::new(node)));
+        proc_index.insert(address, index);
+        index
+    }
+
+    fn add_procs_with_name(
+        name: &str,
+        ctx: &Context,
+        mut graph: &mut StableGraph<
+            Rc<RefCell<Procedure<()>>>,
+            Rc<RefCell<Invocation<(), ()>>>,
+            Directed,
+            u32,
+        >,
+        mut proc_index: &mut HashMap<u64, NodeIndex<u32>, RandomState>,
+    ) {
+        ctx.elf
+            .symbols()
+            .filter(|sym| {
+                sym.name()
+                    .and_then(|name| demangle(name, gimli::DW_LANG_Rust))
+                    .map(|x| x.contains(name))
+                    .unwrap_or(false)
+            })
+            .enumerate()
+            .for_each(|(i, sym)| {
+                add_node(
+                    &format!("{}_{}", name, i),
+                    &demangle(sym.name().unwrap(), gimli::DW_LANG_Rust).unwrap(),
+                    &mut graph,
+                    &mut proc_index,
+                    &ctx,
+                );
+            });
+    }
+
+    /// Disassemble file and find a dynamic call to a parameter with a trait formal type
+    #[test]
+    pub fn test_find_trait_invocation_calls() {
+        // Parse context
+        let file_content = &test_common::load_test_binary_as_bytes(
+            "trait_invocation",
+            &test_common::TestSubjectType::Debug,
+        ).unwrap();
+
+        let ctx = parse(&file_content);
+
+        let mut graph = Default::default();
+        let mut proc_index = HashMap::new();
+        let mut call_index = HashMap::new();
+
+        // Create partial call graph, with three functions
+        let main_name_demangled = "trait_invocation::main";
+        let qux_name_demangled = "trait_invocation::qux";
+        let m5_name_demangled = "<trait_invocation::Foo as trait_invocation::Baz>::m5";
+        let m6_name_demangled = "<trait_invocation::Foo as trait_invocation::Baz>::m6";
+
+        let main_index = add_node(
+            "main",
+            main_name_demangled,
+            &mut graph,
+            &mut proc_index,
+            &ctx,
+        );
+        let m5_index = add_node("m5", m5_name_demangled, &mut graph, &mut proc_index, &ctx);
+        let m6_index = add_node("m6", m6_name_demangled, &mut graph, &mut proc_index, &ctx);
+        add_node("qux", qux_name_demangled, &mut graph, &mut proc_index, &ctx);
+
+        // Add all drop_in_place options
+        add_procs_with_name("drop_in_place", &ctx, &mut graph, &mut proc_index);
+
+        // Find dynamic invocations
+        AddressLoadDynamicInvocationFinder::default().find_invocations(
+            &mut graph,
+            &mut proc_index,
+            &mut call_index,
+            &ctx,
+            CompilationInfo {
+                rust_version: "1.0.0",
+                compilation_dirs: &[],
+                relocated_sections: &relocated_sections::get_relocated_sections(&ctx),
+            },
+        );
+
+        // Assert that 3 dynamic invocations (main -> m5), (main -> m6), (main -> drop_in_place) are found
+        assert_eq!(graph.edge_indices().count(), 3);
+
+        let main_m5_edge_index = graph.find_edge(main_index, m5_index);
+        let main_m6_edge_index = graph.find_edge(main_index, m6_index);
+
+        // Assert edges from main to m5 and from main to m6 exists
+        assert!(main_m5_edge_index.is_some());
+        assert!(main_m6_edge_index.is_some());
+        // Assert both have invocation type vtable
+
+        let m5_node = graph[main_m5_edge_index.unwrap()].borrow();
+        let m6_node = graph[main_m6_edge_index.unwrap()].borrow();
+
+        assert!(!m5_node.frames.is_empty());
+        assert!(!m6_node.frames.is_empty());
+
+        assert_eq!(m5_node.invocation_type, InvocationType::VTable);
+        assert_eq!(m6_node.invocation_type, InvocationType::VTable);
+
+        // Assert the trait implementation is taken from the debug information
+        let implementation = Some(TraitImplementation {
+            trait_name: Some("trait_invocation::Baz".to_string()),
+            type_name: "trait_invocation::Foo".to_string(),
+        });
+        assert_eq!(m5_node.trait_implementation, implementation);
+        assert_eq!(m6_node.trait_implementation, implementation);
+
+        let m5_location = &m5_node.frames[0].location;
+        let m6_location = &m6_node.frames[0].location;
+
+        // assert file location is set correctly
+        assert!(
+            m5_location
+                .file
+                .ends_with("/test_subjects/trait_invocation/src/main.rs")
+        );
+
+        assert_eq!(m5_location.line, 30u64);
+
+        assert!(
+            m6_location
+                .file
+                .ends_with("/test_subjects/trait_invocation/src/main.rs")
+        );
+        assert_eq!(m6_location.line, 30u64);
+    }
+
+    /// Verify the edges precise mode finds in the example binary. `main` passes the vtable of `Foo` to `qux`, which
+    /// calls through the vtable pointer in its argument register. The tracking stops at the call of `qux`, so `main`
+    /// falls back to edges to all functions in the vtable, while the slots of the vtable resolve to `m5` and `m6`.
+    #[test]
+    pub fn test_find_vtable_slot_calls() {
+        // Parse context
+        let file_content = &test_common::load_test_binary_as_bytes(
+            "trait_invocation",
+            &test_common::TestSubjectType::Debug,
+        ).unwrap();
+
+        let ctx = parse(&file_content);
+
+        let mut graph = Default::default();
+        let mut proc_index = HashMap::new();
+        let mut call_index = HashMap::new();
+
+        let main_index = add_node("main", "trait_invocation::main", &mut graph, &mut proc_index, &ctx);
+        let m5_index = add_node(
+            "m5",
+            "<trait_invocation::Foo as trait_invocation::Baz>::m5",
+            &mut graph,
+            &mut proc_index,
+            &ctx,
+        );
+        let m6_index = add_node(
+            "m6",
+            "<trait_invocation::Foo as trait_invocation::Baz>::m6",
+            &mut graph,
+            &mut proc_index,
+            &ctx,
+        );
+        add_node("qux", "trait_invocation::qux", &mut graph, &mut proc_index, &ctx);
+        add_procs_with_name("drop_in_place", &ctx, &mut graph, &mut proc_index);
+
+        AddressLoadDynamicInvocationFinder {
+            precise_dynamic_dispatch: true,
+            phantom: PhantomData,
+        }.find_invocations(
+            &mut graph,
+            &mut proc_index,
+            &mut call_index,
+            &ctx,
+            CompilationInfo {
+                rust_version: "1.0.0",
+                compilation_dirs: &[],
+                relocated_sections: &relocated_sections::get_relocated_sections(&ctx),
+            },
+        );
+
+        // Assert that the same invocations as in imprecise mode are found: (main -> m5), (main -> m6) and
+        // (main -> drop_in_place), all through the vtable
+        assert_eq!(graph.edge_indices().count(), 3);
+        assert!(graph
+            .edge_indices()
+            .all(|edge| graph[edge].borrow().invocation_type == InvocationType::VTable));
+        assert!(graph.find_edge(main_index, m5_index).is_some());
+        assert!(graph.find_edge(main_index, m6_index).is_some());
+
+        // Assert that the slots of the vtable loaded by main resolve to m5 and m6, which follow the destructor, size
+        // and align
+        let relocated_sections = relocated_sections::get_relocated_sections(&ctx);
+        let data = ReferenceData {
+            relocated_sections: &relocated_sections,
+            vtables: vtables::get_vtables(&ctx),
+        };
+        let vtable = graph[main_index]
+            .borrow()
+            .disassembly
+            .iter()
+            .filter_map(|insn| get_loaded_address(&ctx, data.relocated_sections, insn))
+            .map(|(_, address)| address)
+            .find(|address| data.vtables.contains_key(address))
+            .expect("No vtable load in main");
+        let slot = |index| {
+            address_references::get_vtable_slot(&graph, &proc_index, &ctx, &data, vtable, index)
+                .map(|(address, _)| address)
+        };
+
+        assert_eq!(slot(3), Some(graph[m5_index].borrow().start_address));
+        assert_eq!(slot(4), Some(graph[m6_index].borrow().start_address));
+        assert_eq!(slot(5), None);
+    }
+
+    /// Builds the graph of `m5`, `m6`, `qux` and the `drop_in_place` procedures of the example binary, together with a
+    /// synthetic procedure of which the disassembly is created by `disassembly` from the address of the vtable of
+    /// `Foo`, and finds its invocations in precise mode.
+    /// Returns the invocations of the synthetic procedure, by the name of the invoked procedure.
+    fn find_synthetic_slot_calls(disassembly: fn(u64) -> Vec<Instruction>) -> Vec<(String, InvocationType)> {
+        let file_content = &test_common::load_test_binary_as_bytes(
+            "trait_invocation",
+            &test_common::TestSubjectType::Debug,
+        ).unwrap();
+
+        let ctx = parse(&file_content);
+
+        let mut graph = Default::default();
+        let mut proc_index = HashMap::new();
+        let mut call_index = HashMap::new();
+
+        add_node(
+            "m5",
+            "<trait_invocation::Foo as trait_invocation::Baz>::m5",
+            &mut graph,
+            &mut proc_index,
+            &ctx,
+        );
+        add_node(
+            "m6",
+            "<trait_invocation::Foo as trait_invocation::Baz>::m6",
+            &mut graph,
+            &mut proc_index,
+            &ctx,
+        );
+        add_node("qux", "trait_invocation::qux", &mut graph, &mut proc_index, &ctx);
+        add_procs_with_name("drop_in_place", &ctx, &mut graph, &mut proc_index);
+
+        // The vtable of `Foo` is the only vtable of `Baz`
+        let vtable = vtables::get_vtables(&ctx)
+            .into_iter()
+            .find(|(_, vtable)| vtable.implementation.trait_name.as_deref() == Some("trait_invocation::Baz"))
+            .map(|(address, _)| address)
+            .expect("No vtable of Baz");
+
+        // The synthetic procedure is placed at an address without code
+        let caller = Procedure {
+            name: "caller".to_string(),
+            linkage_name: "caller".to_string(),
+            linkage_name_demangled: "caller".to_string(),
+            defining_crate: Crate {
+                name: "crate".to_string(),
+                version: None,
+            },
+            location: None,
+            start_address: 0,
+            size: 12,
+            address_ranges: vec![0..12],
+            language: None,
+            attributes: (),
+            disassembly: disassembly(vtable),
+        };
+        let caller_index = graph.add_node(Rc::new(RefCell::new(caller)));
+        proc_index.insert(0, caller_index);
+
+        AddressLoadDynamicInvocationFinder {
+            precise_dynamic_dispatch: true,
+            phantom: PhantomData,
+        }.find_invocations(
+            &mut graph,
+            &mut proc_index,
+            &mut call_index,
+            &ctx,
+            CompilationInfo {
+                rust_version: "1.0.0",
+                compilation_dirs: &[],
+                relocated_sections: &relocated_sections::get_relocated_sections(&ctx),
+            },
+        );
+
+        graph
+            .edges(caller_index)
+            .map(|edge| (graph[edge.target()].borrow().name.clone(), edge.weight().borrow().invocation_type))
+            .collect()
+    }
+
+    /// Returns an instruction of kind `kind` at `address`, with a size of 4 bytes.
+    fn instruction(address: u64, kind: InstructionKind) -> Instruction {
+        Instruction {
+            address,
+            size: 4,
+            mnemonic: String::new(),
+            kind,
+        }
+    }
+
+    /// Verify a call through a slot of a vtable of which the pointer is moved to another register only gets an edge
+    /// to the function in that slot
+    #[test]
+    pub fn test_find_synthetic_vtable_slot_call() {
+        let invocations = find_synthetic_slot_calls(|vtable| {
+            vec![
+                instruction(0, InstructionKind::AddressLoad {
+                    register: Register::new("rax"),
+                    address: vtable,
+                }),
+                instruction(4, InstructionKind::RegisterMove {
+                    destination: Register::new("rcx"),
+                    source: Operand::Register(Register::new("rax")),
+                }),
+                // `m5` follows the destructor, size and align in slot 3
+                instruction(8, InstructionKind::MemoryCall {
+                    base: Register::new("rcx"),
+                    offset: 3 * 8,
+                }),
+            ]
+        });
+
+        assert_eq!(invocations, vec![("m5".to_string(), InvocationType::VTableSlot)]);
+    }
+
+    /// Verify a call through a register that no longer holds the vtable pointer falls back to edges to all functions
+    /// in the vtable
+    #[test]
+    pub fn test_find_synthetic_vtable_slot_call_clobbered() {
+        let invocations = find_synthetic_slot_calls(|vtable| {
+            vec![
+                instruction(0, InstructionKind::AddressLoad {
+                    register: Register::new("rax"),
+                    address: vtable,
+                }),
+                instruction(4, InstructionKind::Other {
+                    destination: Some(Register::new("rax")),
+                }),
+                instruction(8, InstructionKind::MemoryCall {
+                    base: Register::new("rax"),
+                    offset: 3 * 8,
+                }),
+            ]
+        });
+
+        assert!(invocations
+            .iter()
+            .all(|(_, invocation_type)| *invocation_type == InvocationType::VTable));
+        assert!(invocations.contains(&("m5".to_string(), InvocationType::VTable)));
+        assert!(invocations.contains(&("m6".to_string(), InvocationType::VTable)));
+    }
+
+    /// Disassemble file and find a dynamic call into a capturing procedure
+    #[test]
+    pub fn test_find_capturing_closure_calls() {
+        // Parse context
+        let file_content = &test_common::load_test_binary_as_bytes(
+            "capturing_closure_invocation",
+            &test_common::TestSubjectType::Debug,
+        ).unwrap();
+
+        let ctx = parse(&file_content);
+
+        let mut graph = Default::default();
+        let mut proc_index = HashMap::new();
+        let mut call_index = HashMap::new();
+
+        // Create partial call graph, with three functions
+        let main_name_demangled = "capturing_closure_invocation::main";
+        let clos_name_demangled = "capturing_closure_invocation::main::{{closure}}";
+        let invoke_clos_name_demangled = "capturing_closure_invocation::invoke_clos";
+
+        let main_index = add_node(
+            "main",
+            main_name_demangled,
+            &mut graph,
+            &mut proc_index,
+            &ctx,
+        );
+        let clos_index = add_node(
+            "main::{{closure}}",
+            clos_name_demangled,
+            &mut graph,
+            &mut proc_index,
+            &ctx,
+        );
+        add_node(
+            "invoke_clos",
+            invoke_clos_name_demangled,
+            &mut graph,
+            &mut proc_index,
+            &ctx,
+        );
+
+        // Add all call_once options
+        add_procs_with_name("call_once", &ctx, &mut graph, &mut proc_index);
+        // Add all drop_in_place options
+        add_procs_with_name("drop_in_place", &ctx, &mut graph, &mut proc_index);
+
+        // Find dynamic invocations
+        AddressLoadDynamicInvocationFinder::default().find_invocations(
+            &mut graph,
+            &mut proc_index,
+            &mut call_index,
+            &ctx,
+            CompilationInfo {
+                rust_version: "1.0.0",
+                compilation_dirs: &[],
+                relocated_sections: &relocated_sections::get_relocated_sections(&ctx),
+            },
+        );
+
+        // Assert that the following dynamic invocations are found
+        // - (main -> main::{{closure}})
+        // - (main -> call_once)
+        // - (main -> drop_in_place)
+        assert_eq!(graph.edge_indices().count(), 3);
+
+        let main_fn_once_index = graph
+            .neighbors(main_index)
+            .filter(|x| {
+                graph[*x]
+                    .borrow()
+                    .linkage_name
+                    .starts_with("core::ops::function::FnOnce::call_once")
+            })
+            .nth(0)
+            .unwrap();
+
+        let main_clos_edge_index = graph.find_edge(main_index, clos_index);
+        let main_fn_once_edge_index = graph.find_edge(main_index, main_fn_once_index);
+
+        // Assert edges from main to closure and from main to call_once exists
+        assert!(main_fn_once_edge_index.is_some());
+        assert!(main_clos_edge_index.is_some());
+
+        // Assert both have invocation type vtable
+        assert_eq!(
+            graph[main_fn_once_edge_index.unwrap()]
+                .borrow()
+                .invocation_type,
+            InvocationType::VTable
+        );
+        assert_eq!(
+            graph[main_clos_edge_index.unwrap()]
+                .borrow()
+                .invocation_type,
+            InvocationType::VTable
+        );
+    }
+
+    /// Disassemble file and find a dynamic call into a trait parameter
+    /// Every rust program has such an invocation, from <main> to the crate main
+    /// Therefore, we use hello_world for this test
+    #[test]
+    pub fn test_find_procedure_reference_calls() {
+        // Parse context
+        let file_content = &test_common::load_test_binary_as_bytes(
+            "hello_world",
+            &test_common::TestSubjectType::Debug,
+        ).unwrap();
+
+        let ctx = parse(&file_content);
+
+        let mut graph = Default::default();
+        let mut proc_index = HashMap::new();
+        let mut call_index = HashMap::new();
+
+        // Create partial call graph
+        let crate_main_name_mangled = "hello_world::main";
+
+        let main_symbol = ctx.elf
+            .symbols()
+            .find(|sym| sym.name() == Some("main"))
+            .unwrap();
+
+        let main_index = add_raw_node(
+            "main",
+            "main",
+            &mut graph,
+            &mut proc_index,
+            &ctx,
+            main_symbol.address(),
+            main_symbol.size(),
+        );
+
+        let crate_main_index = add_node(
+            "hello_world::main",
+            crate_main_name_mangled,
+            &mut graph,
+            &mut proc_index,
+            &ctx,
+        );
+
+        // Find dynamic invocations
+        AddressLoadDynamicInvocationFinder::default().find_invocations(
+            &mut graph,
+            &mut proc_index,
+            &mut call_index,
+            &ctx,
+            CompilationInfo {
+                rust_version: "1.0.0",
+                compilation_dirs: &[],
+                relocated_sections: &relocated_sections::get_relocated_sections(&ctx),
+            },
+        );
+
+        // Assert that dynamic invocation (main -> hello_world::main) is found
+        assert_eq!(graph.edge_indices().count(), 1);
+
+        // Assert edges exist
+        let main_hello_world_main_edge_index = graph.find_edge(main_index, crate_main_index);
+        assert!(main_hello_world_main_edge_index.is_some());
+
+        // Assert have invocation type procedure reference
+        assert_eq!(
+            graph[main_hello_world_main_edge_index.unwrap()]
+                .borrow()
+                .invocation_type,
+            InvocationType::ProcedureReference
+        );
+    }
+}
@@ -0,0 +1,474 @@
+#![allow(clippy::type_complexity)]
+
+// (C) COPYRIGHT 2018 TECHNOLUTION BV, GOUDA NL
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Architecture independent part of the dynamic invocation finders.
+//!
+//! The disassembler backends compute the address that is loaded into a register (e.g. `lea` on x86, `adrp` + `add`
+//! on AArch64). The functions in this module find out whether that address references a procedure or a vtable,
+//! and add the corresponding edges to the call graph.
+//!
+//! vtables are delimited using the debug information (see `vtables`). For vtables without debug information, it is
+//! assumed that a vtable starts with a pointer to a `drop_in_place` procedure, and ends before the next one.
+
+use crate::Context;
+use crate::InlineFunctionFrame;
+use crate::Invocation;
+use crate::InvocationType;
+use crate::Procedure;
+use crate::TraitImplementation;
+
+use gimli::Endianity;
+
+use crate::callgraph::relocated_sections::RelocatedSection;
+use crate::callgraph::relocated_sections::VTABLE_SECTIONS;
+use crate::callgraph::vtables::VTable;
+use crate::callgraph::CompilationInfo;
+use crate::callgraph::FallibleIterator;
+
+use object::Machine;
+use object::Object;
+use object::ObjectSection;
+
+use petgraph::stable_graph::NodeIndex;
+use petgraph::stable_graph::StableGraph;
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::ops::Range;
+use std::rc::Rc;
+
+/// Section flag of sections containing machine code (`SHF_EXECINSTR`).
+const SHF_EXECINSTR: u64 = 0x4;
+
+/// Data of the binary that is used to resolve address references, which is collected once for all procedures.
+pub struct ReferenceData<'a> {
+    /// Relocated views of the data sections
+    pub relocated_sections: &'a [RelocatedSection<'a>],
+    /// vtables described in the debug information, by address
+    pub vtables: HashMap<u64, VTable>,
+}
+
+/// Returns the size in bytes of a pointer in the binary of `ctx`.
+pub fn word_size(ctx: &Context) -> usize {
+    if ctx.elf.elf().is_64 {
+        8
+    } else {
+        4
+    }
+}
+
+/// Returns the address of the procedure `address` points to.
+///
+/// On 32-bit ARM, bit 0 of a function pointer is set to indicate Thumb code, while the procedure starts at the even address.
+pub fn code_address(ctx: &Context, address: u64) -> u64 {
+    match ctx.elf.machine() {
+        Machine::Arm => address & !1,
+        _ => address,
+    }
+}
+
+/// Returns the machine code in `range`, if `range` is located in one of the executable sections of the binary (e.g.
+/// `.text`, or `.text.unlikely` if the linker did not merge it into `.text`), or in the code section of a WebAssembly
+/// module.
+pub fn code_in_range<'a>(ctx: &Context<'a>, range: &Range<u64>) -> Option<&'a [u8]> {
+    if let Some(wasm_module) = &ctx.wasm_module {
+        return wasm_module.code_in_range(range);
+    }
+
+    let elf = ctx.elf.elf();
+    let section = ctx.elf
+        .sections()
+        .filter(|section| section.address() <= range.start && range.end <= section.address() + section.size())
+        .find(|section| {
+            elf.section_headers.iter().any(|header| {
+                header.sh_flags & SHF_EXECINSTR != 0
+                    && header.sh_addr == section.address()
+                    && section.name() == Some(&elf.shdr_strtab[header.sh_name])
+            })
+        })?;
+
+    let offset = (range.start - section.address()) as usize;
+    section.data().get(offset..offset + (range.end - range.start) as usize)
+}
+
+/// Reads the pointer at `address`, if `address` is located in one of the sections of the binary.
+/// The relocated view is read for the sections in `relocated_sections`.
+pub fn read_word_at(ctx: &Context, relocated_sections: &[RelocatedSection], address: u64) -> Option<u64> {
+    let word_size = word_size(ctx) as u64;
+    if let Some(section) = relocated_sections.iter().find(|section| section.contains(address, word_size)) {
+        return read_word(ctx, &section.data[(address - section.address) as usize..]);
+    }
+
+    let section = ctx.elf
+        .sections()
+        .find(|section| section.address() <= address && section.address() + section.size() >= address + word_size)?;
+
+    let offset = (address - section.address()) as usize;
+    read_word(ctx, section.data().get(offset..)?)
+}
+
+/// Reads a pointer from the start of `data`, taking the word size and byte order of the binary into account.
+fn read_word(ctx: &Context, data: &[u8]) -> Option<u64> {
+    let endian = ctx.endian();
+    match word_size(ctx) {
+        8 => data.get(..8).map(|word| endian.read_u64(word)),
+        _ => data.get(..4).map(|word| u64::from(endian.read_u32(word))),
+    }
+}
+
+/// Returns the dynamic calls for an address that is loaded by the instruction at `insn_address`.
+/// In the case the address references a function, a vector with length 1 is returned, containing metadata referencing that function.
+/// In the case the address references a vtable, a vector with metadata referencing all functions in the table is returned.
+/// When the reference could not be traced to a function or vtable, an empty vector is returned.
+/// vtables are read from the relocated views in `data`.
+///
+/// ## Return value
+/// Returns a vector of tuples. If `res` is a return tuple of this function, the fields denote:
+///     `res.0`: address of the instruction loading the address
+///     `res.1`: address of the called function
+///     `res.2`: type of invocation (either `InvocationType::ProcedureReference` or `InvocationType::VTable`
+///     `res.3`: the trait implementation of the vtable, if it is described in the debug information
+pub fn resolve_address_reference<P, I, F>(
+    insn_address: u64,
+    mem_location: u64,
+    graph: &StableGraph<Rc<RefCell<Procedure<P>>>, Rc<RefCell<Invocation<I, F>>>>,
+    proc_index: &HashMap<u64, NodeIndex>,
+    ctx: &Context,
+    prc: &Procedure<P>,
+    data: &ReferenceData,
+) -> Vec<(u64, u64, InvocationType, Option<TraitImplementation>)> {
+    let mem_location = code_address(ctx, mem_location);
+
+    if code_in_range(ctx, &(mem_location..mem_location + 1)).is_some() {
+        // memory location is in an executable section
+        // Try to find function at that address
+
+        if proc_index.contains_key(&mem_location) {
+            return vec![(
+                insn_address,
+                mem_location,
+                InvocationType::ProcedureReference,
+                None,
+            )];
+        }
+
+        return vec![];
+    }
+
+    if let Some(vtable) = data.vtables.get(&mem_location) {
+        return get_described_vtable_pointers(graph, proc_index, ctx, prc, insn_address, vtable, data);
+    }
+
+    let vtable_section = find_vtable_section(data.relocated_sections, mem_location);
+
+    // If pointer is in vtable return entries for vtable
+    match vtable_section {
+        Some(vtable_section) => get_vtable_pointers(
+            graph,
+            proc_index,
+            ctx,
+            prc,
+            insn_address,
+            mem_location,
+            vtable_section,
+        ),
+        // Pointer not in an executable section, nor in a vtable section, so return no edges.
+        None => vec![],
+    }
+}
+
+/// Returns the section that can contain a vtable at `mem_location`, if any.
+fn find_vtable_section<'a>(
+    relocated_sections: &'a [RelocatedSection<'a>],
+    mem_location: u64,
+) -> Option<&'a RelocatedSection<'a>> {
+    relocated_sections
+        .iter()
+        .find(|section| VTABLE_SECTIONS.contains(&section.name.as_str()) && section.contains(mem_location, 1))
+}
+
+/// Returns the address of the procedure in slot `slot` of the vtable at `mem_location`, together with the trait
+/// implementation of the vtable if it is described in the debug information.
+/// Returns `None` if `mem_location` does not reference a vtable, or the slot does not reference a procedure.
+pub fn get_vtable_slot<P, I, F>(
+    graph: &StableGraph<Rc<RefCell<Procedure<P>>>, Rc<RefCell<Invocation<I, F>>>>,
+    proc_index: &HashMap<u64, NodeIndex>,
+    ctx: &Context,
+    data: &ReferenceData,
+    mem_location: u64,
+    slot: usize,
+) -> Option<(u64, Option<TraitImplementation>)> {
+    if let Some(vtable) = data.vtables.get(&mem_location) {
+        let slot_offset = (slot * word_size(ctx)) as u64;
+        if slot_offset >= vtable.size {
+            return None;
+        }
+        let fn_address = read_word_at(ctx, data.relocated_sections, vtable.address + slot_offset)?;
+        let idx = proc_index.get(&code_address(ctx, fn_address))?;
+        let start_address = graph[*idx].borrow().start_address;
+        return Some((start_address, Some(vtable.implementation.clone())));
+    }
+
+    let vtable_section = find_vtable_section(data.relocated_sections, mem_location)?;
+    let (offset, vtable_data, _, is_destructor) =
+        get_vtable_metadata(graph, proc_index, ctx, mem_location, vtable_section);
+
+    if !is_destructor {
+        return None;
+    }
+
+    let procedure = get_pointed_proc(offset + slot * word_size(ctx), vtable_data, graph, proc_index, ctx)?;
+    let start_address = procedure.borrow().start_address;
+    Some((start_address, None))
+}
+
+/// Function that returns metadata of all functions in `vtable`, which is described in the debug information.
+///
+/// Unlike in `get_vtable_pointers`, the boundaries of the vtable are known. Entries that do not point to a
+/// procedure (a missing destructor, pointers to the vtables of supertraits, or vacant entries) are skipped.
+///
+/// ## Return value
+/// Returns a vector of tuples. If `res` is a return tuple of this function, the fields denote:
+///     `res.0`: address of the instruction loading the address
+///     `res.1`: address of the called function
+///     `res.2`: type of invocation (`InvocationType::VTable`)
+///     `res.3`: the trait implementation of `vtable`
+fn get_described_vtable_pointers<P, I, F>(
+    graph: &StableGraph<Rc<RefCell<Procedure<P>>>, Rc<RefCell<Invocation<I, F>>>>,
+    proc_index: &HashMap<u64, NodeIndex<u32>>,
+    ctx: &Context,
+    prc: &Procedure<P>,
+    insn_address: u64,
+    vtable: &VTable,
+    data: &ReferenceData,
+) -> Vec<(u64, u64, InvocationType, Option<TraitImplementation>)> {
+    let word_size = word_size(ctx) as u64;
+    let calling_proc_index = proc_index[&prc.start_address];
+    let mut result: Vec<(u64, u64, InvocationType, Option<TraitImplementation>)> = vec![];
+
+    (0..vtable.size / word_size)
+        // Skip the size and align fields
+        .filter(|slot| *slot != 1 && *slot != 2)
+        .for_each(|slot| {
+            let target_index = match read_word_at(ctx, data.relocated_sections, vtable.address + slot * word_size)
+                .and_then(|fn_address| proc_index.get(&code_address(ctx, fn_address)))
+            {
+                Some(target_index) => *target_index,
+                None => return,
+            };
+
+            // Like in `get_vtable_pointers`, the destructor is only added if there is no edge to it yet
+            if slot == 0 && graph.find_edge(calling_proc_index, target_index).is_some() {
+                return;
+            }
+
+            let start_address = graph[target_index].borrow().start_address;
+            if !result.iter().any(|x| x.1 == start_address) {
+                result.push((
+                    insn_address,
+                    start_address,
+                    InvocationType::VTable,
+                    Some(vtable.implementation.clone()),
+                ));
+            }
+        });
+    result
+}
+
+/// Function that returns metadata of all functions in the vtable at address `mem_location`
+///
+/// ## Return value
+/// Returns a vector of tuples. If `res` is a return tuple of this function, the fields denote:
+///     `res.0`: address of the instruction loading the address
+///     `res.1`: address of the called function
+///     `res.2`: type of invocation (either `InvocationType::ProcedureReference` or `InvocationType::VTable`
+///     `res.3`: the trait implementation of the vtable, which is always `None`, as it is not known
+fn get_vtable_pointers<P, I, F>(
+    graph: &StableGraph<Rc<RefCell<Procedure<P>>>, Rc<RefCell<Invocation<I, F>>>>,
+    proc_index: &HashMap<u64, NodeIndex<u32>>,
+    ctx: &Context,
+    prc: &Procedure<P>,
+    insn_address: u64,
+    mem_location: u64,
+    vtable_section: &RelocatedSection,
+) -> Vec<(u64, u64, InvocationType, Option<TraitImplementation>)> {
+    // memory location is in .data.rel.ro section
+    // In that case we assume it is a vtable
+    // Tricky part of this is that the size of the vtable is unknown.
+    // We know the vtable has the following layout
+    // ****************************
+    // <destructor pointer>
+    // <size>
+    // <align>
+    // <trait function 1>
+    // ...
+    // <trait function n>
+    // ****************************
+    // In the loop below, the first 3 fields are skipped
+    // After that, we will push items, until we find a destructor (which we assume is of the next vtable)
+    // or find a pointer that does not map to a procedure (there we assume some other data is meant)
+
+    // First do en early return if the first entry of the assumed vtable is not a destructor
+    let (mut offset, vtable_data, vtable_first_entry, is_destructor) =
+        get_vtable_metadata(graph, proc_index, ctx, mem_location, vtable_section);
+
+    if !is_destructor {
+        return vec![];
+    }
+
+    // The first entry if the vtable is a pointer to a destructor
+    // If there does not exist a static call to that destructor form the calling function
+    // we assume it will be called dynamically somewhere
+    let mut result: Vec<(u64, u64, InvocationType, Option<TraitImplementation>)> = vec![];
+    let destructor = vtable_first_entry.unwrap();
+    let destructor = destructor.borrow();
+    let vtable_data_length = vtable_data.len();
+    let word_size = word_size(ctx);
+
+    let calling_proc_index = proc_index[&prc.start_address];
+    let dest_proc_index = proc_index[&destructor.start_address];
+
+    // If edge to the destructor does not yet exist, add it
+    if graph
+        .find_edge(calling_proc_index, dest_proc_index)
+        .is_none()
+    {
+        result.push((
+            insn_address,
+            destructor.start_address,
+            InvocationType::VTable,
+            None,
+        ));
+    }
+
+    // Skip destructor + size + align fields
+    offset += word_size * 3;
+
+    // Iterate all entries in the vtable, until we find a pointer to something different than a procedure
+    // or to a destructor (which signifies a new vtable)
+    while offset < vtable_data_length {
+        let procedure = get_pointed_proc(offset, vtable_data, graph, proc_index, ctx);
+
+        // Procedure not found, assume we are in some other data structure
+        if procedure.is_none() {
+            break;
+        }
+
+        // Unwrap is safe, because is_none check above
+        let procedure = procedure.unwrap();
+        let procedure: &Procedure<P> = &procedure.deref().borrow();
+
+        if procedure.name.contains("drop_in_place") {
+            // In new vtable, so quit here
+            break;
+        }
+
+        // If an edge of this kind is not yet present, add it
+        let edge_existing = result.iter().any(|x: &(u64, u64, InvocationType, Option<TraitImplementation>)| {
+            x.1 == procedure.start_address && x.2 == InvocationType::VTable
+        });
+
+        if !edge_existing {
+            result.push((insn_address, procedure.start_address, InvocationType::VTable, None));
+        }
+
+        offset += word_size;
+    }
+    result
+}
+
+/// Function that returns metadata about the vtable at `mem_location`.
+///
+/// ## Return value
+/// Returns a tuple. If `res` is a return tuple of this function, the fields denote:
+///     `res.0`: offset of the vtable in the vtable section.
+///     `res.1`: the relocated data of the vtable section ('.data.rel.ro', '.data.rel.ro.local' or '.rodata')
+///     `res.2`: The (optional) procedure that denotes the destructor that is pointed to by the first entry in the vtable
+///     `res.3`: A boolean indicating if the destructor is indeed a destructor.
+fn get_vtable_metadata<'a, P, I, F>(
+    graph: &StableGraph<Rc<RefCell<Procedure<P>>>, Rc<RefCell<Invocation<I, F>>>>,
+    proc_index: &HashMap<u64, NodeIndex<u32>>,
+    ctx: &Context,
+    mem_location: u64,
+    vtable_section: &'a RelocatedSection,
+) -> (usize, &'a [u8], Option<Rc<RefCell<Procedure<P>>>>, bool) {
+    let offset = (mem_location - vtable_section.address) as usize;
+    let vtable_data = &vtable_section.data[..];
+    // If not pointing to a destructor, we assume this is not a vtable
+    let vtable_first_entry = get_pointed_proc(offset, vtable_data, graph, proc_index, ctx);
+    let is_destructor = vtable_first_entry
+        .as_ref()
+        .map(|prc| {
+            let p: &Procedure<P> = &prc.deref().borrow();
+            p.name.contains("drop_in_place")
+        })
+        .unwrap_or(false);
+    (offset, vtable_data, vtable_first_entry, is_destructor)
+}
+
+/// Function that adds edges from the procedure at `idx` for all dynamic invocations in `dest_indices`.
+///
+/// The fields of the tuples in `dest_indices` denote:
+///     `res.0`: address of the instruction loading the address
+///     `res.1`: address of the called function
+///     `res.2`: type of invocation (either `InvocationType::ProcedureReference` or `InvocationType::VTable`
+///     `res.3`: the trait implementation of the vtable, if known
+pub fn create_dynamic_edges<P, I: Default, F: Default>(
+    graph: &mut StableGraph<Rc<RefCell<Procedure<P>>>, Rc<RefCell<Invocation<I, F>>>>,
+    proc_index: &mut HashMap<u64, NodeIndex<u32>>,
+    ctx: &Context,
+    idx: NodeIndex<u32>,
+    dest_indices: &[(u64, u64, InvocationType, Option<TraitImplementation>)],
+    compilation_info: &CompilationInfo,
+) {
+    dest_indices
+        .iter()
+        .for_each(|(insn_addr, target_addr, invocation_type, trait_implementation)| {
+            let frames = match ctx.find_frames(*insn_addr) {
+                Some(frames) => frames
+                    .map(|frame| {
+                        Ok(InlineFunctionFrame::convert_frame(
+                            &frame,
+                            compilation_info.compilation_dirs,
+                            compilation_info.rust_version.to_owned(),
+                        ))
+                    })
+                    .collect()
+                    .unwrap_or_default(),
+                None => vec![],
+            };
+
+            graph.add_edge(
+                idx,
+                proc_index[target_addr],
+                Rc::new(RefCell::new(Invocation {
+                    invocation_type: *invocation_type,
+                    trait_implementation: trait_implementation.clone(),
+                    instruction_address: *insn_addr,
+                    frames,
+                    attributes: I::default(),
+                })),
+            );
+        });
+}
+
+/// Function that returns an (optional) `Procedure` at `offset` in the vtable section.
+fn get_pointed_proc<P, I, F>(
+    offset: usize,
+    vtable_data: &[u8],
+    graph: &StableGraph<Rc<RefCell<Procedure<P>>>, Rc<RefCell<Invocation<I, F>>>>,
+    proc_index: &HashMap<u64, NodeIndex>,
+    ctx: &Context,
+) -> Option<Rc<RefCell<Procedure<P>>>> {
+    let fn_address = code_address(ctx, read_word(ctx, vtable_data.get(offset..)?)?);
+
+    proc_index.get(&fn_address).map(|idx| graph[*idx].clone())
+}
@@ -0,0 +1,279 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use crate::callgraph::{get_compilation_unit_directories, get_procedures_for_compilation_unit, CallGraphBuilder, CompilationInfo, InvocationFinder};
+use crate::callgraph::relocated_sections;
+use crate::callgraph::symbols;
+use crate::{dwarf_utils, CallGraph, Context};
+
+/// Struct able to build a callgraph from a binary of any supported instruction set
+pub struct DefaultCallGraphBuilder<P, I, F> {
+    pub(crate) invocation_finders: Vec<Box<dyn InvocationFinder<P, I, F>>>,
+}
+
+impl<PMetadata: Default, IMetadata: Default, FMetadata: Default> CallGraphBuilder<PMetadata, IMetadata, FMetadata>
+for DefaultCallGraphBuilder<PMetadata, IMetadata, FMetadata>
+{
+    /// Function building the full call graph from the information in `ctx`.
+    fn build_call_graph(&self, ctx: &Context) -> CallGraph<PMetadata, IMetadata, FMetadata> {
+        // Initialize empty fields for callgraph
+        let mut graph = petgraph::stable_graph::StableGraph::new();
+        // Index mapping procedure start addresses to their index in the graph
+        let mut proc_index = HashMap::new();
+        // Index mapping call/jump instruction addresses to the index of their enclosing procedure in the graph
+        let mut call_index = HashMap::new();
+
+        let compilation_unit_dirs = get_compilation_unit_directories(ctx);
+        let rust_version = dwarf_utils::get_rust_version(ctx);
+
+        // Iterator over compilation units
+        let procedures = dwarf_utils::get_compilation_units(ctx)
+            .iter()
+            // Map all compilation units to their respective procedures
+            .flat_map(|unit| get_procedures_for_compilation_unit::<PMetadata>(ctx, &compilation_unit_dirs, unit))
+            .collect::<Vec<_>>();
+
+        // Procedures without debug information are read from the symbol table
+        let covered_ranges = procedures
+            .iter()
+            .flat_map(|procedure| procedure.address_ranges.iter().cloned())
+            .collect::<Vec<_>>();
+        let symbol_procedures = symbols::get_symbol_procedures(ctx, &covered_ranges, rust_version.clone());
+
+        procedures
+            .into_iter()
+            .chain(symbol_procedures)
+            // Add all nodes to the graph, and all (addr, index) pairs to the proc_index map
+            .for_each(|procedure| {
+                let address = procedure.start_address;
+                let idx = graph.add_node(Rc::new(RefCell::new(procedure)));
+
+                // Add every call instruction of a procedure to the address to index map.
+                graph[idx].borrow().disassembly.iter()
+                    .filter(|insn| insn.is_branch())
+                    .for_each(|insn| {
+                        call_index.insert(insn.address, idx); });
+
+                proc_index.insert(address, idx);
+            });
+
+        // Apply the dynamic relocations once, instead of in every invocation finder
+        let relocated_sections = relocated_sections::get_relocated_sections(ctx);
+
+        self.invocation_finders.iter().for_each(|finder| {
+            finder.find_invocations(
+                &mut graph,
+                &mut proc_index,
+                &mut call_index,
+                ctx,
+                CompilationInfo {
+                    compilation_dirs: &compilation_unit_dirs,
+                    rust_version: &rust_version.as_ref().cloned().unwrap_or_default(),
+                    relocated_sections: &relocated_sections,
+                },
+            )
+        });
+
+        CallGraph {
+            graph,
+            proc_index,
+            call_index,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::callgraph::address_load_dynamic_calls::AddressLoadDynamicInvocationFinder;
+    use crate::callgraph::static_calls::StaticCallInvocationFinder;
+    use crate::parse::get_parser;
+    use crate::DebugBuffers;
+    use crate::InvocationType;
+
+    use petgraph::visit::EdgeRef;
+
+    use test_common::TestSubjectType;
+
+    /// Builds the call graph of the `trait_invocation` test subject of `subject_type`, and asserts that
+    /// `trait_invocation::main` calls `qux` directly, and loads the vtable of `Foo`
+    fn assert_main_invocations(subject_type: &TestSubjectType) {
+        let invocations = get_invocations("trait_invocation", "trait_invocation::main", subject_type);
+
+        assert_invocation(&invocations, "trait_invocation::qux", InvocationType::Direct);
+        assert_invocation(&invocations, "<trait_invocation::Foo as trait_invocation::Baz>::m5", InvocationType::VTable);
+        assert_invocation(&invocations, "<trait_invocation::Foo as trait_invocation::Baz>::m6", InvocationType::VTable);
+    }
+
+    /// Builds the call graph of the `firmware` test subject of `subject_type`, and asserts that `firmware::run` calls
+    /// its helpers directly, and loads the vtables of the sensors
+    fn assert_firmware_invocations(subject_type: &TestSubjectType) {
+        let invocations = get_invocations("firmware", "firmware::run", subject_type);
+
+        assert_invocation(&invocations, "firmware::sample", InvocationType::Direct);
+        assert_invocation(&invocations, "firmware::average", InvocationType::Direct);
+        assert_invocation(
+            &invocations,
+            "<firmware::Thermometer as firmware::Sensor>::read",
+            InvocationType::VTable,
+        );
+        assert_invocation(&invocations, "<firmware::Hygrometer as firmware::Sensor>::read", InvocationType::VTable);
+    }
+
+    /// Asserts that `invocations` contains an invocation of type `invocation_type` of a procedure of which the name
+    /// starts with `callee`, as the names of generic procedures may be followed by their generic arguments
+    fn assert_invocation(invocations: &[(String, InvocationType)], callee: &str, invocation_type: InvocationType) {
+        assert!(
+            invocations
+                .iter()
+                .any(|(name, invocation)| name.starts_with(callee) && *invocation == invocation_type),
+            "No {:?} invocation of {} in {:?}",
+            invocation_type,
+            callee,
+            invocations
+        );
+    }
+
+    /// Builds the call graph of the test subject `subject` of `subject_type`, and returns the names of the procedures
+    /// the procedure `procedure` invokes, with the type of the invocation
+    fn get_invocations(
+        subject: &str,
+        procedure: &str,
+        subject_type: &TestSubjectType,
+    ) -> Vec<(String, InvocationType)> {
+        let path = test_common::get_test_subject_path(subject, subject_type);
+        let file_content = &test_common::load_test_binary_as_bytes(subject, subject_type).unwrap();
+
+        let parser = get_parser();
+        let buffers = DebugBuffers::default();
+        let context = parser.parse(file_content, &path, &buffers).expect("Could not parse test file contents");
+        let call_graph: CallGraph<(), (), ()> = DefaultCallGraphBuilder {
+            invocation_finders: vec![
+                Box::new(StaticCallInvocationFinder),
+                Box::new(AddressLoadDynamicInvocationFinder::default()),
+            ],
+        }.build_call_graph(&context);
+
+        let procedure_index = call_graph
+            .graph
+            .node_indices()
+            .find(|index| call_graph.graph[*index].borrow().linkage_name_demangled == procedure)
+            .expect("Procedure not found in call graph");
+
+        call_graph
+            .graph
+            .edges(procedure_index)
+            .map(|edge| {
+                (
+                    call_graph.graph[edge.target()].borrow().linkage_name_demangled.clone(),
+                    edge.weight().borrow().invocation_type,
+                )
+            })
+            .collect()
+    }
+
+    /// Builds the call graph of the `trait_invocation` test subject of `subject_type`, and returns the sorted
+    /// demangled names and crates of its procedures, and the number of invocations
+    fn summarize_call_graph(subject_type: &TestSubjectType) -> (Vec<(String, String)>, usize) {
+        let path = test_common::get_test_subject_path("trait_invocation", subject_type);
+        let file_content = &test_common::load_test_binary_as_bytes("trait_invocation", subject_type).unwrap();
+
+        let parser = get_parser();
+        let buffers = DebugBuffers::default();
+        let context = parser.parse(file_content, &path, &buffers).expect("Could not parse test file contents");
+        let call_graph: CallGraph<(), (), ()> = DefaultCallGraphBuilder {
+            invocation_finders: vec![
+                Box::new(StaticCallInvocationFinder),
+                Box::new(AddressLoadDynamicInvocationFinder::default()),
+            ],
+        }.build_call_graph(&context);
+
+        let mut procedures = call_graph
+            .graph
+            .node_indices()
+            .map(|index| {
+                let procedure = call_graph.graph[index].borrow();
+                (procedure.linkage_name_demangled.clone(), procedure.defining_crate.name.clone())
+            })
+            .collect::<Vec<_>>();
+        procedures.sort();
+
+        (procedures, call_graph.graph.edge_count())
+    }
+
+    /// Test if the builds of the example binary with split debug information and DWARF 5 are analysed like the
+    /// default build
+    #[test]
+    pub fn test_call_graph_creation_debug_info_variants() {
+        let expected = summarize_call_graph(&TestSubjectType::Debug);
+        assert!(!expected.0.is_empty());
+
+        [
+            TestSubjectType::DebugSplitUnpacked,
+            TestSubjectType::DebugSplitPacked,
+            TestSubjectType::DebugDwarf5,
+        ].iter()
+            .filter(|subject_type| !test_common::skip_unbuilt(subject_type))
+            .for_each(|subject_type| {
+                assert_eq!(
+                    summarize_call_graph(subject_type),
+                    expected,
+                    "Call graph of {:?} differs",
+                    subject_type
+                );
+            });
+    }
+
+    /// Test if a call graph can be created from the AArch64 build of the example binary
+    #[test]
+    pub fn test_call_graph_creation_aarch64() {
+        if test_common::skip_unbuilt(&TestSubjectType::DebugAArch64) {
+            return;
+        }
+        // main calls its helpers with `bl`, and loads the vtable of `Foo` with `adrp` + `add`
+        assert_main_invocations(&TestSubjectType::DebugAArch64);
+    }
+
+    /// Test if a call graph can be created from the rv64gc build of the example binary
+    #[test]
+    pub fn test_call_graph_creation_riscv64() {
+        if test_common::skip_unbuilt(&TestSubjectType::DebugRiscV64) {
+            return;
+        }
+        // main calls its helpers with `auipc` + `jalr` or `jal`, and loads the vtable of `Foo` with `auipc` + `addi`
+        assert_main_invocations(&TestSubjectType::DebugRiscV64);
+    }
+
+    /// Test if a call graph can be created from the i686 build of the example binary
+    #[test]
+    pub fn test_call_graph_creation_i686() {
+        if test_common::skip_unbuilt(&TestSubjectType::DebugI686) {
+            return;
+        }
+        // main loads the vtable of `Foo` relative to the GOT, of which the address is loaded in a register first
+        assert_main_invocations(&TestSubjectType::DebugI686);
+    }
+
+    /// Test if a call graph can be created from the Cortex-M (thumbv7m) build of the embedded example firmware
+    #[test]
+    pub fn test_call_graph_creation_thumbv7m() {
+        if test_common::skip_unbuilt(&TestSubjectType::DebugThumbv7m) {
+            return;
+        }
+        // run calls its helpers with `bl`, and loads the vtables of the sensors from a literal pool. The Thumb bit
+        // of the function pointers in the vtables is cleared to find the called procedures.
+        assert_firmware_invocations(&TestSubjectType::DebugThumbv7m);
+    }
+
+    /// Test if a call graph can be created from the rv32imac build of the embedded example firmware
+    #[test]
+    pub fn test_call_graph_creation_riscv32() {
+        if test_common::skip_unbuilt(&TestSubjectType::DebugRiscV32) {
+            return;
+        }
+        // run calls its helpers with `jal` or `auipc` + `jalr`, including compressed instructions, and loads the
+        // vtables of the sensors with `auipc` + `addi`
+        assert_firmware_invocations(&TestSubjectType::DebugRiscV32);
+    }
+}
@@ -0,0 +1,185 @@
+// (C) COPYRIGHT 2018 TECHNOLUTION BV, GOUDA NL
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Targets of the global offset table (GOT) and the procedure linkage table (PLT).
+//!
+//! Procedures in other shared objects (and, in position independent executables, procedures that may be interposed)
+//! are not called directly. They are either called through their slot in the GOT (`call *foo@GOTPCREL(%rip)`, which
+//! rustc emits on x86_64 Linux), or through a PLT stub that jumps to the address in the GOT slot. The GOT slots are
+//! filled in by the dynamic loader, using the relocations against the symbol of the procedure.
+
+use crate::callgraph::address_references::read_word_at;
+use crate::callgraph::address_references::word_size;
+use crate::callgraph::relocated_sections::RelocatedSection;
+use crate::disassembly::InstructionKind;
+use crate::Context;
+
+use object::Object;
+use object::ObjectSection;
+
+use std::collections::HashMap;
+
+/// Names of the sections containing the GOT.
+const GOT_SECTIONS: &[&str] = &[".got", ".got.plt"];
+
+/// Names of the sections containing PLT stubs.
+const PLT_SECTIONS: &[&str] = &[".plt", ".plt.sec", ".plt.got"];
+
+/// Target of a call, or of a GOT slot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CallTarget {
+    /// The target is at `address`, which is known statically (e.g. a direct call, or a GOT slot in a statically
+    /// linked binary)
+    Address(u64),
+    /// The target is the symbol `name`, of which the address is filled in the GOT slot at `slot` by the dynamic loader
+    External { name: String, slot: u64 },
+}
+
+/// Targets of the GOT slots and PLT stubs of a binary.
+#[derive(Debug, Default)]
+pub struct LinkageTables {
+    /// Targets of the GOT slots, by address of the slot
+    pub got_slots: HashMap<u64, CallTarget>,
+    /// Address of the GOT slot a PLT stub jumps through, by address of the instructions of the stub
+    pub plt_stubs: HashMap<u64, u64>,
+}
+
+impl LinkageTables {
+    /// Reads the GOT and PLT of the binary in `ctx`. The GOT is read from `relocated_sections` where possible.
+    pub fn new(ctx: &Context, relocated_sections: &[RelocatedSection]) -> LinkageTables {
+        LinkageTables {
+            got_slots: get_got_slots(ctx, relocated_sections),
+            plt_stubs: get_plt_stubs(ctx),
+        }
+    }
+
+    /// Returns the target of the GOT slot at `address`.
+    pub fn got_target(&self, address: u64) -> Option<&CallTarget> {
+        self.got_slots.get(&address)
+    }
+
+    /// Returns the GOT slot through which the PLT stub at `address` jumps, or `None` if `address` is not a PLT stub.
+    pub fn plt_slot(&self, address: u64) -> Option<u64> {
+        self.plt_stubs.get(&address).cloned()
+    }
+}
+
+/// Returns the targets of all slots in the GOT, by address of the slot.
+fn get_got_slots(ctx: &Context, relocated_sections: &[RelocatedSection]) -> HashMap<u64, CallTarget> {
+    let word_size = word_size(ctx) as u64;
+
+    // Slots holding an address after the relative relocations are applied
+    let mut slots = ctx.elf
+        .sections()
+        .filter(|section| section.name().map(|name| GOT_SECTIONS.contains(&name)).unwrap_or(false))
+        .flat_map(|section| {
+            let address = section.address();
+            (0..section.size() / word_size).map(move |index| address + index * word_size)
+        })
+        .filter_map(|slot| Some((slot, CallTarget::Address(read_word_at(ctx, relocated_sections, slot)?))))
+        .collect::<HashMap<_, _>>();
+
+    // Slots relocated against a symbol, which override the value in the file (e.g. the address of the lazy binding
+    // code in the PLT)
+    let elf = ctx.elf.elf();
+    elf.dynrelas
+        .iter()
+        .chain(elf.dynrels.iter())
+        .chain(elf.pltrelocs.iter())
+        .filter(|reloc| reloc.r_sym != 0)
+        .for_each(|reloc| {
+            let symbol = match elf.dynsyms.get(reloc.r_sym) {
+                Some(symbol) => symbol,
+                None => return,
+            };
+            // A symbol that is defined in the binary itself is bound to that definition
+            let target = if symbol.st_shndx != 0 {
+                CallTarget::Address(symbol.st_value)
+            } else {
+                CallTarget::External {
+                    name: elf.dynstrtab[symbol.st_name].to_string(),
+                    slot: reloc.r_offset as u64,
+                }
+            };
+            slots.insert(reloc.r_offset as u64, target);
+        });
+
+    slots
+}
+
+/// Returns the GOT slot used by the PLT stub each instruction in the PLT belongs to, by address of the instruction.
+///
+/// A PLT stub loads the address from its GOT slot (`InstructionKind::PointerJump` on x86, a `MemoryLoad` on AArch64
+/// and RISC-V), and branches to it. Every instruction following the previous branch is assumed to be part of the stub
+/// (including padding and `endbr64`), so a call to the start of the stub is resolved regardless of the PLT layout.
+/// Stubs that do not load from a statically known GOT slot (e.g. the lazy binding code) are ignored.
+fn get_plt_stubs(ctx: &Context) -> HashMap<u64, u64> {
+    let mut stubs = HashMap::new();
+
+    ctx.elf
+        .sections()
+        .filter(|section| section.name().map(|name| PLT_SECTIONS.contains(&name)).unwrap_or(false))
+        .for_each(|section| {
+            let mut stub_instructions = vec![];
+            let mut slot = None;
+
+            ctx.disassemble(section.data(), section.address())
+                .iter()
+                .for_each(|insn| {
+                    stub_instructions.push(insn.address);
+                    match insn.kind {
+                        InstructionKind::MemoryLoad { address, .. } | InstructionKind::PointerJump { address } => {
+                            slot = Some(address)
+                        }
+                        _ => (),
+                    }
+
+                    if insn.is_branch() {
+                        if let Some(slot) = slot.take() {
+                            stub_instructions.iter().for_each(|address| {
+                                stubs.insert(*address, slot);
+                            });
+                        }
+                        stub_instructions.clear();
+                    }
+                });
+        });
+
+    stubs
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::callgraph::relocated_sections::get_relocated_sections;
+    use crate::parse::get_parser;
+    use crate::DebugBuffers;
+
+    use test_common::TestSubjectType;
+
+    /// Verify the GOT slots of the procedures of the C library are external in the example binary
+    #[test]
+    pub fn test_external_got_slots() {
+        let path = test_common::get_test_subject_path("hello_world", &TestSubjectType::Debug);
+        let file_content = &test_common::load_test_binary_as_bytes("hello_world", &TestSubjectType::Debug).unwrap();
+        let buffers = DebugBuffers::default();
+        let context = get_parser().parse(file_content, &path, &buffers).expect("Could not parse test file contents");
+
+        let tables = LinkageTables::new(&context, &get_relocated_sections(&context));
+
+        // The Rust runtime is started by the C library, and uses its `write` to print
+        assert!(tables
+            .got_slots
+            .values()
+            .any(|target| match target {
+                CallTarget::External { name, .. } => name == "write",
+                CallTarget::Address(_) => false,
+            }));
+    }
+}
@@ -7,18 +7,32 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-mod lea_dynamic_calls;
+mod address_load_dynamic_calls;
+mod address_references;
+mod default_cg_builder;
+mod linkage_tables;
+mod relocatable_calls;
+mod relocated_sections;
+mod shared_libraries;
 mod static_calls;
-mod x86_cg_builder;
+mod symbols;
+mod vtables;
+mod wasm_calls;
+
+pub use self::shared_libraries::is_library_address;
+pub use self::shared_libraries::link_shared_libraries;
 
 use crate::CallGraph;
+use crate::CallGraphOptions;
 use crate::Context;
 use crate::Invocation;
+use crate::Language;
 use crate::Location;
 use crate::Procedure;
 
 use std::cell::RefCell;
 use std::collections::HashMap;
+use std::ops::Range;
 use std::rc::Rc;
 use std::string::String;
 
@@ -26,31 +40,33 @@ use fallible_iterator::FallibleIterator;
 
 use crate::errors::*;
 
-use gimli::{AttributeValue, UnitHeader};
-use gimli::DW_AT_comp_dir;
-use gimli::DW_AT_high_pc;
+use gimli::AttributeValue;
+use gimli::DW_AT_entry_pc;
 use gimli::DW_AT_inline;
 use gimli::DW_AT_language;
 use gimli::DW_AT_low_pc;
 use gimli::DW_TAG_compile_unit;
+use gimli::DW_TAG_skeleton_unit;
 use gimli::DebuggingInformationEntry;
 use gimli::EndianSlice;
 use gimli::EntriesCursor;
-use gimli::LittleEndian;
-
-use object::Object;
-use object::ObjectSection;
+use gimli::RunTimeEndian;
 
 use petgraph::stable_graph::{NodeIndex, StableGraph};
 
 use crate::crate_utils;
 use crate::dwarf_utils;
+use crate::dwarf_utils::CompilationUnit;
+use crate::wasm;
 use std::marker::PhantomData;
-use crate::callgraph::x86_cg_builder::X86CallGraphBuilder;
+use crate::callgraph::default_cg_builder::DefaultCallGraphBuilder;
+use crate::callgraph::relocated_sections::RelocatedSection;
 
 pub struct CompilationInfo<'a> {
     compilation_dirs: &'a [&'a str],
     rust_version: &'a str,
+    /// Relocated views of the data sections of the binary, which are created once for all invocation finders
+    relocated_sections: &'a [RelocatedSection<'a>],
 }
 
 pub trait InvocationFinder<P, I: Default, F: Default> {
@@ -69,9 +85,9 @@ pub trait CallGraphBuilder<PMetadata: Default, IMetadata: Default, FMetadata> {
     fn build_call_graph(&self, ctx: &Context) -> CallGraph<PMetadata, IMetadata, FMetadata>;
 }
 
-/// Struct abstracting the machine code for a procedure.
+/// Struct abstracting the machine code for an address range of a procedure.
 struct MachineCode<'a> {
-    /// Function address in binary
+    /// Address of the machine code in binary
     address: u64,
     /// Instruction bytes
     raw_instr: &'a [u8],
@@ -79,10 +95,10 @@ struct MachineCode<'a> {
 
 /// Metadata about the location af an assembly procedure.
 struct LocationInfo {
-    /// Address of the procedure in the executable
+    /// Address of the entry point of the procedure in the executable
     address: u64,
-    /// Size in bytes of the procedure
-    size: u64,
+    /// Address ranges of the procedure, starting with the range containing the entry point
+    ranges: Vec<Range<u64>>,
 }
 
 /// Metadata about the different names of a procedure
@@ -95,14 +111,16 @@ struct NameInfo {
     linkage_name_demangled: String,
 }
 
-/// If the current compile unit was compiled as Rust code, this function returns all procedures in that compile unit.
+/// If the current compile unit was compiled as Rust, C or C++ code (see `Language`), this function returns all
+/// procedures in that compile unit.
 ///
 /// However:
 /// - If a function was inlined, it will not be returned
-/// - If the cursor was not at a compile unit entry, it will panic
+/// - If the cursor was not at a unit entry, it will panic
+/// - Type units, and skeleton units of which the split unit was not found, contain no procedures
 fn iterate_compilation_unit<PM: Default>(
-    unit: UnitHeader<EndianSlice<LittleEndian>>,
-    entries: &mut EntriesCursor<EndianSlice<LittleEndian>>,
+    unit: &CompilationUnit,
+    entries: &mut EntriesCursor<EndianSlice<RunTimeEndian>>,
     ctx: &Context,
     compilation_unit_dirs: &[&str],
 ) -> Vec<Procedure<PM>> {
@@ -110,33 +128,36 @@ fn iterate_compilation_unit<PM: Default>(
         let cu_entry = entries
             .current()
             .expect("Cursor not advanced to compilation unit entry.");
-        assert_eq!(
-            cu_entry.tag(),
-            DW_TAG_compile_unit,
-            "Current cursor entry is not a compilation unit"
-        );
-
-        // If the language of the current compile unit is not Rust, skip it
-        match cu_entry
-            .attr(DW_AT_language)
-            .map(|att_opt| att_opt.map(|att| att.value()))
-        {
-            Ok(Some(AttributeValue::Language(gimli::DW_LANG_Rust))) => (),
-            _ => return vec![],
+        if cu_entry.tag() != DW_TAG_compile_unit {
+            return vec![];
         }
+
         cu_entry.clone()
     };
 
-    parse_compilation_unit_subprograms(&unit, entries, ctx, compilation_unit_dirs, &cu)
+    // If the current compile unit is written in a language that is not analysed, skip it
+    let language = match cu
+        .attr(DW_AT_language)
+        .map(|att_opt| att_opt.map(|att| att.value()))
+    {
+        Ok(Some(AttributeValue::Language(language))) => match Language::from_dwarf(language) {
+            Some(language) => language,
+            None => return vec![],
+        },
+        _ => return vec![],
+    };
+
+    parse_compilation_unit_subprograms(unit, entries, ctx, compilation_unit_dirs, &cu, language)
 }
 
 /// Function creating `Procedure`s for all nodes in a DWARF compilation unit.
 fn parse_compilation_unit_subprograms<PM: Default>(
-    unit: &UnitHeader<EndianSlice<LittleEndian>, usize>,
-    entries: &mut EntriesCursor<EndianSlice<LittleEndian>>,
+    unit: &CompilationUnit,
+    entries: &mut EntriesCursor<EndianSlice<RunTimeEndian>>,
     ctx: &Context,
     compilation_unit_dirs: &[&str],
-    cu: &DebuggingInformationEntry<EndianSlice<LittleEndian>, usize>,
+    cu: &DebuggingInformationEntry<EndianSlice<RunTimeEndian>, usize>,
+    language: Language,
 ) -> Vec<Procedure<PM>> {
     // Iterate over entries
     // AZ: I feel like we should try to optimize this, by doing next_sibling when an DW_TAG_subprogram was hit
@@ -154,14 +175,13 @@ fn parse_compilation_unit_subprograms<PM: Default>(
                 _ => (),
             }
 
-            let NameInfo { linkage_name, .. } = fetch_function_names(unit, ctx, entry);
             let LocationInfo {
                 address: start_address,
-                size,
-            } = fetch_function_location_info(entry, &linkage_name);
+                ranges,
+            } = fetch_function_location_info(unit, entry);
 
-            // Skip functions with low_pc = 0
-            if start_address == 0 {
+            // Skip functions without machine code, e.g. those removed by the linker
+            if ranges.is_empty() {
                 continue;
             }
 
@@ -172,7 +192,8 @@ fn parse_compilation_unit_subprograms<PM: Default>(
                 cu,
                 entry,
                 start_address,
-                size,
+                ranges,
+                language,
             );
 
             nodes.push(prc)
@@ -183,39 +204,44 @@ fn parse_compilation_unit_subprograms<PM: Default>(
 
 /// Function that builds a `Procedure` for a DWARF DW_AT_subprogram entry(`entry`)
 fn get_procedure<PM: Default>(
-    unit: &UnitHeader<EndianSlice<LittleEndian>, usize>,
+    unit: &CompilationUnit,
     ctx: &Context,
     compilation_unit_dirs: &[&str],
-    cu: &DebuggingInformationEntry<EndianSlice<LittleEndian>, usize>,
-    entry: &DebuggingInformationEntry<EndianSlice<LittleEndian>, usize>,
+    cu: &DebuggingInformationEntry<EndianSlice<RunTimeEndian>, usize>,
+    entry: &DebuggingInformationEntry<EndianSlice<RunTimeEndian>, usize>,
     start_address: u64,
-    size: u64,
+    address_ranges: Vec<Range<u64>>,
+    language: Language,
 ) -> Procedure<PM> {
     let NameInfo {
         name,
         linkage_name,
         linkage_name_demangled,
-    } = fetch_function_names(unit, ctx, entry);
-    let MachineCode {
-        address: code_address,
-        raw_instr: proc_instr_raw,
-    } = fetch_procedure_machine_code(ctx, start_address, size);
-
-    let lookup_result = ctx.loader.find_frames(start_address).unwrap();
-
-    let location = lookup_result
-        .filter_map(|frame| Ok(frame.location))
-        .last()
-        .unwrap()
-        .map(Location::from);
+    } = fetch_function_names(unit, entry, language);
+
+    // Instructions in all ranges belong to the procedure, so calls in split off cold code are attributed to it
+    let disassembly = address_ranges
+        .iter()
+        .filter_map(|range| fetch_procedure_machine_code(ctx, range))
+        .flat_map(|code| ctx.disassemble(code.raw_instr, code.address))
+        .collect();
+    let size = address_ranges.iter().map(|range| range.end - range.start).sum();
+
+    let location = ctx.find_frames(start_address).and_then(|frames| {
+        frames
+            .filter_map(|frame| Ok(frame.location))
+            .last()
+            .unwrap()
+            .map(Location::from)
+    });
 
     let defining_crate = {
         let defining_file = location.as_ref().map(|loc| &loc.file[..]);
         crate_utils::get_crate_details(
-            code_address,
+            start_address,
             defining_file,
+            unit,
             cu,
-            ctx,
             compilation_unit_dirs,
         )
     };
@@ -227,80 +253,82 @@ fn get_procedure<PM: Default>(
         defining_crate,
         start_address,
         size,
+        address_ranges,
+        language: Some(language),
         location,
         attributes: PM::default(),
-        disassembly: ctx.capstone
-            .disasm_all(proc_instr_raw, start_address)
-            .expect("Failed to disassemble procedure"),
+        disassembly,
     }
 }
 
-/// Returns the machine code for a procedure at `start_address`.
-fn fetch_procedure_machine_code<'a>(
-    ctx: &'a Context,
-    start_address: u64,
-    size: u64,
-) -> MachineCode<'a> {
-    let code_section = ctx.elf
-        .sections()
-        .find(|x| x.name() == Some(".text"))
-        .expect("No '.text' section found");
-    let code_address = code_section.address();
-    let code_data = code_section.data();
-    let proc_sec_offset = start_address - code_address;
-    let proc_sec_end = (proc_sec_offset + size) as usize;
-    let proc_instr_raw = &code_data[(proc_sec_offset as usize)..proc_sec_end];
-    MachineCode {
-        address: code_address,
-        raw_instr: proc_instr_raw,
-    }
+/// Returns the machine code in `range` of a procedure, which may be in any executable section.
+/// Returns `None` if `range` is not located in an executable section.
+fn fetch_procedure_machine_code<'a>(ctx: &'a Context, range: &Range<u64>) -> Option<MachineCode<'a>> {
+    address_references::code_in_range(ctx, range).map(|raw_instr| MachineCode {
+        address: range.start,
+        raw_instr,
+    })
 }
 
+/// Returns the entry point and the address ranges of the procedure in `entry`.
+///
+/// The ranges are described either by `DW_AT_low_pc` and `DW_AT_high_pc` (an address, or the size since DWARF 4), or
+/// by `DW_AT_ranges` for procedures that are split by the optimizer. Ranges of code that was removed by the linker,
+/// which start at 0 (or at `wasm::TOMBSTONE_ADDRESS` in WebAssembly modules), are skipped. The entry point is `DW_AT_low_pc` or `DW_AT_entry_pc` if present, and the start of
+/// the first range otherwise.
 fn fetch_function_location_info(
-    entry: &DebuggingInformationEntry<EndianSlice<LittleEndian>, usize>,
-    linkage_name: &str,
+    unit: &CompilationUnit,
+    entry: &DebuggingInformationEntry<EndianSlice<RunTimeEndian>, usize>,
 ) -> LocationInfo {
-    let start_address =
-        dwarf_utils::get_attr_addr_value(entry, DW_AT_low_pc).unwrap_or_else(|| {
-            panic!(
-                "No DW_AT_low_pc attribute found for function {}",
-                linkage_name
-            )
-        });
-    let size = dwarf_utils::get_attr_u64_value(entry, DW_AT_high_pc).unwrap_or_else(|| {
-        panic!(
-            "No DW_AT_high_pc attribute found for function {}",
-            linkage_name
-        )
-    });
-    LocationInfo {
-        address: start_address,
-        size,
-    }
+    let mut ranges = unit
+        .dwarf
+        .die_ranges(&unit.unit, entry)
+        .and_then(|ranges| ranges.collect::<Vec<_>>())
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|range| range.begin != 0 && range.begin != wasm::TOMBSTONE_ADDRESS && range.begin < range.end)
+        .map(|range| range.begin..range.end)
+        .collect::<Vec<_>>();
+
+    let address = [DW_AT_low_pc, DW_AT_entry_pc]
+        .iter()
+        .filter_map(|attr| dwarf_utils::get_attr_addr_value(entry, *attr, unit.dwarf, &unit.unit))
+        .find(|address| ranges.iter().any(|range| range.contains(address)))
+        .or_else(|| ranges.first().map(|range| range.start))
+        .unwrap_or(0);
+
+    // Move the range containing the entry point to the front
+    ranges.sort_by_key(|range| !range.contains(&address));
+
+    LocationInfo { address, ranges }
 }
 
 /// Function returning the names iof the procedure references in `entry`.
+/// The linkage names of C++ procedures are demangled with the C++ mangling scheme.
 fn fetch_function_names(
-    unit: &UnitHeader<EndianSlice<LittleEndian>, usize>,
-    ctx: &Context,
-    entry: &DebuggingInformationEntry<EndianSlice<LittleEndian>>,
+    unit: &CompilationUnit,
+    entry: &DebuggingInformationEntry<EndianSlice<RunTimeEndian>>,
+    language: Language,
 ) -> NameInfo {
     // Fetch function details
     let name = dwarf_utils::get_attr_str_with_origin_traversal(
-        unit,
+        unit.dwarf,
+        &unit.unit,
         entry,
         gimli::DW_AT_name,
-        &unit.abbreviations(&ctx.dwarf_abbrev).unwrap(),
-        &ctx.dwarf_strings,
     );
-    let linkage_name = dwarf_utils::get_attr_str_with_origin_traversal(
-        unit,
+    // Procedures with an unmangled symbol (`#[no_mangle]`) have no linkage name, as their symbol is their name
+    let linkage_name = dwarf_utils::find_attr_str_with_origin_traversal(
+        unit.dwarf,
+        &unit.unit,
         entry,
         gimli::DW_AT_linkage_name,
-        &unit.abbreviations(&ctx.dwarf_abbrev).unwrap(),
-        &ctx.dwarf_strings,
-    );
-    let linkage_name_demangled = dwarf_utils::demangle_symbol(&linkage_name);
+    ).unwrap_or_else(|| name.clone());
+    let linkage_name_demangled = match language {
+        Language::Cpp => addr2line::demangle(&linkage_name, gimli::DW_LANG_C_plus_plus)
+            .unwrap_or_else(|| linkage_name.clone()),
+        _ => dwarf_utils::demangle_symbol(&linkage_name),
+    };
     NameInfo {
         name,
         linkage_name,
@@ -313,25 +341,25 @@ fn fetch_function_names(
 ///
 /// The returned list is guaranteed not to contain duplicates, and is sorted descending lexicographically.
 /// That enables easy determination of the crate name in `get_crate_details`.
-fn get_compilation_unit_directories<'a>(ctx: &'a Context) -> Vec<&'a str> {
+fn get_compilation_unit_directories<'a>(ctx: &Context<'a>) -> Vec<&'a str> {
     let mut compilation_unit_dirs = vec![];
 
     // Iterate over all compilation units to get a list of all compilation directories.
-    ctx.dwarf_info.units().iterator().for_each(|unit_header| {
-        let unit_header = unit_header.unwrap();
-        let abbrevs = unit_header.abbreviations(&ctx.dwarf_abbrev).unwrap();
-        let mut entries = unit_header.entries(&abbrevs);
+    // The compilation directory of a split unit is taken from its skeleton unit.
+    dwarf_utils::get_compilation_units(ctx).iter().for_each(|unit| {
+        let mut entries = unit.unit.entries();
 
         let (_, entry) = entries
             .next_dfs()
             .expect("First compilation unit could not be selected")
             .unwrap();
 
-        // Retrieve the name of the compilation unit
-        if entry.tag() == DW_TAG_compile_unit {
-            let cu_name =
-                dwarf_utils::get_attr_string_buf(entry, DW_AT_comp_dir, &ctx.dwarf_strings);
-            compilation_unit_dirs.push(cu_name.unwrap().to_string().unwrap());
+        // Retrieve the name of the compilation unit. Units without a compilation directory are skipped, of which the
+        // crate is unknown, unless their files are located in a known layout (see `crate_utils::get_crate_from_file`)
+        if entry.tag() == DW_TAG_compile_unit || entry.tag() == DW_TAG_skeleton_unit {
+            if let Some(comp_dir) = unit.unit.comp_dir.and_then(|comp_dir| comp_dir.to_string().ok()) {
+                compilation_unit_dirs.push(comp_dir);
+            }
         }
     });
 
@@ -348,47 +376,61 @@ fn get_compilation_unit_directories<'a>(ctx: &'a Context) -> Vec<&'a str> {
 fn get_procedures_for_compilation_unit<PMetadata: Default>(
     ctx: &Context,
     compilation_unit_dirs: &[&str],
-    unit_header: UnitHeader<EndianSlice<LittleEndian>, usize>,
+    unit: &CompilationUnit,
 ) -> Vec<Procedure<PMetadata>> {
     // Find entries in cu
-    let abbrevs = unit_header.abbreviations(&ctx.dwarf_abbrev).unwrap();
-    let mut entries = unit_header.entries(&abbrevs);
+    let mut entries = unit.unit.entries();
     entries
         .next_dfs()
         .expect("First compilation unit could not be selected");
     // Let cursor select first unit
     // Return found
     let res: Vec<Procedure<PMetadata>> =
-        iterate_compilation_unit(unit_header, &mut entries, ctx, compilation_unit_dirs);
+        iterate_compilation_unit(unit, &mut entries, ctx, compilation_unit_dirs);
     res
 }
 
 /// Returns a call graph builder based on the passed `CallGraphOptions`.
-/// If the binary was compiled for x86 or x86_64, a builder will be returned.
-/// In other cases, the procedure will return an error.
+/// The invocation finders work on the architecture independent disassembly of the procedures, so the same builder
+/// is used for all supported instruction sets. The call graph of relocatable objects (`Context::object_files`) is built
+/// from their relocations, and the indirect calls of WebAssembly modules are resolved through their tables.
 pub fn get_call_graph_builder<
     PMetadata: Default + 'static,
     IMetadata: Default + 'static,
     FMetadata: Default + 'static,
 >(
     ctx: &Context,
+    options: &CallGraphOptions,
 ) -> Result<Box<dyn CallGraphBuilder<PMetadata, IMetadata, FMetadata>>> {
-    match ctx.elf.machine() {
-        object::Machine::X86 | object::Machine::X86_64 => Ok(Box::new(X86CallGraphBuilder {
+    // The calls are found in the disassembly of the procedures, which is not available for big endian machine code
+    if ctx.disassembler.is_none() {
+        bail!(ErrorKind::NotSupported("big endian machine code".to_string()));
+    }
+
+    // The calls in relocatable objects are found from their relocations instead of from their machine code
+    if !ctx.object_files.is_empty() {
+        return Ok(Box::new(relocatable_calls::RelocatableCallGraphBuilder));
+    }
+
+    // WebAssembly modules do not load function addresses into registers, but call functions through tables
+    if ctx.wasm_module.is_some() {
+        return Ok(Box::new(DefaultCallGraphBuilder {
             invocation_finders: vec![
                 Box::new(static_calls::StaticCallInvocationFinder),
-                Box::new(lea_dynamic_calls::LEABasedDynamicInvocationFinder {
-                    phantom: PhantomData,
-                }),
+                Box::new(wasm_calls::WasmInvocationFinder),
             ],
-        })),
-        object::Machine::Arm | object::Machine::Arm64 => {
-            Err(ErrorKind::NotSupported("the ARM Instruction Set".to_string()).into())
-        }
-        object::Machine::Other => {
-            Err(ErrorKind::NotSupported("an unknown instruction set".to_string()).into())
-        }
+        }));
     }
+
+    Ok(Box::new(DefaultCallGraphBuilder {
+        invocation_finders: vec![
+            Box::new(static_calls::StaticCallInvocationFinder),
+            Box::new(address_load_dynamic_calls::AddressLoadDynamicInvocationFinder {
+                precise_dynamic_dispatch: options.precise_dynamic_dispatch,
+                phantom: PhantomData,
+            }),
+        ],
+    }))
 }
 
 #[cfg(test)]
@@ -397,30 +439,33 @@ mod test {
 
     use gimli::*;
 
-    use Context;
+    use crate::Context;
     use crate::InvocationType;
 
-    use super::lea_dynamic_calls::LEABasedDynamicInvocationFinder;
+    use super::address_load_dynamic_calls::AddressLoadDynamicInvocationFinder;
     use super::static_calls::StaticCallInvocationFinder;
 
     use object::Object;
     use crate::parse::get_parser;
+    use crate::DebugBuffers;
 
-    // Two workspaces could have similar names, for example: test_subjects and test_subjects_lib
+    // Two crates could have similar names, for example: the lib_calls member of test_subjects and test_subjects_lib
     // The program should still distinguish between these two
     #[test]
     fn test_similar_workspaces() {
+        let path = test_common::get_test_subject_path("lib_calls", &test_common::TestSubjectType::Debug);
         let file_content = &test_common::load_test_binary_as_bytes(
             "lib_calls",
             &test_common::TestSubjectType::Debug,
         ).unwrap();
 
-        let parser = get_parser().expect("Could not parse test file contents");
-        let context = parser.parse(file_content);
-        let call_graph: CallGraph<(), (), ()> = X86CallGraphBuilder {
+        let buffers = DebugBuffers::default();
+
+        let context = get_parser().parse(file_content, &path, &buffers).expect("Could not parse test file contents");
+        let call_graph: CallGraph<(), (), ()> = DefaultCallGraphBuilder {
             invocation_finders: vec![
                 Box::new(StaticCallInvocationFinder),
-                Box::new(LEABasedDynamicInvocationFinder::default()),
+                Box::new(AddressLoadDynamicInvocationFinder::default()),
             ],
         }.build_call_graph(&context);
 
@@ -428,7 +473,7 @@ mod test {
             .graph
             .node_indices()
             .map(|node_index| call_graph.graph[node_index].borrow())
-            .filter(|node| node.defining_crate.name == "test_subjects")
+            .filter(|node| node.defining_crate.name == "lib_calls")
             .count();
 
         let test_subject_lib_count = call_graph
@@ -445,17 +490,19 @@ mod test {
     /// Test if a call graph can be created from the example binary
     #[test]
     pub fn test_call_graph_creation() {
+        let path = test_common::get_test_subject_path("threads", &test_common::TestSubjectType::DebugStableRustc);
         let file_content = &test_common::load_test_binary_as_bytes(
             "threads",
             &test_common::TestSubjectType::DebugStableRustc,
         ).unwrap();
 
-        let parser = get_parser().expect("Could not parse test file contents");
-        let context = parser.parse(file_content);
-        let call_graph: CallGraph<(), (), ()> = X86CallGraphBuilder {
+        let buffers = DebugBuffers::default();
+
+        let context = get_parser().parse(file_content, &path, &buffers).expect("Could not parse test file contents");
+        let call_graph: CallGraph<(), (), ()> = DefaultCallGraphBuilder {
             invocation_finders: vec![
                 Box::new(StaticCallInvocationFinder),
-                Box::new(LEABasedDynamicInvocationFinder::default()),
+                Box::new(AddressLoadDynamicInvocationFinder::default()),
             ],
         }.build_call_graph(&context);
 
@@ -520,18 +567,20 @@ mod test {
     /// Test if location of invocation addresses and file names can be traced back to source
     #[test]
     pub fn test_get_source_location() {
+        let path = test_common::get_test_subject_path("hello_world", &test_common::TestSubjectType::Debug);
         let file_content = &test_common::load_test_binary_as_bytes(
             "hello_world",
             &test_common::TestSubjectType::Debug,
         ).unwrap();
 
-        let parser = get_parser().expect("Could not parse test file contents");
-        let context = parser.parse(file_content);
+        let buffers = DebugBuffers::default();
 
-        let call_graph: CallGraph<(), (), ()> = X86CallGraphBuilder {
+        let context = get_parser().parse(file_content, &path, &buffers).expect("Could not parse test file contents");
+
+        let call_graph: CallGraph<(), (), ()> = DefaultCallGraphBuilder {
             invocation_finders: vec![
                 Box::new(StaticCallInvocationFinder),
-                Box::new(LEABasedDynamicInvocationFinder::default()),
+                Box::new(AddressLoadDynamicInvocationFinder::default()),
             ],
         }.build_call_graph(&context);
 
@@ -553,4 +602,106 @@ mod test {
         );
         assert_eq!(main_procedure.location.as_ref().unwrap().line, 9);
     }
+
+    /// Test if the address ranges of the procedures in an optimized build cover their disassembly, and start with the
+    /// range containing the entry point
+    #[test]
+    pub fn test_procedure_address_ranges() {
+        let path = test_common::get_test_subject_path("unwrap", &test_common::TestSubjectType::Release);
+        let file_content =
+            &test_common::load_test_binary_as_bytes("unwrap", &test_common::TestSubjectType::Release).unwrap();
+
+        let buffers = DebugBuffers::default();
+
+        let context = get_parser().parse(file_content, &path, &buffers).expect("Could not parse test file contents");
+        let call_graph: CallGraph<(), (), ()> = DefaultCallGraphBuilder {
+            invocation_finders: vec![Box::new(StaticCallInvocationFinder)],
+        }.build_call_graph(&context);
+
+        call_graph.graph.node_indices().for_each(|index| {
+            let procedure = call_graph.graph[index].borrow();
+            // External procedures have no machine code in the binary
+            if procedure.defining_crate.name == crate_utils::EXTERNAL_CRATE_NAME {
+                return;
+            }
+            let ranges = &procedure.address_ranges;
+
+            assert!(ranges[0].contains(&procedure.start_address), "{:?}", procedure);
+            assert_eq!(procedure.size, ranges.iter().map(|range| range.end - range.start).sum::<u64>());
+            assert!(procedure
+                .disassembly
+                .iter()
+                .all(|insn| ranges.iter().any(|range| range.contains(&insn.address))));
+        });
+    }
+
+    /// Test that big endian binaries are parsed, and that building their call graph is rejected, as their machine code
+    /// cannot be disassembled
+    #[test]
+    pub fn test_big_endian_call_graph_not_supported() {
+        if test_common::skip_unbuilt(&test_common::TestSubjectType::DebugArmebv7r) {
+            return;
+        }
+        let path = test_common::get_test_subject_path("firmware", &test_common::TestSubjectType::DebugArmebv7r);
+        let file_content =
+            &test_common::load_test_binary_as_bytes("firmware", &test_common::TestSubjectType::DebugArmebv7r).unwrap();
+
+        let buffers = DebugBuffers::default();
+
+        let context = get_parser().parse(file_content, &path, &buffers).expect("Could not parse test file contents");
+        assert!(context.disassembler.is_none());
+
+        let result = get_call_graph_builder::<(), (), ()>(&context, &CallGraphOptions::default());
+        assert!(matches!(result, Err(Error(ErrorKind::NotSupported(_), _))));
+    }
+
+    /// Test that a compilation unit without `DW_AT_producer` and `DW_AT_comp_dir` attributes (e.g. written by another
+    /// tool than rustc) is skipped in the compilation directories, and that its crate is unknown
+    #[test]
+    pub fn test_unit_without_producer_and_comp_dir() {
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 4,
+            address_size: 8,
+        };
+        let mut dwarf_unit = gimli::write::DwarfUnit::new(encoding);
+        let root = dwarf_unit.unit.root();
+        dwarf_unit
+            .unit
+            .get_mut(root)
+            .set(DW_AT_language, gimli::write::AttributeValue::Language(DW_LANG_Rust));
+        let mut sections = gimli::write::Sections::new(gimli::write::EndianVec::new(RunTimeEndian::Little));
+        dwarf_unit.write(&mut sections).unwrap();
+
+        let dwarf = Dwarf::load(|section| -> std::result::Result<_, ()> {
+            let data = sections.get(section).map(|data| data.slice()).unwrap_or(&[]);
+            Ok(EndianSlice::new(data, RunTimeEndian::Little))
+        }).unwrap();
+        let context = Context {
+            elf: object::ElfFile::parse(wasm::EMPTY_ELF).unwrap(),
+            loader: None,
+            dwarf,
+            split_dwarf: HashMap::new(),
+            disassembler: None,
+            object_files: vec![],
+            wasm_module: None,
+        };
+
+        let compilation_unit_dirs = get_compilation_unit_directories(&context);
+        assert!(compilation_unit_dirs.is_empty());
+
+        let units = dwarf_utils::get_compilation_units(&context);
+        let mut entries = units[0].unit.entries();
+        let (_, cu) = entries.next_dfs().unwrap().unwrap();
+        let defining_crate = crate_utils::get_crate_details(
+            0,
+            Some("/home/user/project/src/main.rs"),
+            &units[0],
+            cu,
+            &compilation_unit_dirs,
+        );
+
+        assert_eq!(defining_crate.name, "<unknown compilation directory>");
+        assert_eq!(defining_crate.version, None);
+    }
 }
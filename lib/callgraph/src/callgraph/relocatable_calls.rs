@@ -0,0 +1,381 @@
+// (C) COPYRIGHT 2018 TECHNOLUTION BV, GOUDA NL
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Call graphs of static libraries, rlibs and relocatable object files (see `relocatable`).
+//!
+//! The procedures are read from the symbol tables of the relocatable objects, at the addresses their sections are
+//! placed at. The displacements of the calls in relocatable machine code are 0, so the calls are found from the
+//! relocations of the code instead. A relocation of a call or jump instruction targets a procedure in the same object,
+//! a procedure that is defined by another member of the archive, or an undefined symbol (e.g. a function of the
+//! standard library), which is added to the call graph as an external procedure named after the symbol (see
+//! `static_calls::get_external_procedure`). Other instructions with a relocation to a procedure (e.g. `lea`) reference
+//! that procedure.
+//!
+//! Only the relocations used for code on x86_64 are resolved: `R_X86_64_PC32` and `R_X86_64_PLT32`, which are relative
+//! to the relocated field, and the `R_X86_64_GOTPCREL` variants, which load the address of the symbol from the GOT
+//! (rustc calls procedures in other crates through the GOT).
+
+use crate::callgraph::linkage_tables::CallTarget;
+use crate::callgraph::static_calls::get_external_procedure;
+use crate::callgraph::CallGraphBuilder;
+use crate::crate_utils;
+use crate::disassembly::InstructionKind;
+use crate::dwarf_utils;
+use crate::relocatable;
+use crate::relocatable::ObjectFile;
+use crate::symbol_path::SymbolPath;
+use crate::CallGraph;
+use crate::Context;
+use crate::Invocation;
+use crate::InvocationType;
+use crate::Procedure;
+
+use byteorder::{ByteOrder, LittleEndian};
+
+use petgraph::stable_graph::StableGraph;
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::rc::Rc;
+
+/// Section type of relocation sections with addends (`SHT_RELA`).
+const SHT_RELA: u32 = 4;
+
+/// Size of a relocation with addend (`Elf64_Rela`).
+const RELA_SIZE: usize = 24;
+
+/// ELF symbol types of procedures (`STT_FUNC`) and sections (`STT_SECTION`).
+const STT_FUNC: u8 = 2;
+const STT_SECTION: u8 = 3;
+
+/// ELF symbol bindings of symbols that are visible outside the object (`STB_GLOBAL` and `STB_WEAK`).
+const STB_GLOBAL: u8 = 1;
+const STB_WEAK: u8 = 2;
+
+/// x86_64 relocation types of PC relative references.
+const R_X86_64_PC32: u32 = 2;
+const R_X86_64_PLT32: u32 = 4;
+
+/// x86_64 relocation types of references through the GOT.
+const R_X86_64_GOTPCREL: u32 = 9;
+const R_X86_64_GOTPCRELX: u32 = 41;
+const R_X86_64_REX_GOTPCRELX: u32 = 42;
+
+/// Size of the address range reserved for an external procedure.
+const EXTERNAL_PROCEDURE_SIZE: u64 = 8;
+
+/// A relocation of the machine code of a relocatable object.
+struct Relocation {
+    /// Address of the relocated field
+    address: u64,
+    /// Index of the symbol in the symbol table of the object
+    symbol: usize,
+    kind: u32,
+    addend: i64,
+}
+
+/// Implementation of `CallGraphBuilder` that builds the call graph of the relocatable objects in `Context::object_files`.
+pub struct RelocatableCallGraphBuilder;
+
+impl<PMetadata: Default, IMetadata: Default, FMetadata: Default> CallGraphBuilder<PMetadata, IMetadata, FMetadata>
+for RelocatableCallGraphBuilder
+{
+    fn build_call_graph(&self, ctx: &Context) -> CallGraph<PMetadata, IMetadata, FMetadata> {
+        let mut graph = StableGraph::new();
+        let mut proc_index = HashMap::new();
+        let mut call_index = HashMap::new();
+
+        get_procedures::<PMetadata>(ctx).into_iter().for_each(|procedure| {
+            let address = procedure.start_address;
+            let idx = graph.add_node(Rc::new(RefCell::new(procedure)));
+
+            graph[idx].borrow().disassembly.iter()
+                .filter(|insn| insn.is_branch())
+                .for_each(|insn| {
+                    call_index.insert(insn.address, idx); });
+
+            proc_index.insert(address, idx);
+        });
+
+        // Procedures by start address, to find the procedure containing a relocation
+        let procedures = proc_index
+            .iter()
+            .map(|(address, idx)| (*address, *idx))
+            .collect::<BTreeMap<_, _>>();
+        let global_procedures = get_global_procedures(&ctx.object_files);
+        let external_slots = get_external_slots(&ctx.object_files, &global_procedures);
+        let mut external_procedures = HashMap::new();
+
+        ctx.object_files
+            .iter()
+            .flat_map(|object_file| {
+                get_relocations(object_file)
+                    .into_iter()
+                    .filter_map(|relocation| {
+                        let target = resolve_symbol(object_file, relocation.symbol, &global_procedures, &external_slots)?;
+                        Some((relocation, target))
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .for_each(|(relocation, target)| {
+                let origin = match procedures.range(..=relocation.address).next_back() {
+                    Some((_, origin)) => *origin,
+                    None => return,
+                };
+                let instruction = graph[origin]
+                    .borrow()
+                    .disassembly
+                    .iter()
+                    .find(|insn| insn.address <= relocation.address && relocation.address < insn.address + insn.size)
+                    .cloned();
+                let instruction = match instruction {
+                    Some(instruction) => instruction,
+                    None => return,
+                };
+
+                let destination = match target {
+                    CallTarget::Address(symbol_address) => {
+                        let target_address = match relocation.kind {
+                            // The field is relative to the end of the instruction, while the addend is relative to the
+                            // field itself
+                            R_X86_64_PC32 | R_X86_64_PLT32 => symbol_address
+                                .wrapping_add(relocation.addend as u64)
+                                .wrapping_add(instruction.address + instruction.size - relocation.address),
+                            _ => symbol_address,
+                        };
+                        match proc_index.get(&target_address) {
+                            Some(destination) => *destination,
+                            None => return,
+                        }
+                    }
+                    // Undefined symbols may be data as well, so only called symbols are known to be procedures
+                    CallTarget::External { name, slot } if instruction.is_branch() => {
+                        get_external_procedure(&mut graph, &mut proc_index, &mut external_procedures, &name, slot)
+                    }
+                    CallTarget::External { .. } => return,
+                };
+
+                let invocation_type = match instruction.kind {
+                    InstructionKind::Call { .. } | InstructionKind::PointerCall { .. } => InvocationType::Direct,
+                    InstructionKind::Jump { .. } | InstructionKind::PointerJump { .. } => InvocationType::Jump,
+                    _ => InvocationType::ProcedureReference,
+                };
+
+                graph.add_edge(
+                    origin,
+                    destination,
+                    Rc::new(RefCell::new(Invocation {
+                        invocation_type,
+                        trait_implementation: None,
+                        instruction_address: instruction.address,
+                        frames: vec![],
+                        attributes: IMetadata::default(),
+                    })));
+            });
+
+        CallGraph {
+            graph,
+            proc_index,
+            call_index,
+        }
+    }
+}
+
+/// Returns the procedures in the symbol tables of the relocatable objects in `ctx`. Symbols with the same address
+/// result in a single procedure, named after the first symbol.
+fn get_procedures<PM: Default>(ctx: &Context) -> Vec<Procedure<PM>> {
+    let mut addresses = HashSet::new();
+
+    ctx.object_files
+        .iter()
+        .flat_map(|object_file| {
+            let elf = object_file.elf.elf();
+            elf.syms
+                .iter()
+                .filter(|symbol| symbol.st_type() == STT_FUNC && symbol.st_size > 0)
+                .filter_map(|symbol| {
+                    let address = object_file.address(symbol.st_shndx, symbol.st_value)?;
+                    let header = &elf.section_headers[symbol.st_shndx];
+                    let start = (header.sh_offset + symbol.st_value) as usize;
+                    let raw_instr = object_file.content.get(start..start + symbol.st_size as usize)?;
+
+                    let linkage_name = elf.strtab[symbol.st_name].to_string();
+                    let linkage_name_demangled = dwarf_utils::demangle_symbol(&linkage_name);
+
+                    Some(Procedure {
+                        name: SymbolPath::parse(&linkage_name_demangled).item,
+                        defining_crate: crate_utils::get_crate_from_symbol(&linkage_name_demangled, None),
+                        linkage_name,
+                        linkage_name_demangled,
+                        start_address: address,
+                        size: symbol.st_size,
+                        address_ranges: vec![address..address + symbol.st_size],
+                        language: None,
+                        location: None,
+                        attributes: PM::default(),
+                        disassembly: ctx.disassemble(raw_instr, address),
+                    })
+                })
+                .collect::<Vec<_>>()
+        })
+        .filter(|procedure| addresses.insert(procedure.start_address))
+        .collect()
+}
+
+/// Returns the addresses of the procedures that are visible to the other relocatable objects in `object_files`, by
+/// symbol name.
+fn get_global_procedures(object_files: &[ObjectFile]) -> HashMap<String, u64> {
+    object_files
+        .iter()
+        .flat_map(|object_file| {
+            let elf = object_file.elf.elf();
+            elf.syms
+                .iter()
+                .filter(|symbol| symbol.st_type() == STT_FUNC)
+                .filter(|symbol| symbol.st_bind() == STB_GLOBAL || symbol.st_bind() == STB_WEAK)
+                .filter_map(|symbol| {
+                    let address = object_file.address(symbol.st_shndx, symbol.st_value)?;
+                    Some((elf.strtab[symbol.st_name].to_string(), address))
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Returns the start addresses of the external procedures, by symbol name: one for every undefined symbol that is not
+/// defined by another relocatable object in `object_files`. The external procedures are placed after the sections of
+/// the objects.
+fn get_external_slots(object_files: &[ObjectFile], global_procedures: &HashMap<String, u64>) -> HashMap<String, u64> {
+    let mut slots = HashMap::new();
+    let base = relocatable::end_address(object_files);
+
+    object_files
+        .iter()
+        .flat_map(|object_file| {
+            let elf = object_file.elf.elf();
+            elf.syms
+                .iter()
+                .filter(|symbol| symbol.st_shndx == 0 && symbol.st_name != 0)
+                .map(|symbol| elf.strtab[symbol.st_name].to_string())
+                .collect::<Vec<_>>()
+        })
+        .filter(|name| !global_procedures.contains_key(name))
+        .for_each(|name| {
+            let slot = base + slots.len() as u64 * EXTERNAL_PROCEDURE_SIZE;
+            slots.entry(name).or_insert(slot);
+        });
+    slots
+}
+
+/// Returns the relocations of the executable sections of `object_file`, which are resolved by this module.
+fn get_relocations(object_file: &ObjectFile) -> Vec<Relocation> {
+    object_file
+        .elf
+        .elf()
+        .section_headers
+        .iter()
+        .filter(|header| header.sh_type == SHT_RELA)
+        .filter_map(|header| {
+            let section_address = object_file.address(header.sh_info as usize, 0)?;
+            let start = header.sh_offset as usize;
+            let data = object_file.content.get(start..start + header.sh_size as usize)?;
+            Some((section_address, data))
+        })
+        .flat_map(|(section_address, data)| {
+            data.chunks(RELA_SIZE)
+                .filter(|entry| entry.len() == RELA_SIZE)
+                .map(move |entry| {
+                    let info = LittleEndian::read_u64(&entry[8..16]);
+                    Relocation {
+                        address: section_address + LittleEndian::read_u64(&entry[0..8]),
+                        symbol: (info >> 32) as usize,
+                        kind: info as u32,
+                        addend: LittleEndian::read_i64(&entry[16..24]),
+                    }
+                })
+        })
+        .filter(|relocation| {
+            [R_X86_64_PC32, R_X86_64_PLT32, R_X86_64_GOTPCREL, R_X86_64_GOTPCRELX, R_X86_64_REX_GOTPCRELX]
+                .contains(&relocation.kind)
+        })
+        .collect()
+}
+
+/// Returns the target of the symbol at `index` in the symbol table of `object_file`: the address of a section or of a
+/// procedure in one of the relocatable objects, or the external procedure of an undefined symbol. Returns `None` for
+/// symbols in sections that do not contain code.
+fn resolve_symbol(
+    object_file: &ObjectFile,
+    index: usize,
+    global_procedures: &HashMap<String, u64>,
+    external_slots: &HashMap<String, u64>,
+) -> Option<CallTarget> {
+    let elf = object_file.elf.elf();
+    let symbol = elf.syms.get(index)?;
+    if symbol.st_type() == STT_SECTION {
+        return object_file.address(symbol.st_shndx, 0).map(CallTarget::Address);
+    }
+    if symbol.st_shndx != 0 {
+        return object_file.address(symbol.st_shndx, symbol.st_value).map(CallTarget::Address);
+    }
+
+    let name = &elf.strtab[symbol.st_name];
+    match global_procedures.get(name) {
+        Some(address) => Some(CallTarget::Address(*address)),
+        None => external_slots.get(name).map(|slot| CallTarget::External {
+            name: name.to_string(),
+            slot: *slot,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::parse::get_parser;
+    use crate::DebugBuffers;
+
+    use test_common::TestSubjectType;
+
+    /// Verify the calls in an rlib are found from its relocations, both between procedures of the crate and to
+    /// procedures of the standard library, which are not defined in the rlib
+    #[test]
+    fn test_rlib_relocations() {
+        let path = test_common::get_test_subject_path("libffi_lib.rlib", &TestSubjectType::Debug);
+        let file_content = &test_common::load_test_binary_as_bytes("libffi_lib.rlib", &TestSubjectType::Debug).unwrap();
+
+        let buffers = DebugBuffers::default();
+
+        let context = get_parser().parse(file_content, &path, &buffers).expect("Could not parse test file contents");
+        assert!(!context.object_files.is_empty());
+
+        let call_graph: CallGraph<(), (), ()> = RelocatableCallGraphBuilder.build_call_graph(&context);
+        let find = |name: &str| {
+            call_graph
+                .graph
+                .node_indices()
+                .find(|idx| call_graph.graph[*idx].borrow().linkage_name_demangled == name)
+                .unwrap_or_else(|| panic!("No procedure {} in call graph", name))
+        };
+
+        let checked_divide = find("checked_divide");
+        let check_divisor = find("ffi_lib::check_divisor");
+        assert_eq!(call_graph.graph[check_divisor].borrow().defining_crate.name, "ffi_lib");
+        assert!(call_graph.graph.neighbors(checked_divide).any(|callee| callee == check_divisor));
+
+        // The panic handler of the standard library is an external procedure
+        assert!(call_graph.graph.neighbors(check_divisor).any(|callee| {
+            let callee = call_graph.graph[callee].borrow();
+            callee.defining_crate.name == crate_utils::EXTERNAL_CRATE_NAME
+                && callee.linkage_name_demangled.starts_with("core::panicking::")
+        }));
+    }
+}
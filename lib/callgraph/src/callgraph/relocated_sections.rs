@@ -0,0 +1,205 @@
+// (C) COPYRIGHT 2018 TECHNOLUTION BV, GOUDA NL
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Relocated views of the data sections that are read by the dynamic invocation finders.
+//!
+//! In position independent executables (the default of rustc), the pointers in `.data.rel.ro` (e.g. the function
+//! pointers in vtables) are zero in the file. They are filled in by the dynamic loader, using the `R_<arch>_RELATIVE`
+//! entries in `.rela.dyn` (or `.rel.dyn`). The analysis applies these relocations itself, as if the binary is loaded
+//! at address 0, so the addresses in the relocated sections match the addresses in the debug information.
+//!
+//! The views are created once per call graph (see `DefaultCallGraphBuilder`), and shared by the invocation finders.
+//! Only the sections to which relocations are applied are copied; the others borrow the contents of the binary.
+
+use crate::callgraph::address_references::word_size;
+use crate::Context;
+
+use gimli::Endianity;
+use gimli::RunTimeEndian;
+
+use object::Object;
+use object::ObjectSection;
+
+use std::borrow::Cow;
+
+/// Names of the sections of which a relocated view is created.
+const RELOCATED_SECTIONS: &[&str] = &[".data.rel.ro", ".data.rel.ro.local", ".rodata", ".got", ".got.plt"];
+
+/// Names of the sections that can contain vtables.
+/// vtables are located in the '.data.rel.ro' (or '.data.rel.ro.local') section for position independent binaries,
+/// and in the '.rodata' section for statically linked (e.g. embedded) binaries.
+pub const VTABLE_SECTIONS: &[&str] = &[".data.rel.ro", ".data.rel.ro.local", ".rodata"];
+
+/// Relative relocation type (`R_<arch>_RELATIVE`) per ELF machine identifier (`e_machine`).
+const RELATIVE_RELOCATION_TYPES: &[(u16, u32)] = &[
+    // EM_386: R_386_RELATIVE
+    (3, 8),
+    // EM_ARM: R_ARM_RELATIVE
+    (40, 23),
+    // EM_X86_64: R_X86_64_RELATIVE
+    (62, 8),
+    // EM_AARCH64: R_AARCH64_RELATIVE
+    (183, 1027),
+    // EM_RISCV: R_RISCV_RELATIVE
+    (243, 3),
+];
+
+/// Data of a section, with the relative relocations applied. The data is only copied if relocations are applied to it.
+#[derive(Debug)]
+pub struct RelocatedSection<'a> {
+    pub name: String,
+    pub address: u64,
+    pub data: Cow<'a, [u8]>,
+}
+
+impl<'a> RelocatedSection<'a> {
+    /// Returns whether the `size` bytes at `address` are located in this section.
+    pub fn contains(&self, address: u64, size: u64) -> bool {
+        self.address <= address && address + size <= self.address + self.data.len() as u64
+    }
+}
+
+/// Returns the relocated views of the data sections of the binary in `ctx`.
+/// Sections that are not present in the binary are omitted.
+pub fn get_relocated_sections<'a>(ctx: &Context<'a>) -> Vec<RelocatedSection<'a>> {
+    let mut sections = RELOCATED_SECTIONS
+        .iter()
+        .filter_map(|name| {
+            let section = ctx.elf.sections().find(|section| section.name() == Some(name))?;
+            Some(RelocatedSection {
+                name: name.to_string(),
+                address: section.address(),
+                data: Cow::Borrowed(section.data()),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let e_machine = ctx.elf.elf().header.e_machine;
+    let relative_type = match RELATIVE_RELOCATION_TYPES.iter().find(|(machine, _)| *machine == e_machine) {
+        Some((_, relative_type)) => *relative_type,
+        None => return sections,
+    };
+
+    let word_size = word_size(ctx);
+    let endian = ctx.endian();
+    let elf = ctx.elf.elf();
+    elf.dynrelas
+        .iter()
+        .chain(elf.dynrels.iter())
+        .filter(|reloc| reloc.r_type == relative_type)
+        .for_each(|reloc| {
+            let address = reloc.r_offset as u64;
+            let section = match sections.iter_mut().find(|section| section.contains(address, word_size as u64)) {
+                Some(section) => section,
+                None => return,
+            };
+
+            let offset = (address - section.address) as usize;
+            let word = &mut section.data.to_mut()[offset..offset + word_size];
+            // REL relocations (x86, ARM) store the addend in the relocated word itself
+            let value = if reloc.is_rela {
+                reloc.r_addend as i64 as u64
+            } else {
+                read_word(word, endian)
+            };
+            write_word(word, value, endian);
+        });
+
+    sections
+}
+
+/// Reads a word in the byte order `endian` from `data`, which is either 4 or 8 bytes long.
+fn read_word(data: &[u8], endian: RunTimeEndian) -> u64 {
+    match data.len() {
+        8 => endian.read_u64(data),
+        _ => u64::from(endian.read_u32(data)),
+    }
+}
+
+/// Writes `value` in the byte order `endian` to `data`, which is either 4 or 8 bytes long.
+fn write_word(data: &mut [u8], value: u64, endian: RunTimeEndian) {
+    match data.len() {
+        8 => endian.write_u64(data, value),
+        _ => endian.write_u32(data, value as u32),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::parse::get_parser;
+    use crate::DebugBuffers;
+
+    use test_common::TestSubjectType;
+
+    /// Verify the vtables in the position independent build of the example binary point to procedures after relocation
+    #[test]
+    pub fn test_relocated_vtables() {
+        let path = test_common::get_test_subject_path("trait_invocation", &TestSubjectType::Debug);
+        let file_content = &test_common::load_test_binary_as_bytes("trait_invocation", &TestSubjectType::Debug).unwrap();
+        let buffers = DebugBuffers::default();
+        let context = get_parser().parse(file_content, &path, &buffers).expect("Could not parse test file contents");
+
+        let drop_in_place_addresses = context.elf
+            .symbols()
+            .filter(|symbol| symbol.name().map(|name| name.contains("drop_in_place")).unwrap_or(false))
+            .map(|symbol| symbol.address())
+            .collect::<Vec<_>>();
+
+        let sections = get_relocated_sections(&context);
+        let data_rel_ro = sections
+            .iter()
+            .find(|section| section.name == ".data.rel.ro")
+            .expect("No .data.rel.ro section in example binary");
+
+        // The first entry of a vtable points to the `drop_in_place` of the type
+        assert!(data_rel_ro
+            .data
+            .chunks(8)
+            .filter(|word| word.len() == 8)
+            .any(|word| drop_in_place_addresses.contains(&read_word(word, context.endian()))));
+    }
+
+    /// Verify only the sections to which relocations are applied are copied, and the others borrow the binary
+    #[test]
+    pub fn test_unrelocated_sections_borrowed() {
+        let path = test_common::get_test_subject_path("trait_invocation", &TestSubjectType::Debug);
+        let file_content = &test_common::load_test_binary_as_bytes("trait_invocation", &TestSubjectType::Debug).unwrap();
+        let buffers = DebugBuffers::default();
+        let context = get_parser().parse(file_content, &path, &buffers).expect("Could not parse test file contents");
+
+        let sections = get_relocated_sections(&context);
+        let section = |name| {
+            sections
+                .iter()
+                .find(|section| section.name == name)
+                .unwrap_or_else(|| panic!("No {} section in example binary", name))
+        };
+
+        // The vtables in `.data.rel.ro` are relocated, the string literals in `.rodata` are not
+        assert!(matches!(section(".data.rel.ro").data, Cow::Owned(_)));
+        assert!(matches!(section(".rodata").data, Cow::Borrowed(_)));
+        assert_eq!(&section(".rodata").data[..], context.elf.section_data_by_name(".rodata").unwrap());
+    }
+
+    /// Verify the relocated words are read and written in the byte order of the binary
+    #[test]
+    pub fn test_word_byte_order() {
+        let mut data = [0u8; 4];
+        write_word(&mut data, 0x1234_5678, RunTimeEndian::Big);
+        assert_eq!(data, [0x12, 0x34, 0x56, 0x78]);
+        assert_eq!(read_word(&data, RunTimeEndian::Big), 0x1234_5678);
+        assert_eq!(read_word(&data, RunTimeEndian::Little), 0x7856_3412);
+
+        let mut data = [0u8; 8];
+        write_word(&mut data, 0x1234_5678_9abc_def0, RunTimeEndian::Little);
+        assert_eq!(data, [0xf0, 0xde, 0xbc, 0x9a, 0x78, 0x56, 0x34, 0x12]);
+        assert_eq!(read_word(&data, RunTimeEndian::Little), 0x1234_5678_9abc_def0);
+    }
+}
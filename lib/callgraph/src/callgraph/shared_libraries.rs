@@ -0,0 +1,358 @@
+// (C) COPYRIGHT 2018 TECHNOLUTION BV, GOUDA NL
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Joining the call graphs of the shared objects a binary is dynamically linked against.
+//!
+//! Binaries built with `-C prefer-dynamic`, or linked against Rust `dylib` crates, call the procedures in those shared
+//! objects through the GOT or PLT. These procedures are added to the call graph as external procedures (see
+//! `static_calls::get_external_procedure`). The shared objects in the `DT_NEEDED` entries of the binary, and of those
+//! shared objects in turn, are searched in `CallGraphOptions::library_paths`. A call graph is built for every shared
+//! object that is found, and merged into the call graph of the binary. Finally, each external procedure is replaced by
+//! the procedure that is exported under its name, like the dynamic loader would do.
+//!
+//! Every shared object is placed at its own base address in the merged call graph, as if it is loaded by the dynamic
+//! loader, so the addresses of its procedures and instructions do not collide with those of other objects. Only the
+//! addresses identifying procedures, instructions and invocations are rebased; the operands of the instructions are
+//! not. Their procedures are recognized by their address (see `is_library_address`), so the operands are not resolved
+//! with the `Context` of the binary.
+
+use crate::callgraph::address_references::code_address;
+use crate::callgraph::get_call_graph_builder;
+use crate::crate_utils;
+use crate::debug_file;
+use crate::debug_file::DebugFile;
+use crate::errors::*;
+use crate::parse;
+use crate::CallGraph;
+use crate::CallGraphOptions;
+use crate::Context;
+use crate::DebugBuffers;
+
+use petgraph::visit::EdgeRef;
+use petgraph::Direction;
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::collections::VecDeque;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Size of the address range reserved for every shared object in the merged call graph.
+const LIBRARY_ADDRESS_SPACE: u64 = 1 << 40;
+
+/// ELF symbol type of procedures (`STT_FUNC`).
+const STT_FUNC: u8 = 2;
+
+/// Builds the call graphs of the shared objects the binary in `ctx` depends on, merges them into `call_graph`, and
+/// replaces the external procedures by the procedures exported by the shared objects.
+///
+/// Shared objects that are not found in `options.library_paths` are skipped. Shared objects that could not be read or
+/// parsed (e.g. because they contain code of an unsupported instruction set) are skipped with a warning. Calls to the
+/// procedures of skipped shared objects remain calls to external procedures.
+pub fn link_shared_libraries<
+    PMetadata: Default + 'static,
+    IMetadata: Default + 'static,
+    FMetadata: Default + 'static,
+>(
+    call_graph: &mut CallGraph<PMetadata, IMetadata, FMetadata>,
+    ctx: &Context,
+    options: &CallGraphOptions,
+) {
+    // Procedures exported by the binary and the shared objects, by symbol name.
+    // The first definition of a symbol is used, following the lookup order of the dynamic loader.
+    let mut exports = get_exported_procedures(ctx, 0);
+    let mut pending = get_needed_libraries(ctx);
+    let mut visited = HashSet::new();
+
+    while let Some(library) = pending.pop_front() {
+        if !visited.insert(library.clone()) {
+            continue;
+        }
+        let path = match find_library(&library, &options.library_paths) {
+            Some(path) => path,
+            None => continue,
+        };
+
+        let file_content = match crate::map_file(&path) {
+            Ok(file_content) => file_content,
+            Err(error) => {
+                warn_skipped(&path, &error);
+                continue;
+            }
+        };
+        let debug_file_path = debug_file::find_debug_file(&file_content, &path, &options.debug_file_directories);
+        let debug_file_content = match debug_file_path.as_deref().map(crate::map_file) {
+            Some(Ok(debug_file_content)) => Some(debug_file_content),
+            Some(Err(error)) => {
+                warn_skipped(&path, &error);
+                continue;
+            }
+            None => None,
+        };
+        let debug_file = debug_file_path
+            .as_ref()
+            .zip(debug_file_content.as_ref())
+            .map(|(path, content)| DebugFile { path, content });
+        let buffers = DebugBuffers::default();
+        let parser = parse::get_parser();
+        let parse_result = match debug_file {
+            Some(debug_file) => parser.parse_with_debug_file(&file_content, &path, debug_file, &buffers),
+            None => parser.parse(&file_content, &path, &buffers),
+        };
+        let library_ctx = match parse_result {
+            Ok(library_ctx) => library_ctx,
+            Err(error) => {
+                warn_skipped(&path, &error);
+                continue;
+            }
+        };
+        let library_call_graph = match get_call_graph_builder(&library_ctx, options) {
+            Ok(call_graph_builder) => call_graph_builder.build_call_graph(&library_ctx),
+            Err(error) => {
+                warn_skipped(&path, &error);
+                continue;
+            }
+        };
+
+        let base = visited.len() as u64 * LIBRARY_ADDRESS_SPACE;
+        merge_call_graph(call_graph, library_call_graph, base);
+
+        get_exported_procedures(&library_ctx, base)
+            .into_iter()
+            .for_each(|(name, address)| {
+                exports.entry(name).or_insert(address);
+            });
+        pending.extend(get_needed_libraries(&library_ctx));
+    }
+
+    join_external_procedures(call_graph, &exports);
+}
+
+/// Prints a warning that the shared object at `path` is skipped because of `error`.
+fn warn_skipped(path: &Path, error: &Error) {
+    eprintln!("Warning: skipping shared object {}: {}", path.display(), error);
+}
+
+/// Returns whether `address` lies in the address range of a shared object that is merged into the call graph by
+/// `link_shared_libraries`. The addresses of the binary itself are expected to be below `LIBRARY_ADDRESS_SPACE`.
+pub fn is_library_address(address: u64) -> bool {
+    address >= LIBRARY_ADDRESS_SPACE
+}
+
+/// Returns the names of the shared objects in the `DT_NEEDED` entries of the binary in `ctx`.
+fn get_needed_libraries(ctx: &Context) -> VecDeque<String> {
+    ctx.elf
+        .elf()
+        .libraries
+        .iter()
+        .map(|library| library.to_string())
+        .collect()
+}
+
+/// Returns the path of the shared object `library` in the first directory of `library_paths` containing it.
+fn find_library(library: &str, library_paths: &[PathBuf]) -> Option<PathBuf> {
+    library_paths
+        .iter()
+        .map(|directory| directory.join(library))
+        .find(|path| path.is_file())
+}
+
+/// Returns the addresses of the procedures exported by the binary in `ctx`, rebased to `base`, by symbol name.
+fn get_exported_procedures(ctx: &Context, base: u64) -> HashMap<String, u64> {
+    let elf = ctx.elf.elf();
+    elf.dynsyms
+        .iter()
+        .filter(|symbol| symbol.st_shndx != 0 && symbol.st_type() == STT_FUNC)
+        .map(|symbol| {
+            let name = elf.dynstrtab[symbol.st_name].to_string();
+            (name, code_address(ctx, symbol.st_value) + base)
+        })
+        .collect()
+}
+
+/// Adds all procedures and invocations of `other` to `call_graph`, with their addresses rebased to `base`.
+fn merge_call_graph<PMetadata, IMetadata, FMetadata>(
+    call_graph: &mut CallGraph<PMetadata, IMetadata, FMetadata>,
+    other: CallGraph<PMetadata, IMetadata, FMetadata>,
+    base: u64,
+) {
+    let mut indices = HashMap::new();
+
+    other.graph.node_indices().for_each(|idx| {
+        let procedure = other.graph[idx].clone();
+        {
+            let mut procedure = procedure.borrow_mut();
+            procedure.start_address += base;
+            procedure.address_ranges = procedure
+                .address_ranges
+                .iter()
+                .map(|range| range.start + base..range.end + base)
+                .collect();
+            procedure
+                .disassembly
+                .iter_mut()
+                .for_each(|insn| insn.address += base);
+        }
+        indices.insert(idx, call_graph.graph.add_node(procedure));
+    });
+
+    other.graph.edge_indices().for_each(|edge| {
+        let (source, target) = other
+            .graph
+            .edge_endpoints(edge)
+            .expect("Edge without endpoints in call graph");
+        let invocation = other.graph[edge].clone();
+        invocation.borrow_mut().instruction_address += base;
+        call_graph
+            .graph
+            .add_edge(indices[&source], indices[&target], invocation);
+    });
+
+    other.proc_index.iter().for_each(|(address, idx)| {
+        call_graph.proc_index.insert(address + base, indices[idx]);
+    });
+    other.call_index.iter().for_each(|(address, idx)| {
+        call_graph.call_index.insert(address + base, indices[idx]);
+    });
+}
+
+/// Replaces every external procedure in `call_graph` that is found in `exports` by the exported procedure: the
+/// invocations of the external procedure are moved to the exported procedure, and the external procedure is removed.
+fn join_external_procedures<PMetadata, IMetadata, FMetadata>(
+    call_graph: &mut CallGraph<PMetadata, IMetadata, FMetadata>,
+    exports: &HashMap<String, u64>,
+) {
+    let external_procedures = call_graph
+        .graph
+        .node_indices()
+        .filter(|idx| call_graph.graph[*idx].borrow().defining_crate.name == crate_utils::EXTERNAL_CRATE_NAME)
+        .collect::<Vec<_>>();
+
+    external_procedures.into_iter().for_each(|external| {
+        let (name, start_address) = {
+            let procedure = call_graph.graph[external].borrow();
+            (procedure.linkage_name.clone(), procedure.start_address)
+        };
+        let definition = match exports
+            .get(&name)
+            .and_then(|address| call_graph.proc_index.get(address))
+        {
+            Some(definition) => *definition,
+            None => return,
+        };
+
+        let invocations = call_graph
+            .graph
+            .edges_directed(external, Direction::Incoming)
+            .map(|edge| (edge.source(), edge.weight().clone()))
+            .collect::<Vec<_>>();
+        invocations.into_iter().for_each(|(source, invocation)| {
+            call_graph.graph.add_edge(source, definition, invocation);
+        });
+
+        call_graph.graph.remove_node(external);
+        call_graph.proc_index.remove(&start_address);
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::Crate;
+    use crate::Invocation;
+    use crate::InvocationType;
+    use crate::Procedure;
+
+    use petgraph::stable_graph::StableGraph;
+
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    /// Helper function to create a procedure at `start_address` in `crate_name`
+    fn procedure(name: &str, crate_name: &str, start_address: u64) -> Rc<RefCell<Procedure<()>>> {
+        Rc::new(RefCell::new(Procedure {
+            name: name.to_string(),
+            linkage_name: name.to_string(),
+            linkage_name_demangled: name.to_string(),
+            defining_crate: Crate {
+                name: crate_name.to_string(),
+                version: None,
+            },
+            start_address,
+            size: 0,
+            address_ranges: vec![],
+            language: None,
+            location: None,
+            attributes: (),
+            disassembly: vec![],
+        }))
+    }
+
+    /// Helper function to create a call at `instruction_address`
+    fn invocation(instruction_address: u64) -> Rc<RefCell<Invocation<(), ()>>> {
+        Rc::new(RefCell::new(Invocation {
+            invocation_type: InvocationType::Direct,
+            trait_implementation: None,
+            frames: vec![],
+            instruction_address,
+            attributes: (),
+        }))
+    }
+
+    /// Verify a call to an external procedure is joined with the procedure exported by a merged shared object
+    #[test]
+    fn test_join_shared_library() {
+        // Binary: main calls `foo` through the GOT slot at 0x3000
+        let mut graph = StableGraph::new();
+        let main = graph.add_node(procedure("main", "binary", 0x1000));
+        let external = graph.add_node(procedure("foo", crate_utils::EXTERNAL_CRATE_NAME, 0x3000));
+        graph.add_edge(main, external, invocation(0x1004));
+        let mut call_graph: CallGraph<(), (), ()> = CallGraph {
+            graph,
+            proc_index: vec![(0x1000, main), (0x3000, external)].into_iter().collect(),
+            call_index: vec![(0x1004, main)].into_iter().collect(),
+        };
+
+        // Shared object: `foo` calls `bar`
+        let mut graph = StableGraph::new();
+        let foo = graph.add_node(procedure("foo", "library", 0x1000));
+        let bar = graph.add_node(procedure("bar", "library", 0x1100));
+        graph.add_edge(foo, bar, invocation(0x1008));
+        let library_call_graph = CallGraph {
+            graph,
+            proc_index: vec![(0x1000, foo), (0x1100, bar)].into_iter().collect(),
+            call_index: vec![(0x1008, foo)].into_iter().collect(),
+        };
+
+        merge_call_graph(&mut call_graph, library_call_graph, LIBRARY_ADDRESS_SPACE);
+        let exports = vec![("foo".to_string(), LIBRARY_ADDRESS_SPACE + 0x1000)].into_iter().collect();
+        join_external_procedures(&mut call_graph, &exports);
+
+        // The external procedure is replaced by `foo` of the shared object
+        assert_eq!(call_graph.graph.node_count(), 3);
+        assert!(call_graph.get_procedure(0x3000).is_none());
+
+        let foo = call_graph.proc_index[&(LIBRARY_ADDRESS_SPACE + 0x1000)];
+        let bar = call_graph.proc_index[&(LIBRARY_ADDRESS_SPACE + 0x1100)];
+        assert!(call_graph.graph.find_edge(main, foo).is_some());
+
+        // The invocations in the shared object are rebased
+        let foo_bar = call_graph.graph.find_edge(foo, bar).expect("No edge from foo to bar");
+        assert_eq!(
+            call_graph.graph[foo_bar].borrow().instruction_address,
+            LIBRARY_ADDRESS_SPACE + 0x1008
+        );
+        assert_eq!(call_graph.call_index[&(LIBRARY_ADDRESS_SPACE + 0x1008)], foo);
+
+        // Only the procedures of the shared object are recognized as such
+        assert!(!call_graph.graph[main].borrow().is_in_shared_library());
+        assert!(call_graph.graph[foo].borrow().is_in_shared_library());
+        assert!(call_graph.graph[bar].borrow().is_in_shared_library());
+    }
+}
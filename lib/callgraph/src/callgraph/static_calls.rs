@@ -7,43 +7,41 @@
 // except according to those terms.
 
 use crate::Context;
+use crate::Crate;
 use crate::InlineFunctionFrame;
 use crate::Invocation;
 use crate::InvocationType;
 use crate::Procedure;
 
+use crate::callgraph::address_references::code_address;
+use crate::callgraph::linkage_tables::CallTarget;
+use crate::callgraph::linkage_tables::LinkageTables;
 use crate::callgraph::FallibleIterator;
 use crate::callgraph::InvocationFinder;
 
+use crate::crate_utils;
+use crate::dwarf_utils;
+
+use crate::disassembly::Instruction;
+use crate::disassembly::InstructionKind;
+
 use std::cell::RefCell;
 use std::collections::hash_map::RandomState;
 use std::collections::HashMap;
 use std::rc::Rc;
-use capstone::*;
 
 use crate::callgraph::CompilationInfo;
 use petgraph::stable_graph::{NodeIndex, StableGraph};
 use petgraph::Directed;
 
-/// Enum indicating how a call was encoded in assembly.
-#[derive(Debug)]
-enum CallType {
-    StaticCall { insn: Insn, target: i64 },
-    StaticJump { insn: Insn, target: i64 },
-}
-
-impl CallType {
-    fn invocation_type(&self) -> InvocationType {
-        match self {
-            CallType::StaticJump { .. } => InvocationType::Jump,
-            CallType::StaticCall { .. } => InvocationType::Direct,
-        }
-    }
-}
-
+/// Implementation of `InvocationFinder` that adds edges for calls (`InvocationType::Direct`) and jumps
+/// (`InvocationType::Jump`) with a static target.
+///
+/// Calls and jumps through a GOT slot or a PLT stub are resolved to the procedure in the GOT slot. Procedures that are
+/// defined in a shared object are added to the call graph as external procedures, see `get_external_procedure`.
 pub struct StaticCallInvocationFinder;
 
-impl<P, I: Default, F: Default> InvocationFinder<P, I, F> for StaticCallInvocationFinder {
+impl<P: Default, I: Default, F: Default> InvocationFinder<P, I, F> for StaticCallInvocationFinder {
     fn find_invocations(
         &self,
         graph: &mut StableGraph<
@@ -57,134 +55,206 @@ impl<P, I: Default, F: Default> InvocationFinder<P, I, F> for StaticCallInvocati
         ctx: &Context,
         compilation_info: CompilationInfo,
     ) {
+        let linkage_tables = LinkageTables::new(ctx, compilation_info.relocated_sections);
+
+        let mut external_procedures = get_external_procedures(graph);
+
         let node_indices: Vec<_> = graph.node_indices().collect();
         node_indices.iter()
-            .map(|idx| parse_calls(&graph[*idx].borrow().disassembly, &ctx.capstone))
+            .map(|idx| parse_calls(&graph[*idx].borrow().disassembly, &linkage_tables))
             .fold(vec!(), |mut vec, mut elem| {
                 vec.append(&mut elem);
                 vec
             })
-            .iter()
-            // Map `CallType` to (origin, destination, invocation type, locations) quadruple.
-            .filter_map(|call_type| {
-                let invocation_type = call_type.invocation_type();
-                match call_type {
-                    CallType::StaticCall { insn, target } | CallType::StaticJump { insn, target } => {
-                        let origin = &call_index[&insn.address()];
-                        let destination = proc_index.get(&(*target as u64))?;
-
-                        let lookup_result = ctx.loader.find_frames(insn.address()).unwrap();
-
-                        let frames = lookup_result
-                            .map(|frame| Ok(InlineFunctionFrame::convert_frame(&frame, compilation_info.compilation_dirs, compilation_info.rust_version.to_owned())))
-                            .collect()
-                            .unwrap();
-
-                        Some((origin, destination, invocation_type, frames, insn.address()))
-                    }
-                }
-            })
+            .into_iter()
             // Add edges for all invocations
-            .for_each(|(origin, destination, invocation_type, frames, instruction_address)| {
+            .for_each(|(address, target, invocation_type)| {
+                let origin = call_index[&address];
+                let destination = match target {
+                    CallTarget::Address(target) => match proc_index.get(&code_address(ctx, target)) {
+                        Some(destination) => *destination,
+                        None => return,
+                    },
+                    CallTarget::External { name, slot } => {
+                        get_external_procedure(graph, proc_index, &mut external_procedures, &name, slot)
+                    }
+                };
+
                 // use addrs2line with call_instr_addr
+                let frames = match ctx.find_frames(address) {
+                    Some(frames) => frames
+                        .map(|frame| Ok(InlineFunctionFrame::convert_frame(&frame, compilation_info.compilation_dirs, compilation_info.rust_version.to_owned())))
+                        .collect()
+                        .unwrap_or_default(),
+                    None => vec![],
+                };
+
                 graph.add_edge(
-                    *origin,
-                    *destination,
+                    origin,
+                    destination,
                     Rc::new(RefCell::new(Invocation {
                         invocation_type,
-                        instruction_address,
+                        trait_implementation: None,
+                        instruction_address: address,
                         frames,
                         attributes: I::default()
                     })));
             });
     }
 }
-/// Transform collection of instructions to their corresponding call types.
-fn parse_calls(instructions: &Instructions, cs: &Capstone) -> Vec<CallType> {
-    instructions
-        .iter()
-        .flat_map(|insn| get_call_type(insn, cs))
-        .collect()
-}
 
-/// Extract the call type for a specific instruction.
-fn get_call_type(insn: Insn, cs: &Capstone) -> Option<CallType> {
-    let jump_group_id = InsnGroupId(1); // Group 1 is jump
-    let call_group_id = InsnGroupId(2); // Group 2 is call
-
-    let is_call = cs.insn_group_ids(&insn)
-        .unwrap()
-        .any(|id| id == call_group_id);
-    let is_jump = cs.insn_group_ids(&insn)
-        .unwrap()
-        .any(|id| id == jump_group_id);
-
-    // Return no call type if instruction is neither a jump nor a call
-    if !is_call && !is_jump {
-        return None;
+/// Returns the node of the external procedure with symbol `name`, which is called through the GOT slot at `slot`.
+///
+/// External procedures have no debug information or machine code in the binary. They are added to the call graph the
+/// first time they are called, with crate `crate_utils::EXTERNAL_CRATE_NAME` and the address of the GOT slot as
+/// start address. Undefined symbols of relocatable objects are added the same way (see `relocatable_calls`).
+/// `external_procedures` holds the nodes of the external procedures by symbol name (see `get_external_procedures`),
+/// and is updated with the added node.
+pub(crate) fn get_external_procedure<P: Default, I, F>(
+    graph: &mut StableGraph<Rc<RefCell<Procedure<P>>>, Rc<RefCell<Invocation<I, F>>>>,
+    proc_index: &mut HashMap<u64, NodeIndex<u32>>,
+    external_procedures: &mut HashMap<String, NodeIndex<u32>>,
+    name: &str,
+    slot: u64,
+) -> NodeIndex<u32> {
+    if let Some(existing) = external_procedures.get(name) {
+        return *existing;
     }
 
-    let operands = {
-        let details = cs.insn_detail(&insn).unwrap();
-        let operands = details.arch_detail().operands();
-
-        if operands.len() != 1 {
-            panic!(
-                "Call instruction should have one operand, but {} were found. Instruction: {:?}. Details: {:?}",
-                operands.len(),
-                insn,
-                details
-            );
-        }
-        operands
-    };
+    let linkage_name_demangled = dwarf_utils::demangle_symbol(name);
+    let idx = graph.add_node(Rc::new(RefCell::new(Procedure {
+        name: linkage_name_demangled.clone(),
+        linkage_name: name.to_string(),
+        linkage_name_demangled,
+        defining_crate: Crate {
+            name: crate_utils::EXTERNAL_CRATE_NAME.to_string(),
+            version: None,
+        },
+        start_address: slot,
+        size: 0,
+        address_ranges: vec![],
+        language: None,
+        location: None,
+        attributes: P::default(),
+        disassembly: vec![],
+    })));
+    proc_index.insert(slot, idx);
+    external_procedures.insert(name.to_string(), idx);
+    idx
+}
+
+/// Returns the nodes of the external procedures in `graph` by symbol name, to be passed to `get_external_procedure`.
+pub(crate) fn get_external_procedures<P, I, F>(
+    graph: &StableGraph<Rc<RefCell<Procedure<P>>>, Rc<RefCell<Invocation<I, F>>>>,
+) -> HashMap<String, NodeIndex<u32>> {
+    graph
+        .node_indices()
+        .filter_map(|idx| {
+            let procedure = graph[idx].borrow();
+            if procedure.defining_crate.name == crate_utils::EXTERNAL_CRATE_NAME {
+                Some((procedure.linkage_name.clone(), idx))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
 
-    let operand = match operands[0] {
-        arch::ArchOperand::X86Operand(ref operand) => operand,
-        _ => panic!("Only x86 instructions are supported"),
+/// Transform collection of instructions to (instruction address, target, invocation type) triples.
+/// Calls and jumps through the GOT, or to a PLT stub, are resolved using `linkage_tables`.
+fn parse_calls(instructions: &[Instruction], linkage_tables: &LinkageTables) -> Vec<(u64, CallTarget, InvocationType)> {
+    let got_target = |slot: u64| linkage_tables.got_target(slot).cloned();
+    // Calls to a PLT stub are calls through the GOT slot of the stub
+    let static_target = |target: u64| match linkage_tables.plt_slot(target) {
+        Some(slot) => got_target(slot),
+        None => Some(CallTarget::Address(target)),
     };
 
-    match operand.op_type {
-        arch::x86::X86OperandType::Imm(target) if is_call => {
-            Some(CallType::StaticCall { insn, target })
-        }
-        arch::x86::X86OperandType::Imm(target) => Some(CallType::StaticJump { insn, target }),
-        arch::x86::X86OperandType::Reg(RegId(_)) | arch::x86::X86OperandType::Mem(_) => None,
-        arch::x86::X86OperandType::Fp(_) => {
-            panic!("Invalid call operand: call to a float is not a valid call")
-        }
-        arch::x86::X86OperandType::Invalid => panic!("Invalid call operand"),
-    }
+    instructions
+        .iter()
+        .filter_map(|insn| match insn.kind {
+            InstructionKind::Call { target: Some(target) } => {
+                Some((insn.address, static_target(target)?, InvocationType::Direct))
+            }
+            InstructionKind::PointerCall { address } => Some((insn.address, got_target(address)?, InvocationType::Direct)),
+            InstructionKind::Jump { target: Some(target) } => {
+                Some((insn.address, static_target(target)?, InvocationType::Jump))
+            }
+            InstructionKind::PointerJump { address } => Some((insn.address, got_target(address)?, InvocationType::Jump)),
+            _ => None,
+        })
+        .collect()
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
 
-    use capstone::arch::BuildsCapstone;
-    use capstone::prelude::*;
-    use capstone::Capstone;
+    use crate::disassembly::Register;
 
-    /// Verify non-calls do not create CallTypes
+    /// Verify only calls and jumps with a static target create call types
     #[test]
-    pub fn test_invalid_calls() {
-        let cs = Capstone::new()
-            .x86()
-            .mode(arch::x86::ArchMode::Mode64)
-            .syntax(arch::x86::ArchSyntax::Att)
-            .detail(true)
-            .build()
-            .unwrap();
-
-        let assembly = &[25, 50, 75, 100];
-
-        let disassembly = {
-            cs.disasm_all(assembly, 0)
-                .expect("Failed to disassemble test data")
+    pub fn test_parse_calls() {
+        let instruction = |address, kind| Instruction {
+            address,
+            size: 4,
+            mnemonic: String::new(),
+            kind,
+        };
+
+        let instructions = vec![
+            instruction(0x1000, InstructionKind::Call { target: Some(0x2000) }),
+            instruction(0x1004, InstructionKind::Call { target: None }),
+            instruction(0x1008, InstructionKind::Jump { target: Some(0x1000) }),
+            instruction(0x100c, InstructionKind::AddressLoad { register: Register::new("x0"), address: 0x2000 }),
+            instruction(0x1010, InstructionKind::Other { destination: None }),
+        ];
+
+        assert_eq!(
+            parse_calls(&instructions, &LinkageTables::default()),
+            vec![
+                (0x1000, CallTarget::Address(0x2000), InvocationType::Direct),
+                (0x1008, CallTarget::Address(0x1000), InvocationType::Jump),
+            ]
+        );
+    }
+
+    /// Verify calls through the GOT and calls to PLT stubs are resolved to the target of the GOT slot
+    #[test]
+    pub fn test_parse_got_calls() {
+        let instruction = |address, kind| Instruction {
+            address,
+            size: 4,
+            mnemonic: String::new(),
+            kind,
+        };
+
+        let write = CallTarget::External {
+            name: "write".to_string(),
+            slot: 0x3000,
         };
+        let mut linkage_tables = LinkageTables::default();
+        linkage_tables.got_slots.insert(0x3000, write.clone());
+        linkage_tables.got_slots.insert(0x3008, CallTarget::Address(0x2000));
+        linkage_tables.plt_stubs.insert(0x4010, 0x3000);
 
-        let call_types = parse_calls(&disassembly, &cs);
+        let instructions = vec![
+            instruction(0x1000, InstructionKind::PointerCall { address: 0x3000 }),
+            instruction(0x1004, InstructionKind::PointerCall { address: 0x3008 }),
+            instruction(0x1008, InstructionKind::Call { target: Some(0x4010) }),
+            instruction(0x100c, InstructionKind::PointerJump { address: 0x3000 }),
+            // Not a GOT slot
+            instruction(0x1010, InstructionKind::PointerCall { address: 0x5000 }),
+        ];
 
-        assert_eq!(call_types.len(), 0);
+        assert_eq!(
+            parse_calls(&instructions, &linkage_tables),
+            vec![
+                (0x1000, write.clone(), InvocationType::Direct),
+                (0x1004, CallTarget::Address(0x2000), InvocationType::Direct),
+                (0x1008, write.clone(), InvocationType::Direct),
+                (0x100c, write, InvocationType::Jump),
+            ]
+        );
     }
 }
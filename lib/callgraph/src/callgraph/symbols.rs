@@ -0,0 +1,230 @@
+// (C) COPYRIGHT 2018 TECHNOLUTION BV, GOUDA NL
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Procedures without debug information, which are read from the symbol table.
+//!
+//! Procedures that have no `DW_TAG_subprogram` in the debug information (hand-written assembly, C objects, crates
+//! built with `debug = 0` or `debug = "line-tables-only"`) are created from their symbols in `.symtab` and `.dynsym`
+//! (or from the `name` section of a WebAssembly module) instead, so calls to them are not lost. The bounds of such a
+//! procedure are read from the frame description entry (FDE) in `.eh_frame` that starts at its address, or from the
+//! size of its symbol if it has no FDE. Its crate is inferred from the demangled symbol name (see
+//! `crate_utils::get_crate_from_symbol`).
+
+use crate::callgraph::address_references::code_address;
+use crate::callgraph::address_references::code_in_range;
+use crate::crate_utils;
+use crate::dwarf_utils;
+use crate::symbol_path::SymbolPath;
+use crate::Context;
+use crate::Location;
+use crate::Procedure;
+
+use gimli::BaseAddresses;
+use gimli::CieOrFde;
+use gimli::EhFrame;
+use gimli::UnwindSection;
+
+use object::Object;
+use object::ObjectSection;
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::ops::Range;
+
+/// ELF symbol type of procedures (`STT_FUNC`).
+const STT_FUNC: u8 = 2;
+
+/// Returns the procedures in the symbol tables of the binary in `ctx` that are not located in any of the
+/// `covered_ranges`, which are the address ranges of the procedures read from the debug information.
+///
+/// Symbols with the same address (e.g. a symbol in both `.symtab` and `.dynsym`) result in a single procedure, named
+/// after the first symbol. Procedures of which the bounds are not known are skipped.
+pub fn get_symbol_procedures<PM: Default>(
+    ctx: &Context,
+    covered_ranges: &[Range<u64>],
+    rust_version: Option<String>,
+) -> Vec<Procedure<PM>> {
+    let covered = covered_ranges
+        .iter()
+        .map(|range| (range.start, range.end))
+        .collect::<BTreeMap<_, _>>();
+    let is_covered = |address: u64| {
+        covered
+            .range(..=address)
+            .next_back()
+            .map(|(_, end)| address < *end)
+            .unwrap_or(false)
+    };
+
+    let fde_sizes = get_fde_sizes(ctx);
+    let mut addresses = HashSet::new();
+    let mut procedures = vec![];
+
+    get_function_symbols(ctx)
+        .into_iter()
+        .filter(|(_, address, _)| *address != 0 && !is_covered(*address))
+        .for_each(|(linkage_name, address, symbol_size)| {
+            if !addresses.insert(address) {
+                return;
+            }
+            let size = fde_sizes.get(&address).cloned().unwrap_or(symbol_size);
+            if size == 0 {
+                return;
+            }
+            let range = address..address + size;
+            let raw_instr = match code_in_range(ctx, &range) {
+                Some(raw_instr) => raw_instr,
+                None => return,
+            };
+
+            let linkage_name_demangled = dwarf_utils::demangle_symbol(&linkage_name);
+            let name = SymbolPath::parse(&linkage_name_demangled).item;
+            let defining_crate = crate_utils::get_crate_from_symbol(&linkage_name_demangled, rust_version.clone());
+
+            // The line table may be present without debugging information entries (`debug = "line-tables-only"`)
+            let location = ctx
+                .loader
+                .as_ref()
+                .and_then(|loader| loader.find_location(address).ok().flatten())
+                .map(Location::from);
+
+            procedures.push(Procedure {
+                name,
+                linkage_name,
+                linkage_name_demangled,
+                defining_crate,
+                start_address: address,
+                size,
+                address_ranges: vec![range],
+                language: None,
+                location,
+                attributes: PM::default(),
+                disassembly: ctx.disassemble(raw_instr, address),
+            });
+        });
+
+    procedures
+}
+
+/// Returns the name, address and size of all defined procedure symbols in `.symtab`, followed by those in `.dynsym`.
+/// For a WebAssembly module, the defined functions that are named in its `name` section are returned instead.
+fn get_function_symbols(ctx: &Context) -> Vec<(String, u64, u64)> {
+    if let Some(wasm_module) = &ctx.wasm_module {
+        return wasm_module
+            .functions
+            .iter()
+            .filter(|function| !function.imported)
+            .filter_map(|function| Some((function.name.clone()?, function.address, function.size)))
+            .collect();
+    }
+
+    let elf = ctx.elf.elf();
+    let symtab = elf.syms.iter().map(|symbol| (symbol, &elf.strtab));
+    let dynsym = elf.dynsyms.iter().map(|symbol| (symbol, &elf.dynstrtab));
+
+    symtab
+        .chain(dynsym)
+        .filter(|(symbol, _)| symbol.st_shndx != 0 && symbol.st_type() == STT_FUNC)
+        .map(|(symbol, strtab)| {
+            (
+                strtab[symbol.st_name].to_string(),
+                code_address(ctx, symbol.st_value),
+                symbol.st_size,
+            )
+        })
+        .collect()
+}
+
+/// Returns the size of the code described by each FDE in `.eh_frame`, by the address of the code.
+fn get_fde_sizes(ctx: &Context) -> HashMap<u64, u64> {
+    let section_address = |name| {
+        ctx.elf
+            .sections()
+            .find(|section| section.name() == Some(name))
+            .map(|section| section.address())
+    };
+    let (eh_frame_address, eh_frame_data) = match ctx.elf
+        .sections()
+        .find(|section| section.name() == Some(".eh_frame"))
+    {
+        Some(section) => (section.address(), section.data()),
+        None => return HashMap::new(),
+    };
+
+    let eh_frame = EhFrame::new(eh_frame_data, ctx.endian());
+    let mut bases = BaseAddresses::default().set_eh_frame(eh_frame_address);
+    if let Some(text_address) = section_address(".text") {
+        bases = bases.set_text(text_address);
+    }
+    if let Some(got_address) = section_address(".got") {
+        bases = bases.set_got(got_address);
+    }
+
+    let mut entries = eh_frame.entries(&bases);
+    let mut sizes = HashMap::new();
+    // Stop at the first entry that could not be parsed, as the remaining entries cannot be located
+    while let Ok(Some(entry)) = entries.next() {
+        if let CieOrFde::Fde(partial) = entry {
+            if let Ok(fde) = partial.parse(|section, bases, offset| section.cie_from_offset(bases, offset)) {
+                sizes.insert(code_address(ctx, fde.initial_address()), fde.len());
+            }
+        }
+    }
+    sizes
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::callgraph::default_cg_builder::DefaultCallGraphBuilder;
+    use crate::callgraph::static_calls::StaticCallInvocationFinder;
+    use crate::callgraph::CallGraphBuilder;
+    use crate::parse::get_parser;
+    use crate::DebugBuffers;
+    use crate::CallGraph;
+
+    use test_common::TestSubjectType;
+
+    /// Verify the procedures of a binary without debug information are read from the symbol table, and calls between
+    /// them are found
+    #[test]
+    fn test_binary_without_debug_information() {
+        let path = test_common::get_test_subject_path("hello_world", &TestSubjectType::ReleaseNoDebugInfo);
+        let file_content =
+            &test_common::load_test_binary_as_bytes("hello_world", &TestSubjectType::ReleaseNoDebugInfo).unwrap();
+
+        let buffers = DebugBuffers::default();
+
+        let context = get_parser().parse(file_content, &path, &buffers).expect("Could not parse test file contents");
+        assert!(dwarf_utils::get_compilation_units(&context).is_empty());
+
+        let call_graph: CallGraph<(), (), ()> = DefaultCallGraphBuilder {
+            invocation_finders: vec![Box::new(StaticCallInvocationFinder)],
+        }.build_call_graph(&context);
+
+        let main = call_graph
+            .graph
+            .node_indices()
+            .find(|index| call_graph.graph[*index].borrow().linkage_name_demangled == "hello_world::main")
+            .expect("No main procedure in call graph");
+        {
+            let procedure = call_graph.graph[main].borrow();
+            assert_eq!(procedure.name, "main");
+            assert_eq!(procedure.defining_crate.name, "hello_world");
+            assert!(procedure.size > 0);
+        }
+
+        // `main` prints through the standard library
+        assert!(call_graph
+            .graph
+            .neighbors(main)
+            .any(|callee| call_graph.graph[callee].borrow().defining_crate.name == "stdlib"));
+    }
+}
@@ -0,0 +1,250 @@
+// (C) COPYRIGHT 2018 TECHNOLUTION BV, GOUDA NL
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! vtables described in the debug information of the binary.
+//!
+//! rustc emits a `DW_TAG_variable` for every vtable when compiling with full debug information, named like
+//! `<trait_invocation::Foo as trait_invocation::Baz>::{vtable}`. Its location is the address of the vtable, and its
+//! type is a structure type (`...::{vtable_type}`) with the size of the vtable. This is used instead of the
+//! `drop_in_place` heuristic in `address_references`, which is only applied to vtables without debug information
+//! (e.g. those of the precompiled standard library).
+
+use crate::callgraph::address_references::word_size;
+use crate::dwarf_utils;
+use crate::dwarf_utils::CompilationUnit;
+use crate::Context;
+use crate::DwarfReader;
+use crate::TraitImplementation;
+
+use gimli::AttributeValue;
+use gimli::DebugAddrIndex;
+use gimli::DebuggingInformationEntry;
+use gimli::EndianSlice;
+use gimli::Endianity;
+use gimli::Reader;
+
+use std::collections::HashMap;
+
+/// Suffix of the names of the vtable variables.
+const VTABLE_NAME_SUFFIX: &str = "::{vtable}";
+
+/// A vtable, as described in the debug information.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VTable {
+    pub address: u64,
+    /// Size of the vtable in bytes, including the destructor, size and align fields
+    pub size: u64,
+    pub implementation: TraitImplementation,
+}
+
+/// Returns all vtables described in the debug information of the binary in `ctx`, by address.
+pub fn get_vtables(ctx: &Context) -> HashMap<u64, VTable> {
+    let mut vtables = HashMap::new();
+
+    dwarf_utils::get_compilation_units(ctx).iter().for_each(|unit| {
+        let mut entries = unit.unit.entries();
+
+        // The rest of a unit of which the entries cannot be read is skipped
+        while let Ok(Some((_, entry))) = entries.next_dfs() {
+            if entry.tag() != gimli::DW_TAG_variable {
+                continue;
+            }
+            if let Some(vtable) = parse_vtable_variable(ctx, unit, entry) {
+                vtables.insert(vtable.address, vtable);
+            }
+        }
+    });
+
+    vtables
+}
+
+/// Returns the vtable described by the variable `entry`, or `None` if `entry` does not describe a vtable.
+fn parse_vtable_variable(
+    ctx: &Context,
+    unit: &CompilationUnit,
+    entry: &DebuggingInformationEntry<DwarfReader, usize>,
+) -> Option<VTable> {
+    let name = dwarf_utils::get_attr_string_value(entry, gimli::DW_AT_name, unit.dwarf, &unit.unit)?;
+    let implementation = parse_vtable_name(&name)?;
+
+    // The location of a static variable is a single `DW_OP_addr` operation, or `DW_OP_addrx` referring to the address
+    // table in DWARF 5
+    let location = dwarf_utils::get_attr_buf(entry, gimli::DW_AT_location)?;
+    let endian = location.endian();
+    let location = location.slice();
+    let word_size = word_size(ctx);
+    let address = match location.first() {
+        Some(&op) if op == gimli::DW_OP_addr.0 && location.len() == 1 + word_size => match word_size {
+            8 => endian.read_u64(&location[1..]),
+            _ => u64::from(endian.read_u32(&location[1..])),
+        },
+        Some(&op) if op == gimli::DW_OP_addrx.0 => {
+            let index = EndianSlice::new(&location[1..], endian).read_uleb128().ok()?;
+            unit.dwarf.address(&unit.unit, DebugAddrIndex(index as usize)).ok()?
+        }
+        _ => return None,
+    };
+
+    let size = match entry.attr_value(gimli::DW_AT_type) {
+        Ok(Some(AttributeValue::UnitRef(offset))) => {
+            let mut type_cursor = unit.unit.entries_at_offset(offset).ok()?;
+            let (_, vtable_type) = type_cursor.next_dfs().ok()??;
+            dwarf_utils::get_attr_u64_value(vtable_type, gimli::DW_AT_byte_size)?
+        }
+        _ => return None,
+    };
+
+    Some(VTable {
+        address,
+        size,
+        implementation,
+    })
+}
+
+/// Parses the name of a vtable variable (`<Type as Trait>::{vtable}`, or `<Type>::{vtable}` for vtables of types
+/// that are only coerced to auto traits).
+/// Returns `None` if `name` is not the name of a vtable.
+fn parse_vtable_name(name: &str) -> Option<TraitImplementation> {
+    let implementation = name
+        .strip_suffix(VTABLE_NAME_SUFFIX)?
+        .strip_prefix('<')?
+        .strip_suffix('>')?;
+
+    // Find the ` as ` separating the type from the trait, outside of any generic arguments of the type
+    let mut depth = 0;
+    let mut previous = ' ';
+    for (index, character) in implementation.char_indices() {
+        match character {
+            '<' => depth += 1,
+            // Skip the arrow in function pointer types
+            '>' if previous != '-' => depth -= 1,
+            ' ' if depth == 0 && implementation[index..].starts_with(" as ") => {
+                return Some(TraitImplementation {
+                    trait_name: Some(implementation[index + " as ".len()..].to_string()),
+                    type_name: implementation[..index].to_string(),
+                });
+            }
+            _ => (),
+        }
+        previous = character;
+    }
+
+    Some(TraitImplementation {
+        trait_name: None,
+        type_name: implementation.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::callgraph::address_references;
+    use crate::callgraph::relocated_sections;
+    use crate::parse::get_parser;
+    use crate::DebugBuffers;
+
+    use object::Object;
+
+    use test_common::TestSubjectType;
+
+    /// Verify the type and trait are parsed from vtable names
+    #[test]
+    pub fn test_parse_vtable_name() {
+        assert_eq!(
+            parse_vtable_name("<trait_invocation::Foo as trait_invocation::Baz>::{vtable}"),
+            Some(TraitImplementation {
+                trait_name: Some("trait_invocation::Baz".to_string()),
+                type_name: "trait_invocation::Foo".to_string(),
+            })
+        );
+        assert_eq!(
+            parse_vtable_name("<alloc::vec::Vec<<u8 as core::ops::Not>::Output> as core::fmt::Debug>::{vtable}"),
+            Some(TraitImplementation {
+                trait_name: Some("core::fmt::Debug".to_string()),
+                type_name: "alloc::vec::Vec<<u8 as core::ops::Not>::Output>".to_string(),
+            })
+        );
+        assert_eq!(
+            parse_vtable_name("<fn() -> u8 as core::ops::function::Fn<()>>::{vtable}"),
+            Some(TraitImplementation {
+                trait_name: Some("core::ops::function::Fn<()>".to_string()),
+                type_name: "fn() -> u8".to_string(),
+            })
+        );
+        assert_eq!(
+            parse_vtable_name("<std::thread::Packet<()>>::{vtable}"),
+            Some(TraitImplementation {
+                trait_name: None,
+                type_name: "std::thread::Packet<()>".to_string(),
+            })
+        );
+        assert_eq!(parse_vtable_name("<trait_invocation::Foo as trait_invocation::Baz>::{vtable_type}"), None);
+        assert_eq!(parse_vtable_name("GLOBAL"), None);
+    }
+
+    /// Verify the vtable of `impl Baz for Foo` is found in the debug information of the example binary
+    #[test]
+    pub fn test_get_vtables() {
+        let path = test_common::get_test_subject_path("trait_invocation", &TestSubjectType::Debug);
+        let file_content = &test_common::load_test_binary_as_bytes("trait_invocation", &TestSubjectType::Debug).unwrap();
+        let buffers = DebugBuffers::default();
+        let context = get_parser().parse(file_content, &path, &buffers).expect("Could not parse test file contents");
+
+        let vtables = get_vtables(&context);
+        let vtable = vtables
+            .values()
+            .find(|vtable| vtable.implementation.trait_name.as_deref() == Some("trait_invocation::Baz"))
+            .expect("No vtable for trait_invocation::Baz in example binary");
+
+        assert_eq!(vtable.implementation.type_name, "trait_invocation::Foo");
+        // Destructor, size, align, `m5` and `m6`
+        assert_eq!(vtable.size, 5 * 8);
+    }
+
+    /// Verify the vtables of the big endian firmware are read from its debug information, and their entries from its
+    /// relocated sections, in the byte order of the binary
+    #[test]
+    pub fn test_get_vtables_big_endian() {
+        if test_common::skip_unbuilt(&TestSubjectType::DebugArmebv7r) {
+            return;
+        }
+        let path = test_common::get_test_subject_path("firmware", &TestSubjectType::DebugArmebv7r);
+        let file_content =
+            &test_common::load_test_binary_as_bytes("firmware", &TestSubjectType::DebugArmebv7r).unwrap();
+        let buffers = DebugBuffers::default();
+        let context = get_parser().parse(file_content, &path, &buffers).expect("Could not parse test file contents");
+        assert_eq!(context.endian(), gimli::RunTimeEndian::Big);
+
+        let vtables = get_vtables(&context);
+        let vtable = vtables
+            .values()
+            .find(|vtable| vtable.implementation.type_name == "firmware::Thermometer")
+            .expect("No vtable for firmware::Thermometer in firmware");
+        assert_eq!(vtable.implementation.trait_name.as_deref(), Some("firmware::Sensor"));
+        // Destructor, size, align and `read`
+        assert_eq!(vtable.size, 4 * 4);
+
+        let read_address = context
+            .elf
+            .symbols()
+            .find(|symbol| {
+                let name = symbol.name().map(|name| format!("{:#}", rustc_demangle::demangle(name)));
+                name.as_deref() == Some("<firmware::Thermometer as firmware::Sensor>::read")
+            })
+            .map(|symbol| symbol.address())
+            .expect("No symbol of read in firmware");
+
+        // `Thermometer` consists of a `u32`
+        let sections = relocated_sections::get_relocated_sections(&context);
+        let read_entry = |index| address_references::read_word_at(&context, &sections, vtable.address + index * 4);
+        assert_eq!(read_entry(1), Some(4));
+        assert_eq!(read_entry(2), Some(4));
+        assert_eq!(read_entry(3), Some(read_address));
+    }
+}
@@ -0,0 +1,198 @@
+// (C) COPYRIGHT 2018 TECHNOLUTION BV, GOUDA NL
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Invocations of WebAssembly modules (see `wasm`) that are not found by `StaticCallInvocationFinder`.
+//!
+//! Calls to imported functions are added as invocations of external procedures, named after the function (see
+//! `static_calls::get_external_procedure`). A `call_indirect` calls a function in a table, of which the index is only
+//! known at runtime. It is assumed to reference every function with the called type that can be placed in a table,
+//! which is the WebAssembly counterpart of the address loads resolved by `AddressLoadDynamicInvocationFinder`.
+
+use crate::Context;
+use crate::InlineFunctionFrame;
+use crate::Invocation;
+use crate::InvocationType;
+use crate::Procedure;
+
+use crate::callgraph::linkage_tables::CallTarget;
+use crate::callgraph::static_calls::get_external_procedure;
+use crate::callgraph::static_calls::get_external_procedures;
+use crate::callgraph::CompilationInfo;
+use crate::callgraph::FallibleIterator;
+use crate::callgraph::InvocationFinder;
+
+use crate::disassembly::Instruction;
+use crate::disassembly::InstructionKind;
+
+use crate::wasm::WasmModule;
+
+use petgraph::stable_graph::{NodeIndex, StableGraph};
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Implementation of `InvocationFinder` that adds edges for calls and tail calls to imported functions, and for
+/// `call_indirect` instructions (`InvocationType::ProcedureReference`) in WebAssembly modules.
+pub struct WasmInvocationFinder;
+
+impl<P: Default, I: Default, F: Default> InvocationFinder<P, I, F> for WasmInvocationFinder {
+    fn find_invocations(
+        &self,
+        graph: &mut StableGraph<Rc<RefCell<Procedure<P>>>, Rc<RefCell<Invocation<I, F>>>>,
+        proc_index: &mut HashMap<u64, NodeIndex>,
+        call_index: &mut HashMap<u64, NodeIndex>,
+        ctx: &Context,
+        compilation_info: CompilationInfo,
+    ) {
+        let wasm_module = match &ctx.wasm_module {
+            Some(wasm_module) => wasm_module,
+            None => return,
+        };
+
+        let invocations = graph
+            .node_indices()
+            .flat_map(|idx| parse_calls(&graph[idx].borrow().disassembly, wasm_module))
+            .collect::<Vec<_>>();
+        let mut external_procedures = get_external_procedures(graph);
+
+        invocations
+            .into_iter()
+            .for_each(|(address, target, invocation_type)| {
+                let origin = call_index[&address];
+                let destination = match target {
+                    CallTarget::Address(target) => match proc_index.get(&target) {
+                        Some(destination) => *destination,
+                        None => return,
+                    },
+                    CallTarget::External { name, slot } => {
+                        get_external_procedure(graph, proc_index, &mut external_procedures, &name, slot)
+                    }
+                };
+
+                let frames = match ctx.find_frames(address) {
+                    Some(frames) => frames
+                        .map(|frame| Ok(InlineFunctionFrame::convert_frame(&frame, compilation_info.compilation_dirs, compilation_info.rust_version.to_owned())))
+                        .collect()
+                        .unwrap_or_default(),
+                    None => vec![],
+                };
+
+                graph.add_edge(
+                    origin,
+                    destination,
+                    Rc::new(RefCell::new(Invocation {
+                        invocation_type,
+                        trait_implementation: None,
+                        instruction_address: address,
+                        frames,
+                        attributes: I::default(),
+                    })),
+                );
+            });
+    }
+}
+
+/// Transform collection of instructions to (instruction address, target, invocation type) triples.
+/// Calls and jumps to imported functions target an external procedure, and indirect calls target all functions
+/// `wasm_module` can call through a table with the type of the call.
+fn parse_calls(instructions: &[Instruction], wasm_module: &WasmModule) -> Vec<(u64, CallTarget, InvocationType)> {
+    let imported_function = |target: u64| {
+        wasm_module
+            .function_at(target)
+            .filter(|function| function.imported)
+            .map(|function| CallTarget::External {
+                name: function.name.clone().unwrap_or_default(),
+                slot: function.address,
+            })
+    };
+
+    instructions
+        .iter()
+        .flat_map(|insn| match insn.kind {
+            InstructionKind::Call { target: Some(target) } => imported_function(target)
+                .map(|target| (insn.address, target, InvocationType::Direct))
+                .into_iter()
+                .collect::<Vec<_>>(),
+            InstructionKind::Jump { target: Some(target) } => imported_function(target)
+                .map(|target| (insn.address, target, InvocationType::Jump))
+                .into_iter()
+                .collect(),
+            InstructionKind::Call { target: None } | InstructionKind::Jump { target: None } => wasm_module
+                .indirect_call_targets(insn)
+                .into_iter()
+                .map(|target| (insn.address, CallTarget::Address(target), InvocationType::ProcedureReference))
+                .collect(),
+            _ => vec![],
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::callgraph::default_cg_builder::DefaultCallGraphBuilder;
+    use crate::callgraph::static_calls::StaticCallInvocationFinder;
+    use crate::callgraph::CallGraphBuilder;
+    use crate::crate_utils;
+    use crate::parse::get_parser;
+    use crate::DebugBuffers;
+    use crate::CallGraph;
+
+    use test_common::TestSubjectType;
+
+    /// Verify the call graph of a `wasm32-wasip1` module is built from its debug information, with the calls to the
+    /// functions imported from WASI as external procedures
+    #[test]
+    fn test_call_graph_creation_wasm32() {
+        if test_common::skip_unbuilt(&TestSubjectType::DebugWasm32) {
+            return;
+        }
+        let path = test_common::get_test_subject_path("trait_invocation.wasm", &TestSubjectType::DebugWasm32);
+        let file_content =
+            &test_common::load_test_binary_as_bytes("trait_invocation.wasm", &TestSubjectType::DebugWasm32).unwrap();
+
+        let buffers = DebugBuffers::default();
+
+        let context = get_parser().parse(file_content, &path, &buffers).expect("Could not parse test file contents");
+        let call_graph: CallGraph<(), (), ()> = DefaultCallGraphBuilder {
+            invocation_finders: vec![Box::new(StaticCallInvocationFinder), Box::new(WasmInvocationFinder)],
+        }.build_call_graph(&context);
+
+        let procedure = |name: &str| {
+            call_graph
+                .graph
+                .node_indices()
+                .find(|index| call_graph.graph[*index].borrow().linkage_name_demangled == name)
+        };
+        let main = procedure("trait_invocation::main").expect("No main procedure in call graph");
+        assert_eq!(call_graph.graph[main].borrow().defining_crate.name, "trait_invocation");
+        assert!(call_graph
+            .graph
+            .edges(main)
+            .any(|edge| edge.weight().borrow().invocation_type == InvocationType::Direct));
+
+        // The standard library writes to the standard output through WASI
+        let fd_write = call_graph
+            .graph
+            .node_indices()
+            .find(|index| call_graph.graph[*index].borrow().linkage_name_demangled.ends_with("fd_write"))
+            .expect("No external fd_write procedure in call graph");
+        assert_eq!(
+            call_graph.graph[fd_write].borrow().defining_crate.name,
+            crate_utils::EXTERNAL_CRATE_NAME
+        );
+
+        // Trait objects are called through the function table
+        assert!(call_graph
+            .graph
+            .edge_indices()
+            .any(|edge| call_graph.graph[edge].borrow().invocation_type == InvocationType::ProcedureReference));
+    }
+}
@@ -0,0 +1,233 @@
+// (C) COPYRIGHT 2018 TECHNOLUTION BV, GOUDA NL
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Decompression of compressed debug sections.
+//!
+//! Debug sections are compressed by the linker (`--compress-debug-sections`) or `objcopy` in one of two formats:
+//! * `SHF_COMPRESSED` sections (`zlib` and `zstd`), which start with a compression header (`Elf32_Chdr` or
+//!   `Elf64_Chdr`) in the byte order of the binary, containing the compression type and the size of the uncompressed
+//!   data.
+//! * `.zdebug_*` sections (`zlib-gnu`, used by older toolchains) instead of `.debug_*`, which start with `ZLIB` and
+//!   the size of the uncompressed data as a big endian 64-bit integer, followed by the zlib stream.
+
+use crate::errors::*;
+use crate::parse::get_endian;
+use crate::DebugBuffers;
+
+use byteorder::{BigEndian, ByteOrder};
+
+use flate2::read::ZlibDecoder;
+
+use gimli::Endianity;
+use gimli::RunTimeEndian;
+
+use object::ElfFile;
+use object::Object;
+
+use std::io::Read;
+
+/// Section flag of `SHF_COMPRESSED` sections.
+const SHF_COMPRESSED: u64 = 0x800;
+
+/// Compression types in the compression header.
+const ELFCOMPRESS_ZLIB: u32 = 1;
+const ELFCOMPRESS_ZSTD: u32 = 2;
+
+/// Magic at the start of `.zdebug_*` sections.
+const ZDEBUG_MAGIC: &[u8] = b"ZLIB";
+
+/// Returns the uncompressed data of the debug section `name` (e.g. `.debug_info`) in `elf`, or `None` if the binary
+/// contains no such section, compressed or not.
+///
+/// The decompressed data is stored in `buffers`, as the DWARF data in the `Context` borrows it for as long as the
+/// binary is analyzed.
+pub fn debug_section_data<'a>(elf: &ElfFile<'a>, name: &str, buffers: &'a DebugBuffers) -> Result<Option<&'a [u8]>> {
+    if let Some(flags) = section_flags(elf, name) {
+        let data = match elf.section_data_by_name(name) {
+            Some(data) => data,
+            None => return Ok(None),
+        };
+        if flags & SHF_COMPRESSED == 0 {
+            return Ok(Some(data));
+        }
+        return decompress_section(data, elf.elf().is_64, get_endian(elf)?)
+            .chain_err(|| ErrorKind::ParseError(format!("Could not decompress {} section", name)))
+            .map(|data| Some(buffers.store(data)));
+    }
+
+    let zdebug_name = zdebug_name(name);
+    match elf.section_data_by_name(&zdebug_name) {
+        Some(data) => decompress_zdebug_section(data)
+            .chain_err(|| ErrorKind::ParseError(format!("Could not decompress {} section", zdebug_name)))
+            .map(|data| Some(buffers.store(data))),
+        None => Ok(None),
+    }
+}
+
+/// Returns `true` if `elf` contains the debug section `name`, compressed or not.
+pub fn has_debug_section(elf: &ElfFile, name: &str) -> bool {
+    section_flags(elf, name).is_some() || section_flags(elf, &zdebug_name(name)).is_some()
+}
+
+/// Returns the flags of the section `name`, or `None` if `elf` contains no such section.
+fn section_flags(elf: &ElfFile, name: &str) -> Option<u64> {
+    let elf = elf.elf();
+    elf.section_headers
+        .iter()
+        .find(|header| &elf.shdr_strtab[header.sh_name] == name)
+        .map(|header| header.sh_flags)
+}
+
+/// Returns the name of the `.zdebug_*` section of the debug section `name`.
+fn zdebug_name(name: &str) -> String {
+    format!(".z{}", name.trim_start_matches('.'))
+}
+
+/// Decompresses the data of a `SHF_COMPRESSED` section, starting with the compression header in the byte order
+/// `endian`.
+fn decompress_section(data: &[u8], is_64: bool, endian: RunTimeEndian) -> Result<Vec<u8>> {
+    // `Elf64_Chdr` has a reserved word after the type, and 64-bit size and alignment fields
+    let (header_size, size) = match is_64 {
+        true if data.len() >= 24 => (24, endian.read_u64(&data[8..16])),
+        false if data.len() >= 12 => (12, u64::from(endian.read_u32(&data[4..8]))),
+        _ => bail!(ErrorKind::ParseError("Compressed section without compression header".to_string())),
+    };
+    let compressed = &data[header_size..];
+
+    let decompressed = match endian.read_u32(&data[0..4]) {
+        ELFCOMPRESS_ZLIB => inflate(compressed)?,
+        ELFCOMPRESS_ZSTD => zstd::stream::decode_all(compressed)
+            .chain_err(|| ErrorKind::ParseError("Invalid zstd stream".to_string()))?,
+        compression_type => bail!(ErrorKind::NotSupported(format!(
+            "debug sections with compression type {}",
+            compression_type
+        ))),
+    };
+    check_size(decompressed, size)
+}
+
+/// Decompresses the data of a `.zdebug_*` section.
+fn decompress_zdebug_section(data: &[u8]) -> Result<Vec<u8>> {
+    if data.len() < 12 || &data[..4] != ZDEBUG_MAGIC {
+        bail!(ErrorKind::ParseError("Compressed section without ZLIB header".to_string()));
+    }
+    let size = BigEndian::read_u64(&data[4..12]);
+
+    check_size(inflate(&data[12..])?, size)
+}
+
+fn inflate(compressed: &[u8]) -> Result<Vec<u8>> {
+    let mut decompressed = vec![];
+    ZlibDecoder::new(compressed)
+        .read_to_end(&mut decompressed)
+        .chain_err(|| ErrorKind::ParseError("Invalid zlib stream".to_string()))?;
+    Ok(decompressed)
+}
+
+/// Verifies the decompressed data has the size in the header of the section.
+fn check_size(decompressed: Vec<u8>, size: u64) -> Result<Vec<u8>> {
+    if decompressed.len() as u64 != size {
+        bail!(ErrorKind::ParseError(format!(
+            "Decompressed section is {} bytes, expected {} bytes",
+            decompressed.len(),
+            size
+        )));
+    }
+    Ok(decompressed)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression;
+
+    use std::io::Write;
+
+    use test_common::TestSubjectType;
+
+    const DATA: &[u8] = b"Debugging information entries, repeated, repeated, repeated";
+
+    fn deflate(data: &[u8]) -> Vec<u8> {
+        let mut encoder = ZlibEncoder::new(vec![], Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    /// Verify zlib and zstd compressed sections are decompressed, for both compression header layouts
+    #[test]
+    fn test_decompress_section() {
+        // Elf64_Chdr: type, reserved, size, alignment
+        let mut section = vec![1, 0, 0, 0, 0, 0, 0, 0];
+        section.extend_from_slice(&(DATA.len() as u64).to_le_bytes());
+        section.extend_from_slice(&1u64.to_le_bytes());
+        section.extend(deflate(DATA));
+        assert_eq!(decompress_section(&section, true, RunTimeEndian::Little).unwrap(), DATA);
+
+        // Elf32_Chdr: type, size, alignment
+        let mut section = vec![2, 0, 0, 0];
+        section.extend_from_slice(&(DATA.len() as u32).to_le_bytes());
+        section.extend_from_slice(&1u32.to_le_bytes());
+        section.extend(zstd::stream::encode_all(DATA, 0).unwrap());
+        assert_eq!(decompress_section(&section, false, RunTimeEndian::Little).unwrap(), DATA);
+
+        // Elf32_Chdr of a big endian binary
+        let mut section = vec![0, 0, 0, 1];
+        section.extend_from_slice(&(DATA.len() as u32).to_be_bytes());
+        section.extend_from_slice(&1u32.to_be_bytes());
+        section.extend(deflate(DATA));
+        assert_eq!(decompress_section(&section, false, RunTimeEndian::Big).unwrap(), DATA);
+
+        // Size in the header does not match
+        let mut section = vec![1, 0, 0, 0];
+        section.extend_from_slice(&(DATA.len() as u32 + 1).to_le_bytes());
+        section.extend_from_slice(&1u32.to_le_bytes());
+        section.extend(deflate(DATA));
+        assert!(decompress_section(&section, false, RunTimeEndian::Little).is_err());
+    }
+
+    /// Verify `.zdebug_*` sections are decompressed
+    #[test]
+    fn test_decompress_zdebug_section() {
+        let mut section = ZDEBUG_MAGIC.to_vec();
+        section.extend_from_slice(&(DATA.len() as u64).to_be_bytes());
+        section.extend(deflate(DATA));
+        assert_eq!(decompress_zdebug_section(&section).unwrap(), DATA);
+
+        assert!(decompress_zdebug_section(&section[4..]).is_err());
+    }
+
+    /// Verify the debug sections of the compressed variants of a test subject are equal to those of the original
+    #[test]
+    fn test_compressed_test_subjects() {
+        let file_content = &test_common::load_test_binary_as_bytes("hello_world", &TestSubjectType::Release).unwrap();
+        let elf = ElfFile::parse(file_content).unwrap();
+        let buffers = DebugBuffers::default();
+        let debug_info = debug_section_data(&elf, ".debug_info", &buffers).unwrap().expect("No .debug_info section");
+
+        [
+            TestSubjectType::ReleaseZlib,
+            TestSubjectType::ReleaseZlibGnu,
+            TestSubjectType::ReleaseZstd,
+        ].iter()
+            .filter(|subject_type| !test_common::skip_unbuilt(subject_type))
+            .for_each(|subject_type| {
+                let file_content = &test_common::load_test_binary_as_bytes("hello_world", subject_type).unwrap();
+                let elf = ElfFile::parse(file_content).unwrap();
+
+                assert!(has_debug_section(&elf, ".debug_info"));
+                assert_eq!(
+                    debug_section_data(&elf, ".debug_info", &buffers).unwrap(),
+                    Some(debug_info),
+                    "Debug information of {:?} differs",
+                    subject_type
+                );
+            });
+    }
+}
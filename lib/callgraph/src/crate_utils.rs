@@ -9,24 +9,52 @@
 use addr2line::Frame;
 
 use crate::dwarf_utils;
+use crate::dwarf_utils::CompilationUnit;
 
-use crate::Context;
+use crate::errors::*;
+use crate::symbol_path::SymbolPath;
+use crate::CallGraph;
 use crate::Crate;
 
 use gimli;
 use gimli::DebuggingInformationEntry;
 use gimli::EndianSlice;
-use gimli::LittleEndian;
 use gimli::RunTimeEndian;
 
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Name of the crate of the procedures that are not defined in the binary, but in a shared object that is loaded at
+/// runtime (e.g. the C library).
+pub const EXTERNAL_CRATE_NAME: &str = "<external>";
+
+/// Name of the crate of the procedures without debug information, of which the crate could not be inferred from the
+/// symbol (e.g. procedures written in C or assembly).
+pub const UNKNOWN_CRATE_NAME: &str = "<unknown>";
+
+/// Crates of the standard library, of which procedures without debug information are attributed to `stdlib`.
+const STDLIB_CRATES: &[&str] = &[
+    "std",
+    "core",
+    "alloc",
+    "panic_unwind",
+    "panic_abort",
+    "compiler_builtins",
+    "std_detect",
+    "unwind",
+    "proc_macro",
+    "test",
+];
+
 /// Function that returns a `Crate` instance from the compilation dir.
 /// Usually, for an external crate, the direactory has the format '/path/to/checkout/<crate-name>-<version>
 /// In that case, `Crate { name: "<crate-name>", version: Some("<version>") }` is returned.
 /// Some special cases are:
-///   - When there is no numeric char after the crate name, it is assumed the version in not known.
+///   - When the directory name does not end with a version number, it is assumed the version in not known.
 ///     In that case, the `version` field is `None`.
-///   - When the checkout dir is '/checkout/src/`, then it is stdlib code
-///     In that case `Crate { name: "stdlib", version: Some("<rust version>") }` is returned
+///   - When the directory is in one of the layouts of the standard library or Cargo (see
+///     `get_crate_from_known_layout`), the crate of that layout is returned. For the standard library, that is
+///     `Crate { name: "stdlib", version: Some("<rust version>") }`
 ///
 pub fn get_crate_from_comp_dir(comp_dir: Option<&str>, rust_version: String) -> Crate {
     let comp_dir = match comp_dir {
@@ -39,54 +67,151 @@ pub fn get_crate_from_comp_dir(comp_dir: Option<&str>, rust_version: String) ->
         }
     };
 
-    // In /checkout/src, the standard library is stored.
-    // So return stdlib-rust-version
-    if comp_dir == "/checkout/src" {
-        return Crate {
-            name: "stdlib".to_string(),
-            version: Some(rust_version),
-        };
+    if let Some(crt) = get_crate_from_known_layout(comp_dir, &rust_version) {
+        return crt;
     }
 
-    let crate_name_version = comp_dir.rsplit('/').next().expect("No / in path");
-    let mut crate_name_split: Vec<_> = crate_name_version.rsplit('-').collect();
+    let crate_name_version = comp_dir.trim_end_matches('/').rsplit('/').next().expect("No / in path");
+    crate_from_directory_name(crate_name_version)
+}
 
-    // Splitting a string always returns at least one element, so we can safely assert this.
-    assert!(!crate_name_split.is_empty());
+/// Returns the crate of the source file `file`, which is compiled in the compilation directory `comp_dir`.
+///
+/// Files in one of the layouts of the standard library or Cargo (see `get_crate_from_known_layout`) belong to the
+/// crate of that layout. Workspace members and path dependencies are compiled in the root directory of the
+/// workspace, so a file in the `src` directory of a subdirectory of `comp_dir` (`<comp_dir>/crates/foo/src/lib.rs`)
+/// belongs to the crate in that subdirectory. Otherwise, the crate is determined by `get_crate_from_comp_dir`.
+pub fn get_crate_from_file(file: Option<&str>, comp_dir: Option<&str>, rust_version: String) -> Crate {
+    if let Some(file) = file {
+        if let Some(crt) = get_crate_from_known_layout(file, &rust_version) {
+            return crt;
+        }
 
-    let (name, version) =
-        if crate_name_split.len() == 1 || !crate_name_version.chars().any(|x| x.is_numeric()) {
-            crate_name_split.reverse();
-            (crate_name_split.join("-").to_string(), None)
-        } else {
-            let version = crate_name_split.remove(0);
-            crate_name_split.reverse();
-            (crate_name_split.join("-"), Some(version.to_string()))
-        };
+        let member_dir = comp_dir
+            .and_then(|comp_dir| file.strip_prefix(comp_dir))
+            .and_then(|relative_path| crate_directory(&path_components(relative_path)));
+        if let Some(member_dir) = member_dir {
+            return crate_from_directory_name(member_dir);
+        }
+    }
 
-    Crate { name, version }
+    get_crate_from_comp_dir(comp_dir, rust_version)
+}
+
+/// Returns the crate of the source file or directory at `path`, if it is located in one of the layouts of the
+/// standard library or Cargo:
+///   - `/rustc/<commit-hash>/library/<crate>/...` (`/rustc/<commit-hash>/src/lib<crate>/...` before Rust 1.47, and
+///     `/checkout/src/...` before that), and `/rust/deps/<crate>-<version>/...` for the dependencies of the standard
+///     library, are stdlib code of version `rust_version`
+///   - `<cargo home>/registry/src/<registry>/<crate>-<version>/...` is a crate from a registry
+///   - `<cargo home>/git/checkouts/<repository>-<hash>/<revision>/...` is a git dependency. It is named after the
+///     directory containing its `src` directory, or after the repository if the crate is in the root of the
+///     repository
+///   - `.../vendor/<crate>[-<version>]/...` is a vendored crate (`cargo vendor`)
+fn get_crate_from_known_layout(path: &str, rust_version: &str) -> Option<Crate> {
+    let components = path_components(path);
+
+    match components.as_slice() {
+        ["rustc", _, ..] | ["checkout", "src", ..] | ["rust", "deps", ..] if path.starts_with('/') => {
+            return Some(Crate {
+                name: "stdlib".to_string(),
+                version: Some(rust_version.to_string()),
+            })
+        }
+        _ => (),
+    }
+
+    let find_components = |first: &str, second: &str| {
+        components
+            .windows(2)
+            .position(|window| window[0] == first && window[1] == second)
+    };
+
+    if let Some(index) = find_components("registry", "src") {
+        return components.get(index + 3).map(|directory| crate_from_directory_name(directory));
+    }
+
+    if let Some(index) = find_components("git", "checkouts") {
+        let repository = *components.get(index + 2)?;
+        let name = components
+            .get(index + 4..)
+            .and_then(crate_directory)
+            .unwrap_or_else(|| repository.rsplit_once('-').map_or(repository, |(name, _)| name));
+        return Some(Crate {
+            name: name.to_string(),
+            version: None,
+        });
+    }
+
+    components
+        .iter()
+        .rposition(|component| *component == "vendor")
+        .and_then(|index| components.get(index + 1))
+        .map(|directory| crate_from_directory_name(directory))
+}
+
+/// Returns the non-empty components of `path`.
+fn path_components(path: &str) -> Vec<&str> {
+    path.split('/').filter(|component| !component.is_empty()).collect()
+}
+
+/// Returns the name of the directory containing the first `src` directory in the path `components`, if that
+/// directory is part of the path.
+fn crate_directory<'a>(components: &[&'a str]) -> Option<&'a str> {
+    components
+        .iter()
+        .position(|component| *component == "src")
+        .filter(|index| *index > 0)
+        .map(|index| components[index - 1])
+}
+
+/// Returns the crate of a directory named `<crate-name>-<version>`, or `<crate-name>` if the version is not known.
+fn crate_from_directory_name(directory_name: &str) -> Crate {
+    let version_start = directory_name
+        .match_indices('-')
+        .map(|(index, _)| index + 1)
+        .find(|start| is_version(&directory_name[*start..]));
+
+    match version_start {
+        Some(start) => Crate {
+            name: directory_name[..start - 1].to_string(),
+            version: Some(directory_name[start..].to_string()),
+        },
+        None => Crate {
+            name: directory_name.to_string(),
+            version: None,
+        },
+    }
+}
+
+/// Returns `true` if `version` is a version number with at least two components (`0.1`, `1.0.0-alpha.1`, ...).
+fn is_version(version: &str) -> bool {
+    let number = version.split(|c| c == '-' || c == '+').next().unwrap_or("");
+    number.contains('.')
+        && number
+            .split('.')
+            .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()))
 }
 
 /// Gets the crate details for a compilation unit
 pub fn get_crate_details(
     _address: u64,
     defining_file: Option<&str>,
-    cu_die: &DebuggingInformationEntry<EndianSlice<LittleEndian>>,
-    ctx: &Context,
+    unit: &CompilationUnit,
+    cu_die: &DebuggingInformationEntry<EndianSlice<RunTimeEndian>>,
     compilation_unit_dirs: &[&str],
 ) -> Crate {
+    // The Rust version is unknown if the compilation unit has no producer (e.g. if it is written by another tool)
     let producer =
-        dwarf_utils::get_attr_string_value(cu_die, gimli::DW_AT_producer, &ctx.dwarf_strings)
-            .expect("No producer for compilation unit");
+        dwarf_utils::get_attr_string_value(cu_die, gimli::DW_AT_producer, unit.dwarf, &unit.unit)
+            .unwrap_or_default();
 
-    // Assume producer is in a format like 'clang LLVM (rustc version 1.26.0 (a77568041 2018-05-07))'
+    // Assume producer is in a format like 'clang LLVM (rustc version 1.26.0 (a77568041 2018-05-07))'.
+    // The producer of C and C++ compilation units (e.g. 'GNU C17 12.2.0') contains no Rust version.
     let rust_version = producer
-        .rsplit("rustc version")
-        .next()
-        .expect("Unexpected producer string format")
-        .split_whitespace()
-        .next()
-        .expect("Unexpected producer string format")
+        .rsplit_once("rustc version")
+        .and_then(|(_, version)| version.split_whitespace().next())
+        .unwrap_or_default()
         .to_string();
 
     // Find a match between the compilation unit directories and the file.
@@ -98,7 +223,27 @@ pub fn get_crate_details(
         None => None,
     };
 
-    get_crate_from_comp_dir(comp_dir, rust_version)
+    get_crate_from_file(defining_file, comp_dir, rust_version)
+}
+
+/// Returns the crate of a procedure without debug information, which is inferred from its demangled symbol name.
+///
+/// The crate is the crate of the `SymbolPath` of the name, so for trait implementations it is the crate of the
+/// implementing type, or of the trait for primitive types (`alloc` for `<alloc::vec::Vec<T> as core::ops::Drop>::drop`,
+/// `core` for `<u8 as core::fmt::Debug>::fmt`). The version is only known for the standard library.
+/// If the name contains no path, `UNKNOWN_CRATE_NAME` is returned.
+pub fn get_crate_from_symbol(demangled_name: &str, rust_version: Option<String>) -> Crate {
+    match SymbolPath::parse(demangled_name).crate_name {
+        Some(name) if STDLIB_CRATES.contains(&name.as_str()) => Crate {
+            name: "stdlib".to_string(),
+            version: rust_version,
+        },
+        Some(name) => Crate { name, version: None },
+        None => Crate {
+            name: UNKNOWN_CRATE_NAME.to_string(),
+            version: None,
+        },
+    }
 }
 
 /// Returns the crate for inlined functions
@@ -107,29 +252,91 @@ pub fn get_crate_for_inlined_functions(
     compilation_dirs: &[&str],
     rust_version: String,
 ) -> Crate {
-    let default_crate = Crate {
-        name: "<unknown compilation directory>".to_string(),
-        version: None,
-    };
+    let file_path = frame.location.as_ref().and_then(|location| location.file);
+
+    let frame_comp_dir = file_path.and_then(|file_path| {
+        compilation_dirs
+            .iter()
+            .find(|comp_dir| file_path.starts_with(**comp_dir))
+            .copied()
+    });
+    get_crate_from_file(file_path, frame_comp_dir, rust_version)
+}
 
-    if frame.location.is_none() {
-        return default_crate;
+/// Versions of the packages in the `Cargo.lock` file of a project.
+#[derive(Debug, Clone, Default)]
+pub struct CargoLock {
+    /// Versions of each package, by package name in which dashes are replaced by underscores
+    versions: HashMap<String, Vec<String>>,
+}
+
+impl CargoLock {
+    /// Reads the `Cargo.lock` file at `path`.
+    pub fn read(path: &Path) -> Result<CargoLock> {
+        let content = std::fs::read_to_string(path)
+            .chain_err(|| ErrorKind::ReadError(path.to_string_lossy().to_string()))?;
+        CargoLock::parse(&content)
     }
 
-    // Safe unwrap: is_none check before
-    let location = frame.location.as_ref().unwrap();
+    /// Parses the contents of a `Cargo.lock` file.
+    pub fn parse(content: &str) -> Result<CargoLock> {
+        let lock = content
+            .parse::<toml::Value>()
+            .chain_err(|| ErrorKind::ParseError("Invalid Cargo.lock file".to_string()))?;
+
+        let mut versions: HashMap<String, Vec<String>> = HashMap::new();
+        lock.get("package")
+            .and_then(toml::Value::as_array)
+            .map(|packages| packages.as_slice())
+            .unwrap_or(&[])
+            .iter()
+            .filter_map(|package| {
+                let name = package.get("name").and_then(toml::Value::as_str)?;
+                let version = package.get("version").and_then(toml::Value::as_str)?;
+                Some((name, version))
+            })
+            .for_each(|(name, version)| {
+                versions
+                    .entry(name.replace('-', "_"))
+                    .or_default()
+                    .push(version.to_string())
+            });
+
+        Ok(CargoLock { versions })
+    }
 
-    if location.file.is_none() {
-        return default_crate;
+    /// Returns the version of the package `name`, if the lock file contains exactly one version of it.
+    /// Dashes and underscores in `name` are equivalent, as crate names derived from symbols only contain underscores.
+    pub fn version(&self, name: &str) -> Option<&str> {
+        match self.versions.get(&name.replace('-', "_")) {
+            Some(versions) if versions.len() == 1 => Some(&versions[0]),
+            _ => None,
+        }
     }
+}
 
-    // Safe unwrap: is_none check before
-    let file_path = location.file.as_ref().unwrap();
+/// Fills in the versions of the crates of the procedures and inlined functions in `call_graph`, of which the version
+/// is not known (e.g. workspace members, git dependencies and crates read from the symbol table), from `cargo_lock`.
+pub fn fill_in_crate_versions<PMetadata, IMetadata, FMetadata>(
+    call_graph: &CallGraph<PMetadata, IMetadata, FMetadata>,
+    cargo_lock: &CargoLock,
+) {
+    let fill_in = |crt: &mut Crate| {
+        if crt.version.is_none() {
+            crt.version = cargo_lock.version(&crt.name).map(str::to_string);
+        }
+    };
 
-    let frame_comp_dir = compilation_dirs
-        .iter()
-        .find(|comp_dir| file_path.starts_with(*comp_dir));
-    get_crate_from_comp_dir(frame_comp_dir.map(|x| x.to_owned()), rust_version)
+    call_graph.graph.node_indices().for_each(|node| {
+        fill_in(&mut call_graph.graph[node].borrow_mut().defining_crate);
+    });
+    call_graph.graph.edge_indices().for_each(|edge| {
+        call_graph.graph[edge]
+            .borrow_mut()
+            .frames
+            .iter_mut()
+            .for_each(|frame| fill_in(&mut frame.defining_crate));
+    });
 }
 
 #[cfg(test)]
@@ -171,4 +378,115 @@ mod tests {
         assert_eq!(crt.name, "<unknown compilation directory>");
         assert_eq!(crt.version, None);
     }
+
+    /// Test `get_crate_from_comp_dir` for the layouts of the standard library and Cargo
+    #[test]
+    pub fn test_get_crate_known_layouts() {
+        let crt = get_crate_from_comp_dir(Some("/rustc/129f3b9964af4d4a709d1383930ade12dfe7c081"), "1.80.0".to_string());
+        assert_eq!(crt.name, "stdlib");
+        assert_eq!(crt.version, Some("1.80.0".to_string()));
+
+        let crt = get_crate_from_comp_dir(
+            Some("/home/test/.cargo/registry/src/index.crates.io-6f17d22bba15001f/serde-json-1.0.0-rc.1"),
+            "1.80.0".to_string(),
+        );
+        assert_eq!(crt.name, "serde-json");
+        assert_eq!(crt.version, Some("1.0.0-rc.1".to_string()));
+
+        let crt = get_crate_from_comp_dir(
+            Some("/home/test/.cargo/git/checkouts/rustig-0123456789abcdef/1a2b3c4"),
+            "1.80.0".to_string(),
+        );
+        assert_eq!(crt.name, "rustig");
+        assert_eq!(crt.version, None);
+
+        let crt = get_crate_from_comp_dir(Some("/home/test/project/vendor/libc-0.2.155"), "1.80.0".to_string());
+        assert_eq!(crt.name, "libc");
+        assert_eq!(crt.version, Some("0.2.155".to_string()));
+    }
+
+    /// Test `get_crate_from_file` for standard library files, git dependencies and workspace members
+    #[test]
+    pub fn test_get_crate_from_file() {
+        let crt = get_crate_from_file(
+            Some("/rustc/129f3b9964af4d4a709d1383930ade12dfe7c081/library/core/src/option.rs"),
+            None,
+            "1.80.0".to_string(),
+        );
+        assert_eq!(crt.name, "stdlib");
+
+        let crt = get_crate_from_file(
+            Some("/home/test/.cargo/git/checkouts/tools-0123456789abcdef/1a2b3c4/crates/tool-lib/src/lib.rs"),
+            Some("/home/test/.cargo/git/checkouts/tools-0123456789abcdef/1a2b3c4"),
+            "1.80.0".to_string(),
+        );
+        assert_eq!(crt.name, "tool-lib");
+
+        let crt = get_crate_from_file(
+            Some("/home/test/workspace/crates/member/src/lib.rs"),
+            Some("/home/test/workspace"),
+            "1.80.0".to_string(),
+        );
+        assert_eq!(crt.name, "member");
+        assert_eq!(crt.version, None);
+
+        let crt = get_crate_from_file(
+            Some("/home/test/workspace/src/main.rs"),
+            Some("/home/test/workspace"),
+            "1.80.0".to_string(),
+        );
+        assert_eq!(crt.name, "workspace");
+    }
+
+    /// Test the versions of packages are read from a `Cargo.lock` file
+    #[test]
+    pub fn test_cargo_lock() {
+        let cargo_lock = CargoLock::parse(
+            r#"
+version = 4
+
+[[package]]
+name = "tool-lib"
+version = "0.3.1"
+
+[[package]]
+name = "bitflags"
+version = "1.3.2"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "bitflags"
+version = "2.6.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+"#,
+        ).unwrap();
+
+        assert_eq!(cargo_lock.version("tool-lib"), Some("0.3.1"));
+        assert_eq!(cargo_lock.version("tool_lib"), Some("0.3.1"));
+        assert_eq!(cargo_lock.version("bitflags"), None);
+        assert_eq!(cargo_lock.version("serde"), None);
+
+        assert!(CargoLock::parse("[[package]").is_err());
+    }
+
+    /// Test `get_crate_from_symbol` for paths, trait implementations and symbols without a path
+    #[test]
+    pub fn test_get_crate_from_symbol() {
+        let crt = get_crate_from_symbol("hello_world::main", Some("1.80.0".to_string()));
+        assert_eq!(crt.name, "hello_world");
+        assert_eq!(crt.version, None);
+
+        let crt = get_crate_from_symbol("<alloc::vec::Vec<T> as core::ops::drop::Drop>::drop", None);
+        assert_eq!(crt.name, "stdlib");
+
+        let crt = get_crate_from_symbol("<&mut [u8] as dep::Reader>::read", None);
+        assert_eq!(crt.name, "dep");
+
+        let crt = get_crate_from_symbol("core::panicking::panic", Some("1.80.0".to_string()));
+        assert_eq!(crt.name, "stdlib");
+        assert_eq!(crt.version, Some("1.80.0".to_string()));
+
+        let crt = get_crate_from_symbol("memcpy", None);
+        assert_eq!(crt.name, UNKNOWN_CRATE_NAME);
+    }
 }
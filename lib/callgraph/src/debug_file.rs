@@ -0,0 +1,245 @@
+// (C) COPYRIGHT 2018 TECHNOLUTION BV, GOUDA NL
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Separate debug files of stripped binaries.
+//!
+//! The debug information of a binary can be moved to a separate file (`objcopy --only-keep-debug`), after which the
+//! binary is stripped. The binary then refers to its debug file in two ways, which are looked up like `gdb` does:
+//! * The `.note.gnu.build-id` section contains the build ID of the binary, which is shared with the debug file. The
+//!   debug file of build ID `abcdef...` is `<dir>/.build-id/ab/cdef....debug`, in each debug file directory.
+//! * The `.gnu_debuglink` section contains the file name and the CRC32 checksum of the debug file. The file is searched
+//!   in the directory of the binary, its `.debug` subdirectory, and each debug file directory (also joined with the
+//!   directory of the binary). Only a file with a matching checksum is used.
+//!
+//! The code sections of a debug file are empty (`SHT_NOBITS`), so the debug information of the debug file is combined
+//! with the code of the binary itself.
+
+use crate::compressed_sections::has_debug_section;
+use crate::parse::get_endian;
+
+use gimli::Endianity;
+use gimli::RunTimeEndian;
+
+use object::ElfFile;
+use object::Object;
+
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Debug file directory of the system, which is searched after the directories passed by the user.
+const SYSTEM_DEBUG_FILE_DIRECTORY: &str = "/usr/lib/debug";
+
+/// ELF note type of build ID notes (`NT_GNU_BUILD_ID`).
+const NT_GNU_BUILD_ID: u32 = 3;
+
+/// A separate debug file, of which the contents are read.
+#[derive(Debug, Clone, Copy)]
+pub struct DebugFile<'a> {
+    pub path: &'a Path,
+    pub content: &'a [u8],
+}
+
+/// Returns the path of the separate debug file of the binary at `file_path`, if the binary contains no debug
+/// information itself. `debug_file_directories` are searched before the debug file directory of the system.
+/// Returns `None` if the binary contains debug information, does not refer to a debug file, or if no debug file is
+/// found.
+pub fn find_debug_file(file_content: &[u8], file_path: &Path, debug_file_directories: &[PathBuf]) -> Option<PathBuf> {
+    let elf = ElfFile::parse(file_content).ok()?;
+    if has_debug_section(&elf, ".debug_info") {
+        return None;
+    }
+    let endian = get_endian(&elf).ok()?;
+
+    let directories = debug_file_directories
+        .iter()
+        .cloned()
+        .chain(std::iter::once(PathBuf::from(SYSTEM_DEBUG_FILE_DIRECTORY)))
+        .collect::<Vec<_>>();
+
+    let build_id_file = elf
+        .section_data_by_name(".note.gnu.build-id")
+        .and_then(|note| parse_build_id(note, endian))
+        .and_then(|build_id| {
+            directories
+                .iter()
+                .map(|directory| directory.join(build_id_path(build_id)))
+                .find(|path| path.is_file())
+        });
+
+    build_id_file.or_else(|| {
+        let (name, crc) = parse_debuglink(elf.section_data_by_name(".gnu_debuglink")?, endian)?;
+        let binary_directory = file_path.parent().unwrap_or_else(|| Path::new(""));
+
+        vec![binary_directory.join(&name), binary_directory.join(".debug").join(&name)]
+            .into_iter()
+            .chain(directories.iter().flat_map(|directory| {
+                vec![
+                    directory.join(&name),
+                    directory.join(binary_directory.strip_prefix("/").unwrap_or(binary_directory)).join(&name),
+                ]
+            }))
+            .find(|path| crate::map_file(path).map(|content| crc32(&content) == crc).unwrap_or(false))
+    })
+}
+
+/// Returns the build ID in the contents of a `.note.gnu.build-id` section, of which the header is in the byte order
+/// `endian`.
+fn parse_build_id(note: &[u8], endian: RunTimeEndian) -> Option<&[u8]> {
+    if note.len() < 12 {
+        return None;
+    }
+    let name_size = endian.read_u32(&note[0..4]) as usize;
+    let descriptor_size = endian.read_u32(&note[4..8]) as usize;
+    if endian.read_u32(&note[8..12]) != NT_GNU_BUILD_ID {
+        return None;
+    }
+
+    // The name (`GNU\0`) is padded to a multiple of 4 bytes
+    let descriptor_start = 12 + (name_size + 3) / 4 * 4;
+    note.get(descriptor_start..descriptor_start + descriptor_size)
+        .filter(|build_id| build_id.len() > 1)
+}
+
+/// Returns the path of the debug file of `build_id`, relative to a debug file directory.
+fn build_id_path(build_id: &[u8]) -> PathBuf {
+    let hex = build_id.iter().map(|byte| format!("{:02x}", byte)).collect::<String>();
+    PathBuf::from(".build-id")
+        .join(&hex[..2])
+        .join(format!("{}.debug", &hex[2..]))
+}
+
+/// Returns the file name and CRC32 checksum of the debug file in the contents of a `.gnu_debuglink` section, of which
+/// the checksum is in the byte order `endian`.
+fn parse_debuglink(debuglink: &[u8], endian: RunTimeEndian) -> Option<(String, u32)> {
+    let name_length = debuglink.iter().position(|byte| *byte == 0)?;
+    let name = String::from_utf8(debuglink[..name_length].to_vec()).ok()?;
+
+    // The checksum follows the terminated name, aligned to 4 bytes
+    let crc_start = (name_length + 1 + 3) / 4 * 4;
+    let crc = debuglink.get(crc_start..crc_start + 4)?;
+    Some((name, endian.read_u32(crc)))
+}
+
+/// Returns the CRC32 checksum of `data`, as used by `.gnu_debuglink`.
+fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |crc, byte| {
+        (0..8).fold(crc ^ u32::from(*byte), |crc, _| {
+            if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            }
+        })
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use test_common::TestSubjectType;
+
+    /// Verify the checksum matches the check value of CRC-32
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b""), 0);
+    }
+
+    /// Verify the file name and checksum are read from a `.gnu_debuglink` section
+    #[test]
+    fn test_parse_debuglink() {
+        let debuglink = b"app.debug\0\0\0\x26\x39\xf4\xcb";
+        assert_eq!(
+            parse_debuglink(debuglink, RunTimeEndian::Little),
+            Some(("app.debug".to_string(), 0xCBF4_3926))
+        );
+
+        let debuglink = b"app.debug\0\0\0\xcb\xf4\x39\x26";
+        assert_eq!(
+            parse_debuglink(debuglink, RunTimeEndian::Big),
+            Some(("app.debug".to_string(), 0xCBF4_3926))
+        );
+
+        // Truncated section
+        assert_eq!(parse_debuglink(b"app.debug\0\0\0\x26", RunTimeEndian::Little), None);
+    }
+
+    /// Verify the build ID is read from a `.note.gnu.build-id` section, and converted to the path of the debug file
+    #[test]
+    fn test_build_id_path() {
+        let note = b"\x04\0\0\0\x04\0\0\0\x03\0\0\0GNU\0\xab\xcd\xef\x01";
+        let build_id = parse_build_id(note, RunTimeEndian::Little).expect("Could not parse build ID note");
+
+        assert_eq!(build_id, &[0xab, 0xcd, 0xef, 0x01]);
+        assert_eq!(build_id_path(build_id), PathBuf::from(".build-id/ab/cdef01.debug"));
+
+        // The header of the note of a big endian binary
+        let note = b"\0\0\0\x04\0\0\0\x04\0\0\0\x03GNU\0\xab\xcd\xef\x01";
+        assert_eq!(parse_build_id(note, RunTimeEndian::Big), Some(&[0xab, 0xcd, 0xef, 0x01][..]));
+        assert_eq!(parse_build_id(note, RunTimeEndian::Little), None);
+    }
+
+    /// Verify no debug file is searched for a binary containing debug information
+    #[test]
+    fn test_binary_with_debug_information() {
+        let path = test_common::get_test_subject_path("hello_world", &TestSubjectType::Debug);
+        let file_content = &test_common::load_test_binary_as_bytes("hello_world", &TestSubjectType::Debug).unwrap();
+
+        assert_eq!(find_debug_file(file_content, &path, &[]), None);
+    }
+
+    /// Verify the debug file of a stripped binary is found by its `.gnu_debuglink`, next to the binary
+    #[test]
+    fn test_find_debug_file_by_debuglink() {
+        let path = test_common::get_test_subject_path("hello_world", &TestSubjectType::ReleaseSeparateDebug);
+        let file_content =
+            &test_common::load_test_binary_as_bytes("hello_world", &TestSubjectType::ReleaseSeparateDebug).unwrap();
+
+        assert_eq!(
+            find_debug_file(file_content, &path, &[]),
+            Some(test_common::get_test_subject_path("hello_world.debug", &TestSubjectType::ReleaseSeparateDebug))
+        );
+    }
+
+    /// Verify the debug file of a stripped binary without `.gnu_debuglink` is found by its build ID, in a debug file
+    /// directory
+    #[test]
+    fn test_find_debug_file_by_build_id() {
+        let path = test_common::get_test_subject_path("build-id/hello_world", &TestSubjectType::ReleaseSeparateDebug);
+        let file_content = &test_common::load_test_binary_as_bytes(
+            "build-id/hello_world",
+            &TestSubjectType::ReleaseSeparateDebug,
+        ).unwrap();
+        let directory = test_common::get_test_subject_path("debug-files", &TestSubjectType::ReleaseSeparateDebug);
+
+        // The debug file is not found without the directory
+        assert_eq!(find_debug_file(file_content, &path, &[]), None);
+
+        let debug_file = find_debug_file(file_content, &path, &[directory.clone()]).expect("No debug file found");
+        assert!(debug_file.starts_with(directory.join(".build-id")));
+        assert_eq!(
+            std::fs::read(&debug_file).unwrap(),
+            test_common::load_test_binary_as_bytes("hello_world.debug", &TestSubjectType::ReleaseSeparateDebug)
+                .unwrap()
+        );
+    }
+
+    /// Verify a file named by the `.gnu_debuglink` of a stripped binary is not used if its checksum does not match
+    #[test]
+    fn test_find_debug_file_wrong_crc() {
+        let path = test_common::get_test_subject_path("wrong-crc/hello_world", &TestSubjectType::ReleaseSeparateDebug);
+        let file_content = &test_common::load_test_binary_as_bytes(
+            "wrong-crc/hello_world",
+            &TestSubjectType::ReleaseSeparateDebug,
+        ).unwrap();
+
+        assert!(path.with_file_name("hello_world.debug").is_file());
+        assert_eq!(find_debug_file(file_content, &path, &[]), None);
+    }
+}
@@ -0,0 +1,274 @@
+// (C) COPYRIGHT 2018 TECHNOLUTION BV, GOUDA NL
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use crate::disassembly::Disassembler;
+use crate::disassembly::Instruction;
+use crate::disassembly::InstructionKind;
+use crate::disassembly::Operand;
+use crate::disassembly::Register;
+
+use capstone::arch::arm64::Arm64OperandType;
+use capstone::arch::ArchOperand;
+use capstone::arch::BuildsCapstone;
+use capstone::Capstone;
+use capstone::Insn;
+use capstone::RegId;
+
+use std::collections::HashMap;
+
+/// Mnemonics of the AArch64 jump instructions with an immediate (pc relative) target.
+/// `b.<cond>` is matched separately.
+const JUMP_MNEMONICS: &[&str] = &["b", "cbz", "cbnz", "tbz", "tbnz"];
+
+/// Number of integer arguments passed in registers (`x0` - `x7`) in the AAPCS64 calling convention.
+const ARGUMENT_REGISTER_COUNT: usize = 8;
+
+/// `Disassembler` for AArch64 code, based on Capstone.
+///
+/// Capstone does not assign call/jump groups to all AArch64 branches, so they are recognized by mnemonic.
+#[derive(Debug)]
+pub struct AArch64Disassembler {
+    capstone: Capstone,
+}
+
+impl AArch64Disassembler {
+    pub fn new() -> AArch64Disassembler {
+        let mut capstone = Capstone::new()
+            .arm64()
+            .mode(capstone::arch::arm64::ArchMode::Arm)
+            .detail(true)
+            .build()
+            .expect("Failed to construct disassembler");
+        capstone
+            .set_detail(true)
+            .expect("Failed to enable detailed mode");
+
+        AArch64Disassembler { capstone }
+    }
+
+    /// Returns the name of `reg_id`, with 32-bit registers (`w<n>`) named after the 64-bit register (`x<n>`).
+    fn register(&self, reg_id: RegId) -> Option<Register> {
+        let name = self.capstone.reg_name(reg_id)?;
+        let name = match &name[..] {
+            "wzr" => "xzr".to_string(),
+            "wsp" => "sp".to_string(),
+            _ if name.starts_with('w') && name[1..].parse::<u8>().is_ok() => format!("x{}", &name[1..]),
+            _ => name,
+        };
+        Some(Register(name))
+    }
+
+    /// Returns the AArch64 operand types of `insn`, or `None` if the details could not be decoded.
+    fn operands(&self, insn: &Insn) -> Option<Vec<Arm64OperandType>> {
+        let details = self.capstone.insn_detail(insn).ok()?;
+        details
+            .arch_detail()
+            .operands()
+            .into_iter()
+            .map(|operand| match operand {
+                ArchOperand::Arm64Operand(operand) => Some(operand.op_type),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Classifies `insn`.
+    ///
+    /// An `adrp` instruction loads the 4KiB page of an address into a register, after which an `add` instruction
+    /// adds the offset within the page, or an `ldr` instruction loads the word at that offset. The page loaded in each register is tracked in `pages`, until the register is
+    /// overwritten by another instruction.
+    fn classify(&self, insn: &Insn, pages: &mut HashMap<Register, u64>) -> InstructionKind {
+        let mnemonic = insn.mnemonic().unwrap_or_default();
+        let operands = self.operands(insn).unwrap_or_default();
+
+        // The branch target is always the last operand (`cbz x0, #target`, `tbz w0, #3, #target`)
+        let target = match operands.last() {
+            Some(Arm64OperandType::Imm(target)) => Some(*target as u64),
+            _ => None,
+        };
+        match mnemonic {
+            "bl" | "blr" => return InstructionKind::Call { target },
+            "br" => return InstructionKind::Jump { target: None },
+            _ if mnemonic.starts_with("b.") || JUMP_MNEMONICS.contains(&mnemonic) => {
+                return InstructionKind::Jump { target }
+            }
+            _ => (),
+        }
+
+        let destination = match operands.first() {
+            Some(Arm64OperandType::Reg(reg_id)) => self.register(*reg_id),
+            _ => None,
+        };
+        let destination = match destination {
+            Some(destination) => destination,
+            None => return InstructionKind::Other { destination: None },
+        };
+        let source_page = match operands.get(1) {
+            Some(Arm64OperandType::Reg(source)) => self.register(*source).and_then(|source| pages.get(&source).cloned()),
+            Some(Arm64OperandType::Mem(mem)) if mem.index() == RegId(0) => {
+                self.register(mem.base()).and_then(|base| pages.get(&base).cloned())
+            }
+            _ => None,
+        };
+        pages.remove(&destination);
+
+        match (mnemonic, &operands[1..]) {
+            ("adrp", [Arm64OperandType::Imm(page)]) => {
+                pages.insert(destination.clone(), *page as u64);
+                InstructionKind::Other { destination: Some(destination) }
+            }
+            ("adr", [Arm64OperandType::Imm(address)]) => InstructionKind::AddressLoad {
+                register: destination,
+                address: *address as u64,
+            },
+            ("add", [Arm64OperandType::Reg(_), Arm64OperandType::Imm(offset)]) => match source_page {
+                Some(page) => InstructionKind::AddressLoad {
+                    register: destination,
+                    address: (page as i64 + offset) as u64,
+                },
+                None => InstructionKind::Other { destination: Some(destination) },
+            },
+            // `ldr x17, [x16, #offset]`, loading an address from the GOT
+            ("ldr", [Arm64OperandType::Mem(mem)]) => match source_page {
+                Some(page) => InstructionKind::MemoryLoad {
+                    register: destination,
+                    address: (page as i64 + i64::from(mem.disp())) as u64,
+                },
+                None => InstructionKind::Other { destination: Some(destination) },
+            },
+            ("mov", [Arm64OperandType::Imm(value)]) | ("movz", [Arm64OperandType::Imm(value)]) => {
+                InstructionKind::RegisterMove {
+                    destination,
+                    source: Operand::Immediate(*value as u64),
+                }
+            }
+            ("mov", [Arm64OperandType::Reg(source)]) => match self.register(*source) {
+                Some(source) => InstructionKind::RegisterMove {
+                    destination,
+                    source: Operand::Register(source),
+                },
+                None => InstructionKind::Other { destination: Some(destination) },
+            },
+            _ => InstructionKind::Other { destination: Some(destination) },
+        }
+    }
+}
+
+impl Disassembler for AArch64Disassembler {
+    fn disassemble(&self, code: &[u8], address: u64) -> Vec<Instruction> {
+        let instructions = self.capstone
+            .disasm_all(code, address)
+            .expect("Failed to disassemble procedure");
+        let mut pages = HashMap::new();
+
+        instructions
+            .iter()
+            .map(|insn| Instruction {
+                address: insn.address(),
+                size: insn.bytes().len() as u64,
+                mnemonic: insn.mnemonic().unwrap_or_default().to_string(),
+                kind: self.classify(&insn, &mut pages),
+            })
+            .collect()
+    }
+
+    fn argument_register(&self, index: usize) -> Option<Register> {
+        if index < ARGUMENT_REGISTER_COUNT {
+            Some(Register(format!("x{}", index)))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Verify `bl`, `b`, `b.ne` and `cbz` are recognized, with targets relative to the instruction address
+    #[test]
+    pub fn test_static_calls() {
+        let assembly = &[
+            0x04, 0x00, 0x00, 0x94, // bl #0x1010
+            0x02, 0x00, 0x00, 0x14, // b #0x100c
+            0x21, 0x00, 0x00, 0x54, // b.ne #0x100c
+            0x20, 0x00, 0x00, 0xb4, // cbz x0, #0x1010
+        ];
+
+        assert_eq!(
+            AArch64Disassembler::new()
+                .disassemble(assembly, 0x1000)
+                .into_iter()
+                .map(|insn| insn.kind)
+                .collect::<Vec<_>>(),
+            vec![
+                InstructionKind::Call { target: Some(0x1010) },
+                InstructionKind::Jump { target: Some(0x100c) },
+                InstructionKind::Jump { target: Some(0x100c) },
+                InstructionKind::Jump { target: Some(0x1010) },
+            ]
+        );
+    }
+
+    /// Verify register branches are branches without a static target, and other instructions are no branches
+    #[test]
+    pub fn test_register_branches() {
+        let assembly = &[
+            0x00, 0x04, 0x00, 0x91, // add x0, x0, #1
+            0x00, 0x00, 0x3f, 0xd6, // blr x0
+            0xc0, 0x03, 0x5f, 0xd6, // ret
+        ];
+
+        let instructions = AArch64Disassembler::new().disassemble(assembly, 0);
+
+        assert_eq!(
+            instructions[0].kind,
+            InstructionKind::Other { destination: Some(Register::new("x0")) }
+        );
+        assert_eq!(instructions[1].kind, InstructionKind::Call { target: None });
+        assert!(!instructions[2].is_branch());
+    }
+
+    /// Verify an `adrp` + `add` pair is recognized as an address load
+    #[test]
+    pub fn test_adrp_address_load() {
+        let assembly = &[
+            0x00, 0x00, 0x00, 0x90, // adrp x0, #0x1000
+            0x00, 0x40, 0x00, 0x91, // add x0, x0, #0x10
+        ];
+
+        assert_eq!(
+            AArch64Disassembler::new().disassemble(assembly, 0x1000)[1].kind,
+            InstructionKind::AddressLoad {
+                register: Register::new("x0"),
+                address: 0x1010,
+            }
+        );
+    }
+
+    /// Verify an `adrp` + `ldr` pair, as in a PLT stub, is recognized as a memory load
+    #[test]
+    pub fn test_adrp_memory_load() {
+        let assembly = &[
+            0x10, 0x00, 0x00, 0x90, // adrp x16, #0x1000
+            0x11, 0x0a, 0x40, 0xf9, // ldr x17, [x16, #0x10]
+            0x20, 0x02, 0x1f, 0xd6, // br x17
+        ];
+
+        let instructions = AArch64Disassembler::new().disassemble(assembly, 0x1000);
+
+        assert_eq!(
+            instructions[1].kind,
+            InstructionKind::MemoryLoad {
+                register: Register::new("x17"),
+                address: 0x1010,
+            }
+        );
+        assert_eq!(instructions[2].kind, InstructionKind::Jump { target: None });
+    }
+}
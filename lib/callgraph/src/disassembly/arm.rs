@@ -0,0 +1,265 @@
+// (C) COPYRIGHT 2018 TECHNOLUTION BV, GOUDA NL
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use crate::disassembly::Disassembler;
+use crate::disassembly::Instruction;
+use crate::disassembly::InstructionKind;
+use crate::disassembly::Operand;
+use crate::disassembly::Register;
+
+use capstone::arch::arm::ArmOperandType;
+use capstone::arch::ArchOperand;
+use capstone::arch::BuildsCapstone;
+use capstone::Capstone;
+use capstone::Insn;
+use capstone::RegId;
+
+use std::collections::HashMap;
+
+// https://github.com/aquynh/capstone/blob/0de0c8b49dba478759eccabb0c9caddc2b653375/include/arm.h#L129
+static REG_ID_PC: RegId = RegId(11);
+
+/// ARM condition codes, which can be appended to the `b` mnemonic.
+const CONDITION_CODES: &[&str] = &[
+    "eq", "ne", "cs", "hs", "cc", "lo", "mi", "pl", "vs", "vc", "hi", "ls", "ge", "lt", "gt", "le",
+];
+
+/// Registers in which the first integer arguments are passed in the AAPCS calling convention.
+const ARGUMENT_REGISTERS: &[&str] = &["r0", "r1", "r2", "r3"];
+
+/// Returns the mnemonic without the Thumb-2 width qualifier (`.w` or `.n`).
+fn strip_width_qualifier(mnemonic: &str) -> &str {
+    mnemonic
+        .trim_end_matches(".w")
+        .trim_end_matches(".n")
+}
+
+/// Returns whether `mnemonic` is a (possibly conditional) jump instruction.
+fn is_jump_mnemonic(mnemonic: &str) -> bool {
+    let mnemonic = strip_width_qualifier(mnemonic);
+    mnemonic == "b"
+        || mnemonic == "bx"
+        || mnemonic == "cbz"
+        || mnemonic == "cbnz"
+        || (mnemonic.starts_with('b') && CONDITION_CODES.contains(&&mnemonic[1..]))
+}
+
+/// Returns whether `mnemonic` is a call instruction.
+fn is_call_mnemonic(mnemonic: &str) -> bool {
+    let mnemonic = strip_width_qualifier(mnemonic);
+    mnemonic == "bl" || mnemonic == "blx"
+}
+
+/// `Disassembler` for 32-bit ARM code in Thumb state (the instruction set of the Cortex-M cores), based on Capstone.
+#[derive(Debug)]
+pub struct ThumbDisassembler {
+    capstone: Capstone,
+}
+
+impl ThumbDisassembler {
+    pub fn new() -> ThumbDisassembler {
+        let mut capstone = Capstone::new()
+            .arm()
+            .mode(capstone::arch::arm::ArchMode::Thumb)
+            .detail(true)
+            .build()
+            .expect("Failed to construct disassembler");
+        capstone
+            .set_detail(true)
+            .expect("Failed to enable detailed mode");
+
+        ThumbDisassembler { capstone }
+    }
+
+    fn register(&self, reg_id: RegId) -> Option<Register> {
+        self.capstone.reg_name(reg_id).map(Register)
+    }
+
+    /// Returns the ARM operand types of `insn`, or `None` if the details could not be decoded.
+    fn operands(&self, insn: &Insn) -> Option<Vec<ArmOperandType>> {
+        let details = self.capstone.insn_detail(insn).ok()?;
+        details
+            .arch_detail()
+            .operands()
+            .into_iter()
+            .map(|operand| match operand {
+                ArchOperand::ArmOperand(operand) => Some(operand.op_type),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Classifies `insn`.
+    ///
+    /// Addresses are either loaded from a literal pool (`ldr rN, [pc, #imm]`), or built with a `movw` + `movt` pair.
+    /// The lower halves loaded by `movw` are tracked in `lower_halves`, until the register is overwritten by another
+    /// instruction.
+    fn classify(&self, insn: &Insn, lower_halves: &mut HashMap<Register, u64>) -> InstructionKind {
+        let mnemonic = strip_width_qualifier(insn.mnemonic().unwrap_or_default());
+        let operands = self.operands(insn).unwrap_or_default();
+
+        // The branch target is always the last operand (`cbz r0, #target`)
+        let target = match operands.last() {
+            Some(ArmOperandType::Imm(target)) => Some(u64::from(*target as u32)),
+            _ => None,
+        };
+        if is_call_mnemonic(mnemonic) {
+            return InstructionKind::Call { target };
+        }
+        if is_jump_mnemonic(mnemonic) {
+            return InstructionKind::Jump { target };
+        }
+
+        let destination = match operands.first() {
+            Some(ArmOperandType::Reg(reg_id)) => self.register(*reg_id),
+            _ => None,
+        };
+        let destination = match destination {
+            Some(destination) => destination,
+            None => return InstructionKind::Other { destination: None },
+        };
+        let lower_half = lower_halves.remove(&destination);
+
+        match (mnemonic, &operands[1..]) {
+            ("ldr", [ArmOperandType::Mem(mem)]) if mem.base() == REG_ID_PC && mem.index() == RegId(0) => {
+                // The pc is 4 bytes ahead in Thumb state, and aligned down to a word for literal loads
+                let literal_address = ((insn.address() + 4) & !3) as i64 + i64::from(mem.disp());
+                InstructionKind::MemoryLoad {
+                    register: destination,
+                    address: literal_address as u64,
+                }
+            }
+            ("movw", [ArmOperandType::Imm(value)]) => {
+                lower_halves.insert(destination.clone(), u64::from(*value as u32 & 0xffff));
+                InstructionKind::Other { destination: Some(destination) }
+            }
+            ("movt", [ArmOperandType::Imm(value)]) => match lower_half {
+                Some(lower) => InstructionKind::AddressLoad {
+                    register: destination,
+                    address: (u64::from(*value as u32 & 0xffff) << 16) | lower,
+                },
+                None => InstructionKind::Other { destination: Some(destination) },
+            },
+            ("mov", [ArmOperandType::Imm(value)]) | ("movs", [ArmOperandType::Imm(value)]) => {
+                InstructionKind::RegisterMove {
+                    destination,
+                    source: Operand::Immediate(u64::from(*value as u32)),
+                }
+            }
+            ("mov", [ArmOperandType::Reg(source)]) => match self.register(*source) {
+                Some(source) => InstructionKind::RegisterMove {
+                    destination,
+                    source: Operand::Register(source),
+                },
+                None => InstructionKind::Other { destination: Some(destination) },
+            },
+            _ => InstructionKind::Other { destination: Some(destination) },
+        }
+    }
+}
+
+impl Disassembler for ThumbDisassembler {
+    fn disassemble(&self, code: &[u8], address: u64) -> Vec<Instruction> {
+        let instructions = self.capstone
+            .disasm_all(code, address)
+            .expect("Failed to disassemble procedure");
+        let mut lower_halves = HashMap::new();
+
+        instructions
+            .iter()
+            .map(|insn| Instruction {
+                address: insn.address(),
+                size: insn.bytes().len() as u64,
+                mnemonic: insn.mnemonic().unwrap_or_default().to_string(),
+                kind: self.classify(&insn, &mut lower_halves),
+            })
+            .collect()
+    }
+
+    fn argument_register(&self, index: usize) -> Option<Register> {
+        ARGUMENT_REGISTERS.get(index).map(|name| Register::new(name))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Verify `bl`, `b`, `beq` and `cbz` are recognized, with targets relative to the instruction address
+    #[test]
+    pub fn test_static_calls() {
+        let assembly = &[
+            0x00, 0xf0, 0x06, 0xf8, // bl #0x1010
+            0x02, 0xe0, // b #0x100c
+            0x01, 0xd0, // beq #0x100c
+            0x00, 0xb1, // cbz r0, #0x100c
+        ];
+
+        assert_eq!(
+            ThumbDisassembler::new()
+                .disassemble(assembly, 0x1000)
+                .into_iter()
+                .map(|insn| insn.kind)
+                .collect::<Vec<_>>(),
+            vec![
+                InstructionKind::Call { target: Some(0x1010) },
+                InstructionKind::Jump { target: Some(0x100c) },
+                InstructionKind::Jump { target: Some(0x100c) },
+                InstructionKind::Jump { target: Some(0x100c) },
+            ]
+        );
+    }
+
+    /// Verify register branches and returns are branches without a static target
+    #[test]
+    pub fn test_register_branches() {
+        let assembly = &[
+            0x98, 0x47, // blx r3
+            0x70, 0x47, // bx lr
+        ];
+
+        assert_eq!(
+            ThumbDisassembler::new()
+                .disassemble(assembly, 0)
+                .into_iter()
+                .map(|insn| insn.kind)
+                .collect::<Vec<_>>(),
+            vec![
+                InstructionKind::Call { target: None },
+                InstructionKind::Jump { target: None },
+            ]
+        );
+    }
+
+    /// Verify the mnemonic helpers do not match instructions that merely start with a `b`
+    #[test]
+    pub fn test_branch_mnemonics() {
+        assert!(is_jump_mnemonic("bne.w"));
+        assert!(is_jump_mnemonic("b.n"));
+        assert!(!is_jump_mnemonic("bic"));
+        assert!(!is_jump_mnemonic("bfi"));
+        assert!(!is_jump_mnemonic("bkpt"));
+        assert!(!is_call_mnemonic("bic"));
+    }
+
+    /// Verify a literal pool load is recognized, with the literal address relative to the aligned pc
+    #[test]
+    pub fn test_literal_pool_load() {
+        let assembly = &[
+            0x01, 0x48, // ldr r0, [pc, #4]
+        ];
+
+        assert_eq!(
+            ThumbDisassembler::new().disassemble(assembly, 0x1002)[0].kind,
+            InstructionKind::MemoryLoad {
+                register: Register::new("r0"),
+                address: 0x1008,
+            }
+        );
+    }
+}
@@ -0,0 +1,159 @@
+// (C) COPYRIGHT 2018 TECHNOLUTION BV, GOUDA NL
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Architecture independent model of disassembled machine code.
+//!
+//! Every supported instruction set implements the `Disassembler` trait, which classifies the instructions that are
+//! relevant for the analysis (calls, jumps, address loads and register moves), with their operands resolved to
+//! addresses. The invocation finders and panic message finders only work on this model.
+
+mod aarch64;
+mod arm;
+mod riscv;
+mod wasm;
+mod x86;
+
+use crate::errors::*;
+use crate::parse::get_endian;
+use crate::wasm::WasmModule;
+
+use gimli::RunTimeEndian;
+
+use object::ElfFile;
+use object::Machine;
+use object::Object;
+
+use std::fmt::Debug;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::fmt;
+
+/// ELF machine identifier for RISC-V, which is not known to `object::Machine`.
+const EM_RISCV: u16 = 243;
+
+/// A register, identified by its (lowercase) name.
+///
+/// Partial registers are named after the full register they are part of (e.g. `esi` on x86_64 is named `rsi`, and
+/// `w1` on AArch64 is named `x1`), so writes to a partial register can be matched against the full register.
+///
+/// For calling conventions that pass arguments on the stack (cdecl on x86), the outgoing argument slots are modelled
+/// as pseudo registers as well, see `Register::stack_slot`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Register(pub String);
+
+impl Register {
+    pub fn new(name: &str) -> Register {
+        Register(name.to_string())
+    }
+
+    /// Returns the pseudo register for the stack slot at `offset` bytes from the stack pointer.
+    pub fn stack_slot(offset: u64) -> Register {
+        Register(format!("[sp+{}]", offset))
+    }
+}
+
+impl Display for Register {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Source operand of a register move.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Operand {
+    Register(Register),
+    Immediate(u64),
+}
+
+/// Classification of an instruction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InstructionKind {
+    /// Call to `target`, or to a target that is not statically known (e.g. a call to a register) if `None`.
+    Call { target: Option<u64> },
+    /// Call to the address stored at `offset` bytes from the address in `base` (e.g. `call qword ptr [rax + 0x18]` on
+    /// x86, which calls a method through a vtable).
+    MemoryCall { base: Register, offset: u64 },
+    /// Call to the address stored at `address` (e.g. `call qword ptr [rip + 0x1234]` on x86_64, which calls a
+    /// procedure through the global offset table).
+    PointerCall { address: u64 },
+    /// Conditional or unconditional jump to `target`, or to a target that is not statically known if `None`.
+    Jump { target: Option<u64> },
+    /// Jump to the address stored at `address` (e.g. `jmp qword ptr [rip + 0x1234]` in a PLT stub on x86_64).
+    PointerJump { address: u64 },
+    /// `address` is loaded into `register` (e.g. `lea` on x86, or an `adrp` + `add` pair on AArch64).
+    /// For address loads that consist of multiple instructions, the last instruction is classified as the address load.
+    AddressLoad { register: Register, address: u64 },
+    /// The word at `address` is loaded into `register` (e.g. a literal pool load on ARM).
+    MemoryLoad { register: Register, address: u64 },
+    /// `source` is copied to `destination`.
+    RegisterMove { destination: Register, source: Operand },
+    /// Any other instruction, with the register it writes (if known).
+    Other { destination: Option<Register> },
+}
+
+/// A disassembled instruction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    pub address: u64,
+    /// Size of the instruction in bytes
+    pub size: u64,
+    pub mnemonic: String,
+    pub kind: InstructionKind,
+}
+
+impl Instruction {
+    /// Returns whether the instruction is a call or a jump.
+    pub fn is_branch(&self) -> bool {
+        matches!(
+            self.kind,
+            InstructionKind::Call { .. }
+                | InstructionKind::MemoryCall { .. }
+                | InstructionKind::PointerCall { .. }
+                | InstructionKind::Jump { .. }
+                | InstructionKind::PointerJump { .. }
+        )
+    }
+}
+
+/// Trait marking objects that are able to disassemble the machine code of a specific instruction set.
+pub trait Disassembler: Debug {
+    /// Disassembles `code`, which is located at `address`.
+    fn disassemble(&self, code: &[u8], address: u64) -> Vec<Instruction>;
+
+    /// Returns the register in which the (integer) argument at `index` is passed, according to the calling convention
+    /// used by Rust on this instruction set. For arguments passed on the stack, the stack slot is returned.
+    /// Returns `None` if the location of the argument is not known.
+    fn argument_register(&self, index: usize) -> Option<Register>;
+}
+
+/// Returns whether `elf` contains RISC-V code.
+fn is_riscv(elf: &ElfFile) -> bool {
+    elf.elf().header.e_machine == EM_RISCV
+}
+
+/// Returns a disassembler for the instruction set of `elf`. Big endian machine code is not supported yet.
+pub fn get_disassembler(elf: &ElfFile) -> Result<Box<dyn Disassembler>> {
+    let is_64 = elf.elf().is_64;
+    match elf.machine() {
+        Machine::Other if !is_riscv(elf) => Err(ErrorKind::NotSupported("an unknown instruction set".to_string()).into()),
+        // The disassemblers decode little endian machine code only
+        _ if get_endian(elf)? == RunTimeEndian::Big => {
+            Err(ErrorKind::NotSupported("big endian machine code".to_string()).into())
+        }
+        Machine::X86 | Machine::X86_64 => Ok(Box::new(x86::X86Disassembler::new(is_64, x86::get_pc_thunks(elf)))),
+        Machine::Arm64 => Ok(Box::new(aarch64::AArch64Disassembler::new())),
+        // Only Thumb code is supported, which is the instruction set of the Cortex-M cores
+        Machine::Arm => Ok(Box::new(arm::ThumbDisassembler::new())),
+        Machine::Other => Ok(Box::new(riscv::RiscVDisassembler::new(is_64))),
+    }
+}
+
+/// Returns a disassembler for the functions of the WebAssembly `module`.
+pub fn get_wasm_disassembler(module: &WasmModule) -> Box<dyn Disassembler> {
+    Box::new(wasm::WasmDisassembler::new(module))
+}
@@ -0,0 +1,580 @@
+// (C) COPYRIGHT 2018 TECHNOLUTION BV, GOUDA NL
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Minimal RISC-V instruction decoder.
+//!
+//! Capstone does not support RISC-V, so the instructions that are relevant for the analysis
+//! (jumps, branches and the instructions used to materialise addresses) are decoded here.
+//! All other instructions are only decoded far enough to know which register they write.
+//! Both the base (32-bit) and the compressed (16-bit, `C` extension) encodings are supported.
+
+use crate::disassembly::Disassembler;
+use crate::disassembly::Instruction;
+use crate::disassembly::InstructionKind;
+use crate::disassembly::Operand;
+use crate::disassembly::Register;
+
+use byteorder::{ByteOrder, LittleEndian};
+
+use std::collections::HashMap;
+
+/// Register holding the return address (`ra`)
+pub const REG_RA: u8 = 1;
+
+/// Width of the integer registers of the RISC-V core the code was compiled for.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Xlen {
+    Rv32,
+    Rv64,
+}
+
+/// The kinds of instructions the call graph builder is interested in.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum RiscVInstructionKind {
+    /// `jal rd, target` (also `c.j` and `c.jal`)
+    Jal { rd: u8, target: u64 },
+    /// `jalr rd, offset(rs1)` (also `c.jr` and `c.jalr`)
+    Jalr { rd: u8, rs1: u8, offset: i64 },
+    /// `auipc rd, imm`, `value` is the address loaded into `rd`
+    Auipc { rd: u8, value: u64 },
+    /// `addi rd, rs1, imm` (also `c.addi`, `c.li` and `c.mv`)
+    Addi { rd: u8, rs1: u8, imm: i64 },
+    /// `lw`/`ld rd, offset(rs1)` (also `c.lw` and `c.ld`)
+    Load { rd: u8, rs1: u8, offset: i64 },
+    /// Conditional branch (`beq`, `bne`, ..., `c.beqz`, `c.bnez`)
+    Branch { target: u64 },
+    /// Any other instruction, with the register it writes (if any)
+    Other { rd: Option<u8> },
+}
+
+/// A decoded RISC-V instruction
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct RiscVInstruction {
+    pub address: u64,
+    /// Size of the instruction in bytes (2 for compressed instructions, 4 otherwise)
+    pub size: u64,
+    pub kind: RiscVInstructionKind,
+}
+
+impl RiscVInstruction {
+    /// Returns the register written by the instruction, if any.
+    pub fn destination_register(&self) -> Option<u8> {
+        match self.kind {
+            RiscVInstructionKind::Jal { rd, .. }
+            | RiscVInstructionKind::Jalr { rd, .. }
+            | RiscVInstructionKind::Auipc { rd, .. }
+            | RiscVInstructionKind::Addi { rd, .. }
+            | RiscVInstructionKind::Load { rd, .. } => Some(rd),
+            RiscVInstructionKind::Other { rd } => rd,
+            RiscVInstructionKind::Branch { .. } => None,
+        }
+    }
+}
+
+/// Keeps track of the addresses loaded into registers by `auipc` instructions.
+///
+/// The value of a register is forgotten as soon as another instruction writes to it.
+#[derive(Debug, Default)]
+pub struct AuipcTracker {
+    values: HashMap<u8, u64>,
+}
+
+impl AuipcTracker {
+    /// Returns the address loaded by `auipc` in `register`, if it was not overwritten since.
+    pub fn get(&self, register: u8) -> Option<u64> {
+        self.values.get(&register).cloned()
+    }
+
+    /// Updates the tracked registers with the effects of `insn`.
+    /// Should be called after the operands of `insn` were inspected, as it may overwrite one of its source registers.
+    pub fn update(&mut self, insn: &RiscVInstruction) {
+        match (insn.kind, insn.destination_register()) {
+            // x0 is hardwired to zero
+            (_, Some(0)) | (_, None) => (),
+            (RiscVInstructionKind::Auipc { rd, value }, _) => {
+                self.values.insert(rd, value);
+            }
+            (_, Some(rd)) => {
+                self.values.remove(&rd);
+            }
+        }
+    }
+}
+
+/// Decodes all instructions in `code`, which is located at `address`.
+/// Decoding stops at the first incomplete instruction.
+pub fn decode(code: &[u8], address: u64, xlen: Xlen) -> Vec<RiscVInstruction> {
+    let mut instructions = vec![];
+    let mut offset = 0;
+
+    while offset + 2 <= code.len() {
+        let insn_address = address + offset as u64;
+        let low = LittleEndian::read_u16(&code[offset..]);
+
+        let (size, kind) = if low & 0b11 == 0b11 {
+            if offset + 4 > code.len() {
+                break;
+            }
+            (4, decode_base(LittleEndian::read_u32(&code[offset..]), insn_address, xlen))
+        } else {
+            (2, decode_compressed(low, insn_address, xlen))
+        };
+
+        instructions.push(RiscVInstruction {
+            address: insn_address,
+            size,
+            kind,
+        });
+        offset += size as usize;
+    }
+
+    instructions
+}
+
+/// Sign extends the lowest `bits` bits of `value`.
+fn sign_extend(value: u32, bits: u32) -> i64 {
+    let shift = 32 - bits;
+    i64::from(((value << shift) as i32) >> shift)
+}
+
+/// Adds `offset` to `address`, wrapping around at the register width.
+fn offset_address(address: u64, offset: i64, xlen: Xlen) -> u64 {
+    let result = (address as i64).wrapping_add(offset) as u64;
+    match xlen {
+        Xlen::Rv32 => result & 0xffff_ffff,
+        Xlen::Rv64 => result,
+    }
+}
+
+/// Decodes a 32-bit (uncompressed) instruction.
+fn decode_base(insn: u32, address: u64, xlen: Xlen) -> RiscVInstructionKind {
+    let opcode = insn & 0x7f;
+    let rd = ((insn >> 7) & 0x1f) as u8;
+    let funct3 = (insn >> 12) & 0x7;
+    let rs1 = ((insn >> 15) & 0x1f) as u8;
+    let i_imm = i64::from((insn as i32) >> 20);
+
+    match opcode {
+        // JAL
+        0x6f => {
+            let imm = ((insn >> 31) & 0x1) << 20
+                | ((insn >> 21) & 0x3ff) << 1
+                | ((insn >> 20) & 0x1) << 11
+                | ((insn >> 12) & 0xff) << 12;
+            RiscVInstructionKind::Jal {
+                rd,
+                target: offset_address(address, sign_extend(imm, 21), xlen),
+            }
+        }
+        // JALR
+        0x67 if funct3 == 0 => RiscVInstructionKind::Jalr {
+            rd,
+            rs1,
+            offset: i_imm,
+        },
+        // AUIPC
+        0x17 => RiscVInstructionKind::Auipc {
+            rd,
+            value: offset_address(address, i64::from((insn & 0xffff_f000) as i32), xlen),
+        },
+        // ADDI
+        0x13 if funct3 == 0 => RiscVInstructionKind::Addi { rd, rs1, imm: i_imm },
+        // LW, LD
+        0x03 if funct3 == 2 || funct3 == 3 => RiscVInstructionKind::Load {
+            rd,
+            rs1,
+            offset: i_imm,
+        },
+        // BEQ, BNE, BLT, BGE, BLTU, BGEU
+        0x63 => {
+            let imm = ((insn >> 31) & 0x1) << 12
+                | ((insn >> 25) & 0x3f) << 5
+                | ((insn >> 8) & 0xf) << 1
+                | ((insn >> 7) & 0x1) << 11;
+            RiscVInstructionKind::Branch {
+                target: offset_address(address, sign_extend(imm, 13), xlen),
+            }
+        }
+        // Other loads, OP-IMM, OP, LUI, OP-IMM-32, OP-32, AMO and SYSTEM (csr) instructions write `rd`
+        0x03 | 0x13 | 0x33 | 0x37 | 0x1b | 0x3b | 0x2f | 0x73 => RiscVInstructionKind::Other { rd: Some(rd) },
+        // Stores, fences and floating point instructions do not write an integer register
+        _ => RiscVInstructionKind::Other { rd: None },
+    }
+}
+
+/// Decodes the offset of a `c.j` or `c.jal` instruction.
+fn compressed_jump_offset(insn: u16) -> i64 {
+    let insn = u32::from(insn);
+    let imm = ((insn >> 12) & 0x1) << 11
+        | ((insn >> 11) & 0x1) << 4
+        | ((insn >> 9) & 0x3) << 8
+        | ((insn >> 8) & 0x1) << 10
+        | ((insn >> 7) & 0x1) << 6
+        | ((insn >> 6) & 0x1) << 7
+        | ((insn >> 3) & 0x7) << 1
+        | ((insn >> 2) & 0x1) << 5;
+    sign_extend(imm, 12)
+}
+
+/// Decodes the offset of a `c.beqz` or `c.bnez` instruction.
+fn compressed_branch_offset(insn: u16) -> i64 {
+    let insn = u32::from(insn);
+    let imm = ((insn >> 12) & 0x1) << 8
+        | ((insn >> 10) & 0x3) << 3
+        | ((insn >> 5) & 0x3) << 6
+        | ((insn >> 3) & 0x3) << 1
+        | ((insn >> 2) & 0x1) << 5;
+    sign_extend(imm, 9)
+}
+
+/// Decodes a 16-bit (compressed) instruction.
+fn decode_compressed(insn: u16, address: u64, xlen: Xlen) -> RiscVInstructionKind {
+    let quadrant = insn & 0b11;
+    let funct3 = (insn >> 13) & 0x7;
+    // Full register number in bits 11:7
+    let rd = ((insn >> 7) & 0x1f) as u8;
+    let rs2 = ((insn >> 2) & 0x1f) as u8;
+    // Registers x8-x15 in bits 9:7 and 4:2
+    let rd_prime_high = 8 + ((insn >> 7) & 0x7) as u8;
+    let rd_prime_low = 8 + ((insn >> 2) & 0x7) as u8;
+
+    match (quadrant, funct3) {
+        // C.ADDI4SPN
+        (0b00, 0b000) => RiscVInstructionKind::Other { rd: Some(rd_prime_low) },
+        // C.LW
+        (0b00, 0b010) => {
+            let offset = ((insn >> 10) & 0x7) << 3 | ((insn >> 6) & 0x1) << 2 | ((insn >> 5) & 0x1) << 6;
+            RiscVInstructionKind::Load {
+                rd: rd_prime_low,
+                rs1: rd_prime_high,
+                offset: i64::from(offset),
+            }
+        }
+        // C.LD (RV64)
+        (0b00, 0b011) if xlen == Xlen::Rv64 => {
+            let offset = ((insn >> 10) & 0x7) << 3 | ((insn >> 5) & 0x3) << 6;
+            RiscVInstructionKind::Load {
+                rd: rd_prime_low,
+                rs1: rd_prime_high,
+                offset: i64::from(offset),
+            }
+        }
+        // C.ADDI
+        (0b01, 0b000) => {
+            let imm = u32::from(((insn >> 12) & 0x1) << 5 | ((insn >> 2) & 0x1f));
+            RiscVInstructionKind::Addi {
+                rd,
+                rs1: rd,
+                imm: sign_extend(imm, 6),
+            }
+        }
+        // C.JAL (RV32)
+        (0b01, 0b001) if xlen == Xlen::Rv32 => RiscVInstructionKind::Jal {
+            rd: REG_RA,
+            target: offset_address(address, compressed_jump_offset(insn), xlen),
+        },
+        // C.LI
+        (0b01, 0b010) => {
+            let imm = u32::from(((insn >> 12) & 0x1) << 5 | ((insn >> 2) & 0x1f));
+            RiscVInstructionKind::Addi {
+                rd,
+                rs1: 0,
+                imm: sign_extend(imm, 6),
+            }
+        }
+        // C.ADDIW (RV64), C.LUI / C.ADDI16SP
+        (0b01, 0b001) | (0b01, 0b011) => RiscVInstructionKind::Other { rd: Some(rd) },
+        // C.SRLI, C.SRAI, C.ANDI, C.SUB, C.XOR, C.OR, C.AND, C.SUBW, C.ADDW
+        (0b01, 0b100) => RiscVInstructionKind::Other { rd: Some(rd_prime_high) },
+        // C.J
+        (0b01, 0b101) => RiscVInstructionKind::Jal {
+            rd: 0,
+            target: offset_address(address, compressed_jump_offset(insn), xlen),
+        },
+        // C.BEQZ, C.BNEZ
+        (0b01, 0b110) | (0b01, 0b111) => RiscVInstructionKind::Branch {
+            target: offset_address(address, compressed_branch_offset(insn), xlen),
+        },
+        // C.SLLI, C.LWSP, C.LDSP
+        (0b10, 0b000) | (0b10, 0b010) => RiscVInstructionKind::Other { rd: Some(rd) },
+        (0b10, 0b011) if xlen == Xlen::Rv64 => RiscVInstructionKind::Other { rd: Some(rd) },
+        (0b10, 0b100) => {
+            let bit12 = (insn >> 12) & 0x1;
+            match (bit12, rd, rs2) {
+                // C.JR
+                (0, rs1, 0) if rs1 != 0 => RiscVInstructionKind::Jalr { rd: 0, rs1, offset: 0 },
+                // C.EBREAK
+                (1, 0, 0) => RiscVInstructionKind::Other { rd: None },
+                // C.JALR
+                (1, rs1, 0) => RiscVInstructionKind::Jalr {
+                    rd: REG_RA,
+                    rs1,
+                    offset: 0,
+                },
+                // C.MV
+                (0, _, rs2) => RiscVInstructionKind::Addi { rd, rs1: rs2, imm: 0 },
+                // C.ADD
+                _ => RiscVInstructionKind::Other { rd: Some(rd) },
+            }
+        }
+        // Stores and floating point loads
+        _ => RiscVInstructionKind::Other { rd: None },
+    }
+}
+
+/// ABI names of the integer registers.
+const REGISTER_NAMES: [&str; 32] = [
+    "zero", "ra", "sp", "gp", "tp", "t0", "t1", "t2", "s0", "s1", "a0", "a1", "a2", "a3", "a4", "a5", "a6", "a7",
+    "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11", "t3", "t4", "t5", "t6",
+];
+
+/// Registers in which the first integer arguments are passed in the standard calling convention.
+const ARGUMENT_REGISTERS: &[u8] = &[10, 11, 12, 13, 14, 15, 16, 17];
+
+/// Returns the ABI name of integer register `number`.
+fn register(number: u8) -> Register {
+    Register::new(REGISTER_NAMES[number as usize])
+}
+
+/// `Disassembler` for RISC-V (rv32 and rv64) code, based on the decoder in this module.
+///
+/// Addresses are materialised using `auipc` pairs: `auipc` + `jalr` for calls (`call` and `tail` pseudo instructions),
+/// `auipc` + `addi` for address loads and `auipc` + `lw`/`ld` for loads from memory (e.g. the global offset table).
+#[derive(Debug)]
+pub struct RiscVDisassembler {
+    xlen: Xlen,
+}
+
+impl RiscVDisassembler {
+    pub fn new(is_64: bool) -> RiscVDisassembler {
+        RiscVDisassembler {
+            xlen: if is_64 { Xlen::Rv64 } else { Xlen::Rv32 },
+        }
+    }
+
+    /// Classifies `insn`, given the addresses loaded by `auipc` instructions preceding it.
+    fn classify(&self, insn: &RiscVInstruction, auipc_values: &AuipcTracker) -> InstructionKind {
+        let link = |rd: u8, target: Option<u64>| {
+            // The return address is discarded (written to `x0`) by jumps
+            if rd == 0 {
+                InstructionKind::Jump { target }
+            } else {
+                InstructionKind::Call { target }
+            }
+        };
+
+        match insn.kind {
+            RiscVInstructionKind::Jal { rd, target } => link(rd, Some(target)),
+            RiscVInstructionKind::Jalr { rd, rs1, offset } => link(
+                rd,
+                auipc_values.get(rs1).map(|value| offset_address(value, offset, self.xlen)),
+            ),
+            RiscVInstructionKind::Branch { target } => InstructionKind::Jump { target: Some(target) },
+            RiscVInstructionKind::Addi { rd: 0, .. } => InstructionKind::Other { destination: None },
+            RiscVInstructionKind::Addi { rd, rs1, imm } => match auipc_values.get(rs1) {
+                Some(value) => InstructionKind::AddressLoad {
+                    register: register(rd),
+                    address: offset_address(value, imm, self.xlen),
+                },
+                // `li rd, imm`
+                None if rs1 == 0 => InstructionKind::RegisterMove {
+                    destination: register(rd),
+                    source: Operand::Immediate(offset_address(0, imm, self.xlen)),
+                },
+                // `mv rd, rs1`
+                None if imm == 0 => InstructionKind::RegisterMove {
+                    destination: register(rd),
+                    source: Operand::Register(register(rs1)),
+                },
+                None => InstructionKind::Other { destination: Some(register(rd)) },
+            },
+            RiscVInstructionKind::Load { rd, rs1, offset } => match auipc_values.get(rs1) {
+                Some(value) => InstructionKind::MemoryLoad {
+                    register: register(rd),
+                    address: offset_address(value, offset, self.xlen),
+                },
+                None => InstructionKind::Other { destination: Some(register(rd)) },
+            },
+            RiscVInstructionKind::Auipc { rd, .. } => InstructionKind::Other { destination: Some(register(rd)) },
+            RiscVInstructionKind::Other { rd } => InstructionKind::Other { destination: rd.map(register) },
+        }
+    }
+}
+
+/// Returns the mnemonic of the instruction kind, as the decoder does not distinguish the other instructions.
+fn mnemonic(kind: &RiscVInstructionKind) -> &'static str {
+    match kind {
+        RiscVInstructionKind::Jal { .. } => "jal",
+        RiscVInstructionKind::Jalr { .. } => "jalr",
+        RiscVInstructionKind::Auipc { .. } => "auipc",
+        RiscVInstructionKind::Addi { .. } => "addi",
+        RiscVInstructionKind::Load { .. } => "load",
+        RiscVInstructionKind::Branch { .. } => "branch",
+        RiscVInstructionKind::Other { .. } => "other",
+    }
+}
+
+impl Disassembler for RiscVDisassembler {
+    fn disassemble(&self, code: &[u8], address: u64) -> Vec<Instruction> {
+        let mut auipc_values = AuipcTracker::default();
+
+        decode(code, address, self.xlen)
+            .into_iter()
+            .map(|insn| {
+                let kind = self.classify(&insn, &auipc_values);
+                auipc_values.update(&insn);
+
+                Instruction {
+                    address: insn.address,
+                    size: insn.size,
+                    mnemonic: mnemonic(&insn.kind).to_string(),
+                    kind,
+                }
+            })
+            .collect()
+    }
+
+    fn argument_register(&self, index: usize) -> Option<Register> {
+        ARGUMENT_REGISTERS.get(index).map(|number| register(*number))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Verify the base encodings of `auipc`, `jalr`, `jal`, `addi` and `beq` are decoded
+    #[test]
+    pub fn test_decode_base() {
+        let code = &[
+            0x97, 0x00, 0x00, 0x00, // auipc ra, 0x0
+            0xe7, 0x80, 0x00, 0x01, // jalr ra, 16(ra)
+            0xef, 0x00, 0x80, 0x00, // jal ra, +8
+            0x13, 0x05, 0x05, 0x01, // addi a0, a0, 16
+            0x63, 0x08, 0xb5, 0x00, // beq a0, a1, +16
+        ];
+
+        let instructions = decode(code, 0x1000, Xlen::Rv64);
+
+        assert_eq!(
+            instructions.iter().map(|insn| insn.kind).collect::<Vec<_>>(),
+            vec![
+                RiscVInstructionKind::Auipc { rd: 1, value: 0x1000 },
+                RiscVInstructionKind::Jalr { rd: 1, rs1: 1, offset: 16 },
+                RiscVInstructionKind::Jal { rd: 1, target: 0x1010 },
+                RiscVInstructionKind::Addi { rd: 10, rs1: 10, imm: 16 },
+                RiscVInstructionKind::Branch { target: 0x1020 },
+            ]
+        );
+    }
+
+    /// Verify compressed instructions are decoded, and have size 2
+    #[test]
+    pub fn test_decode_compressed() {
+        let code = &[
+            0x11, 0xa0, // c.j +4
+            0x82, 0x90, // c.jalr ra
+            0x82, 0x80, // c.jr ra (ret)
+            0x01, 0xc1, // c.beqz a0, +0
+        ];
+
+        let instructions = decode(code, 0x2000, Xlen::Rv64);
+
+        assert!(instructions.iter().all(|insn| insn.size == 2));
+        assert_eq!(
+            instructions.iter().map(|insn| insn.kind).collect::<Vec<_>>(),
+            vec![
+                RiscVInstructionKind::Jal { rd: 0, target: 0x2004 },
+                RiscVInstructionKind::Jalr { rd: 1, rs1: 1, offset: 0 },
+                RiscVInstructionKind::Jalr { rd: 0, rs1: 1, offset: 0 },
+                RiscVInstructionKind::Branch { target: 0x2006 },
+            ]
+        );
+    }
+
+    /// Verify `c.jal` is only decoded for RV32, where it shares its encoding with `c.addiw` on RV64
+    #[test]
+    pub fn test_decode_compressed_jal_rv32() {
+        let code = &[0x11, 0x20]; // c.jal +4 (RV32) / c.addiw zero, 4 (RV64)
+
+        assert_eq!(
+            decode(code, 0x3000, Xlen::Rv32)[0].kind,
+            RiscVInstructionKind::Jal { rd: REG_RA, target: 0x3004 }
+        );
+        assert_eq!(
+            decode(code, 0x3000, Xlen::Rv64)[0].kind,
+            RiscVInstructionKind::Other { rd: Some(0) }
+        );
+    }
+
+    /// Verify `auipc` + `jalr`, `jal` and `c.j` are classified as calls and jumps
+    #[test]
+    pub fn test_static_calls() {
+        let code = &[
+            0x97, 0x00, 0x00, 0x00, // auipc ra, 0x0
+            0xe7, 0x80, 0x00, 0x01, // jalr ra, 16(ra)
+            0xef, 0x00, 0x80, 0x00, // jal ra, +8
+            0x11, 0xa0, // c.j +4
+        ];
+
+        let instructions = RiscVDisassembler::new(true).disassemble(code, 0x1000);
+
+        assert_eq!(
+            instructions.into_iter().skip(1).map(|insn| insn.kind).collect::<Vec<_>>(),
+            vec![
+                InstructionKind::Call { target: Some(0x1010) },
+                InstructionKind::Call { target: Some(0x1010) },
+                InstructionKind::Jump { target: Some(0x1010) },
+            ]
+        );
+    }
+
+    /// Verify `jalr` to a register that was not loaded by `auipc` and returns have no static target
+    #[test]
+    pub fn test_register_branches() {
+        let code = &[
+            0x97, 0x00, 0x00, 0x00, // auipc ra, 0x0
+            0x93, 0x00, 0x00, 0x00, // addi ra, zero, 0 (overwrites ra)
+            0xe7, 0x80, 0x00, 0x01, // jalr ra, 16(ra)
+            0x82, 0x80, // ret
+        ];
+
+        let instructions = RiscVDisassembler::new(true).disassemble(code, 0x1000);
+
+        assert_eq!(instructions[2].kind, InstructionKind::Call { target: None });
+        assert_eq!(instructions[3].kind, InstructionKind::Jump { target: None });
+    }
+
+    /// Verify `auipc` + `addi` and `auipc` + `ld` pairs are classified as address and memory loads
+    #[test]
+    pub fn test_address_loads() {
+        let code = &[
+            0x17, 0x15, 0x00, 0x00, // auipc a0, 0x1
+            0x13, 0x05, 0x05, 0x01, // addi a0, a0, 16
+            0x97, 0x15, 0x00, 0x00, // auipc a1, 0x1
+            0x83, 0xb5, 0x85, 0x00, // ld a1, 8(a1)
+        ];
+
+        let instructions = RiscVDisassembler::new(true).disassemble(code, 0x2000);
+
+        assert_eq!(
+            instructions[1].kind,
+            InstructionKind::AddressLoad {
+                register: Register::new("a0"),
+                address: 0x3010,
+            }
+        );
+        assert_eq!(
+            instructions[3].kind,
+            InstructionKind::MemoryLoad {
+                register: Register::new("a1"),
+                address: 0x3010,
+            }
+        );
+    }
+}
@@ -0,0 +1,323 @@
+// (C) COPYRIGHT 2018 TECHNOLUTION BV, GOUDA NL
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Minimal WebAssembly instruction decoder.
+//!
+//! Capstone does not support WebAssembly, so the instructions are decoded here. Only the calls are classified: `call`
+//! and `return_call` by the index of the called function, which is resolved to its address, and `call_indirect` and
+//! `call_ref` (and their tail call variants) as calls to a target that is not statically known. All other
+//! instructions are only decoded far enough to skip their immediates. WebAssembly has no registers, and values are not
+//! loaded from addresses in the code, so address loads and register moves do not occur.
+//!
+//! Decoding stops at the first instruction with an unknown opcode (e.g. of the GC proposal).
+
+use crate::disassembly::Disassembler;
+use crate::disassembly::Instruction;
+use crate::disassembly::InstructionKind;
+use crate::disassembly::Register;
+use crate::errors::*;
+use crate::wasm::Reader;
+use crate::wasm::WasmModule;
+
+use std::collections::HashSet;
+
+/// The kinds of instructions the call graph builder is interested in.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum WasmInstructionKind {
+    /// `call`, with the index of the called function
+    Call { function: u32 },
+    /// `return_call`, with the index of the called function
+    ReturnCall { function: u32 },
+    /// `call_indirect` or `call_ref`
+    IndirectCall,
+    /// `return_call_indirect` or `return_call_ref`
+    ReturnIndirectCall,
+    Other,
+}
+
+/// Disassembler of the functions of a WebAssembly module.
+#[derive(Debug)]
+pub struct WasmDisassembler {
+    /// Addresses of the functions, by function index
+    function_addresses: Vec<u64>,
+    /// Addresses at which the body of a function starts with the declarations of its locals
+    body_addresses: HashSet<u64>,
+}
+
+impl WasmDisassembler {
+    pub fn new(module: &WasmModule) -> WasmDisassembler {
+        WasmDisassembler {
+            function_addresses: module.functions.iter().map(|function| function.address).collect(),
+            body_addresses: module
+                .functions
+                .iter()
+                .filter(|function| !function.imported)
+                .map(|function| function.address)
+                .collect(),
+        }
+    }
+}
+
+/// Returns the mnemonic of the instruction kind, as the decoder does not distinguish the other instructions.
+fn mnemonic(kind: &WasmInstructionKind) -> &'static str {
+    match kind {
+        WasmInstructionKind::Call { .. } => "call",
+        WasmInstructionKind::ReturnCall { .. } => "return_call",
+        WasmInstructionKind::IndirectCall => "call_indirect",
+        WasmInstructionKind::ReturnIndirectCall => "return_call_indirect",
+        WasmInstructionKind::Other => "other",
+    }
+}
+
+impl Disassembler for WasmDisassembler {
+    fn disassemble(&self, code: &[u8], address: u64) -> Vec<Instruction> {
+        let mut reader = Reader::new(code);
+        if self.body_addresses.contains(&address) && skip_locals(&mut reader).is_err() {
+            return vec![];
+        }
+
+        let mut instructions = vec![];
+        while !reader.is_empty() {
+            let offset = reader.position();
+            let kind = match decode(&mut reader) {
+                Ok(kind) => kind,
+                Err(_) => break,
+            };
+            let function_address = |function: u32| self.function_addresses.get(function as usize).cloned();
+
+            instructions.push(Instruction {
+                address: address + offset as u64,
+                size: (reader.position() - offset) as u64,
+                mnemonic: mnemonic(&kind).to_string(),
+                kind: match kind {
+                    WasmInstructionKind::Call { function } => InstructionKind::Call {
+                        target: function_address(function),
+                    },
+                    WasmInstructionKind::ReturnCall { function } => InstructionKind::Jump {
+                        target: function_address(function),
+                    },
+                    WasmInstructionKind::IndirectCall => InstructionKind::Call { target: None },
+                    WasmInstructionKind::ReturnIndirectCall => InstructionKind::Jump { target: None },
+                    WasmInstructionKind::Other => InstructionKind::Other { destination: None },
+                },
+            });
+        }
+        instructions
+    }
+
+    fn argument_register(&self, _index: usize) -> Option<Register> {
+        // Arguments are passed on the value stack
+        None
+    }
+}
+
+/// Decodes the instruction at the position of `reader`, and skips its immediates.
+/// Returns an error for unknown opcodes and truncated instructions.
+fn decode(reader: &mut Reader) -> Result<WasmInstructionKind> {
+    let opcode = reader.read_u8()?;
+    match opcode {
+        // call
+        0x10 => return Ok(WasmInstructionKind::Call { function: reader.read_u32()? }),
+        // return_call
+        0x12 => return Ok(WasmInstructionKind::ReturnCall { function: reader.read_u32()? }),
+        // call_indirect: type index and table index
+        0x11 => {
+            reader.read_leb()?;
+            reader.read_leb()?;
+            return Ok(WasmInstructionKind::IndirectCall);
+        }
+        // return_call_indirect
+        0x13 => {
+            reader.read_leb()?;
+            reader.read_leb()?;
+            return Ok(WasmInstructionKind::ReturnIndirectCall);
+        }
+        // call_ref: type index
+        0x14 => {
+            reader.read_leb()?;
+            return Ok(WasmInstructionKind::IndirectCall);
+        }
+        // return_call_ref
+        0x15 => {
+            reader.read_leb()?;
+            return Ok(WasmInstructionKind::ReturnIndirectCall);
+        }
+        // unreachable, nop, else, throw_ref, end, return, catch_all, drop, select
+        0x00 | 0x01 | 0x05 | 0x0a | 0x0b | 0x0f | 0x19 | 0x1a | 0x1b => (),
+        // block, loop, if, try
+        0x02..=0x04 | 0x06 => skip_block_type(reader)?,
+        // catch, throw, rethrow, br, br_if, delegate, local.*, global.*, table.get, table.set, memory.size,
+        // memory.grow, i32.const, i64.const, ref.null, ref.func, br_on_null, br_on_non_null
+        0x07..=0x09 | 0x0c | 0x0d | 0x18 | 0x20..=0x26 | 0x3f..=0x42 | 0xd0 | 0xd2 | 0xd4 | 0xd6 => {
+            reader.read_leb()?;
+        }
+        // br_table: the label indices, followed by the default label
+        0x0e => {
+            reader.read_vec(Reader::read_leb)?;
+            reader.read_leb()?;
+        }
+        // select with types
+        0x1c => {
+            reader.read_vec(Reader::skip_value_type)?;
+        }
+        // try_table: the block type, followed by the catch clauses
+        0x1f => {
+            skip_block_type(reader)?;
+            reader.read_vec(|reader| {
+                // catch and catch_ref have a tag index, catch_all and catch_all_ref only a label
+                if reader.read_u8()? < 2 {
+                    reader.read_leb()?;
+                }
+                reader.read_leb()
+            })?;
+        }
+        // Loads and stores
+        0x28..=0x3e => skip_memory_argument(reader)?,
+        // f32.const, f64.const
+        0x43 => {
+            reader.read_bytes(4)?;
+        }
+        0x44 => {
+            reader.read_bytes(8)?;
+        }
+        // Numeric instructions, ref.is_null, ref.as_non_null, ref.eq
+        0x45..=0xc4 | 0xd1 | 0xd3 | 0xd5 => (),
+        // Saturating truncation, bulk memory and table instructions
+        0xfc => match reader.read_u32()? {
+            0..=7 => (),
+            // memory.init, memory.copy, table.init, table.copy
+            8 | 10 | 12 | 14 => {
+                reader.read_leb()?;
+                reader.read_leb()?;
+            }
+            // data.drop, memory.fill, elem.drop, table.grow, table.size, table.fill
+            9 | 11 | 13 | 15..=17 => {
+                reader.read_leb()?;
+            }
+            _ => bail!(ErrorKind::NotSupported(format!("WebAssembly opcode {:#x}", opcode))),
+        },
+        // Vector instructions
+        0xfd => match reader.read_u32()? {
+            // Loads and stores
+            0..=11 | 92 | 93 => skip_memory_argument(reader)?,
+            // v128.const, i8x16.shuffle
+            12 | 13 => {
+                reader.read_bytes(16)?;
+            }
+            // Lane extraction and replacement
+            21..=34 => {
+                reader.read_u8()?;
+            }
+            // Lane loads and stores
+            84..=91 => {
+                skip_memory_argument(reader)?;
+                reader.read_u8()?;
+            }
+            _ => (),
+        },
+        // Atomic instructions, of which atomic.fence has a reserved byte and the others access memory
+        0xfe => match reader.read_u32()? {
+            3 => {
+                reader.read_u8()?;
+            }
+            _ => skip_memory_argument(reader)?,
+        },
+        _ => bail!(ErrorKind::NotSupported(format!("WebAssembly opcode {:#x}", opcode))),
+    }
+    Ok(WasmInstructionKind::Other)
+}
+
+/// Skips the declarations of the locals at the start of the code of a function: a vector of counts and value types.
+fn skip_locals(reader: &mut Reader) -> Result<()> {
+    reader
+        .read_vec(|reader| {
+            reader.read_u32()?;
+            reader.skip_value_type()
+        })
+        .map(|_| ())
+}
+
+/// Skips the type of a block: empty (`0x40`), a value type, or the index of a function type.
+fn skip_block_type(reader: &mut Reader) -> Result<()> {
+    // Value types are a single byte or a reference type with a heap type, both of which are read as a LEB128 integer
+    // like type indices
+    match reader.remaining().first() {
+        Some(0x63) | Some(0x64) => reader.skip_value_type(),
+        _ => reader.read_leb().map(|_| ()),
+    }
+}
+
+/// Skips the alignment and offset of a memory access, preceded by a memory index if bit 6 of the alignment is set.
+fn skip_memory_argument(reader: &mut Reader) -> Result<()> {
+    let alignment = reader.read_u32()?;
+    if alignment & 0x40 != 0 {
+        reader.read_leb()?;
+    }
+    reader.read_leb().map(|_| ())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Verify calls are decoded, and the immediates of other instructions are skipped
+    #[test]
+    pub fn test_decode() {
+        let disassembler = WasmDisassembler {
+            function_addresses: vec![0x1_0000_0000, 0x10, 0x40],
+            body_addresses: [0x10, 0x40].iter().cloned().collect(),
+        };
+        let code = &[
+            0x02, 0x02, 0x7f, 0x01, 0x7e, // 2 locals of type i32, 1 of type i64
+            0x02, 0x40, // block
+            0x28, 0x02, 0x08, // i32.load offset=8
+            0x0e, 0x02, 0x00, 0x01, 0x00, // br_table 0 1 0
+            0x0b, // end
+            0x42, 0x80, 0x80, 0x04, // i64.const 65536
+            0x10, 0x02, // call 2
+            0xfd, 0x0c, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, // v128.const 0
+            0x11, 0x01, 0x00, // call_indirect (type 1)
+            0x10, 0x00, // call 0
+            0x12, 0x01, // return_call 1
+        ];
+
+        let instructions = disassembler.disassemble(code, 0x40);
+
+        assert_eq!(
+            instructions
+                .iter()
+                .map(|insn| (insn.address, insn.size, insn.kind.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                (0x45, 2, InstructionKind::Other { destination: None }),
+                (0x47, 3, InstructionKind::Other { destination: None }),
+                (0x4a, 5, InstructionKind::Other { destination: None }),
+                (0x4f, 1, InstructionKind::Other { destination: None }),
+                (0x50, 4, InstructionKind::Other { destination: None }),
+                (0x54, 2, InstructionKind::Call { target: Some(0x40) }),
+                (0x56, 18, InstructionKind::Other { destination: None }),
+                (0x68, 3, InstructionKind::Call { target: None }),
+                (0x6b, 2, InstructionKind::Call { target: Some(0x1_0000_0000) }),
+                (0x6d, 2, InstructionKind::Jump { target: Some(0x10) }),
+            ]
+        );
+        assert_eq!(instructions[7].mnemonic, "call_indirect");
+    }
+
+    /// Verify decoding stops at an unknown opcode
+    #[test]
+    pub fn test_decode_unknown_opcode() {
+        let disassembler = WasmDisassembler {
+            function_addresses: vec![0x10],
+            body_addresses: HashSet::new(),
+        };
+        let code = &[0x10, 0x00, 0xfb, 0x00, 0x10, 0x00];
+
+        assert_eq!(disassembler.disassemble(code, 0x20).len(), 1);
+    }
+}
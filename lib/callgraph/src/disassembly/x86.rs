@@ -0,0 +1,538 @@
+// (C) COPYRIGHT 2018 TECHNOLUTION BV, GOUDA NL
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use crate::disassembly::Disassembler;
+use crate::disassembly::Instruction;
+use crate::disassembly::InstructionKind;
+use crate::disassembly::Operand;
+use crate::disassembly::Register;
+
+use capstone::arch::x86::X86OpMem;
+use capstone::arch::x86::X86OperandType;
+use capstone::arch::ArchOperand;
+use capstone::arch::BuildsCapstone;
+use capstone::Capstone;
+use capstone::Insn;
+use capstone::InsnGroupId;
+use capstone::RegId;
+
+use object::ElfFile;
+use object::Object;
+
+use std::collections::HashMap;
+
+// https://github.com/aquynh/capstone/blob/0de0c8b49dba478759eccabb0c9caddc2b653375/include/x86.h#L1567
+static GROUP_ID_JUMP: InsnGroupId = InsnGroupId(1);
+static GROUP_ID_CALL: InsnGroupId = InsnGroupId(2);
+
+/// Names of the general purpose registers which have partial registers with an irregular name,
+/// as (64-bit, 32-bit, 16-bit, low 8-bit, high 8-bit) tuples.
+const LEGACY_REGISTERS: &[[&str; 5]] = &[
+    ["rax", "eax", "ax", "al", "ah"],
+    ["rbx", "ebx", "bx", "bl", "bh"],
+    ["rcx", "ecx", "cx", "cl", "ch"],
+    ["rdx", "edx", "dx", "dl", "dh"],
+    ["rsi", "esi", "si", "sil", ""],
+    ["rdi", "edi", "di", "dil", ""],
+    ["rbp", "ebp", "bp", "bpl", ""],
+    ["rsp", "esp", "sp", "spl", ""],
+];
+
+/// Registers in which the first integer arguments are passed in the System V AMD64 calling convention.
+const ARGUMENT_REGISTERS_64: &[&str] = &["rdi", "rsi", "rdx", "rcx", "r8", "r9"];
+
+/// Registers that are not preserved across calls in the cdecl calling convention.
+const CALLER_SAVED_REGISTERS_32: &[&str] = &["eax", "ecx", "edx"];
+
+/// Size of a stack slot in the cdecl calling convention.
+const STACK_SLOT_SIZE: u64 = 4;
+
+/// Prefix of the names of the procedures that return their return address in a register, used by position independent
+/// 32-bit code to find the address of the GOT (e.g. `__x86.get_pc_thunk.bx` returns it in `ebx`).
+const PC_THUNK_PREFIX: &str = "__x86.get_pc_thunk.";
+
+/// Returns the `__x86.get_pc_thunk.*` procedures in `elf`, mapped to the register they load the program counter in.
+pub fn get_pc_thunks(elf: &ElfFile) -> HashMap<u64, Register> {
+    elf.symbols()
+        .filter_map(|symbol| {
+            let register = symbol.name()?.strip_prefix(PC_THUNK_PREFIX)?;
+            Some((symbol.address(), Register(format!("e{}", register))))
+        })
+        .collect()
+}
+
+/// Values of the registers that are tracked while disassembling 32-bit position independent code.
+///
+/// Position independent code on x86 can not address memory relative to the program counter. Instead, the program
+/// counter is loaded into a register, either by a call to a `__x86.get_pc_thunk.*` procedure, or by a `call` to the
+/// next instruction followed by a `pop`. An `add` then turns it into the address of the GOT, relative to which all
+/// data is addressed.
+#[derive(Default)]
+struct PicRegisters {
+    /// Registers holding an address
+    values: HashMap<Register, u64>,
+    /// Return address pushed by a `call` to the next instruction, which is popped into a register next
+    pushed_return_address: Option<u64>,
+}
+
+/// `Disassembler` for x86 and x86_64 code, based on Capstone.
+#[derive(Debug)]
+pub struct X86Disassembler {
+    capstone: Capstone,
+    is_64: bool,
+    /// Start addresses of the `__x86.get_pc_thunk.*` procedures, mapped to the register they load
+    pc_thunks: HashMap<u64, Register>,
+}
+
+impl X86Disassembler {
+    pub fn new(is_64: bool, pc_thunks: HashMap<u64, Register>) -> X86Disassembler {
+        let mode = if is_64 {
+            capstone::arch::x86::ArchMode::Mode64
+        } else {
+            capstone::arch::x86::ArchMode::Mode32
+        };
+
+        let mut capstone = Capstone::new()
+            .x86()
+            .mode(mode)
+            .detail(true)
+            .build()
+            .expect("Failed to construct disassembler");
+        capstone
+            .set_detail(true)
+            .expect("Failed to enable detailed mode");
+
+        X86Disassembler {
+            capstone,
+            is_64,
+            pc_thunks,
+        }
+    }
+
+    /// Returns the name of `reg_id`, named after the full register it is part of.
+    fn register(&self, reg_id: RegId) -> Option<Register> {
+        let name = self.capstone.reg_name(reg_id)?;
+        let full_width = if self.is_64 { 0 } else { 1 };
+
+        if let Some(names) = LEGACY_REGISTERS.iter().find(|names| names.contains(&&name[..])) {
+            return Some(Register::new(names[full_width]));
+        }
+
+        // r8 - r15, with their partial registers r8d, r8w and r8b
+        let without_suffix = name.trim_end_matches(|c| c == 'd' || c == 'w' || c == 'b');
+        if without_suffix.starts_with('r') && without_suffix[1..].parse::<u8>().is_ok() {
+            return Some(Register::new(without_suffix));
+        }
+
+        Some(Register(name))
+    }
+
+    /// Returns the address referenced by `mem`, if it can be determined statically.
+    fn memory_address(&self, insn: &Insn, mem: &X86OpMem, pic_registers: &PicRegisters) -> Option<u64> {
+        // We cannot determine the value of an index register accurately
+        if mem.index() != RegId(0) {
+            return None;
+        }
+
+        // Formula: address = base_reg_value + displacement
+        let base_reg_value = match mem.base() {
+            // No base register specified, so the address is absolute
+            RegId(0) => 0,
+            // rip is updated before the instruction is executed, therefore the instruction size is added
+            base if self.capstone.reg_name(base).as_ref().map(String::as_str) == Some("rip") => {
+                insn.address() + insn.bytes().len() as u64
+            }
+            // Register holding the address of the GOT in 32-bit position independent code, or another register,
+            // for which we can not determine the value
+            base => *pic_registers.values.get(&self.register(base)?)?,
+        };
+
+        Some((base_reg_value as i64).wrapping_add(mem.disp()) as u64)
+    }
+
+    /// Returns the base register and (non-negative) offset of `mem`, if it is addressed relative to a general purpose
+    /// register only (`[rax + offset]`).
+    fn register_relative(&self, mem: &X86OpMem) -> Option<(Register, u64)> {
+        if mem.index() != RegId(0) || mem.base() == RegId(0) || mem.disp() < 0 {
+            return None;
+        }
+        let base = self.register(mem.base())?;
+        if base.0 == "rip" {
+            return None;
+        }
+        Some((base, mem.disp() as u64))
+    }
+
+    /// Returns the stack slot `mem` refers to, if it is addressed relative to the stack pointer (`[esp + offset]`).
+    fn stack_slot(&self, mem: &X86OpMem) -> Option<Register> {
+        if mem.index() != RegId(0) || mem.disp() < 0 || self.register(mem.base())? != Register::new("esp") {
+            return None;
+        }
+        Some(Register::stack_slot(mem.disp() as u64))
+    }
+
+    /// Returns the source operand of a move or push with `operand` as source.
+    fn source_operand(&self, operand: &X86OperandType) -> Option<Operand> {
+        match operand {
+            X86OperandType::Imm(value) => Some(Operand::Immediate(*value as u64)),
+            X86OperandType::Reg(reg_id) => self.register(*reg_id).map(Operand::Register),
+            _ => None,
+        }
+    }
+
+    /// Classifies `insn`.
+    ///
+    /// In 32-bit code, the registers holding the program counter or the address of the GOT are tracked in
+    /// `pic_registers`, until the register is overwritten by another instruction.
+    fn classify(&self, insn: &Insn, pic_registers: &mut PicRegisters) -> InstructionKind {
+        let pushed_return_address = pic_registers.pushed_return_address.take();
+        let groups = match self.capstone.insn_group_ids(insn) {
+            Ok(groups) => groups.collect::<Vec<_>>(),
+            Err(_) => return InstructionKind::Other { destination: None },
+        };
+        let operands = match self.capstone.insn_detail(insn) {
+            Ok(details) => details
+                .arch_detail()
+                .operands()
+                .into_iter()
+                .filter_map(|operand| match operand {
+                    ArchOperand::X86Operand(operand) => Some(operand.op_type),
+                    _ => None,
+                })
+                .collect::<Vec<_>>(),
+            Err(_) => return InstructionKind::Other { destination: None },
+        };
+
+        let target = match operands.first() {
+            Some(X86OperandType::Imm(target)) if operands.len() == 1 => Some(*target as u64),
+            _ => None,
+        };
+        if groups.contains(&GROUP_ID_CALL) {
+            if !self.is_64 {
+                let return_address = insn.address() + insn.bytes().len() as u64;
+                CALLER_SAVED_REGISTERS_32.iter().for_each(|name| {
+                    pic_registers.values.remove(&Register::new(name));
+                });
+                match target {
+                    Some(target) if target == return_address => {
+                        pic_registers.pushed_return_address = Some(return_address);
+                    }
+                    Some(target) => {
+                        if let Some(register) = self.pc_thunks.get(&target) {
+                            pic_registers.values.insert(register.clone(), return_address);
+                        }
+                    }
+                    None => (),
+                }
+            }
+            if let [X86OperandType::Mem(mem)] = &operands[..] {
+                if let Some(address) = self.memory_address(insn, mem, pic_registers) {
+                    return InstructionKind::PointerCall { address };
+                }
+                if let Some((base, offset)) = self.register_relative(mem) {
+                    return InstructionKind::MemoryCall { base, offset };
+                }
+            }
+            return InstructionKind::Call { target };
+        }
+        if groups.contains(&GROUP_ID_JUMP) {
+            if let [X86OperandType::Mem(mem)] = &operands[..] {
+                if let Some(address) = self.memory_address(insn, mem, pic_registers) {
+                    return InstructionKind::PointerJump { address };
+                }
+            }
+            return InstructionKind::Jump { target };
+        }
+
+        let mnemonic = insn.mnemonic().unwrap_or_default();
+        let destination = match operands.first() {
+            Some(X86OperandType::Reg(reg_id)) => self.register(*reg_id),
+            _ => None,
+        };
+
+        if !self.is_64 {
+            if let Some(kind) = self.classify_32(insn, mnemonic, destination.as_ref(), &operands, pic_registers) {
+                return kind;
+            }
+            if let (Some(destination), Some(address)) = (&destination, pushed_return_address) {
+                if mnemonic == "pop" {
+                    pic_registers.values.insert(destination.clone(), address);
+                    return InstructionKind::Other { destination: Some(destination.clone()) };
+                }
+            }
+        }
+
+        let kind = match (mnemonic, destination, operands.get(1)) {
+            ("lea", Some(register), Some(X86OperandType::Mem(mem))) => {
+                match self.memory_address(insn, mem, pic_registers) {
+                    Some(address) => InstructionKind::AddressLoad { register, address },
+                    None => InstructionKind::Other { destination: Some(register) },
+                }
+            }
+            ("mov", Some(destination), Some(X86OperandType::Imm(value)))
+            | ("movabs", Some(destination), Some(X86OperandType::Imm(value))) => InstructionKind::RegisterMove {
+                destination,
+                source: Operand::Immediate(*value as u64),
+            },
+            ("mov", Some(destination), Some(X86OperandType::Reg(source))) => match self.register(*source) {
+                Some(source) => InstructionKind::RegisterMove {
+                    destination,
+                    source: Operand::Register(source),
+                },
+                None => InstructionKind::Other { destination: Some(destination) },
+            },
+            (_, destination, _) => InstructionKind::Other { destination },
+        };
+
+        // Any other write to a register invalidates the address it holds
+        let written = match &kind {
+            InstructionKind::AddressLoad { register, .. } | InstructionKind::MemoryLoad { register, .. } => Some(register),
+            InstructionKind::RegisterMove { destination, .. } => Some(destination),
+            InstructionKind::Other { destination } => destination.as_ref(),
+            _ => None,
+        };
+        if let Some(written) = written {
+            pic_registers.values.remove(written);
+        }
+
+        kind
+    }
+
+    /// Classifies the instructions specific to 32-bit code: the GOT relative loads of position independent code, and
+    /// the stores of the arguments on the stack (cdecl). Returns `None` for any other instruction.
+    fn classify_32(
+        &self,
+        insn: &Insn,
+        mnemonic: &str,
+        destination: Option<&Register>,
+        operands: &[X86OperandType],
+        pic_registers: &mut PicRegisters,
+    ) -> Option<InstructionKind> {
+        match (mnemonic, destination, operands) {
+            // `add ebx, _GLOBAL_OFFSET_TABLE_ - pc`
+            ("add", Some(register), [_, X86OperandType::Imm(offset)]) => {
+                let value = pic_registers.values.get_mut(register)?;
+                *value = (*value as i64).wrapping_add(*offset) as u64;
+                Some(InstructionKind::Other { destination: Some(register.clone()) })
+            }
+            // `mov eax, dword ptr [ebx + offset]`, loading an address from the GOT
+            ("mov", Some(register), [_, X86OperandType::Mem(mem)]) if mem.base() != RegId(0) => {
+                let address = self.memory_address(insn, mem, pic_registers)?;
+                pic_registers.values.remove(register);
+                Some(InstructionKind::MemoryLoad {
+                    register: register.clone(),
+                    address,
+                })
+            }
+            // `mov dword ptr [esp + offset], eax`
+            ("mov", None, [X86OperandType::Mem(mem), source]) => Some(InstructionKind::RegisterMove {
+                destination: self.stack_slot(mem)?,
+                source: self.source_operand(source)?,
+            }),
+            // The stack slot of a pushed argument is only known at the call, see `assign_pushed_arguments`
+            ("push", _, [source]) => Some(InstructionKind::RegisterMove {
+                destination: Register::stack_slot(0),
+                source: self.source_operand(source)?,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Assigns the stack slots to the arguments that are pushed before a call, in the order of the cdecl calling
+/// convention (the last argument is pushed first).
+fn assign_pushed_arguments(instructions: &mut [Instruction]) {
+    let mut pushes = vec![];
+    let mut slots = HashMap::new();
+    instructions.iter().enumerate().for_each(|(index, insn)| {
+        if insn.is_branch() {
+            // The last push before the call is the first argument
+            pushes.drain(..).rev().enumerate().for_each(|(slot, push_index)| {
+                slots.insert(push_index, slot as u64 * STACK_SLOT_SIZE);
+            });
+        } else if insn.mnemonic == "push" {
+            pushes.push(index);
+        }
+    });
+
+    slots.into_iter().for_each(|(index, offset)| {
+        if let InstructionKind::RegisterMove { destination, .. } = &mut instructions[index].kind {
+            *destination = Register::stack_slot(offset);
+        }
+    });
+}
+
+impl Disassembler for X86Disassembler {
+    fn disassemble(&self, code: &[u8], address: u64) -> Vec<Instruction> {
+        let instructions = self.capstone
+            .disasm_all(code, address)
+            .expect("Failed to disassemble procedure");
+        let mut pic_registers = PicRegisters::default();
+
+        let mut instructions = instructions
+            .iter()
+            .map(|insn| Instruction {
+                address: insn.address(),
+                size: insn.bytes().len() as u64,
+                mnemonic: insn.mnemonic().unwrap_or_default().to_string(),
+                kind: self.classify(&insn, &mut pic_registers),
+            })
+            .collect::<Vec<_>>();
+
+        if !self.is_64 {
+            assign_pushed_arguments(&mut instructions);
+        }
+        instructions
+    }
+
+    fn argument_register(&self, index: usize) -> Option<Register> {
+        // On x86, arguments are passed on the stack
+        if !self.is_64 {
+            return Some(Register::stack_slot(index as u64 * STACK_SLOT_SIZE));
+        }
+        ARGUMENT_REGISTERS_64.get(index).map(|name| Register::new(name))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Verify non-calls do not create calls or jumps
+    #[test]
+    pub fn test_invalid_calls() {
+        let disassembler = X86Disassembler::new(true, HashMap::new());
+
+        let assembly = &[25, 50, 75, 100];
+
+        assert!(!disassembler.disassemble(assembly, 0).iter().any(Instruction::is_branch));
+    }
+
+    /// Verify calls, calls through a vtable or the GOT, rip relative address loads and moves are classified, with partial
+    /// registers named after the full register
+    #[test]
+    pub fn test_classify() {
+        let disassembler = X86Disassembler::new(true, HashMap::new());
+
+        let assembly = &[
+            0x48, 0x8d, 0x3d, 0x10, 0x00, 0x00, 0x00, // lea rdi, [rip + 0x10]
+            0xbe, 0x2b, 0x00, 0x00, 0x00, // mov esi, 0x2b
+            0x89, 0xc6, // mov esi, eax
+            0xe8, 0x00, 0x01, 0x00, 0x00, // call 0x1113
+            0xff, 0xd0, // call rax
+            0xff, 0x50, 0x18, // call qword ptr [rax + 0x18]
+            0xff, 0x15, 0x20, 0x00, 0x00, 0x00, // call qword ptr [rip + 0x20]
+            0xff, 0x25, 0x30, 0x00, 0x00, 0x00, // jmp qword ptr [rip + 0x30]
+        ];
+
+        assert_eq!(
+            disassembler
+                .disassemble(assembly, 0x1000)
+                .into_iter()
+                .map(|insn| insn.kind)
+                .collect::<Vec<_>>(),
+            vec![
+                InstructionKind::AddressLoad {
+                    register: Register::new("rdi"),
+                    address: 0x1017,
+                },
+                InstructionKind::RegisterMove {
+                    destination: Register::new("rsi"),
+                    source: Operand::Immediate(0x2b),
+                },
+                InstructionKind::RegisterMove {
+                    destination: Register::new("rsi"),
+                    source: Operand::Register(Register::new("rax")),
+                },
+                InstructionKind::Call { target: Some(0x1113) },
+                InstructionKind::Call { target: None },
+                InstructionKind::MemoryCall {
+                    base: Register::new("rax"),
+                    offset: 0x18,
+                },
+                InstructionKind::PointerCall { address: 0x103e },
+                InstructionKind::PointerJump { address: 0x1054 },
+            ]
+        );
+    }
+
+    /// Verify GOT relative address loads and stack arguments are classified in 32-bit position independent code,
+    /// with the program counter loaded by a `call` to the next instruction and a `pop`
+    #[test]
+    pub fn test_classify_32() {
+        let disassembler = X86Disassembler::new(false, HashMap::new());
+
+        let assembly = &[
+            0xe8, 0x00, 0x00, 0x00, 0x00, // call 0x1005
+            0x5b, // pop ebx
+            0x81, 0xc3, 0x00, 0x10, 0x00, 0x00, // add ebx, 0x1000
+            0x8d, 0x83, 0x10, 0x00, 0x00, 0x00, // lea eax, [ebx + 0x10]
+            0x89, 0x04, 0x24, // mov dword ptr [esp], eax
+            0x8b, 0x8b, 0xfc, 0xff, 0xff, 0xff, // mov ecx, dword ptr [ebx - 4]
+            0x6a, 0x2b, // push 0x2b
+            0x51, // push ecx
+            0xff, 0xd0, // call eax
+        ];
+
+        assert_eq!(
+            disassembler
+                .disassemble(assembly, 0x1000)
+                .into_iter()
+                .map(|insn| insn.kind)
+                .collect::<Vec<_>>(),
+            vec![
+                InstructionKind::Call { target: Some(0x1005) },
+                InstructionKind::Other { destination: Some(Register::new("ebx")) },
+                InstructionKind::Other { destination: Some(Register::new("ebx")) },
+                InstructionKind::AddressLoad {
+                    register: Register::new("eax"),
+                    address: 0x2015,
+                },
+                InstructionKind::RegisterMove {
+                    destination: Register::stack_slot(0),
+                    source: Operand::Register(Register::new("eax")),
+                },
+                InstructionKind::MemoryLoad {
+                    register: Register::new("ecx"),
+                    address: 0x2001,
+                },
+                InstructionKind::RegisterMove {
+                    destination: Register::stack_slot(4),
+                    source: Operand::Immediate(0x2b),
+                },
+                InstructionKind::RegisterMove {
+                    destination: Register::stack_slot(0),
+                    source: Operand::Register(Register::new("ecx")),
+                },
+                InstructionKind::Call { target: None },
+            ]
+        );
+    }
+
+    /// Verify the program counter loaded by a `__x86.get_pc_thunk.*` procedure is tracked
+    #[test]
+    pub fn test_pc_thunk() {
+        let mut pc_thunks = HashMap::new();
+        pc_thunks.insert(0x2000, Register::new("ebx"));
+        let disassembler = X86Disassembler::new(false, pc_thunks);
+
+        let assembly = &[
+            0xe8, 0xfb, 0x0f, 0x00, 0x00, // call 0x2000 (__x86.get_pc_thunk.bx)
+            0x83, 0xc3, 0x10, // add ebx, 0x10
+            0x8d, 0x43, 0x04, // lea eax, [ebx + 4]
+        ];
+
+        assert_eq!(
+            disassembler.disassemble(assembly, 0x1000)[2].kind,
+            InstructionKind::AddressLoad {
+                register: Register::new("eax"),
+                address: 0x1019,
+            }
+        );
+    }
+}
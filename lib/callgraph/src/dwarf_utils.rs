@@ -10,16 +10,72 @@ use std::string::String;
 
 use fallible_iterator::FallibleIterator;
 
-use gimli::{Abbreviations, UnitHeader};
 use gimli::AttributeValue::*;
-use gimli::DebugStr;
 use gimli::DebuggingInformationEntry;
 use gimli::DwAt;
+use gimli::Dwarf;
 use gimli::Expression;
 use gimli::Reader;
 use gimli::ReaderOffset;
+use gimli::Unit;
 
 use crate::Context;
+use crate::DwarfReader;
+
+use object::Object;
+
+/// A compilation unit, together with the debug information it is read from.
+/// For a skeleton unit of split debug information (`-C split-debuginfo`), this is the split unit in the `.dwo` or
+/// `.dwp` file, so the split debug information is read like that of any other unit.
+pub struct CompilationUnit<'c, 'a> {
+    pub dwarf: &'c Dwarf<DwarfReader<'a>>,
+    pub unit: Unit<DwarfReader<'a>>,
+}
+
+/// Returns all compilation units in the debug information of `ctx`.
+/// Skeleton units are replaced by their split unit, if the split debug information was found.
+pub fn get_compilation_units<'c, 'a>(ctx: &'c Context<'a>) -> Vec<CompilationUnit<'c, 'a>> {
+    ctx.dwarf
+        .units()
+        .map(|unit_header| ctx.dwarf.unit(unit_header))
+        .iterator()
+        .map(|unit| {
+            let unit = unit.expect("Could not parse compilation unit");
+            match get_split_unit(ctx, &unit) {
+                Some(split_unit) => split_unit,
+                None => CompilationUnit {
+                    dwarf: &ctx.dwarf,
+                    unit,
+                },
+            }
+        })
+        .collect()
+}
+
+/// Returns the split unit of `skeleton`, or `None` if `skeleton` is not a skeleton unit, or if its split debug
+/// information was not found.
+fn get_split_unit<'c, 'a>(
+    ctx: &'c Context<'a>,
+    skeleton: &Unit<DwarfReader<'a>>,
+) -> Option<CompilationUnit<'c, 'a>> {
+    let dwo_id = skeleton.dwo_id?;
+    let dwarf = ctx.split_dwarf.get(&dwo_id)?;
+
+    let mut unit = dwarf
+        .units()
+        .map(|unit_header| dwarf.unit(unit_header))
+        .iterator()
+        .filter_map(Result::ok)
+        .find(|unit| unit.dwo_id == Some(dwo_id))?;
+
+    // The base addresses and the compilation directory are only present in the skeleton unit
+    unit.copy_relocated_attributes(skeleton);
+    if unit.comp_dir.is_none() {
+        unit.comp_dir = skeleton.comp_dir;
+    }
+
+    Some(CompilationUnit { dwarf, unit })
+}
 
 /// Function that returns a string value from an entry's attribute.
 /// If no string was found, or an error occurred
@@ -27,36 +83,40 @@ use crate::Context;
 pub fn get_attr_string_value_safe<R: Reader>(
     entry: &DebuggingInformationEntry<R, R::Offset>,
     attr: DwAt,
-    strings: &DebugStr<R>,
+    dwarf: &Dwarf<R>,
+    unit: &Unit<R>,
 ) -> String {
     entry.attr(attr).expect("Error reading attributes") // Note that, if no attribute is present Ok(None) is returned.
         .map(|attrib| {
-            let tmp: Option<String> = attrib.string_value(strings)
+            let tmp: Option<String> = dwarf.attr_string(unit, attrib.value()).ok()
                 .map(|x: R| x.to_string().unwrap_or_else(|_| panic!("Failed to convert {} value to string", attr)).to_string());
             tmp.unwrap_or_else(|| format!("<{} is not a string>", attr).to_string())
         })
         .unwrap_or_else(|| format!("<no {} attribute given>", attr).to_string())
 }
 
-/// Function that returns the buffer of a string value from an entries attribute. If no string value was found, `None` will be returned
+/// Function that returns the buffer of a string value from an entries attribute. If no string value was found, `None` will be returned.
+/// Strings in `.debug_str`, `.debug_line_str` and the string offsets table (`DW_FORM_strx`) are resolved.
 pub fn get_attr_string_buf<R: Reader>(
     entry: &DebuggingInformationEntry<R, R::Offset>,
     attr: DwAt,
-    strings: &DebugStr<R>,
+    dwarf: &Dwarf<R>,
+    unit: &Unit<R>,
 ) -> Option<R> {
     entry
         .attr(attr)
         .ok()
-        .and_then(|att_opt| att_opt.and_then(|att| att.string_value(strings)))
+        .and_then(|att_opt| att_opt.and_then(|att| dwarf.attr_string(unit, att.value()).ok()))
 }
 
 /// Function that returns a string value from an entries attribute. If no string was found, `None` will be returned
 pub fn get_attr_string_value<R: Reader>(
     entry: &DebuggingInformationEntry<R, R::Offset>,
     attr: DwAt,
-    strings: &DebugStr<R>,
+    dwarf: &Dwarf<R>,
+    unit: &Unit<R>,
 ) -> Option<String> {
-    get_attr_string_buf(entry, attr, strings).map(|buf| buf.to_string().unwrap().to_string())
+    get_attr_string_buf(entry, attr, dwarf, unit).map(|buf| buf.to_string().unwrap().to_string())
 }
 
 /// Returns a byte buffer representing the attribute value.
@@ -86,42 +146,37 @@ pub fn get_attr_u64_value<R: Reader>(
         .and_then(|att_opt| att_opt.and_then(|att| att.udata_value()))
 }
 
-/// If the attribute has an Addr value, it will be returned. Else `None` will be given.
+/// If the attribute has an address value, it will be returned. Else `None` will be given.
+/// Addresses in the address table (`DW_FORM_addrx`) are resolved.
 pub fn get_attr_addr_value<R: Reader>(
     entry: &DebuggingInformationEntry<R, R::Offset>,
     attr: DwAt,
+    dwarf: &Dwarf<R>,
+    unit: &Unit<R>,
 ) -> Option<u64> {
-    match entry
-        .attr(attr)
-        .map(|att_opt| att_opt.map(|att| att.value()))
-    {
-        Ok(Some(Addr(value))) => Some(value),
+    match entry.attr_value(attr) {
+        Ok(Some(value)) => dwarf.attr_address(unit, value).ok().flatten(),
         _ => None,
     }
 }
 
 /// Function demangling assembly symbol names.
+/// Both legacy and v0 (`-C symbol-mangling-version=v0`) Rust symbols are demangled, other symbols are returned as is.
 pub fn demangle_symbol(linkage_name: &str) -> String {
-    let demangled = addr2line::demangle(linkage_name, gimli::DW_LANG_Rust)
-        .unwrap_or_else(|| linkage_name.to_string());
-
-    // Every demangled linkage name contain a hash at the end of the string
-    // These calls take care to remove that
-    let last_crate_index = demangled
-        .rfind("::")
-        .unwrap_or_else(|| demangled.chars().count());
-    demangled[..last_crate_index].to_string()
+    // The alternate format omits the hash of legacy symbols, and the crate disambiguators of v0 symbols
+    format!("{:#}", rustc_demangle::demangle(linkage_name))
 }
 
 /// Prints detailed information of an entry (fo debugging purposes)
 #[allow(dead_code)]
 pub fn print_entry_details<R: Reader>(
     entry: &DebuggingInformationEntry<R, R::Offset>,
-    strings: &DebugStr<R>,
+    dwarf: &Dwarf<R>,
+    unit: &Unit<R>,
 ) {
     let offset = entry.offset().0.into_u64();
-    let name = get_attr_string_value_safe(entry, gimli::DW_AT_name, strings);
-    let linkage_name = get_attr_string_value_safe(entry, gimli::DW_AT_linkage_name, strings);
+    let name = get_attr_string_value_safe(entry, gimli::DW_AT_name, dwarf, unit);
+    let linkage_name = get_attr_string_value_safe(entry, gimli::DW_AT_linkage_name, dwarf, unit);
 
     println!(
         "Entry details: offset: {:x}, name: {}, linkage_name: {}, present attributes:",
@@ -139,52 +194,73 @@ pub fn print_entry_details<R: Reader>(
 
 /// Function that determines the Rust version that was used to compile the binary in `ctx`.
 pub fn get_rust_version(ctx: &Context) -> Option<String> {
-    ctx.dwarf_info
-        .units()
-        .iterator()
-        .filter_map(|unit_header| {
-            let unit_header = unit_header.unwrap();
-            let abbrevs = unit_header.abbreviations(&ctx.dwarf_abbrev).unwrap();
-            let mut entries = unit_header.entries(&abbrevs);
+    get_compilation_units(ctx)
+        .iter()
+        .filter_map(|unit| {
+            let mut entries = unit.unit.entries();
 
             let (_, entry) = entries
                 .next_dfs()
                 .expect("First compilation unit could not be selected")
                 .unwrap();
 
-            let producer = get_attr_string_value(entry, gimli::DW_AT_producer, &ctx.dwarf_strings);
-
-            // Assume producer is in a format like 'clang LLVM (rustc version 1.26.0 (a77568041 2018-05-07))'
-            producer.map(|p| {
-                p.rsplit("rustc version")
-                    .next()
-                    .expect("Unexpected producer string format")
-                    .split_whitespace()
-                    .next()
-                    .expect("Unexpected producer string format")
-                    .to_string()
+            let producer = get_attr_string_value(entry, gimli::DW_AT_producer, unit.dwarf, &unit.unit);
+
+            // Assume producer is in a format like 'clang LLVM (rustc version 1.26.0 (a77568041 2018-05-07))'.
+            // C and C++ compilation units are skipped, as their producer contains no Rust version.
+            producer.and_then(|p| {
+                let (_, version) = p.rsplit_once("rustc version")?;
+                Some(
+                    version
+                        .split_whitespace()
+                        .next()
+                        .expect("Unexpected producer string format")
+                        .to_string(),
+                )
             })
         })
         .next()
+        // Without debug information, the version is read from the `.comment` section, to which rustc adds it
+        .or_else(|| {
+            ctx.elf
+                .section_data_by_name(".comment")?
+                .split(|byte| *byte == 0)
+                .filter_map(|comment| std::str::from_utf8(comment).ok())
+                .find_map(|comment| comment.strip_prefix("rustc version "))
+                .and_then(|version| version.split_whitespace().next())
+                .map(str::to_string)
+        })
 }
 
 /// Function getting an attribute string value. If the attribute is not present, but an DW_AT_abstract_origin
 /// attribute is present, the references entry will be checked for the desired attribute.
 pub fn get_attr_str_with_origin_traversal<R: Reader>(
-    cu: &UnitHeader<R, R::Offset>,
+    dwarf: &Dwarf<R>,
+    unit: &Unit<R>,
     entry: &DebuggingInformationEntry<R, R::Offset>,
     attr: DwAt,
-    abbrev: &Abbreviations,
-    strings: &DebugStr<R>,
 ) -> String {
-    match get_attr_string_value(entry, attr, strings) {
-        Some(name) => name,
+    find_attr_str_with_origin_traversal(dwarf, unit, entry, attr)
+        .unwrap_or_else(|| format!("<no {} attribute given>", attr))
+}
+
+/// Like `get_attr_str_with_origin_traversal`, but returns `None` if neither the entry nor the entry referenced by its
+/// DW_AT_abstract_origin attribute has the desired attribute.
+pub fn find_attr_str_with_origin_traversal<R: Reader>(
+    dwarf: &Dwarf<R>,
+    unit: &Unit<R>,
+    entry: &DebuggingInformationEntry<R, R::Offset>,
+    attr: DwAt,
+) -> Option<String> {
+    match get_attr_string_value(entry, attr, dwarf, unit) {
+        Some(name) => Some(name),
         _ => match entry.attr_value(gimli::DW_AT_abstract_origin) {
             Ok(Some(UnitRef(offset))) => {
-                let mut origin_cursor = cu.entries_at_offset(abbrev, offset).unwrap();
+                let mut origin_cursor = unit.entries_at_offset(offset).unwrap();
                 let (_, origin) = origin_cursor.next_dfs().unwrap().unwrap();
-                get_attr_string_value_safe(origin, attr, strings)
+                get_attr_string_value(origin, attr, dwarf, unit)
             }
+            Ok(None) => None,
             oth => panic!("DW_AT_abstract_origin is not UnitRef, but {:x?}", oth),
         },
     }
@@ -194,10 +270,13 @@ pub fn get_attr_str_with_origin_traversal<R: Reader>(
 mod tests {
     use super::*;
 
-    /// Test demangling a symbol with a hash
+    /// Test demangling a legacy symbol with a hash
     #[test]
     pub fn test_demangle_symbol() {
-        assert_eq!(demangle_symbol(&"sym::hash".to_string()), "sym".to_string());
+        assert_eq!(
+            demangle_symbol(&"_ZN4core6option13expect_failed17h0123456789abcdefE".to_string()),
+            "core::option::expect_failed".to_string()
+        );
     }
 
     /// Test demangling a symbol without a hash
@@ -8,31 +8,52 @@
 // except according to those terms.
 
 mod callgraph;
+mod compressed_sections;
 pub mod crate_utils;
+pub mod debug_file;
+pub mod disassembly;
 pub mod dwarf_utils;
 mod parse;
+pub mod relocatable;
+mod split_dwarf;
+pub mod symbol_path;
+pub mod wasm;
 
 pub mod errors;
 
+// Re-exported for the crates that construct a `Context` themselves, such as the test helpers of panic_analysis
+pub use addr2line;
+pub use gimli;
+pub use object;
+pub use petgraph;
+
 use crate::errors::*;
 
-use addr2line::{Loader};
+use addr2line::Context as Addr2LineContext;
 use addr2line::Frame as Addr2LineFrame;
+use addr2line::FrameIter;
+use addr2line::LookupContinuation;
+use addr2line::LookupResult;
 use addr2line::Location as Addr2LineLocation;
 
-use capstone::prelude::Capstone;
-use capstone::Instructions;
-
-use gimli::DebugAbbrev;
-use gimli::DebugInfo;
-use gimli::DebugLine;
-use gimli::DebugStr;
+use gimli::DwoId;
+use gimli::Dwarf;
 use gimli::EndianSlice;
-use gimli::LittleEndian;
 use gimli::RunTimeEndian;
 
 use core::fmt;
 
+use crate::debug_file::DebugFile;
+use crate::disassembly::Disassembler;
+use crate::disassembly::Instruction;
+use crate::relocatable::ObjectFile;
+use crate::symbol_path::SymbolPath;
+use crate::wasm::WasmModule;
+
+use memmap2::Mmap;
+
+use typed_arena::Arena;
+
 use object::ElfFile;
 
 use petgraph::dot::{Config, Dot};
@@ -44,13 +65,31 @@ use std::collections::HashMap;
 use std::fmt::Debug;
 use std::fmt::Display;
 use std::fmt::Formatter;
+use std::fs::File;
+use std::ops::Range;
 use std::path::Path;
+use std::path::PathBuf;
 use std::rc::Rc;
+use std::sync::Arc;
 
 /// Configuration to be passed to the call-graph builder
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct CallGraphOptions<'a> {
-    pub path: &'a Path,
+    /// Path of the binary, next to which a DWARF package (`.dwp`) is searched. `None` for a binary that is passed in
+    /// memory, without a file.
+    pub path: Option<&'a Path>,
+    /// If `true`, a call through a vtable is resolved to the called slot of the vtable, if the vtable pointer can be
+    /// tracked from the instruction loading the vtable to the call in the same procedure.
+    /// Otherwise, all functions in a vtable are assumed to be called by the procedure loading it.
+    pub precise_dynamic_dispatch: bool,
+    /// Directories in which the shared objects in the `DT_NEEDED` entries of the binary are searched.
+    /// The call graphs of the shared objects that are found are joined with the call graph of the binary.
+    pub library_paths: Vec<PathBuf>,
+    /// Directories in which separate debug files of stripped binaries are searched, before `/usr/lib/debug`.
+    pub debug_file_directories: Vec<PathBuf>,
+    /// Path of the `Cargo.lock` file of the analyzed project. If passed, the versions of the crates of which the
+    /// version is not known from their directory are read from it.
+    pub cargo_lock_path: Option<PathBuf>,
 }
 
 /// Crate metadata
@@ -75,6 +114,44 @@ impl Display for Crate {
     }
 }
 
+/// Source language of a procedure, as specified by the `DW_AT_language` attribute of its compilation unit.
+/// Besides Rust, the C and C++ code of `-sys` crates (e.g. built with the `cc` crate) is analysed.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Language {
+    Rust,
+    C,
+    Cpp,
+}
+
+impl Language {
+    /// Returns the language of the `DW_AT_language` value `language`, or `None` if the language is not analysed.
+    pub fn from_dwarf(language: gimli::DwLang) -> Option<Language> {
+        match language {
+            gimli::DW_LANG_Rust => Some(Language::Rust),
+            gimli::DW_LANG_C89 | gimli::DW_LANG_C | gimli::DW_LANG_C99 | gimli::DW_LANG_C11 | gimli::DW_LANG_C17 => {
+                Some(Language::C)
+            }
+            gimli::DW_LANG_C_plus_plus
+            | gimli::DW_LANG_C_plus_plus_03
+            | gimli::DW_LANG_C_plus_plus_11
+            | gimli::DW_LANG_C_plus_plus_14
+            | gimli::DW_LANG_C_plus_plus_17
+            | gimli::DW_LANG_C_plus_plus_20 => Some(Language::Cpp),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Language {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Language::Rust => write!(f, "Rust"),
+            Language::C => write!(f, "C"),
+            Language::Cpp => write!(f, "C++"),
+        }
+    }
+}
+
 /// Struct representing a procedure in assembly
 ///
 /// Note that it has a type parameter MetaData. This can be used to add custom metadata to the procedure, of which the callgraph library is agnostic.
@@ -88,10 +165,35 @@ pub struct Procedure<MetaData> {
     pub defining_crate: Crate,
     pub start_address: u64, // Why u64: see Addr entry in https://docs.rs/gimli/0.15.0/gimli/enum.AttributeValue.html
     pub size: u64, // Not pretty sure about this data type yet. On the other hand, it will not be problematic to change it later on
+    /// Address ranges of the machine code of the procedure, starting with the range containing `start_address`.
+    /// Procedures split by the optimizer (e.g. into `.text.unlikely` or `foo.cold` parts) have multiple ranges, in any
+    /// of the executable sections; `size` is their total size.
+    pub address_ranges: Vec<Range<u64>>,
+    /// Source language of the procedure, or `None` if the procedure is not described by the debug information (e.g.
+    /// procedures read from the symbol table, and external procedures).
+    pub language: Option<Language>,
     pub location: Option<Location>,
     pub attributes: MetaData,
     /// Procedure disassembly
-    pub disassembly: Instructions,
+    pub disassembly: Vec<Instruction>,
+}
+
+impl<MetaData> Procedure<MetaData> {
+    /// Returns the structured path of the procedure, parsed from its demangled linkage name.
+    pub fn symbol_path(&self) -> SymbolPath {
+        SymbolPath {
+            // The crate disambiguator is omitted from the demangled linkage name
+            crate_disambiguator: symbol_path::crate_disambiguator(&self.linkage_name),
+            ..SymbolPath::parse(&self.linkage_name_demangled)
+        }
+    }
+
+    /// Returns whether the procedure is defined in a shared object of which the call graph is merged into the call
+    /// graph of the binary (see `CallGraphOptions::library_paths`). The operands of its instructions are addresses
+    /// in the shared object, so they cannot be resolved with the `Context` of the binary.
+    pub fn is_in_shared_library(&self) -> bool {
+        callgraph::is_library_address(self.start_address)
+    }
 }
 
 impl<MetaData> Display for Procedure<MetaData> {
@@ -114,9 +216,31 @@ impl<MetaData: Debug> Debug for Procedure<MetaData> {
     }
 }
 
+/// The trait implementation a vtable belongs to, as in `impl Trait for Type`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraitImplementation {
+    /// Name of the implemented trait, `None` for vtables of types that are only coerced to auto traits
+    /// (e.g. `dyn Send`).
+    pub trait_name: Option<String>,
+    /// Name of the concrete type implementing the trait
+    pub type_name: String,
+}
+
+impl Display for TraitImplementation {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match &self.trait_name {
+            Some(trait_name) => write!(f, "impl {} for {}", trait_name, self.type_name),
+            None => write!(f, "impl {}", self.type_name),
+        }
+    }
+}
+
 /// Invocation metadata
 pub struct Invocation<MetaData, InlineFunctionFrameMetaData> {
     pub invocation_type: InvocationType,
+    /// For invocations through a vtable, the trait implementation the vtable belongs to, if it is known from the
+    /// debug information.
+    pub trait_implementation: Option<TraitImplementation>,
     pub frames: Vec<InlineFunctionFrame<InlineFunctionFrameMetaData>>,
     pub instruction_address: u64,
     pub attributes: MetaData,
@@ -166,8 +290,8 @@ impl<MetaData: Debug, InlineFunctionFrameMetaData: Debug> Debug
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         write!(
             f,
-            "Invocation {{ invocation_type: {:?}, frames: {:?}, attributes: {:x?} }}",
-            self.invocation_type, self.frames, self.attributes
+            "Invocation {{ invocation_type: {:?}, trait_implementation: {:?}, frames: {:?}, attributes: {:x?} }}",
+            self.invocation_type, self.trait_implementation, self.frames, self.attributes
         )
     }
 }
@@ -198,17 +322,8 @@ impl<'a, InlineFunctionFrameMetaData: Default> InlineFunctionFrame<InlineFunctio
             function_name: frame
                 .function
                 .as_ref()
-                .and_then(|mangled_fun| {
-                    mangled_fun
-                        .demangle()
-                        .ok()
-                        .map(|demangled_fun| demangled_fun.to_string())
-                })
-                // Remove hash at the end of the function name
-                .map(|fun| {
-                    let hash_location = fun.rfind("::").unwrap_or_else(|| fun.chars().count());
-                    fun[..hash_location].to_string()
-                })
+                .and_then(|mangled_fun| mangled_fun.raw_name().ok())
+                .map(|mangled_fun| dwarf_utils::demangle_symbol(&mangled_fun))
                 .unwrap_or_else(|| "unknown_function_name".to_string()),
 
             // Convert `addr2line::Location` to our `Location` type
@@ -300,27 +415,82 @@ impl<PMetadata: Debug, IMetadata: Debug, FMetadata: Debug>
     }
 }
 
+/// Reader of the DWARF sections of the binary
+pub type DwarfReader<'a> = EndianSlice<'a, RunTimeEndian>;
+
 /// Parsed information about the binary
 pub struct Context<'a> {
+    /// The binary, the first relocatable object of a static library (see `object_files`), or an ELF file without
+    /// sections for a WebAssembly module (see `wasm_module`)
     pub elf: ElfFile<'a>,
-    pub loader: Loader,
-    pub dwarf_info: DebugInfo<EndianSlice<'a, LittleEndian>>,
-    pub dwarf_abbrev: DebugAbbrev<EndianSlice<'a, LittleEndian>>,
-    pub dwarf_strings: DebugStr<EndianSlice<'a, LittleEndian>>,
-    pub dwarf_line: DebugLine<EndianSlice<'a, LittleEndian>>,
-    pub capstone: Capstone,
+    /// Reader of the inlined functions and source locations in `dwarf`, which borrows the same contents of the binary.
+    /// Use `find_frames` to look up the inlined functions, which reads split units from `split_dwarf`. `None` for
+    /// relocatable objects, of which the debug information is not used, and for WebAssembly modules it cannot read.
+    pub loader: Option<Addr2LineContext<DwarfReader<'a>>>,
+    /// Debug information of the binary. Use `dwarf_utils::get_compilation_units` to iterate its compilation units,
+    /// which resolves skeleton units to their split units.
+    pub dwarf: Dwarf<DwarfReader<'a>>,
+    /// Split debug information (`.dwo` files, or the `.dwp` package) of the skeleton units in `dwarf`, by DWO ID
+    pub split_dwarf: HashMap<DwoId, Dwarf<DwarfReader<'a>>>,
+    /// Disassembler of the machine code of the binary. `None` for big endian binaries, of which the machine code cannot
+    /// be disassembled yet, so no call graph is built for them (see `callgraph::get_call_graph_builder`).
+    pub disassembler: Option<Box<dyn Disassembler>>,
+    /// Relocatable objects of a static library, rlib or object file that is analysed instead of a linked binary (see
+    /// `relocatable`). Empty for linked binaries.
+    pub object_files: Vec<ObjectFile<'a>>,
+    /// WebAssembly module that is analysed instead of an ELF binary (see `wasm`)
+    pub wasm_module: Option<WasmModule<'a>>,
+}
+
+impl<'a> Context<'a> {
+    /// Byte order of the binary. WebAssembly modules are little endian.
+    pub fn endian(&self) -> RunTimeEndian {
+        // The header of `elf` is validated when the binary is parsed
+        parse::get_endian(&self.elf).unwrap_or(RunTimeEndian::Little)
+    }
+
+    /// Disassembles `code`, which is located at `address`. Returns no instructions if the machine code of the binary
+    /// cannot be disassembled (see `disassembler`).
+    pub fn disassemble(&self, code: &[u8], address: u64) -> Vec<Instruction> {
+        self.disassembler
+            .as_ref()
+            .map(|disassembler| disassembler.disassemble(code, address))
+            .unwrap_or_default()
+    }
+
+    /// Returns the frames of the functions that are inlined at `address`, from the innermost inlined function to the
+    /// procedure containing `address`. Returns `None` if the debug information cannot be read (see `loader`).
+    pub fn find_frames(&self, address: u64) -> Option<FrameIter<'_, DwarfReader<'a>>> {
+        let loader = self.loader.as_ref()?;
+        let mut lookup = loader.find_frames(address);
+        loop {
+            match lookup {
+                LookupResult::Output(frames) => return frames.ok(),
+                // The split units are already loaded, of which those that were not found are skipped. `borrow` does
+                // not keep the file type of the split units, which is restored by `make_dwo`
+                LookupResult::Load { load, continuation } => {
+                    let split_dwarf = self.split_dwarf.get(&load.dwo_id).map(|split_dwarf| {
+                        let mut split_dwarf = split_dwarf.borrow(|section| *section);
+                        split_dwarf.make_dwo(&load.parent);
+                        Arc::new(split_dwarf)
+                    });
+                    lookup = continuation.resume(split_dwarf);
+                }
+            }
+        }
+    }
 }
 
-// `file_context` does not implement `Debug`, so write a custom implementation, omitting that field, here
+// `loader` does not implement `Debug`, so write a custom implementation, omitting that field, here.
+// Relocatable objects are identified by their name, and the contents of a WebAssembly module are omitted.
 impl<'a> Debug for Context<'a> {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        write!(f, "Context {{ elf: {:?}, dwarf_info: {:?}, dwarf_abbrev: {:?}, dwarf_strings: {:?}, dwarf_line: {:?}, capstone: {:?} }}",
+        write!(f, "Context {{ elf: {:?}, dwarf: {:?}, split_dwarf: {:?}, disassembler: {:?}, object_files: {:?} }}",
                self.elf,
-               self.dwarf_info,
-               self.dwarf_abbrev,
-               self.dwarf_strings,
-               self.dwarf_line,
-               self.capstone
+               self.dwarf,
+               self.split_dwarf,
+               self.disassembler,
+               self.object_files.iter().map(|object_file| &object_file.name).collect::<Vec<_>>()
         )
     }
 }
@@ -330,11 +500,16 @@ pub enum InvocationType {
     Direct,
     ProcedureReference,
     VTable,
+    /// Call through a specific slot of a vtable, found by tracking the vtable pointer to the call site.
+    /// Only created if `CallGraphOptions::precise_dynamic_dispatch` is set.
+    VTableSlot,
     Jump,
 }
 
 /// Checks whether the callgraph contains other crates than stdlib.
-/// If this is not the case, it means the binary has not been compiled with debug information.
+/// If this is not the case, it means the binary has neither debug information nor a symbol table for its own
+/// procedures. Procedures of which the crate is not known (external procedures, or procedures read from the symbol
+/// table of which the crate could not be inferred) are not counted.
 pub fn check_debug_information<PMetadata, IMetadata, FMetadata>(
     callgraph: &CallGraph<PMetadata, IMetadata, FMetadata>,
 ) -> Result<()> {
@@ -346,34 +521,84 @@ pub fn check_debug_information<PMetadata, IMetadata, FMetadata>(
             let node = callgraph.graph[node].borrow();
             node.defining_crate.name.clone()
         })
-        .filter(|crate_name| crate_name != std_lib_name)
+        .filter(|crate_name| {
+            crate_name != std_lib_name
+                && crate_name != crate_utils::EXTERNAL_CRATE_NAME
+                && crate_name != crate_utils::UNKNOWN_CRATE_NAME
+        })
         .count();
 
     if number_of_crates == 0 {
         Err(ErrorKind::ParseError(
-            "No DWARF debugging information found. Was the binary compiled with debug information enabled?"
-                .to_string(),
+            "No debugging information or symbols found. Was the binary stripped?".to_string(),
         ).into())
     } else {
         Ok(())
     }
 }
 
+/// Owner of the data the `Context` of a binary borrows besides the contents of the binary and its debug file: the
+/// decompressed debug sections (see `compressed_sections`), and the mapped `.dwo` and `.dwp` files of split debug
+/// information. Like the contents of the binary, it must outlive the `Context`.
+#[derive(Default)]
+pub struct DebugBuffers {
+    buffers: Arena<Vec<u8>>,
+    files: Arena<Mmap>,
+}
+
+impl DebugBuffers {
+    /// Takes ownership of `data`, and returns it borrowed for as long as `self`.
+    pub(crate) fn store(&self, data: Vec<u8>) -> &[u8] {
+        self.buffers.alloc(data)
+    }
+
+    /// Takes ownership of the mapped `file` (see `map_file`), and returns its contents borrowed for as long as `self`.
+    pub(crate) fn store_file(&self, file: Mmap) -> &[u8] {
+        self.files.alloc(file)
+    }
+}
+
+/// Maps the file at `path` into memory, so the ELF parser, the DWARF reader and the addr2line context (see `Context`)
+/// share the contents of the binary, without reading it into memory first.
+pub fn map_file(path: &Path) -> Result<Mmap> {
+    let file = File::open(path).chain_err(|| ErrorKind::IOError(path.to_string_lossy().to_string()))?;
+    // The file is not expected to be modified while it is analysed
+    unsafe { Mmap::map(&file) }.chain_err(|| ErrorKind::ReadError(path.to_string_lossy().to_string()))
+}
+
+/// Builds the call graph of the binary in `file_content`, which is usually mapped into memory (see `map_file`).
+/// The debug information is read from `debug_file` if it is passed (see `debug_file::find_debug_file`), or from the
+/// binary otherwise. The other data the returned `Context` borrows is stored in `buffers`.
 pub fn build_call_graph<'a,
     PMetadata: Default + Debug + 'static,
     IMetadata: Default + Debug + 'static,
     FMetadata: Default + Debug + 'static>(
     file_content: &'a[u8],
-    file_path: &'a Path,
+    debug_file: Option<DebugFile<'a>>,
+    buffers: &'a DebugBuffers,
+    options: &CallGraphOptions,
 ) -> Result<(CallGraph<PMetadata, IMetadata, FMetadata>, Context<'a>)> {
     let parser = parse::get_parser();
-    let context = parser.parse(file_content, file_path)?;
+    let context = match (options.path, debug_file) {
+        (Some(path), Some(debug_file)) => parser.parse_with_debug_file(file_content, path, debug_file, buffers)?,
+        (Some(path), None) => parser.parse(file_content, path, buffers)?,
+        // Debug files are only searched for binaries that are read from a file
+        (None, _) => parser.parse_in_memory(file_content, buffers)?,
+    };
 
-    let call_graph_builder = callgraph::get_call_graph_builder(&context)?;
-    let call_graph = call_graph_builder.build_call_graph(&context);
+    let call_graph_builder = callgraph::get_call_graph_builder(&context, options)?;
+    let mut call_graph = call_graph_builder.build_call_graph(&context);
 
     check_debug_information(&call_graph)?;
 
+    if !options.library_paths.is_empty() {
+        callgraph::link_shared_libraries(&mut call_graph, &context, options);
+    }
+
+    if let Some(cargo_lock_path) = &options.cargo_lock_path {
+        crate_utils::fill_in_crate_versions(&call_graph, &crate_utils::CargoLock::read(cargo_lock_path)?);
+    }
+
     Ok((call_graph, context))
 }
 
@@ -384,22 +609,11 @@ mod test {
     use std::cell::RefCell;
     use std::rc::Rc;
 
-    use Crate;
-    use Procedure;
-
-    use capstone::arch::BuildsCapstone;
+    use crate::Crate;
+    use crate::Procedure;
 
     /// Helper function to create a procedure with a given name and crate name
     fn create_procedure_with_name(name: String) -> Procedure<()> {
-        let capstone = Capstone::new()
-            .x86()
-            .mode(capstone::arch::x86::ArchMode::Mode64)
-            .detail(true)
-            .build()
-            .expect("Failed to construct disassembler");
-
-        let empty_vec = Vec::new();
-
         Procedure {
             name: name.clone(),
             linkage_name: "linkage_name".to_string(),
@@ -410,9 +624,11 @@ mod test {
             },
             start_address: 32,
             size: 64,
+            address_ranges: vec![],
+            language: None,
             location: None,
             attributes: (),
-            disassembly: capstone.disasm_all(&empty_vec, 0x1000).unwrap(),
+            disassembly: vec![],
         }
     }
 
@@ -447,6 +663,17 @@ mod test {
         );
     }
 
+    /// Test the languages of the compilation units that are analysed
+    #[test]
+    fn test_language_from_dwarf() {
+        assert_eq!(Language::from_dwarf(gimli::DW_LANG_Rust), Some(Language::Rust));
+        assert_eq!(Language::from_dwarf(gimli::DW_LANG_C99), Some(Language::C));
+        assert_eq!(Language::from_dwarf(gimli::DW_LANG_C11), Some(Language::C));
+        assert_eq!(Language::from_dwarf(gimli::DW_LANG_C_plus_plus_14), Some(Language::Cpp));
+        assert_eq!(Language::from_dwarf(gimli::DW_LANG_Mips_Assembler), None);
+        assert_eq!(Language::Cpp.to_string(), "C++");
+    }
+
     /// Test implementation `Display` trait for `Crate`
     #[test]
     fn test_crate_display() {
@@ -475,15 +702,11 @@ mod test {
             },
             start_address: 1000,
             size: 200,
+            address_ranges: vec![],
+            language: None,
             location: None,
             attributes: (),
-            disassembly: Capstone::new()
-                .x86()
-                .mode(capstone::arch::x86::ArchMode::Mode64)
-                .build()
-                .unwrap()
-                .disasm_all(&vec![], 0)
-                .unwrap(),
+            disassembly: vec![],
         };
         assert_eq!(
             procedure.to_string(),
@@ -496,6 +719,7 @@ mod test {
     fn test_invocation_inline_display() {
         let invocation = Invocation {
             invocation_type: InvocationType::Direct,
+            trait_implementation: None,
             instruction_address: 0x136656,
             frames: vec![
                 InlineFunctionFrame {
@@ -571,6 +795,7 @@ mod test {
     fn test_invocation_no_inline_display() {
         let invocation = Invocation {
             invocation_type: InvocationType::Direct,
+            trait_implementation: None,
             instruction_address: 0x136656,
             frames: vec![InlineFunctionFrame {
                 function_name: "rust_begin_unwind".to_string(),
@@ -9,122 +9,220 @@
 use std::path::Path;
 use crate::errors::*;
 
+use crate::disassembly::get_disassembler;
+use crate::disassembly::get_wasm_disassembler;
+use crate::compressed_sections::debug_section_data;
+use crate::debug_file::DebugFile;
+use crate::relocatable;
+use crate::split_dwarf;
+use crate::wasm;
+use crate::wasm::WasmModule;
 use crate::Context;
+use crate::DebugBuffers;
+use crate::DwarfReader;
 
-use addr2line::{Loader};
+use addr2line::Context as Addr2LineContext;
 
-use capstone::arch::BuildsCapstone;
-use capstone::{Capstone};
-use gimli::{DebugAbbrev};
-use gimli::DebugInfo;
-use gimli::DebugLine;
-use gimli::DebugStr;
+use gimli::Dwarf;
 use gimli::EndianSlice;
-use gimli::LittleEndian;
-use goblin::container::Endian::Big;
+use gimli::RunTimeEndian;
+use gimli::SectionId;
+use goblin::container::Endian;
 
 use object::ElfFile;
+use object::Machine;
 use object::Object;
 
+use std::collections::HashMap;
+
 /// Trait marking objects that are able to parse a binary into appropriate ELF/DWARF/Disassembled information
+///
+/// The data the `Context` borrows besides `file_content` (e.g. decompressed debug sections) is stored in `buffers`.
 pub trait Parser {
-    fn parse<'a>(&self, file_content: &'a [u8], file_path: &Path) -> Result<Context<'a>>;
+    fn parse<'a>(&self, file_content: &'a [u8], file_path: &Path, buffers: &'a DebugBuffers) -> Result<Context<'a>>;
+
+    /// Parses a stripped binary, of which the debug information is read from the separate `debug_file`.
+    fn parse_with_debug_file<'a>(
+        &self,
+        file_content: &'a [u8],
+        file_path: &Path,
+        debug_file: DebugFile<'a>,
+        buffers: &'a DebugBuffers,
+    ) -> Result<Context<'a>>;
+
+    /// Parses a binary that is passed in memory, without a file. Split debug information is only read from the `.dwo`
+    /// files, as there is no DWARF package next to the binary.
+    fn parse_in_memory<'a>(&self, file_content: &'a [u8], buffers: &'a DebugBuffers) -> Result<Context<'a>>;
 }
 
 // Implementation of `Parser` that does parsing without any extraordinary processing.
 struct DefaultParser;
 
-/// Wrapper data structure for debug info
-type DebugData<'a> = (
-    DebugInfo<EndianSlice<'a, LittleEndian>>,
-    DebugAbbrev<EndianSlice<'a, LittleEndian>>,
-    DebugStr<EndianSlice<'a, LittleEndian>>,
-    DebugLine<EndianSlice<'a, LittleEndian>>,
-);
-
 impl Parser for DefaultParser {
-    fn parse<'a>(&self, file_content: &'a [u8], file_path: &Path) -> Result<Context<'a>> {
+    fn parse<'a>(&self, file_content: &'a [u8], file_path: &Path, buffers: &'a DebugBuffers) -> Result<Context<'a>> {
+        self.parse_binary(file_content, Some(file_path), None, buffers)
+    }
+
+    fn parse_with_debug_file<'a>(
+        &self,
+        file_content: &'a [u8],
+        file_path: &Path,
+        debug_file: DebugFile<'a>,
+        buffers: &'a DebugBuffers,
+    ) -> Result<Context<'a>> {
+        self.parse_binary(file_content, Some(file_path), Some(debug_file), buffers)
+    }
+
+    fn parse_in_memory<'a>(&self, file_content: &'a [u8], buffers: &'a DebugBuffers) -> Result<Context<'a>> {
+        self.parse_binary(file_content, None, None, buffers)
+    }
+}
+
+impl DefaultParser {
+    /// Parses the binary in `file_content`, which is read from `file_path` if it is passed. If `debug_file` is passed,
+    /// the debug information is read from it instead of from the binary.
+    fn parse_binary<'a>(
+        &self,
+        file_content: &'a [u8],
+        file_path: Option<&Path>,
+        debug_file: Option<DebugFile<'a>>,
+        buffers: &'a DebugBuffers,
+    ) -> Result<Context<'a>> {
+        if relocatable::is_relocatable(file_content) {
+            return self.parse_relocatable(file_content);
+        }
+        if wasm::is_wasm(file_content) {
+            return self.parse_wasm(file_content, file_path, buffers);
+        }
+
         let elf = ElfFile::parse(file_content)
             .map_err(|message| Error::from(ErrorKind::ParseError(message.to_string())))?;
 
-        let loader = Loader::new(file_path).map_err(|message| {
+        // The sections are empty for binaries without debug information, of which the procedures are read from the
+        // symbol table instead
+        let dwarf = match debug_file {
+            Some(debug_file) => {
+                let debug_elf = ElfFile::parse(debug_file.content)
+                    .map_err(|message| Error::from(ErrorKind::ParseError(message.to_string())))?;
+                load_dwarf(&debug_elf, false, buffers)?
+            }
+            None => load_dwarf(&elf, false, buffers)?,
+        };
+        let split_dwarf = split_dwarf::load_split_dwarf(&dwarf, file_path, buffers);
+
+        // The debug file has the same addresses as the binary, so the inlined functions are looked up in it directly
+        let loader = Addr2LineContext::from_dwarf(dwarf.borrow(|section| *section)).map_err(|message| {
             Error::from(ErrorKind::ParseError(format!(
-                "Could not construct addr2line Loader to read Dwarf info: {}",
+                "Could not construct addr2line context to read Dwarf info: {}",
                 message
             )))
         })?;
 
-        let data_byte = elf.elf()
-            .header
-            .endianness()
-            .map_err(|_e| ErrorKind::ParseError("Invalid endianness specifier".to_string()))?;
+        // The debug information of big endian binaries is still read, of which the call graph cannot be built yet
+        let disassembler = match get_endian(&elf)? {
+            RunTimeEndian::Big => None,
+            RunTimeEndian::Little => Some(get_disassembler(&elf)?),
+        };
+
+        Ok(Context {
+            elf,
+            loader: Some(loader),
+            dwarf,
+            split_dwarf,
+            disassembler,
+            object_files: vec![],
+            wasm_module: None,
+        })
+    }
 
-        if data_byte == Big {
-            return Err(
-                ErrorKind::NotSupported("Big endian files not supported yet".to_string()).into(),
-            );
-        }
-        let endianness = LittleEndian;
-
-        let mode_byte = elf.elf().header.e_ident[elf::abi::EI_CLASS];
-        let mode = match mode_byte {
-            1 => capstone::arch::x86::ArchMode::Mode32,
-            2 => capstone::arch::x86::ArchMode::Mode64,
-            _ => {
-                return Err(
-                    ErrorKind::ParseError("ELF file has invalid mode bit".to_string()).into(),
-                )
-            }
+    /// Parses the static library, rlib or relocatable object in `file_content`, of which all relocatable objects must
+    /// contain x86_64 code. The machine code is disassembled with the disassembler of its first relocatable object; the
+    /// debug information is not used.
+    fn parse_relocatable<'a>(&self, file_content: &'a [u8]) -> Result<Context<'a>> {
+        let object_files = relocatable::get_object_files(file_content)?;
+        let elf = match object_files.first() {
+            Some(object_file) => ElfFile::parse(object_file.content)
+                .map_err(|message| Error::from(ErrorKind::ParseError(message.to_string())))?,
+            None => bail!(ErrorKind::ParseError("Archive contains no relocatable objects".to_string())),
         };
 
-        let (dwarf_info, dwarf_abbrev, dwarf_strings, dwarf_line) =
-            self.parse_dwarf_info(&elf, endianness)?;
+        // Only the relocations of x86_64 code are resolved, so an archive with an object of another instruction set
+        // is rejected as a whole
+        if object_files.iter().any(|object_file| !matches!(object_file.elf.machine(), Machine::X86_64)) {
+            bail!(ErrorKind::NotSupported(
+                "relocatable objects of other instruction sets than x86_64".to_string()
+            ));
+        }
+
+        let disassembler = Some(get_disassembler(&elf)?);
+
+        Ok(Context {
+            elf,
+            loader: None,
+            dwarf: Dwarf::default(),
+            split_dwarf: HashMap::new(),
+            disassembler,
+            object_files,
+            wasm_module: None,
+        })
+    }
 
-        let mut capstone = Capstone::new()
-            .x86()
-            .mode(mode)
-            .detail(true)
-            .build()
-            .expect("Failed to construct disassembler");
-        capstone
-            .set_detail(true)
-            .expect("Failed to enable detailed mode");
+    /// Parses the WebAssembly module in `file_content`, of which the debug information is read from its custom
+    /// sections. An ELF file without sections stands in for the binary.
+    fn parse_wasm<'a>(
+        &self,
+        file_content: &'a [u8],
+        file_path: Option<&Path>,
+        buffers: &'a DebugBuffers,
+    ) -> Result<Context<'a>> {
+        let wasm_module = WasmModule::parse(file_content)?;
+        let elf = ElfFile::parse(wasm::EMPTY_ELF)
+            .map_err(|message| Error::from(ErrorKind::ParseError(message.to_string())))?;
+
+        let dwarf = Dwarf::load(|section: SectionId| -> Result<DwarfReader<'a>> {
+            let data = wasm_module.custom_section(section.name()).unwrap_or(&[]);
+            Ok(EndianSlice::new(data, RunTimeEndian::Little))
+        })?;
+        let split_dwarf = split_dwarf::load_split_dwarf(&dwarf, file_path, buffers);
+
+        // The source locations are not known if the debug information of the module cannot be read
+        let loader = Addr2LineContext::from_dwarf(dwarf.borrow(|section| *section)).ok();
+
+        let disassembler = Some(get_wasm_disassembler(&wasm_module));
 
         Ok(Context {
             elf,
             loader,
-            dwarf_info,
-            dwarf_abbrev,
-            dwarf_strings,
-            dwarf_line,
-            capstone,
+            dwarf,
+            split_dwarf,
+            disassembler,
+            object_files: vec![],
+            wasm_module: Some(wasm_module),
         })
     }
 }
 
-impl DefaultParser {
-    fn parse_dwarf_info<'a>(
-        &self,
-        elf: &ElfFile<'a>,
-        endianness: LittleEndian,
-    ) -> Result<DebugData<'a>> {
-        let debug_info_data = elf.section_data_by_name(".debug_info")
-            .chain_err(|| ErrorKind::ParseError("No .debug_info section in binary".to_string()))?;
-        let dwarf_info = DebugInfo::new(debug_info_data, endianness);
-
-        let debug_abbrev_data = elf.section_data_by_name(".debug_abbrev")
-            .chain_err(|| ErrorKind::ParseError("No .debug_abbrev section in binary".to_string()))?;
-        let dwarf_abbrev = DebugAbbrev::new(debug_abbrev_data, endianness);
-
-        let debug_str_data = elf.section_data_by_name(".debug_str")
-            .chain_err(|| ErrorKind::ParseError("No .debug_str section in binary".to_string()))?;
-        let dwarf_strings = DebugStr::new(debug_str_data, endianness);
-
-        let debug_line_data = elf.section_data_by_name(".debug_line")
-            .chain_err(|| ErrorKind::ParseError("No .debug_line section in binary".to_string()))?;
-        let dwarf_line = DebugLine::new(debug_line_data, endianness);
-
-        Ok((dwarf_info, dwarf_abbrev, dwarf_strings, dwarf_line))
+/// Loads the DWARF sections of `elf`, of which the sections that are not present are empty.
+/// If `dwo` is `true`, the sections of split debug information (e.g. `.debug_info.dwo`) are loaded.
+/// Compressed sections are decompressed into `buffers`.
+pub fn load_dwarf<'a>(elf: &ElfFile<'a>, dwo: bool, buffers: &'a DebugBuffers) -> Result<Dwarf<DwarfReader<'a>>> {
+    let endian = get_endian(elf)?;
+    Dwarf::load(|section: SectionId| -> Result<DwarfReader<'a>> {
+        let name = if dwo { section.dwo_name() } else { Some(section.name()) };
+        let data = match name {
+            Some(name) => debug_section_data(elf, name, buffers)?,
+            None => None,
+        };
+        Ok(EndianSlice::new(data.unwrap_or(&[]), endian))
+    })
+}
+
+/// Returns the byte order of `elf`, as specified in its header.
+pub fn get_endian(elf: &ElfFile) -> Result<RunTimeEndian> {
+    match elf.elf().header.endianness() {
+        Ok(Endian::Little) => Ok(RunTimeEndian::Little),
+        Ok(Endian::Big) => Ok(RunTimeEndian::Big),
+        Err(_) => bail!(ErrorKind::ParseError("Invalid endianness specifier".to_string())),
     }
 }
 
@@ -135,28 +233,51 @@ pub fn get_parser() -> Box<dyn Parser> {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::disassembly::InstructionKind;
     use gimli::AttributeValue::DebugStrRef;
     use gimli::*;
+    use object::Object;
 
     /// Test if the function panics if the passed byte array is not a valid elf file
     #[test]
     pub fn test_invalid_file_content() {
-        assert!(DefaultParser.parse(&[]).is_err());
+        assert!(DefaultParser.parse(&[], Path::new(""), &DebugBuffers::default()).is_err());
+    }
+
+    /// Test if relocatable objects of other instruction sets than x86_64 are rejected
+    #[test]
+    pub fn test_aarch64_rlib_not_supported() {
+        if test_common::skip_unbuilt(&test_common::TestSubjectType::DebugAArch64) {
+            return;
+        }
+        let path = test_common::get_test_subject_path("libffi_lib.rlib", &test_common::TestSubjectType::DebugAArch64);
+        let file_content =
+            &test_common::load_test_binary_as_bytes("libffi_lib.rlib", &test_common::TestSubjectType::DebugAArch64)
+                .unwrap();
+
+        let result = DefaultParser.parse(file_content, &path, &DebugBuffers::default());
+        assert!(matches!(
+            result,
+            Err(crate::errors::Error(crate::errors::ErrorKind::NotSupported(_), _))
+        ));
     }
 
     /// Test if the `DefaultParser` parses debug abbreviations correctly.
     /// We validate this by checking the tag, children and an attribute for 2 DIEs.
     #[test]
     pub fn test_example_binary_debug_abbrev() {
+        let path = test_common::get_test_subject_path("threads", &test_common::TestSubjectType::DebugStableRustc);
         let file_content = &test_common::load_test_binary_as_bytes(
             "threads",
             &test_common::TestSubjectType::DebugStableRustc,
         ).unwrap();
 
-        let context = DefaultParser.parse(file_content).unwrap();
+        let buffers = DebugBuffers::default();
+        let context = DefaultParser.parse(file_content, &path, &buffers).unwrap();
         // In order to compare debug sections, we will select a subset of abbreviations, and validate if they match
         let abbreviations = context
-            .dwarf_abbrev
+            .dwarf
+            .debug_abbrev
             .abbreviations(DebugAbbrevOffset(0))
             .expect("Error parsing abbreviations");
 
@@ -191,20 +312,23 @@ mod test {
     /// We validate this by checking the name attribute of an DIE.
     #[test]
     pub fn test_example_binary_debug_info() {
+        let path = test_common::get_test_subject_path("threads", &test_common::TestSubjectType::DebugStableRustc);
         let file_content = &test_common::load_test_binary_as_bytes(
             "threads",
             &test_common::TestSubjectType::DebugStableRustc,
         ).unwrap();
 
-        let context = DefaultParser.parse(file_content).unwrap();
+        let buffers = DebugBuffers::default();
+        let context = DefaultParser.parse(file_content, &path, &buffers).unwrap();
 
         // Next, we will inspect some of the debug info entries, and check if they match
-        let unit_1 = context.dwarf_info.units().next().unwrap().unwrap();
+        let unit_1 = context.dwarf.units().next().unwrap().unwrap();
 
         assert_eq!(unit_1.header_size(), 11);
 
         let abbr = context
-            .dwarf_abbrev
+            .dwarf
+            .debug_abbrev
             .abbreviations(DebugAbbrevOffset(0))
             .expect("Failed to parse abbreviations");
         let mut cursor = unit_1.entries(&abbr);
@@ -216,34 +340,55 @@ mod test {
             DebugStrRef(offset) => offset,
             _ => panic!("No DebugStrRef return type"),
         };
-        let name_value = context.dwarf_strings.get_str(name_value_ref).unwrap();
+        let name_value = context.dwarf.debug_str.get_str(name_value_ref).unwrap();
 
         assert_eq!(entry_1.tag(), DW_TAG_namespace);
-        assert_eq!(name_value, EndianSlice::new(b"core", LittleEndian));
+        assert_eq!(name_value, EndianSlice::new(b"core", RunTimeEndian::Little));
     }
 
     /// Test if the `DefaultParser` parses debug info correctly.
     /// We validate this by checking the first instruction of the text section.
     #[test]
     pub fn test_example_binary_disassembly() {
+        let path = test_common::get_test_subject_path("threads", &test_common::TestSubjectType::DebugStableRustc);
         let file_content = &test_common::load_test_binary_as_bytes(
             "threads",
             &test_common::TestSubjectType::DebugStableRustc,
         ).unwrap();
 
-        let context = DefaultParser.parse(file_content).unwrap();
+        let buffers = DebugBuffers::default();
+        let context = DefaultParser.parse(file_content, &path, &buffers).unwrap();
         // Test 42nd instruction
         let instr = context
-            .capstone
-            .disasm_all(context.elf.section_data_by_name(".text").unwrap(), 0x6210)
-            .unwrap()
-            .iter()
+            .disassemble(context.elf.section_data_by_name(".text").unwrap(), 0x6210)
+            .into_iter()
             .next()
             .unwrap();
 
-        assert_eq!(instr.address(), 0x6210);
-        assert_eq!(instr.bytes(), &[65u8, 87u8]);
-        assert_eq!(instr.mnemonic(), Some("push"));
-        assert_eq!(instr.op_str(), Some("r15"));
+        assert_eq!(instr.address, 0x6210);
+        assert_eq!(instr.size, 2);
+        assert_eq!(instr.mnemonic, "push");
+        assert_eq!(instr.kind, InstructionKind::Other { destination: None });
+    }
+
+    /// Test if the byte order is read from the ELF header, and used for the DWARF sections.
+    /// We validate this with a big endian variant of the ELF file without sections of WebAssembly modules.
+    #[test]
+    pub fn test_big_endian_header() {
+        let little_endian = ElfFile::parse(wasm::EMPTY_ELF).unwrap();
+        assert_eq!(get_endian(&little_endian).unwrap(), RunTimeEndian::Little);
+
+        // ELFDATA2MSB, with the multi-byte fields of the header in big endian order
+        let mut file_content = wasm::EMPTY_ELF.to_vec();
+        file_content[5] = 2;
+        for field in &[20..24, 32..36, 40..42, 42..44, 46..48, 48..50] {
+            file_content[field.clone()].reverse();
+        }
+        let big_endian = ElfFile::parse(&file_content).unwrap();
+        assert_eq!(get_endian(&big_endian).unwrap(), RunTimeEndian::Big);
+
+        let buffers = DebugBuffers::default();
+        let dwarf = load_dwarf(&big_endian, false, &buffers).unwrap();
+        assert_eq!(dwarf.debug_info.reader().endian(), RunTimeEndian::Big);
     }
 }
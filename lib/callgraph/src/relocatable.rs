@@ -0,0 +1,294 @@
+// (C) COPYRIGHT 2018 TECHNOLUTION BV, GOUDA NL
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Static libraries (`.a`), Rust libraries (`.rlib`) and relocatable object files (`.o`).
+//!
+//! Library crates can be analysed before they are linked into an executable. A static library or rlib is an `ar`
+//! archive, of which the ELF members with type `ET_REL` are analysed together; other members (like the `lib.rmeta`
+//! metadata of an rlib) are skipped. A relocatable object file is analysed as an archive with a single member.
+//!
+//! The sections of a relocatable object have no address yet, so the executable sections of all members are placed
+//! after each other, like the linker would do. The call targets in the machine code are not filled in either; the calls
+//! are found from the relocations of the code instead (see `callgraph::relocatable_calls`). The debug information of
+//! relocatable objects is not relocated, so it is not used: procedures are read from the symbol tables of the members.
+
+use crate::errors::*;
+
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+
+use object::ElfFile;
+
+use std::collections::HashMap;
+
+/// Magic at the start of an `ar` archive.
+const ARCHIVE_MAGIC: &[u8] = b"!<arch>\n";
+
+/// Size of the header of an archive member.
+const ARCHIVE_HEADER_SIZE: usize = 60;
+
+/// Magic at the start of an ELF file.
+const ELF_MAGIC: &[u8] = b"\x7fELF";
+
+/// Data encoding of big endian ELF files (`ELFDATA2MSB`), stored in `e_ident[EI_DATA]`.
+const ELFDATA2MSB: u8 = 2;
+
+/// ELF file type of relocatable objects (`ET_REL`).
+const ET_REL: u16 = 1;
+
+/// Section flags of sections that occupy memory (`SHF_ALLOC`), and of sections containing machine code
+/// (`SHF_EXECINSTR`).
+const SHF_ALLOC: u64 = 0x2;
+const SHF_EXECINSTR: u64 = 0x4;
+
+/// ELF symbol type of procedures (`STT_FUNC`).
+const STT_FUNC: u8 = 2;
+
+/// ELF symbol bindings of symbols that are visible outside the object (`STB_GLOBAL` and `STB_WEAK`).
+const STB_GLOBAL: u8 = 1;
+const STB_WEAK: u8 = 2;
+
+/// ELF symbol visibility of exported symbols (`STV_DEFAULT`), stored in the lowest 2 bits of `st_other`.
+const STV_DEFAULT: u8 = 0;
+
+/// Address of the first section, so no procedure is placed at address 0.
+const BASE_ADDRESS: u64 = 0x1000;
+
+/// A relocatable object: a member of a static library, or an object file that is analysed on its own.
+pub struct ObjectFile<'a> {
+    /// Name of the member in the archive, or the empty string for an object file
+    pub name: String,
+    pub content: &'a [u8],
+    pub elf: ElfFile<'a>,
+    /// Addresses at which the executable sections are placed, by section index
+    pub section_addresses: HashMap<usize, u64>,
+}
+
+impl<'a> ObjectFile<'a> {
+    /// Returns the address of `offset` bytes into the section at `section_index`, if that section is placed.
+    pub fn address(&self, section_index: usize, offset: u64) -> Option<u64> {
+        self.section_addresses.get(&section_index).map(|address| address + offset)
+    }
+}
+
+/// Returns `true` if `file_content` is an `ar` archive, or a relocatable ELF object.
+pub fn is_relocatable(file_content: &[u8]) -> bool {
+    file_content.starts_with(ARCHIVE_MAGIC) || is_relocatable_elf(file_content)
+}
+
+fn is_relocatable_elf(content: &[u8]) -> bool {
+    if !content.starts_with(ELF_MAGIC) || content.len() < 18 {
+        return false;
+    }
+    let e_type = match content[5] {
+        ELFDATA2MSB => BigEndian::read_u16(&content[16..18]),
+        _ => LittleEndian::read_u16(&content[16..18]),
+    };
+    e_type == ET_REL
+}
+
+/// Returns the relocatable objects in `file_content`, which is an `ar` archive or a relocatable object, with their
+/// executable sections placed after each other.
+pub fn get_object_files(file_content: &[u8]) -> Result<Vec<ObjectFile<'_>>> {
+    let members = match file_content.starts_with(ARCHIVE_MAGIC) {
+        true => get_archive_members(file_content)?,
+        false => vec![(String::new(), file_content)],
+    };
+
+    let mut address = BASE_ADDRESS;
+    members
+        .into_iter()
+        .filter(|(_, content)| is_relocatable_elf(content))
+        .map(|(name, content)| {
+            let elf = ElfFile::parse(content).map_err(|message| {
+                Error::from(ErrorKind::ParseError(format!("Invalid archive member {}: {}", name, message)))
+            })?;
+
+            let section_addresses = elf
+                .elf()
+                .section_headers
+                .iter()
+                .enumerate()
+                .filter(|(_, header)| header.sh_flags & (SHF_ALLOC | SHF_EXECINSTR) == SHF_ALLOC | SHF_EXECINSTR)
+                .map(|(index, header)| {
+                    let alignment = header.sh_addralign.max(1);
+                    let section_address = address.div_ceil(alignment) * alignment;
+                    address = section_address + header.sh_size;
+                    (index, section_address)
+                })
+                .collect();
+
+            Ok(ObjectFile {
+                name,
+                content,
+                elf,
+                section_addresses,
+            })
+        })
+        .collect()
+}
+
+/// Returns the end of the address range in which the executable sections of `object_files` are placed.
+pub fn end_address(object_files: &[ObjectFile]) -> u64 {
+    object_files
+        .iter()
+        .flat_map(|object_file| {
+            let headers = &object_file.elf.elf().section_headers;
+            object_file
+                .section_addresses
+                .iter()
+                .map(move |(index, address)| address + headers[*index].sh_size)
+        })
+        .max()
+        .unwrap_or(BASE_ADDRESS)
+}
+
+/// Returns the addresses of the procedures that are visible outside `object_files`: the defined procedures in their
+/// symbol tables with global or weak binding, and default visibility. These are the functions a program that links the
+/// library can call.
+pub fn get_global_procedures(object_files: &[ObjectFile]) -> Vec<u64> {
+    object_files
+        .iter()
+        .flat_map(|object_file| {
+            object_file
+                .elf
+                .elf()
+                .syms
+                .iter()
+                .filter(|symbol| symbol.st_type() == STT_FUNC)
+                .filter(|symbol| symbol.st_bind() == STB_GLOBAL || symbol.st_bind() == STB_WEAK)
+                .filter(|symbol| symbol.st_other & 0x3 == STV_DEFAULT)
+                .filter_map(|symbol| object_file.address(symbol.st_shndx, symbol.st_value))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Returns the name and content of each member of the `ar` archive in `content`.
+///
+/// The symbol table (`/` or `/SYM64/`) and the table of long member names (`//`) are not returned. Long member names
+/// are supported in both the GNU (`/<offset>`) and BSD (`#1/<length>`) format.
+fn get_archive_members(content: &[u8]) -> Result<Vec<(String, &[u8])>> {
+    let mut members = vec![];
+    let mut long_names: &[u8] = &[];
+    let mut offset = ARCHIVE_MAGIC.len();
+
+    while offset + ARCHIVE_HEADER_SIZE <= content.len() {
+        let header = &content[offset..offset + ARCHIVE_HEADER_SIZE];
+        if &header[58..60] != b"`\n" {
+            bail!(ErrorKind::ParseError(format!("Invalid archive member header at offset {}", offset)));
+        }
+        let size = parse_decimal(&header[48..58])
+            .ok_or_else(|| ErrorKind::ParseError(format!("Invalid archive member size at offset {}", offset)))?;
+        let data_offset = offset + ARCHIVE_HEADER_SIZE;
+        let mut data = content
+            .get(data_offset..data_offset + size)
+            .ok_or_else(|| ErrorKind::ParseError(format!("Truncated archive member at offset {}", offset)))?;
+        // Members are aligned to 2 bytes
+        offset = data_offset + size + size % 2;
+
+        let name = std::str::from_utf8(&header[..16]).unwrap_or_default().trim_end();
+        let name = match name {
+            "/" | "/SYM64/" => continue,
+            "//" => {
+                long_names = data;
+                continue;
+            }
+            _ if name.starts_with("#1/") => {
+                let length = parse_decimal(name[3..].as_bytes()).filter(|length| *length <= data.len());
+                let length = length
+                    .ok_or_else(|| ErrorKind::ParseError(format!("Invalid archive member name {}", name)))?;
+                let long_name = String::from_utf8_lossy(&data[..length]).trim_end_matches('\0').to_string();
+                data = &data[length..];
+                long_name
+            }
+            _ if name.len() > 1 && name.starts_with('/') => {
+                let long_name = parse_decimal(name[1..].as_bytes())
+                    .and_then(|name_offset| long_names.get(name_offset..))
+                    .and_then(|long_name| long_name.split(|byte| *byte == b'\n').next())
+                    .ok_or_else(|| ErrorKind::ParseError(format!("Invalid archive member name {}", name)))?;
+                String::from_utf8_lossy(long_name).trim_end_matches('/').to_string()
+            }
+            _ => name.trim_end_matches('/').to_string(),
+        };
+        members.push((name, data));
+    }
+    Ok(members)
+}
+
+/// Parses a decimal number in a field of an archive member header, which is padded with spaces.
+fn parse_decimal(field: &[u8]) -> Option<usize> {
+    std::str::from_utf8(field).ok()?.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Returns the header and data of an archive member named `name`.
+    fn member(name: &str, data: &[u8]) -> Vec<u8> {
+        let mut member = format!("{:<16}{:<12}{:<6}{:<6}{:<8}{:<10}`\n", name, 0, 0, 0, 644, data.len()).into_bytes();
+        member.extend_from_slice(data);
+        if data.len() % 2 == 1 {
+            member.push(b'\n');
+        }
+        member
+    }
+
+    /// Verify the members of an archive are read, with their short, GNU and BSD long names
+    #[test]
+    fn test_archive_members() {
+        let long_names = b"ffi_lib-0123456789abcdef.ffi_lib.1a2b3c4d-cgu.0.rcgu.o/\n";
+        let archive = [
+            ARCHIVE_MAGIC.to_vec(),
+            member("/", &[0, 0, 0, 0]),
+            member("//", long_names),
+            member("lib.rmeta/", b"rust"),
+            member("/0", b"odd"),
+            member("#1/8", b"bsd.o\0\0\0data"),
+        ].concat();
+
+        assert_eq!(
+            get_archive_members(&archive).unwrap(),
+            vec![
+                ("lib.rmeta".to_string(), &b"rust"[..]),
+                ("ffi_lib-0123456789abcdef.ffi_lib.1a2b3c4d-cgu.0.rcgu.o".to_string(), &b"odd"[..]),
+                ("bsd.o".to_string(), &b"data"[..]),
+            ]
+        );
+
+        // Members that are not relocatable objects are skipped
+        assert!(is_relocatable(&archive));
+        assert!(get_object_files(&archive).unwrap().is_empty());
+
+        let truncated = &archive[..archive.len() - 2];
+        assert!(get_archive_members(truncated).is_err());
+    }
+
+    /// Verify a linked executable is not relocatable
+    #[test]
+    fn test_executable_is_not_relocatable() {
+        let file_content =
+            test_common::load_test_binary_as_bytes("hello_world", &test_common::TestSubjectType::Debug).unwrap();
+
+        assert!(!is_relocatable(&file_content));
+    }
+
+    /// Verify the file type of an ELF file is read in the byte order of its header
+    #[test]
+    fn test_big_endian_relocatable() {
+        let mut header = [0u8; 18];
+        header[..4].copy_from_slice(ELF_MAGIC);
+        header[5] = ELFDATA2MSB;
+        header[17] = 1;
+        assert!(is_relocatable(&header));
+
+        // ELFDATA2LSB: the file type is 0x0100
+        header[5] = 1;
+        assert!(!is_relocatable(&header));
+    }
+}
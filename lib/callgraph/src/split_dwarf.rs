@@ -0,0 +1,128 @@
+// (C) COPYRIGHT 2018 TECHNOLUTION BV, GOUDA NL
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Split debug information (`-C split-debuginfo=unpacked` or `packed`).
+//!
+//! With split debug information, the compilation units in the binary are skeleton units. These only contain the DWO ID
+//! of their split unit, and the name of the `.dwo` file containing it (`DW_AT_dwo_name`, relative to
+//! `DW_AT_comp_dir`). With `packed`, the `.dwo` files are combined into a DWARF package next to the binary
+//! (`<binary>.dwp`). The split units are looked up in the package first, and in the `.dwo` files otherwise.
+//! Skeleton units of which the split unit is not found are analyzed as they are.
+
+use crate::compressed_sections::debug_section_data;
+use crate::dwarf_utils;
+use crate::parse;
+use crate::DebugBuffers;
+use crate::DwarfReader;
+
+use fallible_iterator::FallibleIterator;
+
+use gimli::DwarfPackage;
+use gimli::DwoId;
+use gimli::Dwarf;
+use gimli::EndianSlice;
+use gimli::SectionId;
+
+use object::ElfFile;
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Returns the split debug information of the skeleton units in `dwarf`, by DWO ID.
+/// `file_path` is the path of the binary, next to which the DWARF package is searched. Only the `.dwo` files are read for
+/// binaries that are analyzed in memory, without a path.
+///
+/// The contents of the `.dwo` and `.dwp` files are stored in `buffers`, as the split debug information borrows them for
+/// as long as the binary is analyzed.
+pub fn load_split_dwarf<'a>(
+    dwarf: &Dwarf<DwarfReader<'a>>,
+    file_path: Option<&Path>,
+    buffers: &'a DebugBuffers,
+) -> HashMap<DwoId, Dwarf<DwarfReader<'a>>> {
+    let skeleton_units = get_skeleton_units(dwarf);
+    if skeleton_units.is_empty() {
+        return HashMap::new();
+    }
+
+    let package = file_path.and_then(|file_path| load_package(file_path, buffers));
+
+    skeleton_units
+        .into_iter()
+        .filter_map(|(dwo_id, dwo_path)| {
+            let split_dwarf = package
+                .as_ref()
+                .and_then(|package| package.find_cu(dwo_id, dwarf).ok().flatten())
+                .or_else(|| load_dwo(dwarf, dwo_path.as_ref()?, buffers))?;
+            Some((dwo_id, split_dwarf))
+        })
+        .collect()
+}
+
+/// Returns the DWO ID and the path of the `.dwo` file of all skeleton units in `dwarf`.
+fn get_skeleton_units(dwarf: &Dwarf<DwarfReader>) -> Vec<(DwoId, Option<PathBuf>)> {
+    dwarf
+        .units()
+        .map(|unit_header| dwarf.unit(unit_header))
+        .iterator()
+        .filter_map(Result::ok)
+        .filter_map(|unit| {
+            let dwo_id = unit.dwo_id?;
+
+            let dwo_name = {
+                let mut entries = unit.entries();
+                let (_, skeleton) = entries.next_dfs().ok()??;
+                [gimli::DW_AT_dwo_name, gimli::DW_AT_GNU_dwo_name]
+                    .iter()
+                    .find_map(|attr| dwarf_utils::get_attr_string_value(skeleton, *attr, dwarf, &unit))
+            };
+            let comp_dir = unit.comp_dir.and_then(|comp_dir| comp_dir.to_string().ok());
+
+            // An absolute `DW_AT_dwo_name` replaces the compilation directory
+            let dwo_path = dwo_name.map(|dwo_name| match comp_dir {
+                Some(comp_dir) => Path::new(comp_dir).join(dwo_name),
+                None => PathBuf::from(dwo_name),
+            });
+            Some((dwo_id, dwo_path))
+        })
+        .collect()
+}
+
+/// Loads the DWARF package of the binary at `file_path`, or returns `None` if there is no package.
+fn load_package<'a>(file_path: &Path, buffers: &'a DebugBuffers) -> Option<DwarfPackage<DwarfReader<'a>>> {
+    let mut package_path = file_path.as_os_str().to_owned();
+    package_path.push(".dwp");
+
+    let content = buffers.store_file(crate::map_file(Path::new(&package_path)).ok()?);
+    let elf = ElfFile::parse(content).ok()?;
+    let endian = parse::get_endian(&elf).ok()?;
+
+    DwarfPackage::load(
+        |section: SectionId| -> gimli::Result<DwarfReader<'a>> {
+            let data = section
+                .dwo_name()
+                .and_then(|name| debug_section_data(&elf, name, buffers).ok().flatten());
+            Ok(EndianSlice::new(data.unwrap_or(&[]), endian))
+        },
+        EndianSlice::new(&[], endian),
+    ).ok()
+}
+
+/// Loads the split debug information in the `.dwo` file at `path`, of which the skeleton units are in `parent`.
+fn load_dwo<'a>(
+    parent: &Dwarf<DwarfReader<'a>>,
+    path: &Path,
+    buffers: &'a DebugBuffers,
+) -> Option<Dwarf<DwarfReader<'a>>> {
+    let content = buffers.store_file(crate::map_file(path).ok()?);
+    let elf = ElfFile::parse(content).ok()?;
+
+    let mut dwarf = parse::load_dwarf(&elf, true, buffers).ok()?;
+    dwarf.make_dwo(parent);
+    Some(dwarf)
+}
@@ -0,0 +1,305 @@
+// (C) COPYRIGHT 2018 TECHNOLUTION BV, GOUDA NL
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Structured paths of demangled Rust symbols.
+//!
+//! Symbols are demangled by `dwarf_utils::demangle_symbol`, for both the legacy mangling and v0 mangling
+//! (`-C symbol-mangling-version=v0`). The demangled names differ between the two: v0 names contain the generic
+//! arguments of items (`core::ptr::drop_in_place::<alloc::string::String>`) and of impl self types
+//! (`<core::option::Option<u8>>::unwrap` instead of `<core::option::Option<T>>::unwrap`), and closures are named
+//! `{closure#0}` instead of `{{closure}}`. Matching procedures by their `SymbolPath` instead of by a suffix of their
+//! demangled name works for both.
+
+/// Path of a procedure, parsed from its demangled symbol name.
+///
+/// For `<alloc::vec::Vec<T> as core::ops::drop::Drop>::drop`, the crate is `alloc`, the self type
+/// `alloc::vec::Vec<T>`, the trait `core::ops::drop::Drop` and the item `drop`. For `hello_world::foo::bar::<u8>`, the
+/// crate is `hello_world`, the module path `foo`, the item `bar` and the generic arguments `u8`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SymbolPath {
+    /// Crate of the path, or of the self type of an impl (or of the trait, if the self type is not a path, e.g. `u8`).
+    /// `None` if the name contains no path (e.g. a C procedure).
+    pub crate_name: Option<String>,
+    /// Disambiguator of the crate in v0 symbols, which distinguishes crates with the same name
+    pub crate_disambiguator: Option<u64>,
+    /// Segments between the crate (or the impl) and the item
+    pub module_path: Vec<String>,
+    /// Self type of the impl containing the item
+    pub self_type: Option<String>,
+    /// Trait implemented by the impl containing the item
+    pub trait_name: Option<String>,
+    /// Name of the item, e.g. `unwrap` or `{closure#0}`
+    pub item: String,
+    /// Generic arguments of the item, including const generics, e.g. `["u8", "3"]` for `foo::<u8, 3>`
+    pub generic_args: Vec<String>,
+}
+
+impl SymbolPath {
+    /// Parses the demangled symbol name `name`.
+    /// A crate disambiguator is read from a crate written as `name[<hex>]`, as in v0 symbols that are demangled
+    /// without omitting the disambiguators (see `crate_disambiguator`).
+    pub fn parse(name: &str) -> SymbolPath {
+        let segments = split_path(name);
+        let (first, rest) = match segments.split_first() {
+            Some(split) => split,
+            None => return SymbolPath::default(),
+        };
+
+        // A qualified path, `<Type as Trait>::item` or `<Type>::item`
+        let (mut path, rest) = if is_angle_bracketed(first.name) {
+            let qualified = &first.name[1..first.name.len() - 1];
+            let (self_type, trait_name) = match find_top_level(qualified, " as ") {
+                Some(index) => (&qualified[..index], Some(&qualified[index + 4..])),
+                None => (qualified, None),
+            };
+            let (crate_name, crate_disambiguator) = type_crate(self_type)
+                .or_else(|| trait_name.and_then(type_crate))
+                .map(|(name, disambiguator)| (Some(name), disambiguator))
+                .unwrap_or((None, None));
+            let path = SymbolPath {
+                crate_name,
+                crate_disambiguator,
+                self_type: Some(self_type.to_string()),
+                trait_name: trait_name.map(str::to_string),
+                ..SymbolPath::default()
+            };
+            (path, rest)
+        } else if !rest.is_empty() {
+            let (crate_name, crate_disambiguator) = split_disambiguator(first.name);
+            let path = SymbolPath {
+                crate_name: Some(crate_name.to_string()),
+                crate_disambiguator,
+                ..SymbolPath::default()
+            };
+            (path, rest)
+        } else {
+            (SymbolPath::default(), &segments[..])
+        };
+
+        if let Some((item, modules)) = rest.split_last() {
+            path.module_path = modules.iter().map(|segment| segment.name.to_string()).collect();
+            path.item = item.name.to_string();
+            path.generic_args = item.generic_args.iter().map(|arg| arg.to_string()).collect();
+        }
+        path
+    }
+
+    /// Returns the segments of the path from the crate to the item, without generic arguments. For items in an impl,
+    /// the path of the self type is used as the start of the path.
+    pub fn segments(&self) -> Vec<&str> {
+        self.segments_from(self.self_type.as_ref().map(String::as_str))
+    }
+
+    /// Returns `true` if `filter` is a suffix of the path, comparing whole segments without generic arguments.
+    /// For items in a trait impl, `filter` may also start with the path of the trait. For example, `Option::unwrap`
+    /// and `unwrap` match `<core::option::Option<T>>::unwrap`, while `ption::unwrap` does not.
+    pub fn matches(&self, filter: &str) -> bool {
+        let filter = split_path(filter)
+            .iter()
+            .map(|segment| strip_generic_args(segment.name))
+            .collect::<Vec<_>>();
+        if filter.is_empty() {
+            return false;
+        }
+
+        let trait_segments = self
+            .trait_name
+            .as_ref()
+            .map(|trait_name| self.segments_from(Some(trait_name.as_str())));
+        std::iter::once(self.segments())
+            .chain(trait_segments)
+            .any(|segments| segments.ends_with(&filter))
+    }
+
+    fn segments_from<'a>(&'a self, start: Option<&'a str>) -> Vec<&'a str> {
+        let start = match start {
+            Some(start) => split_path(start)
+                .iter()
+                .map(|segment| strip_generic_args(segment.name))
+                .collect(),
+            None => self.crate_name.iter().map(String::as_str).collect::<Vec<_>>(),
+        };
+        start
+            .into_iter()
+            .chain(self.module_path.iter().map(|segment| strip_generic_args(segment.as_str())))
+            .chain(std::iter::once(self.item.as_str()))
+            .filter(|segment| !segment.is_empty())
+            .collect()
+    }
+}
+
+/// Returns the disambiguator of the crate of the v0 symbol `linkage_name`, or `None` for other symbols.
+pub fn crate_disambiguator(linkage_name: &str) -> Option<u64> {
+    match rustc_demangle::try_demangle(linkage_name) {
+        Ok(demangled) => SymbolPath::parse(&demangled.to_string()).crate_disambiguator,
+        Err(_) => None,
+    }
+}
+
+/// Segment of a path, with the generic arguments of a turbofish (`::<...>`) following it.
+struct Segment<'a> {
+    name: &'a str,
+    generic_args: Vec<&'a str>,
+}
+
+/// Splits `path` at the `::` separators that are not nested in generic arguments, brackets or braces.
+fn split_path(path: &str) -> Vec<Segment> {
+    let mut segments: Vec<Segment> = vec![];
+    split_top_level(path, "::").into_iter().for_each(|segment| {
+        // A turbofish contains the generic arguments of the previous segment
+        match segments.last_mut() {
+            Some(previous) if is_angle_bracketed(segment) => {
+                previous.generic_args = split_top_level(&segment[1..segment.len() - 1], ", ");
+            }
+            _ => segments.push(Segment {
+                name: segment,
+                generic_args: vec![],
+            }),
+        }
+    });
+    segments
+}
+
+/// Returns `true` if `segment` is a qualified path (`<Type as Trait>`) or a turbofish, but not an inherent impl of a
+/// primitive type in a legacy symbol (`<impl [T]>`).
+fn is_angle_bracketed(segment: &str) -> bool {
+    segment.len() >= 2 && segment.starts_with('<') && segment.ends_with('>') && !segment.starts_with("<impl ")
+}
+
+/// Returns the crate and its disambiguator of the type `ty`, if it is a path with at least two segments (possibly
+/// behind a reference or pointer).
+fn type_crate(ty: &str) -> Option<(String, Option<u64>)> {
+    let ty = ["&mut ", "&", "*const ", "*mut ", "dyn "]
+        .iter()
+        .fold(ty.trim(), |ty, prefix| ty.trim_start_matches(prefix));
+    let segments = split_path(ty);
+    if segments.len() < 2 {
+        return None;
+    }
+    let (name, disambiguator) = split_disambiguator(segments[0].name);
+    if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        return None;
+    }
+    Some((name.to_string(), disambiguator))
+}
+
+/// Splits a crate written as `name[<hex>]` into its name and disambiguator.
+fn split_disambiguator(segment: &str) -> (&str, Option<u64>) {
+    match segment.find('[') {
+        Some(index) if segment.ends_with(']') => (
+            &segment[..index],
+            u64::from_str_radix(&segment[index + 1..segment.len() - 1], 16).ok(),
+        ),
+        _ => (segment, None),
+    }
+}
+
+/// Returns `segment` without the generic arguments of a type (`Option<T>`).
+fn strip_generic_args(segment: &str) -> &str {
+    match segment.find('<') {
+        Some(index) if index > 0 => &segment[..index],
+        _ => segment,
+    }
+}
+
+/// Returns the index of the first occurrence of `separator` in `s` that is not nested in generic arguments, brackets,
+/// parentheses or braces.
+fn find_top_level(s: &str, separator: &str) -> Option<usize> {
+    let mut depth = 0i32;
+    let mut previous = ' ';
+    for (index, c) in s.char_indices() {
+        if depth == 0 && s[index..].starts_with(separator) {
+            return Some(index);
+        }
+        match c {
+            '<' | '(' | '[' | '{' => depth += 1,
+            // The `>` of `->` in function types does not close generic arguments
+            '>' if previous != '-' => depth -= 1,
+            ')' | ']' | '}' => depth -= 1,
+            _ => (),
+        }
+        previous = c;
+    }
+    None
+}
+
+/// Splits `s` at the occurrences of `separator` that are not nested, see `find_top_level`.
+fn split_top_level<'a>(s: &'a str, separator: &str) -> Vec<&'a str> {
+    let mut parts = vec![];
+    let mut rest = s;
+    while let Some(index) = find_top_level(rest, separator) {
+        parts.push(&rest[..index]);
+        rest = &rest[index + separator.len()..];
+    }
+    parts.push(rest);
+    parts.into_iter().filter(|part| !part.is_empty()).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::dwarf_utils::demangle_symbol;
+
+    /// Verify legacy and v0 symbols of the same procedure are demangled into the same path
+    #[test]
+    fn test_legacy_and_v0_symbols() {
+        let legacy = SymbolPath::parse(&demangle_symbol("_ZN11hello_world4main17h0123456789abcdefE"));
+        let v0 = SymbolPath::parse(&demangle_symbol("_RNvCs1234_11hello_world4main"));
+
+        assert_eq!(legacy.crate_name, Some("hello_world".to_string()));
+        assert_eq!(legacy.item, "main");
+        assert_eq!(legacy.segments(), vec!["hello_world", "main"]);
+        assert_eq!(v0.segments(), legacy.segments());
+
+        assert_eq!(crate_disambiguator("_RNvCs1234_11hello_world4main"), Some(0x3c1bf));
+        assert_eq!(crate_disambiguator("_ZN11hello_world4main17h0123456789abcdefE"), None);
+    }
+
+    /// Verify the self type, trait, item and generic arguments of trait implementations and generic items are parsed
+    #[test]
+    fn test_parse_symbol_path() {
+        let path = SymbolPath::parse("<alloc::vec::Vec<u8> as core::ops::drop::Drop>::drop");
+        assert_eq!(path.crate_name, Some("alloc".to_string()));
+        assert_eq!(path.self_type, Some("alloc::vec::Vec<u8>".to_string()));
+        assert_eq!(path.trait_name, Some("core::ops::drop::Drop".to_string()));
+        assert_eq!(path.item, "drop");
+        assert!(path.matches("Vec::drop"));
+        assert!(path.matches("Drop::drop"));
+
+        let path = SymbolPath::parse("<u8 as core::fmt::Debug>::fmt::{closure#0}");
+        assert_eq!(path.crate_name, Some("core".to_string()));
+        assert_eq!(path.module_path, vec!["fmt"]);
+        assert_eq!(path.item, "{closure#0}");
+
+        let path = SymbolPath::parse("hello_world::foo::bar::<[u8; 3], 3, fn(u8) -> u8>");
+        assert_eq!(path.module_path, vec!["foo"]);
+        assert_eq!(path.item, "bar");
+        assert_eq!(path.generic_args, vec!["[u8; 3]", "3", "fn(u8) -> u8"]);
+
+        let path = SymbolPath::parse("memcpy");
+        assert_eq!(path.crate_name, None);
+        assert_eq!(path.item, "memcpy");
+    }
+
+    /// Verify filters match whole segments at the end of the path
+    #[test]
+    fn test_matches() {
+        let path = SymbolPath::parse("<core::option::Option<u8>>::unwrap");
+        assert!(path.matches("unwrap"));
+        assert!(path.matches("Option::unwrap"));
+        assert!(path.matches("core::option::Option<T>::unwrap"));
+        assert!(!path.matches("ption::unwrap"));
+        assert!(!path.matches("Result::unwrap"));
+        assert!(!path.matches(""));
+
+        let path = SymbolPath::parse("alloc::slice::<impl [T]>::to_vec");
+        assert!(path.matches("to_vec"));
+        assert_eq!(path.crate_name, Some("alloc".to_string()));
+    }
+}
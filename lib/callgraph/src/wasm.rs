@@ -0,0 +1,649 @@
+// (C) COPYRIGHT 2018 TECHNOLUTION BV, GOUDA NL
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! WebAssembly modules (e.g. built for `wasm32-unknown-unknown` or `wasm32-wasip1`).
+//!
+//! A module is not an ELF file, but it carries DWARF in custom sections (`.debug_info`, `.debug_line`, ...). The
+//! addresses in that debug information are offsets into the contents of the code section, so the procedures are read
+//! from the debug information like those of an ELF binary, and their machine code is read from the code section at
+//! those offsets. The address of a defined function is the offset of its body, after the size of the body. Imported
+//! functions have no body; they are given addresses starting at `IMPORTED_FUNCTION_BASE`, so calls to them can be
+//! recognised.
+//!
+//! Functions are called by their index (`call`), or through a table (`call_indirect`). The functions a table can
+//! contain are listed in the element segments of the module, of which the functions with the type of the
+//! `call_indirect` instruction are its possible targets (see `callgraph::wasm_calls`).
+//!
+//! Only core modules (version 1) are supported, components are not.
+
+use crate::disassembly::Instruction;
+use crate::errors::*;
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// Magic at the start of a WebAssembly module.
+const WASM_MAGIC: &[u8] = b"\0asm";
+
+/// Version of core WebAssembly modules. Components have another version.
+const WASM_VERSION: &[u8] = &[1, 0, 0, 0];
+
+/// Section identifiers.
+const SECTION_CUSTOM: u8 = 0;
+const SECTION_TYPE: u8 = 1;
+const SECTION_IMPORT: u8 = 2;
+const SECTION_FUNCTION: u8 = 3;
+const SECTION_EXPORT: u8 = 7;
+const SECTION_ELEMENT: u8 = 9;
+const SECTION_CODE: u8 = 10;
+
+/// Kind of imports and exports of functions.
+const EXTERNAL_KIND_FUNCTION: u8 = 0;
+
+/// Identifier of the subsection with function names in the `name` custom section.
+const NAME_SUBSECTION_FUNCTIONS: u8 = 1;
+
+/// Encoding of function types in the type section.
+const FUNCTION_TYPE: u8 = 0x60;
+
+/// Reference types that are followed by a heap type (`(ref null ht)` and `(ref ht)`).
+const REF_NULL_TYPE: u8 = 0x63;
+const REF_TYPE: u8 = 0x64;
+
+/// Opcodes of `call_indirect` and `return_call_indirect`, followed by the index of the type of the called function.
+const OPCODE_CALL_INDIRECT: u8 = 0x11;
+const OPCODE_RETURN_CALL_INDIRECT: u8 = 0x13;
+
+/// Address of the first imported function. It is beyond the 4 GiB addressable by the code section, so imported
+/// functions cannot be confused with defined functions.
+pub const IMPORTED_FUNCTION_BASE: u64 = 1 << 32;
+
+/// Address with which the linker (`wasm-ld`) replaces the addresses of functions it removed in the debug information.
+pub const TOMBSTONE_ADDRESS: u64 = 0xffff_ffff;
+
+/// ELF file without sections, which stands in for `Context::elf` when a module is analysed, so all lookups in the ELF
+/// sections and symbol tables find nothing. It is a 32-bit file, like the `wasm32` targets.
+pub const EMPTY_ELF: &[u8] = &[
+    0x7f, b'E', b'L', b'F', 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, // e_ident: ELFCLASS32, ELFDATA2LSB
+    0, 0, 0, 0, 1, 0, 0, 0, // e_type: ET_NONE, e_machine: EM_NONE, e_version
+    0, 0, 0, 0, 0, 0, 0, 0, 52, 0, 0, 0, // e_entry, e_phoff, e_shoff
+    0, 0, 0, 0, 52, 0, 32, 0, 0, 0, // e_flags, e_ehsize, e_phentsize, e_phnum
+    40, 0, 1, 0, 0, 0, // e_shentsize, e_shnum, e_shstrndx
+    // Null section header
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+];
+
+/// A function of a module, which is either imported or defined by the module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WasmFunction {
+    /// Name in the `name` custom section, or the field name of an imported function without one
+    pub name: Option<String>,
+    pub type_index: u32,
+    /// Offset of the body in the code section, or an address from `IMPORTED_FUNCTION_BASE` for an imported function
+    pub address: u64,
+    /// Size of the body in bytes, 0 for an imported function
+    pub size: u64,
+    pub imported: bool,
+}
+
+/// The parts of a WebAssembly module that are used by the analysis.
+pub struct WasmModule<'a> {
+    /// Contents of the code section, to which the addresses of the functions are relative
+    pub code: &'a [u8],
+    /// Functions by function index: the imported functions, followed by the functions defined by the module
+    pub functions: Vec<WasmFunction>,
+    /// Encoded parameter and result types of the function types, by type index
+    types: Vec<&'a [u8]>,
+    /// Indices of the functions in the element segments, which are the functions that can be placed in a table
+    element_functions: Vec<u32>,
+    /// Indices of the exported functions
+    exports: Vec<u32>,
+    custom_sections: HashMap<&'a str, &'a [u8]>,
+}
+
+/// Returns `true` if `file_content` is a WebAssembly module or component.
+pub fn is_wasm(file_content: &[u8]) -> bool {
+    file_content.starts_with(WASM_MAGIC)
+}
+
+impl<'a> WasmModule<'a> {
+    /// Parses the WebAssembly module in `file_content`.
+    pub fn parse(file_content: &'a [u8]) -> Result<WasmModule<'a>> {
+        if !is_wasm(file_content) {
+            bail!(ErrorKind::ParseError("Not a WebAssembly module".to_string()));
+        }
+        if file_content.get(4..8) != Some(WASM_VERSION) {
+            bail!(ErrorKind::NotSupported("WebAssembly components".to_string()));
+        }
+
+        let mut module = WasmModule {
+            code: &[],
+            functions: vec![],
+            types: vec![],
+            element_functions: vec![],
+            exports: vec![],
+            custom_sections: HashMap::new(),
+        };
+        let mut defined_types = vec![];
+        let mut bodies = vec![];
+
+        let mut reader = Reader::new(&file_content[8..]);
+        while !reader.is_empty() {
+            let id = reader.read_u8()?;
+            let size = reader.read_u32()? as usize;
+            let mut section = Reader::new(reader.read_bytes(size)?);
+
+            match id {
+                SECTION_CUSTOM => {
+                    let name = section.read_name()?;
+                    module.custom_sections.insert(name, section.remaining());
+                }
+                SECTION_TYPE => module.types = parse_types(&mut section)?,
+                SECTION_IMPORT => module.functions = parse_function_imports(&mut section)?,
+                SECTION_FUNCTION => defined_types = section.read_vec(Reader::read_u32)?,
+                SECTION_EXPORT => module.exports = parse_function_exports(&mut section)?,
+                SECTION_ELEMENT => module.element_functions = parse_element_functions(&mut section)?,
+                SECTION_CODE => {
+                    module.code = section.remaining();
+                    bodies = parse_bodies(&mut section)?;
+                }
+                _ => (),
+            }
+        }
+
+        if defined_types.len() != bodies.len() {
+            bail!(ErrorKind::ParseError(
+                "Number of functions and function bodies of WebAssembly module differ".to_string()
+            ));
+        }
+        module.functions.extend(
+            defined_types
+                .into_iter()
+                .zip(bodies)
+                .map(|(type_index, body)| WasmFunction {
+                    name: None,
+                    type_index,
+                    address: body.start,
+                    size: body.end - body.start,
+                    imported: false,
+                }),
+        );
+
+        if let Some(name_section) = module.custom_sections.get("name") {
+            // The name section is informative, so a malformed one is ignored
+            parse_function_names(name_section)
+                .unwrap_or_default()
+                .into_iter()
+                .for_each(|(index, name)| {
+                    if let Some(function) = module.functions.get_mut(index as usize) {
+                        function.name = Some(name);
+                    }
+                });
+        }
+
+        Ok(module)
+    }
+
+    /// Returns the contents of the custom section named `name` (e.g. `.debug_info`), if the module contains it.
+    pub fn custom_section(&self, name: &str) -> Option<&'a [u8]> {
+        self.custom_sections.get(name).cloned()
+    }
+
+    /// Returns the machine code in `range`, if `range` is located in the code section.
+    pub fn code_in_range(&self, range: &Range<u64>) -> Option<&'a [u8]> {
+        self.code.get(range.start as usize..range.end as usize)
+    }
+
+    /// Returns the function at `address`, if any.
+    pub fn function_at(&self, address: u64) -> Option<&WasmFunction> {
+        self.functions.iter().find(|function| function.address == address)
+    }
+
+    /// Returns the addresses of the defined functions that are exported by the module.
+    pub fn exported_functions(&self) -> Vec<u64> {
+        self.exports
+            .iter()
+            .filter_map(|index| self.functions.get(*index as usize))
+            .filter(|function| !function.imported)
+            .map(|function| function.address)
+            .collect()
+    }
+
+    /// Returns the addresses of the functions that may be called by `instruction`, which is a `call_indirect` or
+    /// `return_call_indirect` instruction: the functions in the element segments with the type of the called
+    /// function. Returns an empty vector for other instructions.
+    pub fn indirect_call_targets(&self, instruction: &Instruction) -> Vec<u64> {
+        let code = match self.code.get(instruction.address as usize..) {
+            Some(code) => code,
+            None => return vec![],
+        };
+        let mut reader = Reader::new(code);
+        let type_index = match reader.read_u8() {
+            Ok(OPCODE_CALL_INDIRECT) | Ok(OPCODE_RETURN_CALL_INDIRECT) => reader.read_u32(),
+            _ => return vec![],
+        };
+        let called_type = match type_index.ok().and_then(|index| self.types.get(index as usize)) {
+            Some(called_type) => called_type,
+            None => return vec![],
+        };
+
+        let mut targets = self
+            .element_functions
+            .iter()
+            .filter_map(|index| self.functions.get(*index as usize))
+            // Function types are equal if their parameter and result types are
+            .filter(|function| self.types.get(function.type_index as usize) == Some(called_type))
+            .map(|function| function.address)
+            .collect::<Vec<_>>();
+        targets.sort();
+        targets.dedup();
+        targets
+    }
+}
+
+/// Returns the encoded parameter and result types of the function types in the type section.
+fn parse_types<'a>(section: &mut Reader<'a>) -> Result<Vec<&'a [u8]>> {
+    section.read_vec(|reader| {
+        if reader.read_u8()? != FUNCTION_TYPE {
+            bail!(ErrorKind::NotSupported("WebAssembly types other than function types".to_string()));
+        }
+        let (data, start) = (reader.data, reader.position);
+        reader.read_vec(Reader::skip_value_type)?;
+        reader.read_vec(Reader::skip_value_type)?;
+        Ok(&data[start..reader.position])
+    })
+}
+
+/// Returns the imported functions in the import section, named after the imported field.
+fn parse_function_imports(section: &mut Reader) -> Result<Vec<WasmFunction>> {
+    let imports = section.read_vec(|reader| {
+        reader.read_name()?;
+        let field = reader.read_name()?;
+        match reader.read_u8()? {
+            EXTERNAL_KIND_FUNCTION => return Ok(Some((field.to_string(), reader.read_u32()?))),
+            // Table
+            1 => {
+                reader.skip_value_type()?;
+                reader.skip_limits()?;
+            }
+            // Memory
+            2 => reader.skip_limits()?,
+            // Global, followed by its mutability
+            3 => {
+                reader.skip_value_type()?;
+                reader.read_u8()?;
+            }
+            // Tag, of which the attribute precedes the type
+            4 => {
+                reader.read_u8()?;
+                reader.read_u32()?;
+            }
+            kind => bail!(ErrorKind::ParseError(format!("Invalid WebAssembly import kind {}", kind))),
+        }
+        Ok(None)
+    })?;
+
+    Ok(imports
+        .into_iter()
+        .flatten()
+        .enumerate()
+        .map(|(index, (field, type_index))| WasmFunction {
+            name: Some(field),
+            type_index,
+            address: IMPORTED_FUNCTION_BASE + index as u64,
+            size: 0,
+            imported: true,
+        })
+        .collect())
+}
+
+/// Returns the indices of the exported functions in the export section.
+fn parse_function_exports(section: &mut Reader) -> Result<Vec<u32>> {
+    let exports = section.read_vec(|reader| {
+        reader.read_name()?;
+        let kind = reader.read_u8()?;
+        let index = reader.read_u32()?;
+        Ok(Some(index).filter(|_| kind == EXTERNAL_KIND_FUNCTION))
+    })?;
+    Ok(exports.into_iter().flatten().collect())
+}
+
+/// Returns the indices of the functions in the active and passive segments of the element section.
+///
+/// The 8 encodings of segments are distinguished by the flags at their start: bit 0 is set for passive and declarative
+/// segments, bit 1 for declarative segments and active segments with a table index, and bit 2 for segments with
+/// expressions (`ref.func`) instead of function indices. Declarative segments only declare the functions that are
+/// referenced by `ref.func` instructions, so they are skipped.
+fn parse_element_functions(section: &mut Reader) -> Result<Vec<u32>> {
+    let segments = section.read_vec(|reader| {
+        let flags = reader.read_u32()?;
+        if flags > 7 {
+            bail!(ErrorKind::ParseError(format!("Invalid WebAssembly element segment flags {}", flags)));
+        }
+        let active = flags & 0b001 == 0;
+        let explicit_table = flags & 0b010 != 0;
+        let expressions = flags & 0b100 != 0;
+
+        if active && explicit_table {
+            reader.read_u32()?;
+        }
+        if active {
+            reader.skip_constant_expression()?;
+        }
+        // The element kind or reference type, which is implicit for the active segments of table 0
+        if !active || explicit_table {
+            match expressions {
+                true => reader.skip_value_type()?,
+                false => {
+                    reader.read_u8()?;
+                }
+            }
+        }
+
+        let functions: Vec<u32> = match expressions {
+            true => reader
+                .read_vec(Reader::skip_constant_expression)?
+                .into_iter()
+                .flatten()
+                .collect(),
+            false => reader.read_vec(Reader::read_u32)?,
+        };
+        let declarative = !active && explicit_table;
+        Ok(match declarative {
+            true => vec![],
+            false => functions,
+        })
+    })?;
+    Ok(segments.into_iter().flatten().collect())
+}
+
+/// Returns the address ranges of the function bodies in the code section, without the size preceding each body.
+fn parse_bodies(section: &mut Reader) -> Result<Vec<Range<u64>>> {
+    section.read_vec(|reader| {
+        let size = reader.read_u32()? as usize;
+        let start = reader.position;
+        reader.read_bytes(size)?;
+        Ok(start as u64..(start + size) as u64)
+    })
+}
+
+/// Returns the function names in the `name` custom section, by function index.
+fn parse_function_names(name_section: &[u8]) -> Result<Vec<(u32, String)>> {
+    let mut reader = Reader::new(name_section);
+    while !reader.is_empty() {
+        let id = reader.read_u8()?;
+        let size = reader.read_u32()? as usize;
+        let mut subsection = Reader::new(reader.read_bytes(size)?);
+        if id == NAME_SUBSECTION_FUNCTIONS {
+            return subsection.read_vec(|reader| Ok((reader.read_u32()?, reader.read_name()?.to_string())));
+        }
+    }
+    Ok(vec![])
+}
+
+/// Reader of the binary encoding of WebAssembly, in which integers are encoded as LEB128.
+pub(crate) struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(data: &'a [u8]) -> Reader<'a> {
+        Reader { data, position: 0 }
+    }
+
+    /// Returns the number of bytes that were read.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.position >= self.data.len()
+    }
+
+    /// Returns the bytes that were not read yet.
+    pub fn remaining(&self) -> &'a [u8] {
+        &self.data[self.position.min(self.data.len())..]
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8> {
+        let byte = *self.data.get(self.position).ok_or_else(truncated)?;
+        self.position += 1;
+        Ok(byte)
+    }
+
+    pub fn read_bytes(&mut self, length: usize) -> Result<&'a [u8]> {
+        let bytes = self
+            .data
+            .get(self.position..self.position + length)
+            .ok_or_else(truncated)?;
+        self.position += length;
+        Ok(bytes)
+    }
+
+    /// Reads an unsigned LEB128 integer. Signed integers (e.g. the immediate of `i64.const`) have the same length, so
+    /// they are skipped with this function as well.
+    pub fn read_leb(&mut self) -> Result<u64> {
+        let mut value = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.read_u8()?;
+            if shift < 64 {
+                value |= u64::from(byte & 0x7f) << shift;
+            }
+            shift += 7;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+    }
+
+    pub fn read_u32(&mut self) -> Result<u32> {
+        Ok(self.read_leb()? as u32)
+    }
+
+    /// Reads a name, which is a UTF-8 string preceded by its length.
+    pub fn read_name(&mut self) -> Result<&'a str> {
+        let length = self.read_u32()? as usize;
+        std::str::from_utf8(self.read_bytes(length)?)
+            .map_err(|_| Error::from(ErrorKind::ParseError("Invalid name in WebAssembly module".to_string())))
+    }
+
+    /// Reads a vector, which is a number of elements followed by the elements.
+    pub fn read_vec<T, R: FnMut(&mut Reader<'a>) -> Result<T>>(&mut self, mut read_element: R) -> Result<Vec<T>> {
+        let count = self.read_u32()?;
+        (0..count).map(|_| read_element(self)).collect()
+    }
+
+    /// Skips a value type. Reference types with a heap type consist of two bytes, all other value types of one.
+    pub fn skip_value_type(&mut self) -> Result<()> {
+        match self.read_u8()? {
+            REF_NULL_TYPE | REF_TYPE => self.read_leb().map(|_| ()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Skips the limits of a table or memory: flags, followed by the minimum size and the maximum size if bit 0 of the
+    /// flags is set.
+    fn skip_limits(&mut self) -> Result<()> {
+        let flags = self.read_u8()?;
+        self.read_leb()?;
+        if flags & 1 != 0 {
+            self.read_leb()?;
+        }
+        Ok(())
+    }
+
+    /// Skips a constant expression (e.g. the offset of an element segment) up to its `end`, and returns the index of
+    /// the function it references with `ref.func`, if any.
+    fn skip_constant_expression(&mut self) -> Result<Option<u32>> {
+        let mut function = None;
+        loop {
+            match self.read_u8()? {
+                // end
+                0x0b => return Ok(function),
+                // i32.const, i64.const, global.get, ref.null
+                0x41 | 0x42 | 0x23 | 0xd0 => {
+                    self.read_leb()?;
+                }
+                // ref.func
+                0xd2 => function = Some(self.read_u32()?),
+                // Arithmetic of the extended constant expressions
+                0x6a | 0x6b | 0x6c | 0x7c | 0x7d | 0x7e => (),
+                opcode => bail!(ErrorKind::NotSupported(format!(
+                    "WebAssembly constant expressions with opcode {:#x}",
+                    opcode
+                ))),
+            }
+        }
+    }
+}
+
+fn truncated() -> Error {
+    ErrorKind::ParseError("Truncated WebAssembly module".to_string()).into()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::disassembly::InstructionKind;
+
+    /// Returns the encoding of a section with identifier `id` and `contents`.
+    fn section(id: u8, contents: &[u8]) -> Vec<u8> {
+        [vec![id, contents.len() as u8], contents.to_vec()].concat()
+    }
+
+    /// Returns a module importing `env.log`, and defining 3 functions:
+    /// 0. `log` (imported, type 0)
+    /// 1. `main` (type 1), which calls `log`, and `call_indirect`s a function of type 0 from the table
+    /// 2. `first` (type 0)
+    /// 3. `second` (type 0)
+    ///
+    /// `first` and `second` are in the table, as is `main`, which has another type. `main` and `first` are exported.
+    fn example_module() -> Vec<u8> {
+        let body_main = [
+            0x01, 0x01, 0x7f, // 1 local of type i32
+            0x41, 0x07, // i32.const 7
+            0x10, 0x00, // call 0
+            0x41, 0x2a, // i32.const 42
+            0x41, 0x01, // i32.const 1
+            0x11, 0x00, 0x00, // call_indirect (type 0) (table 0)
+            0x0b, // end
+        ];
+        let body_first = [0x00, 0x0b];
+        let body_second = [0x00, 0x12, 0x00, 0x0b]; // return_call 0
+
+        let code = [
+            vec![3],
+            vec![body_main.len() as u8],
+            body_main.to_vec(),
+            vec![body_first.len() as u8],
+            body_first.to_vec(),
+            vec![body_second.len() as u8],
+            body_second.to_vec(),
+        ].concat();
+
+        [
+            WASM_MAGIC.to_vec(),
+            WASM_VERSION.to_vec(),
+            // (func (param i32)), (func)
+            section(SECTION_TYPE, &[2, 0x60, 1, 0x7f, 0, 0x60, 0, 0]),
+            section(SECTION_IMPORT, &[1, 3, b'e', b'n', b'v', 3, b'l', b'o', b'g', 0, 0]),
+            section(SECTION_FUNCTION, &[3, 1, 0, 0]),
+            // (table 3 funcref)
+            section(4, &[1, 0x70, 0, 3]),
+            section(SECTION_EXPORT, &[2, 4, b'm', b'a', b'i', b'n', 0, 1, 5, b'f', b'i', b'r', b's', b't', 0, 2]),
+            // Active segment at offset 0 of table 0, and a declarative segment
+            section(SECTION_ELEMENT, &[2, 0, 0x41, 0, 0x0b, 3, 2, 3, 1, 3, 0, 1, 3]),
+            section(SECTION_CODE, &code),
+            section(
+                SECTION_CUSTOM,
+                &[4, b'n', b'a', b'm', b'e', 1, 14, 2, 1, 4, b'm', b'a', b'i', b'n', 2, 5, b'f', b'i', b'r', b's', b't'],
+            ),
+        ].concat()
+    }
+
+    /// Verify the functions of a module are read, with their names and addresses in the code section
+    #[test]
+    fn test_parse_module() {
+        let content = example_module();
+        let module = WasmModule::parse(&content).unwrap();
+
+        assert_eq!(
+            module.functions,
+            vec![
+                WasmFunction {
+                    name: Some("log".to_string()),
+                    type_index: 0,
+                    address: IMPORTED_FUNCTION_BASE,
+                    size: 0,
+                    imported: true,
+                },
+                WasmFunction {
+                    name: Some("main".to_string()),
+                    type_index: 1,
+                    address: 2,
+                    size: 15,
+                    imported: false,
+                },
+                WasmFunction {
+                    name: Some("first".to_string()),
+                    type_index: 0,
+                    address: 18,
+                    size: 2,
+                    imported: false,
+                },
+                WasmFunction {
+                    name: None,
+                    type_index: 0,
+                    address: 21,
+                    size: 4,
+                    imported: false,
+                },
+            ]
+        );
+        assert_eq!(module.exported_functions(), vec![2, 18]);
+        assert_eq!(module.code_in_range(&(18..20)), Some(&[0x00, 0x0b][..]));
+        assert!(module.custom_section("name").is_some());
+        assert!(module.custom_section(".debug_info").is_none());
+    }
+
+    /// Verify `call_indirect` is resolved to the functions in the table with the called type
+    #[test]
+    fn test_indirect_call_targets() {
+        let content = example_module();
+        let module = WasmModule::parse(&content).unwrap();
+
+        let call_indirect = Instruction {
+            address: 13,
+            size: 3,
+            mnemonic: "call_indirect".to_string(),
+            kind: InstructionKind::Call { target: None },
+        };
+        // `main` is in the table as well, but has another type
+        assert_eq!(module.indirect_call_targets(&call_indirect), vec![18, 21]);
+
+        let call = Instruction {
+            address: 7,
+            size: 2,
+            mnemonic: "call".to_string(),
+            kind: InstructionKind::Call { target: Some(IMPORTED_FUNCTION_BASE) },
+        };
+        assert!(module.indirect_call_targets(&call).is_empty());
+    }
+
+    /// Verify components and other files are rejected
+    #[test]
+    fn test_parse_invalid_module() {
+        assert!(WasmModule::parse(b"\x7fELF").is_err());
+        assert!(WasmModule::parse(b"\0asm\x0d\0\x01\0").is_err());
+        assert!(WasmModule::parse(b"\0asm\x01\0\0\0\x01\x05\x01").is_err());
+    }
+}
@@ -91,14 +91,7 @@ mod tests {
     /// Check if the correct type of panic filter is returned
     #[test]
     fn correct_panic_filter_type() {
-        let options = AnalysisOptions {
-            binary_path: None,
-            crate_names: Vec::new(),
-            full_crate_analysis: false,
-            output_full_callgraph: false,
-            output_filtered_callgraph: false,
-            whitelisted_functions: Vec::new(),
-        };
+        let options = AnalysisOptions::default();
 
         let filter = get_node_filters(&options);
 
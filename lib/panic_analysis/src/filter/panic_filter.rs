@@ -38,9 +38,6 @@ mod tests {
     use callgraph::Crate;
     use callgraph::Procedure;
 
-    use capstone::arch::BuildsCapstone;
-    use capstone::prelude::Capstone;
-
     use std::cell::Cell;
     use std::cell::RefCell;
 
@@ -48,8 +45,8 @@ mod tests {
     use std::collections::HashMap;
     use std::rc::Rc;
     use crate::IntermediateBacktrace::NoTrace;
-    use RDPProcedureMetaData;
-    use RustigCallGraph;
+    use crate::RDPProcedureMetaData;
+    use crate::RustigCallGraph;
 
     use crate::test_utils;
     use crate::RDPProcedureMetaData;
@@ -60,15 +57,6 @@ mod tests {
         crate_name: String,
         is_panic: bool,
     ) -> Procedure<RDPProcedureMetaData> {
-        let capstone = Capstone::new()
-            .x86()
-            .mode(capstone::arch::x86::ArchMode::Mode64)
-            .detail(true)
-            .build()
-            .expect("Failed to construct disassembler");
-
-        let empty_vec = Vec::new();
-
         Procedure {
             name: name.clone(),
             linkage_name: "linkage_name".to_string(),
@@ -79,6 +67,8 @@ mod tests {
             },
             start_address: 32,
             size: 64,
+            address_ranges: vec![],
+            language: None,
             location: None,
             attributes: RDPProcedureMetaData {
                 analysis_target: Cell::new(false),
@@ -90,7 +80,7 @@ mod tests {
                 whitelisted: Cell::new(false),
                 reachable_from_entry_point: Cell::new(true),
             },
-            disassembly: capstone.disasm_all(&empty_vec, 0x1000).unwrap(),
+            disassembly: vec![],
         }
     }
 
@@ -114,14 +104,7 @@ mod tests {
     /// Check if the correct type of panic filter is returned
     #[test]
     fn correct_panic_filter_type() {
-        let options = AnalysisOptions {
-            binary_path: None,
-            crate_names: Vec::new(),
-            full_crate_analysis: false,
-            output_full_callgraph: false,
-            output_filtered_callgraph: false,
-            whitelisted_functions: Vec::new(),
-        };
+        let options = AnalysisOptions::default();
 
         let filter = get_panic_filter(&options);
 
@@ -92,9 +92,6 @@ mod tests {
     use callgraph::Crate;
     use callgraph::Procedure;
 
-    use capstone::arch::BuildsCapstone;
-    use capstone::prelude::Capstone;
-
     use std::cell::Cell;
     use std::cell::RefCell;
 
@@ -104,7 +101,7 @@ mod tests {
     use std::rc::Rc;
     use crate::IntermediateBacktrace::NoTrace;
     use crate::RDPInvocationMetaData;
-    use RDPProcedureMetaData;
+    use crate::RDPProcedureMetaData;
 
     use crate::test_utils;
     use crate::RDPProcedureMetaData;
@@ -116,15 +113,6 @@ mod tests {
         is_entry: bool,
         whitelisted: bool,
     ) -> Procedure<RDPProcedureMetaData> {
-        let capstone = Capstone::new()
-            .x86()
-            .mode(capstone::arch::x86::ArchMode::Mode64)
-            .detail(true)
-            .build()
-            .expect("Failed to construct disassembler");
-
-        let empty_vec = Vec::new();
-
         Procedure {
             name: name.clone(),
             linkage_name: "linkage_name".to_string(),
@@ -135,6 +123,8 @@ mod tests {
             },
             start_address: 32,
             size: 64,
+            address_ranges: vec![],
+            language: None,
             location: None,
             attributes: RDPProcedureMetaData {
                 analysis_target: Cell::new(true),
@@ -146,7 +136,7 @@ mod tests {
                 whitelisted: Cell::new(whitelisted),
                 reachable_from_entry_point: Cell::new(false),
             },
-            disassembly: capstone.disasm_all(&empty_vec, 0x1000).unwrap(),
+            disassembly: vec![],
         }
     }
 
@@ -172,6 +162,7 @@ mod tests {
         let invocation = Rc::new(RefCell::new(Invocation {
             instruction_address: 0x144562,
             invocation_type: InvocationType::Direct,
+            trait_implementation: None,
             frames: Vec::new(),
             attributes: RDPInvocationMetaData {
                 whitelisted: Cell::new(whitelist_edge),
@@ -194,14 +185,7 @@ mod tests {
     /// Check if the correct type of panic filter is returned
     #[test]
     fn correct_whitelist_filter_type() {
-        let options = AnalysisOptions {
-            binary_path: None,
-            crate_names: Vec::new(),
-            full_crate_analysis: false,
-            output_full_callgraph: false,
-            output_filtered_callgraph: false,
-            whitelisted_functions: Vec::new(),
-        };
+        let options = AnalysisOptions::default();
 
         let filter = get_whitelist_filter(&options);
 
@@ -212,12 +196,8 @@ mod tests {
     #[test]
     fn correct_whitelist_filter_full_crate_type() {
         let options = AnalysisOptions {
-            binary_path: None,
-            crate_names: Vec::new(),
             full_crate_analysis: true,
-            output_full_callgraph: false,
-            output_filtered_callgraph: false,
-            whitelisted_functions: Vec::new(),
+            ..Default::default()
         };
 
         let filter = get_whitelist_filter(&options);
@@ -230,14 +210,7 @@ mod tests {
         $(
             #[test]
             fn $name() {
-                let options = AnalysisOptions {
-                    binary_path: None,
-                    crate_names: Vec::new(),
-                    full_crate_analysis: false,
-                    output_full_callgraph: false,
-                    output_filtered_callgraph: false,
-                    whitelisted_functions: Vec::new(),
-                };
+                let options = AnalysisOptions::default();
 
                 let filter = get_whitelist_filter(&options);
 
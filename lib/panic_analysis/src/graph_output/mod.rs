@@ -107,16 +107,15 @@ mod test {
     use test_common::*;
     use super::*;
 
-    use AnalysisOptions;
+    use crate::AnalysisOptions;
 
     fn create_options(path: Option<String>, full: bool, filtered: bool) -> AnalysisOptions {
         AnalysisOptions {
             binary_path: path,
-            crate_names: vec![],
             full_crate_analysis: true,
             output_full_callgraph: full,
             output_filtered_callgraph: filtered,
-            whitelisted_functions: vec![],
+            ..Default::default()
         }
     }
 
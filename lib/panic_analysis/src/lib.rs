@@ -17,8 +17,9 @@ pub mod errors;
 pub mod test_utils;
 
 use callgraph::*;
+use callgraph::debug_file::DebugFile;
+use callgraph::symbol_path::SymbolPath;
 
-use std::io::Read;
 use petgraph::stable_graph::NodeIndex;
 use petgraph::stable_graph::StableGraph;
 
@@ -30,6 +31,7 @@ use std::fmt;
 use std::fmt::Display;
 use std::fmt::Formatter;
 use std::path::Path;
+use std::path::PathBuf;
 use callgraph::errors::*;
 
 #[derive(Debug, Clone, Default)]
@@ -81,7 +83,8 @@ pub struct FunctionWhiteListEntry {
     ///
     /// This name may include namespaces. However, both the name and the namespace should be complete.
     /// For example, if a function has name `core::fmt::format`, the names `format`, `fmt::format` and `core::fmt::format` would match,
-    /// but `ormat` or `mt::format` would not.
+    /// but `ormat` or `mt::format` would not. Generic arguments are ignored, so `Option::unwrap` matches both
+    /// `<core::option::Option<T>>::unwrap` and (with v0 symbol mangling) `<core::option::Option<u8>>::unwrap`.
     pub function_name: String,
     /// The version filter of the crate.
     ///
@@ -90,16 +93,12 @@ pub struct FunctionWhiteListEntry {
     pub crate_version: FunctionWhitelistCrateVersion,
 }
 
-fn fn_names_match(filter_name: &str, procedure_name: &str) -> bool {
-    procedure_name.ends_with(&format!("::{}", &filter_name)) || procedure_name == filter_name
-}
-
 impl FunctionWhiteListEntry {
     /// Function that checks if an `FunctionWhiteList` matches a `Procedure`
     fn matches_procedure<P>(&self, prc: &Procedure<P>) -> bool {
         // Compare by linkage name, since it contains more information than name
         // e.g. name can be `new`: then it is not clear on which struct it was defined.
-        if !fn_names_match(&self.function_name, &prc.linkage_name_demangled) {
+        if !prc.symbol_path().matches(&self.function_name) {
             return false;
         }
 
@@ -125,7 +124,7 @@ impl FunctionWhiteListEntry {
         if !invocation
             .frames
             .iter()
-            .any(|frame| fn_names_match(&self.function_name, &frame.function_name))
+            .any(|frame| SymbolPath::parse(&frame.function_name).matches(&self.function_name))
         {
             return false;
         }
@@ -146,11 +145,13 @@ impl FunctionWhiteListEntry {
 /// Struct containing all the options that can be passed to [find_panics].
 ///
 /// For more information on the effect of certain options, see the command line options for the equally named options in the [cli documentation](index.html#options).
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct AnalysisOptions {
-    /// The path to the binary we want to analyze.
+    /// The path to the binary we want to analyze. This is an executable, a shared library, a static library, rlib
+    /// or relocatable object (see `callgraph::relocatable`), or a WebAssembly module (see `callgraph::wasm`).
     ///
-    /// This is an `Option`, because we might implement building a cargo project itself. However, for now, the library will return an error if it is set to `None`.
+    /// The library will return an error if it is set to `None`. Cargo projects are built by the command line interface
+    /// (`--manifest-path`), which analyzes each binary that was built.
     pub binary_path: Option<String>,
     /// The list of crates which belong to the analysis target.
     pub crate_names: Vec<String>,
@@ -160,6 +161,25 @@ pub struct AnalysisOptions {
     pub output_full_callgraph: bool,
     /// If `true`, the filtered callgraph will be written to a dot file in the present working directory. This call graph will only contain nodes that eventually lead to a panic
     pub output_filtered_callgraph: bool,
+    /// If `true`, calls through a vtable are resolved to the called function where possible, instead of assuming all
+    /// functions in the vtable are called. See `callgraph::CallGraphOptions::precise_dynamic_dispatch`.
+    pub precise_dynamic_dispatch: bool,
+    /// Directories in which the shared objects the binary is dynamically linked against are searched.
+    /// See `callgraph::CallGraphOptions::library_paths`.
+    pub library_paths: Vec<String>,
+    /// Directories in which the separate debug file of a stripped binary is searched.
+    /// See `callgraph::CallGraphOptions::debug_file_directories`.
+    pub debug_file_directories: Vec<String>,
+    /// Path of the `Cargo.lock` file of the project the binary is built from, of which the package versions are used
+    /// for crates of which the version is not known otherwise.
+    /// See `callgraph::CallGraphOptions::cargo_lock_path`.
+    pub cargo_lock_path: Option<String>,
+    /// If `true`, the binary is analyzed as a shared library (e.g. a `cdylib`): the entry points are the functions it
+    /// exports in its dynamic symbol table, instead of the main procedure, and panic calls are reported per exported
+    /// function (see [PanicCall::entry_points](struct.PanicCall.html#structfield.entry_points)).
+    /// Static libraries, rlibs and relocatable objects are always analyzed in library mode, with their global functions
+    /// as entry points.
+    pub library_mode: bool,
     /// List of whitelisted functions.
     pub whitelisted_functions: Vec<FunctionWhiteListEntry>, // Add all options to the tool here :-)
                                                             // Make sure to implement correct argument parsing in /bin/cli as well
@@ -237,22 +257,51 @@ pub struct PanicCall {
     pub contains_dynamic_invocation: bool,
     /// Message that is passed to this panic, if known.
     pub message: Option<String>,
+    /// Names of the exported functions from which this panic call is reachable, if the binary is analyzed as a shared
+    /// library. Empty otherwise.
+    pub entry_points: Vec<String>,
+}
+
+impl PanicCall {
+    /// Returns whether the `backtrace` contains procedures written in different languages, e.g. a Rust callback that
+    /// is invoked from C. Unwinding a panic through the frames of C or C++ code is undefined behaviour, unless these
+    /// are called through an unwinding ABI like `extern "C-unwind"`.
+    /// Procedures of which the language is not known are ignored.
+    pub fn crosses_language_boundary(&self) -> bool {
+        let mut languages = self
+            .backtrace
+            .iter()
+            .filter_map(|entry| entry.procedure.borrow().language);
+        match languages.next() {
+            Some(first) => languages.any(|language| language != first),
+            None => false,
+        }
+    }
 }
 
 impl Display for PanicCall {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         if f.alternate() {
             let mut call_string = format!(
-                "-- Pattern: {:?}{}{}\n\n",
+                "-- Pattern: {:?}{}{}{}{}\n\n",
                 self.pattern.borrow(),
                 if self.contains_dynamic_invocation {
                     " -- Trace contains dynamic invocation(s)"
                 } else {
                     ""
                 },
+                if self.crosses_language_boundary() {
+                    " -- Trace crosses language boundary"
+                } else {
+                    ""
+                },
                 self.message
                     .as_ref()
-                    .map_or("".to_string(), |msg| format!(" -- Message: '{}'", msg))
+                    .map_or("".to_string(), |msg| format!(" -- Message: '{}'", msg)),
+                match self.entry_points.is_empty() {
+                    true => "".to_string(),
+                    false => format!(" -- Entry points: {}", self.entry_points.join(", ")),
+                }
             );
             for (i, entry) in self.backtrace.iter().enumerate() {
                 call_string.push_str(&format!("{:2}: {:#6}\n", i, entry));
@@ -311,12 +360,67 @@ pub type RustigCallGraph =
 ///
 /// This function can be used in conjunction with [print_results](../panic_calls_output/fn.print_results.html) in [panic_call_output](../panic_calls_output/index.html) to format and print results.
 ///
+/// The file can be a little endian ELF executable or shared library of x86, x86_64, AArch64, 32 or 64-bit RISC-V or
+/// Thumb (Cortex-M) code, a static library, rlib or relocatable object of x86_64 code, or a WebAssembly module.
+///
 /// # Errors
+/// The errors of reading and parsing the file are the `callgraph` errors, wrapped in `ErrorKind::CallGraph`.
+///
 /// * If [`options`.binary_path](struct.AnalysisOptions.html#structfield.binary_path) is `None`.
-/// * If the file to be analyzed was not found, or could not be read.
-/// * If the file is not a valid x86 or x86_64 ELF file.
+/// * `IOError` or `ReadError` if the file to be analyzed, or its separate debug file, was not found, or could not be
+///   read.
+/// * `ParseError` if the file is none of the formats above, or cannot be parsed.
+/// * `NotSupported` if the file is of one of the formats above, but contains code of another instruction set, big
+///   endian code, or WebAssembly features that cannot be analyzed (e.g. a WebAssembly component).
 ///
 pub fn find_panics(options: &AnalysisOptions) -> Result<PanicCallsCollection> {
+    let path_str = &options.binary_path.clone().ok_or("No path to binary provided.")?;
+    let binary_path = Path::new(path_str);
+    // The binary is mapped into memory instead of read, as debug builds can be larger than a gigabyte
+    let file_content = map_file(binary_path)?;
+
+    let debug_file_directories = options.debug_file_directories.iter().map(PathBuf::from).collect::<Vec<_>>();
+    let debug_file_path = debug_file::find_debug_file(&file_content, binary_path, &debug_file_directories);
+    let debug_file_content = match &debug_file_path {
+        Some(debug_file_path) => Some(map_file(debug_file_path)?),
+        None => None,
+    };
+    let debug_file = debug_file_path
+        .as_ref()
+        .zip(debug_file_content.as_ref())
+        .map(|(path, content)| DebugFile { path, content });
+
+    analyze_binary(&file_content, Some(binary_path), debug_file, options)
+}
+
+/// Entry point of the `panic_analysis` crate for binaries that are not read from a file, e.g. because they are
+/// embedded in another program.
+///
+/// Works like [find_panics](fn.find_panics.html) on the binary in `file_content`.
+/// [`options`.binary_path](struct.AnalysisOptions.html#structfield.binary_path) is ignored, and the debug information
+/// is only read from the binary itself, and from the `.dwo` files of split debug information.
+///
+/// # Errors
+/// * `ParseError` or `NotSupported` if the binary cannot be analyzed, like the file passed to
+///   [find_panics](fn.find_panics.html).
+///
+pub fn find_panics_in_memory(file_content: &[u8], options: &AnalysisOptions) -> Result<PanicCallsCollection> {
+    analyze_binary(file_content, None, None, options)
+}
+
+/// Finds the panic calls in the binary in `file_content`, which is read from `binary_path` if it is passed.
+fn analyze_binary(
+    file_content: &[u8],
+    binary_path: Option<&Path>,
+    debug_file: Option<DebugFile>,
+    options: &AnalysisOptions,
+) -> Result<PanicCallsCollection> {
+    // Static libraries and relocatable objects have no main procedure, so they are always analyzed in library mode
+    let options = &AnalysisOptions {
+        library_mode: options.library_mode || relocatable::is_relocatable(file_content),
+        ..options.clone()
+    };
+
     let markers = marker::get_code_markers(options);
     let filters = filter::get_node_filters(options);
 
@@ -327,11 +431,16 @@ pub fn find_panics(options: &AnalysisOptions) -> Result<PanicCallsCollection> {
     let graph_output_filtered = graph_output::get_graph_output_filtered(options);
 
     // Create callgraph
-    let path_str = &options.binary_path.clone().ok_or("No path to binary provided.")?;
-    let binary_path = Path::new(path_str);
-    let file_content = read(binary_path)?;
-    let (mut call_graph, context): (RustigCallGraph, Context) = build_call_graph(&file_content,
-    binary_path)?;
+    let call_graph_options = CallGraphOptions {
+        path: binary_path,
+        precise_dynamic_dispatch: options.precise_dynamic_dispatch,
+        library_paths: options.library_paths.iter().map(PathBuf::from).collect(),
+        debug_file_directories: options.debug_file_directories.iter().map(PathBuf::from).collect(),
+        cargo_lock_path: options.cargo_lock_path.as_ref().map(PathBuf::from),
+    };
+    let buffers = DebugBuffers::default();
+    let (mut call_graph, context): (RustigCallGraph, Context) = build_call_graph(file_content,
+    debug_file, &buffers, &call_graph_options)?;
 
     graph_output_full.write_graph(&call_graph);
 
@@ -349,25 +458,12 @@ pub fn find_panics(options: &AnalysisOptions) -> Result<PanicCallsCollection> {
     Ok(panic_calls)
 }
 
-fn read(path: &Path) -> callgraph::errors::Result<Vec<u8>> {
-    let mut file_content = Vec::new();
-    let mut file = std::fs::File::open(path)
-        .chain_err(|| ErrorKind::IOError(path.to_str().unwrap().to_string()))?;
-
-    file.read_to_end(&mut file_content)
-        .chain_err(|| ErrorKind::ReadError(path.to_str().unwrap().to_string()))?;
-
-    Ok(file_content)
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
     use callgraph::InlineFunctionFrame;
     use callgraph::Procedure;
-    use capstone::arch::BuildsCapstone;
-    use capstone::prelude::Capstone;
-    use RDPProcedureMetaData;
+    use crate::RDPProcedureMetaData;
 
     /// Test implementation `Display` trait for `BacktraceEntry` without outgoing invocation
     #[test]
@@ -383,15 +479,11 @@ mod tests {
                 },
                 start_address: 0x1240,
                 size: 0x40,
+                address_ranges: vec![],
+                language: None,
                 location: None,
                 attributes: RDPProcedureMetaData::default(),
-                disassembly: Capstone::new()
-                    .x86()
-                    .mode(capstone::arch::x86::ArchMode::Mode64)
-                    .build()
-                    .unwrap()
-                    .disasm_all(&vec![], 0x1240)
-                    .unwrap(),
+                disassembly: vec![],
             })),
             outgoing_invocation: None,
         };
@@ -416,19 +508,16 @@ mod tests {
                 },
                 start_address: 0x1240,
                 size: 0x40,
+                address_ranges: vec![],
+                language: None,
                 location: None,
                 attributes: RDPProcedureMetaData::default(),
-                disassembly: Capstone::new()
-                    .x86()
-                    .mode(capstone::arch::x86::ArchMode::Mode64)
-                    .build()
-                    .unwrap()
-                    .disasm_all(&vec![], 0x1240)
-                    .unwrap(),
+                disassembly: vec![],
             })),
             outgoing_invocation: Some(Rc::new(RefCell::new(Invocation {
                 instruction_address: 0x144562,
                 invocation_type: InvocationType::Direct,
+                trait_implementation: None,
                 frames: vec![
                     InlineFunctionFrame {
                         function_name: "alloc::slice::<impl [T]>::to_vec".to_string(),
@@ -528,13 +617,6 @@ mod tests {
     /// Test to ensure that a `FunctionWhiteListEntry` does not match a `Procedure` if the names differ
     #[test]
     fn fn_whitelist_not_matching_different_name() {
-        let capstone = Capstone::new()
-            .x86()
-            .mode(capstone::arch::x86::ArchMode::Mode64)
-            .detail(true)
-            .build()
-            .expect("Failed to construct disassembler");
-
         let prc = Procedure {
             name: "some_name".to_string(),
             linkage_name: "linkage_name".to_string(),
@@ -545,9 +627,11 @@ mod tests {
             },
             start_address: 0x1240,
             size: 0x40,
+            address_ranges: vec![],
+            language: None,
             location: None,
             attributes: (),
-            disassembly: capstone.disasm_all(&[], 0x1240).unwrap(),
+            disassembly: vec![],
         };
 
         let whitelist_filter = FunctionWhiteListEntry {
@@ -563,13 +647,6 @@ mod tests {
     /// if the name is not fully given
     #[test]
     fn fn_whitelist_not_matching_invalid_suffix() {
-        let capstone = Capstone::new()
-            .x86()
-            .mode(capstone::arch::x86::ArchMode::Mode64)
-            .detail(true)
-            .build()
-            .expect("Failed to construct disassembler");
-
         let prc = Procedure {
             name: "some_name".to_string(),
             linkage_name: "a::b::name".to_string(),
@@ -580,9 +657,11 @@ mod tests {
             },
             start_address: 0x1240,
             size: 0x40,
+            address_ranges: vec![],
+            language: None,
             location: None,
             attributes: (),
-            disassembly: capstone.disasm_all(&[], 0x1240).unwrap(),
+            disassembly: vec![],
         };
 
         let whitelist_filter = FunctionWhiteListEntry {
@@ -598,13 +677,6 @@ mod tests {
     /// when the full name is given
     #[test]
     fn fn_whitelist_matching_valid_suffix() {
-        let capstone = Capstone::new()
-            .x86()
-            .mode(capstone::arch::x86::ArchMode::Mode64)
-            .detail(true)
-            .build()
-            .expect("Failed to construct disassembler");
-
         let prc = Procedure {
             name: "some_name".to_string(),
             linkage_name: "a::b::name".to_string(),
@@ -615,9 +687,11 @@ mod tests {
             },
             start_address: 0x1240,
             size: 0x40,
+            address_ranges: vec![],
+            language: None,
             location: None,
             attributes: (),
-            disassembly: capstone.disasm_all(&[], 0x1240).unwrap(),
+            disassembly: vec![],
         };
 
         let whitelist_filter = FunctionWhiteListEntry {
@@ -633,13 +707,6 @@ mod tests {
     /// when the no crate name is geven
     #[test]
     fn fn_whitelist_matching_no_crate() {
-        let capstone = Capstone::new()
-            .x86()
-            .mode(capstone::arch::x86::ArchMode::Mode64)
-            .detail(true)
-            .build()
-            .expect("Failed to construct disassembler");
-
         let prc = Procedure {
             name: "some_name".to_string(),
             linkage_name: "a::b::name".to_string(),
@@ -650,9 +717,11 @@ mod tests {
             },
             start_address: 0x1240,
             size: 0x40,
+            address_ranges: vec![],
+            language: None,
             location: None,
             attributes: (),
-            disassembly: capstone.disasm_all(&[], 0x1240).unwrap(),
+            disassembly: vec![],
         };
 
         let whitelist_filter = FunctionWhiteListEntry {
@@ -668,13 +737,6 @@ mod tests {
     /// when the full name with a module is given
     #[test]
     fn fn_whitelist_matching_full_name() {
-        let capstone = Capstone::new()
-            .x86()
-            .mode(capstone::arch::x86::ArchMode::Mode64)
-            .detail(true)
-            .build()
-            .expect("Failed to construct disassembler");
-
         let prc = Procedure {
             name: "some_name".to_string(),
             linkage_name: "a::b::name".to_string(),
@@ -685,9 +747,11 @@ mod tests {
             },
             start_address: 0x1240,
             size: 0x40,
+            address_ranges: vec![],
+            language: None,
             location: None,
             attributes: (),
-            disassembly: capstone.disasm_all(&[], 0x1240).unwrap(),
+            disassembly: vec![],
         };
 
         let whitelist_filter = FunctionWhiteListEntry {
@@ -703,13 +767,6 @@ mod tests {
     /// when the full name with all modules is given
     #[test]
     fn fn_whitelist_matching_invalid_full_suffix() {
-        let capstone = Capstone::new()
-            .x86()
-            .mode(capstone::arch::x86::ArchMode::Mode64)
-            .detail(true)
-            .build()
-            .expect("Failed to construct disassembler");
-
         let prc = Procedure {
             name: "some_name".to_string(),
             linkage_name: "a::b::name".to_string(),
@@ -720,9 +777,11 @@ mod tests {
             },
             start_address: 0x1240,
             size: 0x40,
+            address_ranges: vec![],
+            language: None,
             location: None,
             attributes: (),
-            disassembly: capstone.disasm_all(&[], 0x1240).unwrap(),
+            disassembly: vec![],
         };
 
         let whitelist_filter = FunctionWhiteListEntry {
@@ -738,13 +797,6 @@ mod tests {
     /// when the a different crate name is given
     #[test]
     fn fn_whitelist_not_matching_different_crate() {
-        let capstone = Capstone::new()
-            .x86()
-            .mode(capstone::arch::x86::ArchMode::Mode64)
-            .detail(true)
-            .build()
-            .expect("Failed to construct disassembler");
-
         let prc = Procedure {
             name: "name".to_string(),
             linkage_name: "linkage_name".to_string(),
@@ -755,9 +807,11 @@ mod tests {
             },
             start_address: 0x1240,
             size: 0x40,
+            address_ranges: vec![],
+            language: None,
             location: None,
             attributes: (),
-            disassembly: capstone.disasm_all(&[], 0x1240).unwrap(),
+            disassembly: vec![],
         };
 
         let whitelist_filter = FunctionWhiteListEntry {
@@ -773,13 +827,6 @@ mod tests {
     /// when a different version is specified
     #[test]
     fn fn_whitelist_not_matching_different_version() {
-        let capstone = Capstone::new()
-            .x86()
-            .mode(capstone::arch::x86::ArchMode::Mode64)
-            .detail(true)
-            .build()
-            .expect("Failed to construct disassembler");
-
         let prc = Procedure {
             name: "name".to_string(),
             linkage_name: "linkage_name".to_string(),
@@ -790,9 +837,11 @@ mod tests {
             },
             start_address: 0x1240,
             size: 0x40,
+            address_ranges: vec![],
+            language: None,
             location: None,
             attributes: (),
-            disassembly: capstone.disasm_all(&[], 0x1240).unwrap(),
+            disassembly: vec![],
         };
 
         let whitelist_filter = FunctionWhiteListEntry {
@@ -808,13 +857,6 @@ mod tests {
     /// when all fields are matching
     #[test]
     fn fn_whitelist_matching_same_version() {
-        let capstone = Capstone::new()
-            .x86()
-            .mode(capstone::arch::x86::ArchMode::Mode64)
-            .detail(true)
-            .build()
-            .expect("Failed to construct disassembler");
-
         let prc = Procedure {
             name: "name".to_string(),
             linkage_name: "linkage_name::name".to_string(),
@@ -825,9 +867,11 @@ mod tests {
             },
             start_address: 0x1240,
             size: 0x40,
+            address_ranges: vec![],
+            language: None,
             location: None,
             attributes: (),
-            disassembly: capstone.disasm_all(&[], 0x1240).unwrap(),
+            disassembly: vec![],
         };
 
         let whitelist_filter = FunctionWhiteListEntry {
@@ -842,13 +886,6 @@ mod tests {
     /// Test to ensure that a `FunctionWhiteListEntry` does not match a `Procedure` if the names differ
     #[test]
     fn fn_inv_whitelist_not_matching_different_name() {
-        let capstone = Capstone::new()
-            .x86()
-            .mode(capstone::arch::x86::ArchMode::Mode64)
-            .detail(true)
-            .build()
-            .expect("Failed to construct disassembler");
-
         let prc = Procedure {
             name: "some_name".to_string(),
             linkage_name: "linkage_name".to_string(),
@@ -859,14 +896,17 @@ mod tests {
             },
             start_address: 0x1240,
             size: 0x40,
+            address_ranges: vec![],
+            language: None,
             location: None,
             attributes: (),
-            disassembly: capstone.disasm_all(&[], 0x1240).unwrap(),
+            disassembly: vec![],
         };
 
         let inv = Invocation {
             instruction_address: 0x144562,
             invocation_type: InvocationType::Direct,
+            trait_implementation: None,
             frames: vec![InlineFunctionFrame {
                 function_name: "mod1::mod2::inline_func".to_string(),
                 location: Location {
@@ -895,13 +935,6 @@ mod tests {
     /// if the name is not fully given
     #[test]
     fn fn_inv_whitelist_not_matching_invalid_suffix() {
-        let capstone = Capstone::new()
-            .x86()
-            .mode(capstone::arch::x86::ArchMode::Mode64)
-            .detail(true)
-            .build()
-            .expect("Failed to construct disassembler");
-
         let prc = Procedure {
             name: "some_name".to_string(),
             linkage_name: "a::b::name".to_string(),
@@ -912,14 +945,17 @@ mod tests {
             },
             start_address: 0x1240,
             size: 0x40,
+            address_ranges: vec![],
+            language: None,
             location: None,
             attributes: (),
-            disassembly: capstone.disasm_all(&[], 0x1240).unwrap(),
+            disassembly: vec![],
         };
 
         let inv = Invocation {
             instruction_address: 0x144562,
             invocation_type: InvocationType::Direct,
+            trait_implementation: None,
             frames: vec![InlineFunctionFrame {
                 function_name: "mod1::mod2::inline_func".to_string(),
                 location: Location {
@@ -948,13 +984,6 @@ mod tests {
     /// when the full name is given
     #[test]
     fn fn_inv_whitelist_matching_valid_suffix() {
-        let capstone = Capstone::new()
-            .x86()
-            .mode(capstone::arch::x86::ArchMode::Mode64)
-            .detail(true)
-            .build()
-            .expect("Failed to construct disassembler");
-
         let prc = Procedure {
             name: "some_name".to_string(),
             linkage_name: "a::b::name".to_string(),
@@ -965,14 +994,17 @@ mod tests {
             },
             start_address: 0x1240,
             size: 0x40,
+            address_ranges: vec![],
+            language: None,
             location: None,
             attributes: (),
-            disassembly: capstone.disasm_all(&[], 0x1240).unwrap(),
+            disassembly: vec![],
         };
 
         let inv = Invocation {
             instruction_address: 0x144562,
             invocation_type: InvocationType::Direct,
+            trait_implementation: None,
             frames: vec![InlineFunctionFrame {
                 function_name: "mod1::mod2::inline_func".to_string(),
                 location: Location {
@@ -1001,13 +1033,6 @@ mod tests {
     /// when the full name with a module is given
     #[test]
     fn fn_inv_whitelist_matching_full_name() {
-        let capstone = Capstone::new()
-            .x86()
-            .mode(capstone::arch::x86::ArchMode::Mode64)
-            .detail(true)
-            .build()
-            .expect("Failed to construct disassembler");
-
         let prc = Procedure {
             name: "some_name".to_string(),
             linkage_name: "a::b::name".to_string(),
@@ -1018,14 +1043,17 @@ mod tests {
             },
             start_address: 0x1240,
             size: 0x40,
+            address_ranges: vec![],
+            language: None,
             location: None,
             attributes: (),
-            disassembly: capstone.disasm_all(&[], 0x1240).unwrap(),
+            disassembly: vec![],
         };
 
         let inv = Invocation {
             instruction_address: 0x144562,
             invocation_type: InvocationType::Direct,
+            trait_implementation: None,
             frames: vec![InlineFunctionFrame {
                 function_name: "mod1::mod2::inline_func".to_string(),
                 location: Location {
@@ -1054,13 +1082,6 @@ mod tests {
     /// when the full name with all modules is given
     #[test]
     fn fn_inv_whitelist_matching_invalid_full_suffix() {
-        let capstone = Capstone::new()
-            .x86()
-            .mode(capstone::arch::x86::ArchMode::Mode64)
-            .detail(true)
-            .build()
-            .expect("Failed to construct disassembler");
-
         let prc = Procedure {
             name: "some_name".to_string(),
             linkage_name: "a::b::name".to_string(),
@@ -1071,14 +1092,17 @@ mod tests {
             },
             start_address: 0x1240,
             size: 0x40,
+            address_ranges: vec![],
+            language: None,
             location: None,
             attributes: (),
-            disassembly: capstone.disasm_all(&[], 0x1240).unwrap(),
+            disassembly: vec![],
         };
 
         let inv = Invocation {
             instruction_address: 0x144562,
             invocation_type: InvocationType::Direct,
+            trait_implementation: None,
             frames: vec![InlineFunctionFrame {
                 function_name: "mod1::mod2::inline_func".to_string(),
                 location: Location {
@@ -1107,13 +1131,6 @@ mod tests {
     /// when the a different crate name is given
     #[test]
     fn fn_inv_whitelist_not_matching_different_crate() {
-        let capstone = Capstone::new()
-            .x86()
-            .mode(capstone::arch::x86::ArchMode::Mode64)
-            .detail(true)
-            .build()
-            .expect("Failed to construct disassembler");
-
         let prc = Procedure {
             name: "name".to_string(),
             linkage_name: "linkage_name".to_string(),
@@ -1124,14 +1141,17 @@ mod tests {
             },
             start_address: 0x1240,
             size: 0x40,
+            address_ranges: vec![],
+            language: None,
             location: None,
             attributes: (),
-            disassembly: capstone.disasm_all(&[], 0x1240).unwrap(),
+            disassembly: vec![],
         };
 
         let inv = Invocation {
             instruction_address: 0x144562,
             invocation_type: InvocationType::Direct,
+            trait_implementation: None,
             frames: vec![InlineFunctionFrame {
                 function_name: "mod1::mod2::inline_func".to_string(),
                 location: Location {
@@ -1160,13 +1180,6 @@ mod tests {
     /// when a different version is specified
     #[test]
     fn fn_inv_whitelist_not_matching_different_version() {
-        let capstone = Capstone::new()
-            .x86()
-            .mode(capstone::arch::x86::ArchMode::Mode64)
-            .detail(true)
-            .build()
-            .expect("Failed to construct disassembler");
-
         let prc = Procedure {
             name: "name".to_string(),
             linkage_name: "linkage_name".to_string(),
@@ -1177,14 +1190,17 @@ mod tests {
             },
             start_address: 0x1240,
             size: 0x40,
+            address_ranges: vec![],
+            language: None,
             location: None,
             attributes: (),
-            disassembly: capstone.disasm_all(&[], 0x1240).unwrap(),
+            disassembly: vec![],
         };
 
         let inv = Invocation {
             instruction_address: 0x144562,
             invocation_type: InvocationType::Direct,
+            trait_implementation: None,
             frames: vec![InlineFunctionFrame {
                 function_name: "mod1::mod2::inline_func".to_string(),
                 location: Location {
@@ -1213,13 +1229,6 @@ mod tests {
     /// when all fields are matching
     #[test]
     fn fn_inv_whitelist_matching_same_version() {
-        let capstone = Capstone::new()
-            .x86()
-            .mode(capstone::arch::x86::ArchMode::Mode64)
-            .detail(true)
-            .build()
-            .expect("Failed to construct disassembler");
-
         let prc = Procedure {
             name: "name".to_string(),
             linkage_name: "linkage_name::name".to_string(),
@@ -1230,14 +1239,17 @@ mod tests {
             },
             start_address: 0x1240,
             size: 0x40,
+            address_ranges: vec![],
+            language: None,
             location: None,
             attributes: (),
-            disassembly: capstone.disasm_all(&[], 0x1240).unwrap(),
+            disassembly: vec![],
         };
 
         let inv = Invocation {
             instruction_address: 0x144562,
             invocation_type: InvocationType::Direct,
+            trait_implementation: None,
             frames: vec![InlineFunctionFrame {
                 function_name: "mod1::mod2::inline_func".to_string(),
                 location: Location {
@@ -1261,4 +1273,42 @@ mod tests {
 
         assert!(whitelist_filter.matches_invocation(&prc, &inv));
     }
+
+    /// Test that a trace through procedures written in different languages crosses a language boundary, and that
+    /// procedures of which the language is not known are ignored
+    #[test]
+    fn test_crosses_language_boundary() {
+        let panic_call = |languages: Vec<Option<Language>>| PanicCall {
+            backtrace: languages
+                .into_iter()
+                .map(|language| BackTraceEntry {
+                    procedure: Rc::new(RefCell::new(Procedure {
+                        name: "callback".to_string(),
+                        linkage_name: "callback".to_string(),
+                        linkage_name_demangled: "callback".to_string(),
+                        defining_crate: Crate {
+                            name: "crate".to_string(),
+                            version: None,
+                        },
+                        start_address: 0x1240,
+                        size: 0x40,
+                        address_ranges: vec![],
+                        language,
+                        location: None,
+                        attributes: RDPProcedureMetaData::default(),
+                        disassembly: vec![],
+                    })),
+                    outgoing_invocation: None,
+                })
+                .collect(),
+            pattern: RefCell::new(PanicPattern::Unrecognized),
+            contains_dynamic_invocation: false,
+            message: None,
+            entry_points: vec![],
+        };
+
+        assert!(panic_call(vec![Some(Language::Rust), Some(Language::C), Some(Language::Rust)]).crosses_language_boundary());
+        assert!(!panic_call(vec![Some(Language::Rust), None, Some(Language::Rust)]).crosses_language_boundary());
+        assert!(!panic_call(vec![None, None]).crosses_language_boundary());
+    }
 }
@@ -98,9 +98,6 @@ pub fn get_panic_analysis_target_marker(options: &AnalysisOptions) -> Box<dyn Co
 
 #[cfg(test)]
 mod test {
-    use capstone::arch::BuildsCapstone;
-    use capstone::Capstone;
-
     use super::*;
 
     use callgraph::Crate;
@@ -112,14 +109,14 @@ mod test {
 
     use crate::RDPInlineFrameMetaData;
     use crate::RDPInvocationMetaData;
-    use RDPProcedureMetaData;
+    use crate::RDPProcedureMetaData;
 
     use std::cell::Cell;
     use std::cell::RefCell;
     use std::collections::HashMap;
     use std::rc::Rc;
 
-    use AnalysisOptions;
+    use crate::AnalysisOptions;
     use crate::IntermediateBacktrace::NoTrace;
 
     use crate::test_utils;
@@ -131,15 +128,6 @@ mod test {
         crate_name: String,
         entry_point: bool,
     ) -> Procedure<RDPProcedureMetaData> {
-        let capstone = Capstone::new()
-            .x86()
-            .mode(capstone::arch::x86::ArchMode::Mode64)
-            .detail(true)
-            .build()
-            .expect("Failed to construct disassembler");
-
-        let empty_vec = Vec::new();
-
         Procedure {
             name: name.clone(),
             linkage_name: "linkage_name".to_string(),
@@ -150,6 +138,8 @@ mod test {
             },
             start_address: 32,
             size: 64,
+            address_ranges: vec![],
+            language: None,
             location: None,
             attributes: RDPProcedureMetaData {
                 analysis_target: Cell::new(false),
@@ -161,7 +151,7 @@ mod test {
                 whitelisted: Cell::new(false),
                 reachable_from_entry_point: Cell::new(true),
             },
-            disassembly: capstone.disasm_all(&empty_vec, 0x1000).unwrap(),
+            disassembly: vec![],
         }
     }
 
@@ -258,6 +248,7 @@ mod test {
         let invocation_foo_bar = Invocation {
             instruction_address: 0x144562,
             invocation_type: InvocationType::Direct,
+            trait_implementation: None,
             frames: vec![
                 InlineFunctionFrame {
                     function_name: "analysis_target_function".to_string(),
@@ -335,6 +326,7 @@ mod test {
         let invocation_foo_bar = Invocation {
             instruction_address: 0x144562,
             invocation_type: InvocationType::Direct,
+            trait_implementation: None,
             frames: vec![
                 InlineFunctionFrame {
                     function_name: "analysis_target_function".to_string(),
@@ -407,10 +399,7 @@ mod test {
         let options = AnalysisOptions {
             binary_path: Some("".to_string()),
             crate_names: vec!["std".to_string(), "core".to_string()],
-            whitelisted_functions: vec![],
-            output_full_callgraph: false,
-            output_filtered_callgraph: false,
-            full_crate_analysis: false,
+            ..Default::default()
         };
 
         let marker = super::get_panic_analysis_target_marker(&options);
@@ -423,11 +412,7 @@ mod test {
     fn get_panic_analysis_target_marker_null() {
         let options = AnalysisOptions {
             binary_path: Some("".to_string()),
-            crate_names: vec![],
-            whitelisted_functions: vec![],
-            output_full_callgraph: false,
-            output_filtered_callgraph: false,
-            full_crate_analysis: false,
+            ..Default::default()
         };
 
         let marker = super::get_panic_analysis_target_marker(&options);
@@ -12,11 +12,31 @@ use crate::RustigCallGraph;
 use callgraph::Context;
 
 use crate::marker::CodeMarker;
+use crate::marker::CombinedCodeMarker;
 
 use callgraph::dwarf_utils;
+use callgraph::dwarf_utils::CompilationUnit;
+use callgraph::relocatable;
 
-use gimli::{EndianSlice, UnitHeader};
-use gimli::LittleEndian;
+use gimli::Endianity;
+use gimli::RunTimeEndian;
+
+use object::Machine;
+use object::Object;
+use object::ObjectSection;
+
+/// Size of a vector table entry, the vector table is only used on 32-bit ARM (Cortex-M) cores.
+const VECTOR_TABLE_ENTRY_SIZE: usize = 4;
+
+/// ELF symbol type of procedures (`STT_FUNC`).
+const STT_FUNC: u8 = 2;
+
+/// ELF symbol bindings of symbols that are visible outside the binary (`STB_GLOBAL` and `STB_WEAK`).
+const STB_GLOBAL: u8 = 1;
+const STB_WEAK: u8 = 2;
+
+/// ELF symbol visibility of exported symbols (`STV_DEFAULT`), stored in the lowest 2 bits of `st_other`.
+const STV_DEFAULT: u8 = 0;
 
 /// Implementation of the `CodeMarker` to mark the main entry procedure
 #[derive(Debug)]
@@ -24,14 +44,10 @@ struct MainEntryCodeMarker;
 
 impl CodeMarker for MainEntryCodeMarker {
     fn mark_code(&self, call_graph: &RustigCallGraph, context: &Context) {
-        let mut iter = context
-            .dwarf_info
-            .units();
-
-        while let Some(unit) = iter.next().unwrap() {
-                // Find entries in compilation unit
-                self.mark_entry_point(call_graph, context, unit)
-        }
+        dwarf_utils::get_compilation_units(context)
+            .iter()
+            // Find entries in compilation unit
+            .for_each(|unit| self.mark_entry_point(call_graph, unit));
     }
 
     #[cfg(test)]
@@ -44,11 +60,9 @@ impl MainEntryCodeMarker {
     fn mark_entry_point(
         &self,
         call_graph: &RustigCallGraph,
-        context: &Context,
-        unit: UnitHeader<EndianSlice<LittleEndian>>,
+        unit: &CompilationUnit,
     ) {
-        let abbrevs = unit.abbreviations(&context.dwarf_abbrev).unwrap();
-        let mut entries = unit.entries(&abbrevs);
+        let mut entries = unit.unit.entries();
         // Iterate over the entries
         while let Some((_, entry)) = entries.next_dfs().unwrap() {
             // If we find an entry for a function that has DW_AT_main_subprogram set ot true
@@ -57,7 +71,7 @@ impl MainEntryCodeMarker {
                     entry.attr_value(gimli::DW_AT_main_subprogram).unwrap()
                 {
                     let start_address =
-                        dwarf_utils::get_attr_addr_value(entry, gimli::DW_AT_low_pc)
+                        dwarf_utils::get_attr_addr_value(entry, gimli::DW_AT_low_pc, unit.dwarf, &unit.unit)
                             .expect("No DW_AT_low_pc attribute found for function");
 
                     let node_index = call_graph.proc_index[&start_address];
@@ -71,21 +85,145 @@ impl MainEntryCodeMarker {
     }
 }
 
-pub fn get_entry_points_marker(_options: &AnalysisOptions) -> Box<dyn CodeMarker> {
-    Box::new(MainEntryCodeMarker)
+/// Implementation of the `CodeMarker` to mark the reset and interrupt handlers in the vector table as entry points.
+///
+/// Firmware for Cortex-M cores does not have a main procedure marked with `DW_AT_main_subprogram`. Instead, the
+/// hardware jumps to the handlers in the vector table (`.vector_table`, as emitted by `cortex-m-rt`).
+#[derive(Debug)]
+struct VectorTableEntryCodeMarker;
+
+impl CodeMarker for VectorTableEntryCodeMarker {
+    fn mark_code(&self, call_graph: &RustigCallGraph, context: &Context) {
+        let handlers = match context.elf.section_data_by_name(".vector_table") {
+            Some(vector_table) => get_vector_table_handlers(vector_table, context.endian()),
+            None => get_reset_vector_handler(context).into_iter().collect(),
+        };
+
+        handlers
+            .iter()
+            .filter_map(|address| call_graph.proc_index.get(address))
+            .for_each(|node_index| {
+                call_graph.graph[*node_index]
+                    .borrow()
+                    .attributes
+                    .entry_point
+                    .replace(true);
+            });
+    }
+
+    #[cfg(test)]
+    fn get_type_name(&self) -> &str {
+        "VectorTableEntryCodeMarker"
+    }
+}
+
+/// Returns the addresses of the handlers in `vector_table`.
+/// The first entry is the initial stack pointer and is skipped, as are reserved (zero) entries.
+fn get_vector_table_handlers(vector_table: &[u8], endian: RunTimeEndian) -> Vec<u64> {
+    vector_table
+        .chunks(VECTOR_TABLE_ENTRY_SIZE)
+        .skip(1)
+        .filter(|entry| entry.len() == VECTOR_TABLE_ENTRY_SIZE)
+        .map(|entry| endian.read_u32(entry))
+        .filter(|address| *address != 0)
+        // Clear the Thumb bit
+        .map(|address| u64::from(address & !1))
+        .collect()
+}
+
+/// Returns the address of the reset handler, referenced by the `__RESET_VECTOR` symbol.
+/// Used when the vector table is not placed in a separate `.vector_table` section.
+fn get_reset_vector_handler(context: &Context) -> Option<u64> {
+    let reset_vector = context
+        .elf
+        .symbols()
+        .find(|symbol| symbol.name() == Some("__RESET_VECTOR"))?
+        .address();
+
+    let section = context.elf.sections().find(|section| {
+        section.address() <= reset_vector
+            && section.address() + section.size() >= reset_vector + VECTOR_TABLE_ENTRY_SIZE as u64
+    })?;
+    let offset = (reset_vector - section.address()) as usize;
+    let entry = section.data().get(offset..offset + VECTOR_TABLE_ENTRY_SIZE)?;
+
+    Some(u64::from(context.endian().read_u32(entry) & !1))
+}
+
+/// Implementation of the `CodeMarker` to mark the functions exported by a shared library as entry points.
+///
+/// Shared libraries (e.g. a `cdylib`) have no main procedure. Instead, their exported functions (like
+/// `#[no_mangle] extern "C"` functions) are called by the program that loads them. The same holds for the global
+/// functions of static libraries, which are called by the program they are linked into.
+#[derive(Debug)]
+struct ExportedFunctionCodeMarker;
+
+impl CodeMarker for ExportedFunctionCodeMarker {
+    fn mark_code(&self, call_graph: &RustigCallGraph, context: &Context) {
+        get_exported_functions(context)
+            .iter()
+            .filter_map(|address| call_graph.proc_index.get(address))
+            .for_each(|node_index| {
+                call_graph.graph[*node_index]
+                    .borrow()
+                    .attributes
+                    .entry_point
+                    .replace(true);
+            });
+    }
+
+    #[cfg(test)]
+    fn get_type_name(&self) -> &str {
+        "ExportedFunctionCodeMarker"
+    }
+}
+
+/// Returns the addresses of the functions exported by the binary in `context`: the defined procedures in its dynamic
+/// symbol table with global or weak binding, and default visibility. For a static library or relocatable object, these
+/// are the global procedures in the symbol tables of its objects, and for a WebAssembly module the functions in its
+/// export section.
+fn get_exported_functions(context: &Context) -> Vec<u64> {
+    if !context.object_files.is_empty() {
+        return relocatable::get_global_procedures(&context.object_files);
+    }
+    if let Some(wasm_module) = &context.wasm_module {
+        return wasm_module.exported_functions();
+    }
+
+    context
+        .elf
+        .elf()
+        .dynsyms
+        .iter()
+        .filter(|symbol| symbol.st_shndx != 0 && symbol.st_type() == STT_FUNC)
+        .filter(|symbol| symbol.st_bind() == STB_GLOBAL || symbol.st_bind() == STB_WEAK)
+        .filter(|symbol| symbol.st_other & 0x3 == STV_DEFAULT)
+        .map(|symbol| match context.elf.machine() {
+            // Clear the Thumb bit
+            Machine::Arm => symbol.st_value & !1,
+            _ => symbol.st_value,
+        })
+        .collect()
+}
+
+pub fn get_entry_points_marker(options: &AnalysisOptions) -> Box<dyn CodeMarker> {
+    if options.library_mode {
+        Box::new(ExportedFunctionCodeMarker)
+    } else {
+        Box::new(CombinedCodeMarker {
+            markers: vec![Box::new(MainEntryCodeMarker), Box::new(VectorTableEntryCodeMarker)],
+        })
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use capstone::arch::BuildsCapstone;
-    use capstone::Capstone;
-
     use super::*;
 
     use callgraph::Crate;
     use callgraph::Procedure;
 
-    use RDPProcedureMetaData;
+    use crate::RDPProcedureMetaData;
 
     use std::cell::Cell;
     use std::cell::RefCell;
@@ -103,15 +241,6 @@ mod test {
         crate_name: String,
         starting_address: u64,
     ) -> Procedure<RDPProcedureMetaData> {
-        let capstone = Capstone::new()
-            .x86()
-            .mode(capstone::arch::x86::ArchMode::Mode64)
-            .detail(true)
-            .build()
-            .expect("Failed to construct disassembler");
-
-        let empty_vec = Vec::new();
-
         Procedure {
             name: name.clone(),
             linkage_name: "linkage_name".to_string(),
@@ -122,6 +251,8 @@ mod test {
             },
             start_address: starting_address,
             size: 64,
+            address_ranges: vec![],
+            language: None,
             location: None,
             attributes: RDPProcedureMetaData {
                 analysis_target: Cell::new(false),
@@ -133,26 +264,22 @@ mod test {
                 whitelisted: Cell::new(false),
                 reachable_from_entry_point: Cell::new(true),
             },
-            disassembly: capstone.disasm_all(&empty_vec, 0x1000).unwrap(),
+            disassembly: vec![],
         }
     }
 
     // Given some specific context, find the address of main
     fn find_main_address(context: &Context) -> u64 {
-        let mut iter = context.dwarf_info.units();
         let mut start_address: u64 = 0x0;
-        while let Some(unit) = iter.next().unwrap() {
-            // Parse the abbreviations for this compilation unit.
-            let abbrevs = unit.abbreviations(&context.dwarf_abbrev).unwrap();
-
+        for unit in dwarf_utils::get_compilation_units(context) {
             // Iterate over all of this compilation unit's entries.
-            let mut entries = unit.entries(&abbrevs);
+            let mut entries = unit.unit.entries();
             while let Some((_, entry)) = entries.next_dfs().unwrap() {
                 // If we find an entry for a function, print it.
                 if let Some(gimli::AttributeValue::Flag(true)) =
                     entry.attr_value(gimli::constants::DW_AT_main_subprogram).unwrap()
                 {
-                    start_address = dwarf_utils::get_attr_addr_value(&entry, gimli::constants::DW_AT_low_pc)
+                    start_address = dwarf_utils::get_attr_addr_value(&entry, gimli::constants::DW_AT_low_pc, unit.dwarf, &unit.unit)
                         .expect("No DW_AT_low_pc attribute found for function");
                 }
             }
@@ -207,4 +334,87 @@ mod test {
         assert!(value_main.get());
         assert!(!value_notmain.get());
     }
+
+    /// Test if the handlers are read from a vector table, skipping the initial stack pointer and reserved entries
+    #[test]
+    fn test_vector_table_handlers() {
+        let vector_table = &[
+            0x00, 0x00, 0x02, 0x20, // initial stack pointer
+            0x01, 0x04, 0x00, 0x00, // reset handler (Thumb)
+            0x4b, 0x04, 0x00, 0x00, // NMI handler (Thumb)
+            0x00, 0x00, 0x00, 0x00, // reserved
+            0x4d, 0x04, 0x00, 0x00, // hard fault handler (Thumb)
+        ];
+
+        assert_eq!(
+            get_vector_table_handlers(vector_table, RunTimeEndian::Little),
+            vec![0x400, 0x44a, 0x44c]
+        );
+
+        // Firmware for big endian (BE8) cores
+        let vector_table = &[
+            0x20, 0x02, 0x00, 0x00, // initial stack pointer
+            0x00, 0x00, 0x04, 0x01, // reset handler (Thumb)
+            0x00, 0x00, 0x04, 0x4b, // NMI handler (Thumb)
+        ];
+
+        assert_eq!(
+            get_vector_table_handlers(vector_table, RunTimeEndian::Big),
+            vec![0x400, 0x44a]
+        );
+    }
+
+    /// Test that binaries without a vector table do not get additional entry points
+    #[test]
+    fn test_no_vector_table() {
+        let file_content = &test_common::load_test_binary_as_bytes(
+            "hello_world",
+            &test_common::TestSubjectType::Debug,
+        ).unwrap();
+        let context = test_utils::parse_context(file_content);
+
+        let procedure =
+            create_procedure_with_name("main".to_string(), "mycrate".to_string(), 0x400);
+
+        let mut og = callgraph::petgraph::stable_graph::StableGraph::new();
+        let node_index = og.add_node(Rc::new(RefCell::new(procedure)));
+
+        let mut proc_index = HashMap::new();
+        proc_index.insert(0x400, node_index);
+
+        let call_graph = RustigCallGraph {
+            graph: og,
+            proc_index,
+            call_index: HashMap::new(),
+        };
+
+        VectorTableEntryCodeMarker.mark_code(&call_graph, &context);
+
+        assert!(!call_graph.graph[node_index].borrow().attributes.entry_point.get());
+    }
+
+    /// Test that the exported functions of a shared library are read from its dynamic symbol table
+    #[test]
+    fn test_exported_functions() {
+        let file_content = &test_common::load_test_binary_as_bytes(
+            "libffi_lib.so",
+            &test_common::TestSubjectType::Debug,
+        ).unwrap();
+        let context = test_utils::parse_context(file_content);
+
+        let symbol_address = |name: &str| {
+            context
+                .elf
+                .symbols()
+                .find(|symbol| symbol.name().map_or(false, |symbol_name| symbol_name.contains(name)))
+                .map(|symbol| symbol.address())
+                .expect("Symbol not found")
+        };
+
+        let exported_functions = get_exported_functions(&context);
+        assert!(exported_functions.contains(&symbol_address("checked_divide")));
+        assert!(exported_functions.contains(&symbol_address("wrapping_sum")));
+        // Procedures of the library that are not exported are no entry points
+        assert!(!exported_functions.contains(&symbol_address("check_divisor")));
+    }
 }
@@ -49,9 +49,6 @@ impl CodeMarker for FunctionWhitelistMarker {
 
 #[cfg(test)]
 mod tests {
-    use capstone::arch::BuildsCapstone;
-    use capstone::Capstone;
-
     use super::*;
 
     use callgraph::Context;
@@ -63,6 +60,7 @@ mod tests {
     use callgraph::Procedure;
 
     use callgraph::addr2line::Context as Addr2LineContext;
+    use callgraph::disassembly::get_disassembler;
 
     use crate::RDPInlineFrameMetaData;
     use crate::RDPInvocationMetaData;
@@ -74,14 +72,12 @@ mod tests {
     use std::io::Read;
     use std::rc::Rc;
 
-    use self::gimli::DebugAbbrev;
-    use self::gimli::DebugInfo;
-    use self::gimli::DebugLine;
-    use self::gimli::DebugStr;
+    use callgraph::gimli;
+    use callgraph::gimli::Dwarf;
+    use callgraph::gimli::EndianSlice;
 
-    use self::object::ElfFile;
-    use self::object::File;
-    use self::object::Object;
+    use callgraph::object::ElfFile;
+    use callgraph::object::Object;
 
     use crate::IntermediateBacktrace::NoTrace;
 
@@ -90,47 +86,25 @@ mod tests {
     /// Local helper function to create a Context
     fn parse<'a>(file_content: &'a [u8]) -> Context<'a> {
         let elf = ElfFile::parse(&file_content).expect("Failed to parse file content");
-        let file = File::parse(&file_content).expect("Failed to parse file content to File format");
-        let file_context =
-            Addr2LineContext::new(&file).expect("Could not construct context from file");
-
-        let endianness = gimli::LittleEndian;
-        let mode = capstone::arch::x86::ArchMode::Mode64;
-
-        let debug_info_data = elf.section_data_by_name(".debug_info")
-            .expect("No .debug_info section in binary");
-        let dwarf_info = DebugInfo::new(debug_info_data, endianness);
-
-        let debug_abbrev_data = elf.section_data_by_name(".debug_abbrev")
-            .expect("No .debug_abbrev section in binary");
-        let dwarf_abbrev = DebugAbbrev::new(debug_abbrev_data, endianness);
-
-        let debug_str_data = elf.section_data_by_name(".debug_str")
-            .expect("No .debug_str section in binary");
-        let dwarf_strings = DebugStr::new(debug_str_data, endianness);
-
-        let debug_line_data = elf.section_data_by_name(".debug_line")
-            .expect("No .debug_line section in binary");
-        let dwarf_line = DebugLine::new(debug_line_data, endianness);
-
-        let mut capstone = Capstone::new()
-            .x86()
-            .mode(mode)
-            .detail(true)
-            .build()
-            .expect("Failed to construct disassembler");
-        capstone
-            .set_detail(true)
-            .expect("Failed to enable detailed mode");
+        let endianness = gimli::RunTimeEndian::Little;
+
+        let dwarf = Dwarf::load(|section| -> Result<_, ()> {
+            let data = elf.section_data_by_name(section.name()).unwrap_or(&[]);
+            Ok(EndianSlice::new(data, endianness))
+        }).expect("Failed to load DWARF sections");
+        let file_context = Addr2LineContext::from_dwarf(dwarf.borrow(|section| *section))
+            .expect("Could not construct context from file");
+
+        let disassembler = get_disassembler(&elf).expect("Failed to construct disassembler");
 
         Context {
             elf,
-            loader: file_context,
-            dwarf_info,
-            dwarf_abbrev,
-            dwarf_strings,
-            dwarf_line,
-            capstone,
+            loader: Some(file_context),
+            dwarf,
+            split_dwarf: HashMap::new(),
+            disassembler: Some(disassembler),
+            object_files: vec![],
+            wasm_module: None,
         }
     }
 
@@ -140,15 +114,6 @@ mod tests {
         crate_name: String,
         starting_address: u64,
     ) -> Procedure<RDPProcedureMetaData> {
-        let capstone = Capstone::new()
-            .x86()
-            .mode(capstone::arch::x86::ArchMode::Mode64)
-            .detail(true)
-            .build()
-            .expect("Failed to construct disassembler");
-
-        let empty_vec = Vec::new();
-
         Procedure {
             name: name.clone(),
             linkage_name: format!("linkage_name::{}", name).to_string(),
@@ -159,6 +124,8 @@ mod tests {
             },
             start_address: starting_address,
             size: 64,
+            address_ranges: vec![],
+            language: None,
             location: None,
             attributes: RDPProcedureMetaData {
                 analysis_target: Cell::new(false),
@@ -170,7 +137,7 @@ mod tests {
                 whitelisted: Cell::new(false),
                 reachable_from_entry_point: Cell::new(true),
             },
-            disassembly: capstone.disasm_all(&empty_vec, 0x1000).unwrap(),
+            disassembly: vec![],
         }
     }
 
@@ -257,6 +224,7 @@ mod tests {
         let inv = Rc::new(RefCell::new(Invocation {
             instruction_address: 0x144562,
             invocation_type: InvocationType::Direct,
+            trait_implementation: None,
             frames: vec![InlineFunctionFrame {
                 function_name: "mod1::mod2::inline_func".to_string(),
                 location: Location {
@@ -33,8 +33,9 @@ impl CodeMarker for DefaultPanicMarker {
             .graph
             .node_indices()
             .filter(|index| {
-                let name = &call_graph.graph[*index].borrow().linkage_name_demangled;
-                PANIC_HANDLERS.contains(&name.as_str())
+                // Generic arguments (`std::panicking::begin_panic::<&str>` in v0 symbols) are ignored
+                let segments = call_graph.graph[*index].borrow().symbol_path().segments().join("::");
+                PANIC_HANDLERS.contains(&segments.as_str())
             })
             .for_each(|index| {
                 call_graph.graph[index]
@@ -85,8 +86,6 @@ pub fn get_panic_marker(_options: &AnalysisOptions) -> Box<dyn CodeMarker> {
 mod test {
     use super::*;
 
-    use capstone::arch::BuildsCapstone;
-
     use callgraph::Crate;
     use callgraph::InvocationType::Direct;
     use callgraph::Procedure;
@@ -123,6 +122,8 @@ mod test {
             },
             start_address: current_address,
             size: proc_size,
+            address_ranges: vec![],
+            language: None,
             location: None,
             attributes: RDPProcedureMetaData {
                 analysis_target: Cell::new(analysis_target),
@@ -134,13 +135,7 @@ mod test {
                 whitelisted: Cell::new(false),
                 reachable_from_entry_point: Cell::new(true),
             },
-            disassembly: capstone::Capstone::new()
-                .x86()
-                .mode(capstone::arch::x86::ArchMode::Mode64)
-                .build()
-                .unwrap()
-                .disasm_all(&vec![], 0)
-                .unwrap(),
+            disassembly: vec![],
         }
     }
 
@@ -215,6 +210,7 @@ mod test {
         let invocation = Rc::new(RefCell::new(Invocation {
             instruction_address: 0x144562,
             invocation_type: Direct,
+            trait_implementation: None,
             frames: vec![],
             attributes: RDPInvocationMetaData {
                 ..Default::default()
@@ -264,6 +260,7 @@ mod test {
         let invocation = Rc::new(RefCell::new(Invocation {
             instruction_address: 0x144562,
             invocation_type: Direct,
+            trait_implementation: None,
             frames: vec![],
             attributes: RDPInvocationMetaData {
                 ..Default::default()
@@ -299,6 +296,7 @@ mod test {
         let invocation = Rc::new(RefCell::new(Invocation {
             instruction_address: 0x144562,
             invocation_type: Direct,
+            trait_implementation: None,
             frames: vec![],
             attributes: RDPInvocationMetaData {
                 ..Default::default()
@@ -337,6 +335,7 @@ mod test {
         let invocation = Rc::new(RefCell::new(Invocation {
             instruction_address: 0x144562,
             invocation_type: Direct,
+            trait_implementation: None,
             frames: vec![],
             attributes: RDPInvocationMetaData {
                 ..Default::default()
@@ -369,6 +368,7 @@ mod test {
         let invocation = Rc::new(RefCell::new(Invocation {
             instruction_address: 0x144562,
             invocation_type: Direct,
+            trait_implementation: None,
             frames: vec![],
             attributes: RDPInvocationMetaData {
                 ..Default::default()
@@ -401,6 +401,7 @@ mod test {
         let invocation = Rc::new(RefCell::new(Invocation {
             instruction_address: 0x144562,
             invocation_type: Direct,
+            trait_implementation: None,
             frames: vec![],
             attributes: RDPInvocationMetaData {
                 ..Default::default()
@@ -437,6 +438,7 @@ mod test {
         let invocation = Rc::new(RefCell::new(Invocation {
             instruction_address: 0x144562,
             invocation_type: Direct,
+            trait_implementation: None,
             frames: vec![],
             attributes: RDPInvocationMetaData {
                 ..Default::default()
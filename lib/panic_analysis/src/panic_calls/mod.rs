@@ -13,6 +13,7 @@ use crate::PanicCallsCollection;
 use crate::AnalysisOptions;
 
 use petgraph::prelude::Direction::Incoming;
+use petgraph::prelude::Direction::Outgoing;
 use petgraph::stable_graph::EdgeIndex;
 use petgraph::stable_graph::NodeIndex;
 
@@ -20,6 +21,7 @@ use callgraph::Context;
 use callgraph::InvocationType;
 
 use std::cell::RefCell;
+use std::collections::HashSet;
 use std::collections::VecDeque;
 
 use crate::BackTraceEntry;
@@ -39,12 +41,19 @@ pub trait PanicCallsFinder {
 /// Implementation of the `PanicCallsFinder` to find a trace from an analysis target to a panic.
 struct DefaultPanicCallsFinder {
     message_finders: Vec<Box<dyn PanicMessageFinder>>,
+    /// Flag indicating if the entry points from which a panic call is reachable should be reported (library mode)
+    report_entry_points: bool,
 }
 
 impl PanicCallsFinder for DefaultPanicCallsFinder {
     fn find_panics(&self, call_graph: &RustigCallGraph, context: &Context) -> PanicCallsCollection {
         self.traverse_graph(&call_graph.graph);
 
+        let entry_point_reach = match self.report_entry_points {
+            true => DefaultPanicCallsFinder::get_entry_point_reach(&call_graph.graph),
+            false => vec![],
+        };
+
         let panic_calls = call_graph
             .graph
             .edge_indices()
@@ -87,11 +96,18 @@ impl PanicCallsFinder for DefaultPanicCallsFinder {
                     .filter_map(|finder| finder.find_panic_message(&backtrace, call_graph, context))
                     .next();
 
+                let entry_points = entry_point_reach
+                    .iter()
+                    .filter(|(_, reach)| reach.contains(&index_source))
+                    .map(|(name, _)| name.clone())
+                    .collect();
+
                 PanicCall {
                     backtrace,
                     message,
                     pattern: RefCell::new(Unrecognized),
                     contains_dynamic_invocation,
+                    entry_points,
                 }
             })
             .collect::<Vec<_>>();
@@ -156,6 +172,30 @@ impl DefaultPanicCallsFinder {
 }
 
 impl DefaultPanicCallsFinder {
+    /// Returns the name of each entry point, with the nodes that are reachable from it without going through a
+    /// whitelisted node. Entry points are sorted by name.
+    fn get_entry_point_reach(graph: &RustigGraph) -> Vec<(String, HashSet<NodeIndex<u32>>)> {
+        let mut entry_point_reach = graph
+            .node_indices()
+            .filter(|index| graph[*index].borrow().attributes.entry_point.get())
+            .map(|entry_point| {
+                let mut reach = HashSet::new();
+                let mut stack = vec![entry_point];
+                while let Some(index) = stack.pop() {
+                    if graph[index].borrow().attributes.whitelisted.get() || !reach.insert(index) {
+                        continue;
+                    }
+                    stack.extend(graph.neighbors_directed(index, Outgoing));
+                }
+
+                (graph[entry_point].borrow().linkage_name_demangled.clone(), reach)
+            })
+            .collect::<Vec<_>>();
+
+        entry_point_reach.sort_by(|(name, _), (other_name, _)| name.cmp(other_name));
+        entry_point_reach
+    }
+
     fn leaves_analysis_target(call_graph: &RustigCallGraph, edge_index: EdgeIndex<u32>) -> bool {
         let endpoints = &call_graph.graph.edge_endpoints(edge_index);
         let (index_source, index_target) = endpoints.unwrap();
@@ -211,6 +251,7 @@ impl DefaultPanicCallsFinder {
                             let invocation_type = invocation.borrow().invocation_type;
 
                             if invocation_type == InvocationType::VTable
+                                || invocation_type == InvocationType::VTableSlot
                                 || invocation_type == InvocationType::ProcedureReference
                             {
                                 contains_dynamic_invocation = true;
@@ -233,6 +274,7 @@ impl DefaultPanicCallsFinder {
 pub fn get_panic_call_finder(options: &AnalysisOptions) -> Box<dyn PanicCallsFinder> {
     Box::new(DefaultPanicCallsFinder {
         message_finders: panic_message::get_panic_message_finders(options),
+        report_entry_points: options.library_mode,
     })
 }
 
@@ -240,8 +282,6 @@ pub fn get_panic_call_finder(options: &AnalysisOptions) -> Box<dyn PanicCallsFin
 mod test {
     use super::*;
 
-    use capstone::arch::BuildsCapstone;
-
     use crate::RDPProcedureMetaData;
 
     use callgraph::Crate;
@@ -279,6 +319,8 @@ mod test {
             },
             start_address: current_address,
             size: proc_size,
+            address_ranges: vec![],
+            language: None,
             location: None,
             attributes: RDPProcedureMetaData {
                 analysis_target: Cell::new(analysis_target),
@@ -290,13 +332,7 @@ mod test {
                 whitelisted: Cell::new(whitelisted),
                 reachable_from_entry_point: Cell::new(true),
             },
-            disassembly: capstone::Capstone::new()
-                .x86()
-                .mode(capstone::arch::x86::ArchMode::Mode64)
-                .build()
-                .unwrap()
-                .disasm_all(&vec![], 0)
-                .unwrap(),
+            disassembly: vec![],
         }
     }
 
@@ -323,6 +359,7 @@ mod test {
         let invocation_foo_bar = Rc::new(RefCell::new(Invocation {
             instruction_address: 0x135564,
             invocation_type: Direct,
+            trait_implementation: None,
             frames: vec![],
             attributes: RDPInvocationMetaData {
                 ..Default::default()
@@ -332,6 +369,7 @@ mod test {
         let invocation_bar_baz = Rc::new(RefCell::new(Invocation {
             instruction_address: 0x135564,
             invocation_type: Direct,
+            trait_implementation: None,
             frames: vec![],
             attributes: RDPInvocationMetaData {
                 ..Default::default()
@@ -341,6 +379,7 @@ mod test {
         let invocation_baz_buz = Rc::new(RefCell::new(Invocation {
             instruction_address: 0x135564,
             invocation_type: Direct,
+            trait_implementation: None,
             frames: vec![],
             attributes: RDPInvocationMetaData {
                 ..Default::default()
@@ -359,6 +398,7 @@ mod test {
 
         let panics = DefaultPanicCallsFinder {
             message_finders: vec![],
+            report_entry_points: false,
         }.find_panics(&call_graph, &context);
 
         let trace: Vec<_> = panics.calls[0]
@@ -401,6 +441,7 @@ mod test {
         let invocation_foo_bar_1 = Rc::new(RefCell::new(Invocation {
             instruction_address: 0x135564,
             invocation_type: Direct,
+            trait_implementation: None,
             frames: vec![],
             attributes: RDPInvocationMetaData {
                 ..Default::default()
@@ -410,6 +451,7 @@ mod test {
         let invocation_foo_bar_2 = Rc::new(RefCell::new(Invocation {
             instruction_address: 0x135564,
             invocation_type: Direct,
+            trait_implementation: None,
             frames: vec![],
             attributes: RDPInvocationMetaData {
                 ..Default::default()
@@ -427,6 +469,7 @@ mod test {
 
         let panics = DefaultPanicCallsFinder {
             message_finders: vec![],
+            report_entry_points: false,
         }.find_panics(&call_graph, &context);
 
         let trace: Vec<_> = panics.calls[0]
@@ -478,6 +521,7 @@ mod test {
         let invocation = Rc::new(RefCell::new(Invocation {
             instruction_address: 0x135564,
             invocation_type: Direct,
+            trait_implementation: None,
             frames: vec![],
             attributes: RDPInvocationMetaData {
                 ..Default::default()
@@ -495,6 +539,7 @@ mod test {
 
         DefaultPanicCallsFinder {
             message_finders: vec![],
+            report_entry_points: false,
         }.find_panics(&call_graph, &context);
 
         assert_eq!(
@@ -549,6 +594,7 @@ mod test {
         let invocation = Rc::new(RefCell::new(Invocation {
             instruction_address: 0x135564,
             invocation_type: Direct,
+            trait_implementation: None,
             frames: vec![],
             attributes: RDPInvocationMetaData {
                 ..Default::default()
@@ -567,6 +613,7 @@ mod test {
 
         DefaultPanicCallsFinder {
             message_finders: vec![],
+            report_entry_points: false,
         }.find_panics(&call_graph, &context);
 
         assert_eq!(
@@ -621,6 +668,7 @@ mod test {
         let invocation = Rc::new(RefCell::new(Invocation {
             instruction_address: 0x135564,
             invocation_type: Direct,
+            trait_implementation: None,
             frames: vec![],
             attributes: RDPInvocationMetaData {
                 ..Default::default()
@@ -639,6 +687,7 @@ mod test {
 
         DefaultPanicCallsFinder {
             message_finders: vec![],
+            report_entry_points: false,
         }.find_panics(&call_graph, &context);
 
         let foo_attributes = &call_graph.graph[foo].borrow().attributes;
@@ -680,6 +729,7 @@ mod test {
         let invocation = Rc::new(RefCell::new(Invocation {
             instruction_address: 0x135564,
             invocation_type: Direct,
+            trait_implementation: None,
             frames: vec![],
             attributes: RDPInvocationMetaData {
                 ..Default::default()
@@ -698,6 +748,7 @@ mod test {
 
         DefaultPanicCallsFinder {
             message_finders: vec![],
+            report_entry_points: false,
         }.find_panics(&call_graph, &context);
 
         let foo_attributes = &call_graph.graph[foo].borrow().attributes;
@@ -751,6 +802,7 @@ mod test {
         let invocation = Rc::new(RefCell::new(Invocation {
             instruction_address: 0x135564,
             invocation_type: Direct,
+            trait_implementation: None,
             frames: vec![],
             attributes: RDPInvocationMetaData {
                 ..Default::default()
@@ -770,6 +822,7 @@ mod test {
 
         DefaultPanicCallsFinder {
             message_finders: vec![],
+            report_entry_points: false,
         }.find_panics(&call_graph, &context);
 
         let foo_attributes = &call_graph.graph[foo].borrow().attributes;
@@ -797,4 +850,67 @@ mod test {
         assert_eq!(bar_result, vec![quz, bar]);
         assert_eq!(foo_result, vec![quz, foo])
     }
+
+    /// Test that in library mode, each panic call reports the exported functions it is reachable from
+    #[test]
+    fn test_entry_points_reported() {
+        let file_content = &test_common::load_test_binary_as_bytes(
+            "hello_world",
+            &test_common::TestSubjectType::Debug,
+        ).unwrap();
+        let context = test_utils::parse_context(&file_content);
+
+        let procedure_export_b = create_procedure("export_b".to_string(), true, true, false, false);
+        let procedure_export_a = create_procedure("export_a".to_string(), true, true, false, false);
+        let procedure_helper = create_procedure("helper".to_string(), false, true, false, false);
+        let procedure_divide = create_procedure("divide".to_string(), false, true, false, false);
+        let procedure_panic = create_procedure("panic".to_string(), false, false, true, false);
+
+        let mut graph = callgraph::petgraph::stable_graph::StableGraph::new();
+        let export_b = graph.add_node(Rc::new(RefCell::new(procedure_export_b)));
+        let export_a = graph.add_node(Rc::new(RefCell::new(procedure_export_a)));
+        let helper = graph.add_node(Rc::new(RefCell::new(procedure_helper)));
+        let divide = graph.add_node(Rc::new(RefCell::new(procedure_divide)));
+        let panic = graph.add_node(Rc::new(RefCell::new(procedure_panic)));
+
+        let invocation = Rc::new(RefCell::new(Invocation {
+            instruction_address: 0x135564,
+            invocation_type: Direct,
+            trait_implementation: None,
+            frames: vec![],
+            attributes: RDPInvocationMetaData {
+                ..Default::default()
+            },
+        }));
+
+        graph.add_edge(export_a, helper, invocation.clone());
+        graph.add_edge(export_b, helper, invocation.clone());
+        graph.add_edge(export_a, divide, invocation.clone());
+        graph.add_edge(helper, panic, invocation.clone());
+        graph.add_edge(divide, panic, invocation.clone());
+
+        let call_graph = RustigCallGraph {
+            graph,
+            proc_index: HashMap::new(),
+            call_index: HashMap::new(),
+        };
+
+        let panics = DefaultPanicCallsFinder {
+            message_finders: vec![],
+            report_entry_points: true,
+        }.find_panics(&call_graph, &context);
+
+        let entry_points = |name: &str| {
+            panics
+                .calls
+                .iter()
+                .find(|call| call.backtrace[0].procedure.borrow().name == name)
+                .map(|call| call.entry_points.clone())
+                .unwrap()
+        };
+
+        assert_eq!(panics.calls.len(), 2);
+        assert_eq!(entry_points("helper"), vec!["export_a", "export_b"]);
+        assert_eq!(entry_points("divide"), vec!["export_a"]);
+    }
 }
@@ -6,28 +6,26 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use byteorder::{LittleEndian, ReadBytesExt};
-
-use capstone::{Capstone, Insn, InsnId, RegId};
-use capstone::arch::x86::{X86OpMem, X86OperandType};
+use gimli::Endianity;
 
 use object::Object;
 use object::ObjectSection;
 
+use callgraph::disassembly::Instruction;
+use callgraph::disassembly::InstructionKind;
+use callgraph::disassembly::Operand;
+use callgraph::disassembly::Register;
 use callgraph::Context;
+use callgraph::symbol_path::SymbolPath;
 
-use std::io::{Cursor};
 use std::str::from_utf8;
-use capstone::arch::DetailsArchInsn;
 use crate::AnalysisOptions;
 use crate::BackTraceEntry;
 use crate::RustigCallGraph;
 
-static REG_ID_RDI: RegId = RegId(39);
-static REG_ID_ESI: RegId = RegId(29);
-
-static INS_ID_LEA: InsnId = InsnId(315);
-static INS_ID_MOV: InsnId = InsnId(442);
+/// Number of instructions before a call to a panic function that are searched for the instructions setting the
+/// arguments.
+const ARGUMENT_WINDOW: usize = 8;
 
 /// Trait that can be implemented by types that can try to retrieve panic messages.
 pub trait PanicMessageFinder {
@@ -65,27 +63,18 @@ impl PanicMessageFinder for CorePanickingPanicMessageFinder {
         _call_graph: &RustigCallGraph,
         context: &Context,
     ) -> Option<String> {
-        // get_call_instruction guarantees array length of `count`, so indexing is safe
-        let instruction = &get_call_instruction(backtrace, 1, "core::panicking::panic")?[0];
-        let registers = get_instruction_operand_registers(&context.capstone, instruction);
-        let operands = get_instruction_operand_values(&context.capstone, instruction);
-
-        // registers[0] is the destination register, which should be equal to RDI
-        if registers[0] == Some(REG_ID_RDI) && instruction.id() == INS_ID_LEA {
-            // operands[1] is the source operand (i.e. the loaded address)
-            if let Some(target_address) = operands[1] {
-                let str_bytes = get_bytes_at_address(context, target_address, 16)?;
-                assert_eq!(str_bytes.len(), 16, "Return value of get_bytes_at_address for string literal pointer bytes must have length 16.");
-                let mut str_cursor = Cursor::new(str_bytes);
-
-                let str_ptr = str_cursor.read_u64::<LittleEndian>().ok()?;
-                let str_size = str_cursor.read_u64::<LittleEndian>().ok()?;
-
-                return get_panic_message(context, str_ptr, str_size);
-            }
-        }
+        let instructions = get_call_instruction(backtrace, ARGUMENT_WINDOW, "core::panicking::panic")?;
+
+        // The address of the (pointer, size) pair is loaded into the first argument
+        // (%rdi on x86_64, the top of the stack on x86)
+        let argument = context.disassembler.as_ref()?.argument_register(0)?;
+        let target_address = get_loaded_value(context, find_value_origin(&instructions, &argument)?)?;
+
+        let word_size = get_word_size(context);
+        let str_ptr = get_word_at_address(context, target_address)?;
+        let str_size = get_word_at_address(context, target_address + word_size)?;
 
-        None
+        get_panic_message(context, str_ptr, str_size)
     }
 }
 
@@ -109,8 +98,8 @@ impl PanicMessageFinder for CorePanickingPanicMessageFinder {
 /// ```
 ///
 /// Here the following properties hold:
-/// * Where the string size is loaded in %eax and %esi.
-/// * The string literal pointer is loaded in %rdi, (pointing to `6640aa`, which is the string literal "assertion failed: self.next_value.is_none")
+/// * Where the string size is loaded in %eax and %esi (the second argument).
+/// * The string literal pointer is loaded in %rdi (the first argument), pointing to `6640aa`, which is the string literal "assertion failed: self.next_value.is_none"
 /// * The file name `&str` pointer is loaded in %rdx (but unused by Rustig)
 ///
 /// ### Release
@@ -125,19 +114,21 @@ impl PanicMessageFinder for CorePanickingPanicMessageFinder {
 ///    860f6:       be 2b 00 00 00          mov    $0x2b,%esi
 /// ```
 /// The `StdPanickingBeginPanicMessageFinder` implementation should find both cases.
+/// Similar instruction sequences are found on the other instruction sets, using their argument registers.
+/// On x86, the arguments are stored in the stack slots (`mov %eax,(%esp)`) or pushed onto the stack instead.
 struct StdPanickingBeginPanicMessageFinder {
-    /// Register id of the register in which the string literal pointer is passed.
-    /// Should be `%rdi` in the example above
-    string_pointer_reg_id: RegId,
-    /// Register id of the register in which the string size is passed.
-    /// Should be `%esi` in the example above
-    string_size_reg_id: RegId,
+    /// Index of the argument in which the string literal pointer is passed.
+    /// Should be 0 (`%rdi`) in the example above
+    string_pointer_argument: usize,
+    /// Index of the argument in which the string size is passed.
+    /// Should be 1 (`%esi`) in the example above
+    string_size_argument: usize,
     /// Name of the function to which the parameters are passed.
     /// Should be `"std::panicking::begin_panic"` in the example above.
     function_name: &'static str,
 }
 
-// Note IN this implementation, we assume %esi and %rdi as parameter registers
+// Note IN this implementation, we assume the arguments are passed in the registers (or stack slots) of the calling convention
 // If this assumption turns out to be invalid in the future, DWARF information could be used to find the
 // actual parameter location
 impl PanicMessageFinder for StdPanickingBeginPanicMessageFinder {
@@ -147,71 +138,22 @@ impl PanicMessageFinder for StdPanickingBeginPanicMessageFinder {
         _call_graph: &RustigCallGraph,
         context: &Context,
     ) -> Option<String> {
-        let instructions = get_call_instruction(backtrace, 4, self.function_name)?;
-        let mut instruction_iter = instructions.iter();
-
-        let last_instruction = &instruction_iter.next_back()?;
-
-        // We assumed the last instruction would be a 'mov $..,%esi' or 'mov %eax,%esi'
-        // So return if it is not a move
-        if last_instruction.id() != INS_ID_MOV {
-            return None;
-        }
-
-        let instruction_registers =
-            get_instruction_operand_registers(&context.capstone, last_instruction);
-        let instruction_operands =
-            get_instruction_operand_values(&context.capstone, last_instruction);
-
-        let str_size = match &instruction_registers[..] {
-            // mov $..,%esi case
-            // Check if first operand is %esi
-            [Some(target_reg_id), None] if target_reg_id == &self.string_size_reg_id => {
-                match &instruction_operands[..] {
-                    // Check if second operand is a constant
-                    [None, Some(size)] => *size,
-                    _ => return None,
-                }
-            }
-            // 'mov %eax,%esi' case
-            [Some(target_reg_id), Some(source_reg_id)]
-                // Check if first operand is %esi
-                if target_reg_id == &self.string_size_reg_id =>
-            {
-                // Retrieve the previous instruction
-                let last_instruction = &instruction_iter.next_back()?;
-                let instruction_registers =
-                    get_instruction_operand_registers(&context.capstone, last_instruction);
-                let instruction_operands =
-                    get_instruction_operand_values(&context.capstone, last_instruction);
-
-                // Check if this instruction matches `mov $.., %<source_reg_id>'
-                match &instruction_registers[..] {
-                    [Some(next_target_reg_id), None] if next_target_reg_id == source_reg_id => {
-                        match &instruction_operands[..] {
-                            [None, Some(size)] => *size,
-                            _ => return None,
-                        }
-                    }
-                    _ => return None,
-                }
-            }
+        let string_pointer_reg = context.disassembler.as_ref()?.argument_register(self.string_pointer_argument)?;
+        let string_size_reg = context.disassembler.as_ref()?.argument_register(self.string_size_argument)?;
+
+        let instructions = get_call_instruction(backtrace, ARGUMENT_WINDOW, self.function_name)?;
+
+        // The size is either moved into the argument directly ('mov $..,%esi'), or through another register
+        // ('mov $..,%eax' + 'mov %eax,%esi')
+        let str_size = match &find_value_origin(&instructions, &string_size_reg)?.kind {
+            InstructionKind::RegisterMove {
+                source: Operand::Immediate(size),
+                ..
+            } => *size,
             _ => return None,
         };
 
-        let str_ptr = instruction_iter
-            .rfind(|insn| {
-                let registers = get_instruction_operand_registers(&context.capstone, insn);
-                registers.len() == 2 && registers[0] == Some(self.string_pointer_reg_id)
-            })
-            .and_then(|insn| {
-                let operands = get_instruction_operand_values(&context.capstone, insn);
-                if operands.len() == 2 {
-                    operands[1]
-                } else {
-                    None
-                }
-            })?;
+        let str_ptr = get_loaded_value(context, find_value_origin(&instructions, &string_pointer_reg)?)?;
 
         get_panic_message(context, str_ptr, str_size)
     }
@@ -222,73 +164,94 @@ fn get_panic_message(context: &Context, str_ptr: u64, str_size: u64) -> Option<S
     from_utf8(str_bytes).ok().map(|x| x.to_string())
 }
 
-fn get_instruction_operand_registers(
-    capstone: &Capstone,
-    instruction: &Insn,
-) -> Vec<Option<RegId>> {
-    let insn_details = match capstone.insn_detail(instruction) {
-        Ok(det) => det,
-        _ => return vec![],
-    };
-
-    let arch_detail = insn_details.arch_detail();
-    let x86_arch_details = match arch_detail.x86() {
-        Some(arch_det) => arch_det,
-        None => return vec![],
-    };
-
-    x86_arch_details
-        .operands()
-        .map(|operand| match operand.op_type {
-            X86OperandType::Reg(id) => Some(id),
-            _ => None,
-        })
-        .collect()
+/// Returns the register written by `instruction`, if any.
+fn get_destination_register(instruction: &Instruction) -> Option<&Register> {
+    match &instruction.kind {
+        InstructionKind::AddressLoad { register, .. } | InstructionKind::MemoryLoad { register, .. } => Some(register),
+        InstructionKind::RegisterMove { destination, .. } => Some(destination),
+        InstructionKind::Other { destination } => destination.as_ref(),
+        InstructionKind::Call { .. }
+        | InstructionKind::MemoryCall { .. }
+        | InstructionKind::PointerCall { .. }
+        | InstructionKind::Jump { .. }
+        | InstructionKind::PointerJump { .. } => None,
+    }
 }
 
-fn get_instruction_operand_values(capstone: &Capstone, instruction: &Insn) -> Vec<Option<u64>> {
-    let instruction_address = instruction.address();
-    let instruction_size = instruction.bytes().len();
-    let rip_value = instruction_address + instruction_size as u64;
-
-    let insn_details = match capstone.insn_detail(instruction) {
-        Ok(det) => det,
-        _ => return vec![],
-    };
-
-    let arch_detail = insn_details.arch_detail();
-    let x86_arch_details = match arch_detail.x86() {
-        Some(arch_det) => arch_det,
-        None => return vec![],
-    };
-
-    x86_arch_details
-        .operands()
-        .map(|operand| match operand.op_type {
-            X86OperandType::Imm(value) => Some(value as u64),
-            X86OperandType::Mem(mem_operand) if mem_operand.base() == RegId(0) => {
-                get_mem_op_value(&mem_operand, 0)
-            }
-            X86OperandType::Mem(mem_operand) if mem_operand.base() == RegId(41) => {
-                get_mem_op_value(&mem_operand, rip_value)
-            }
-            _ => None,
-        })
-        .collect()
+/// Returns the instruction that determines the value of `register` after the last instruction of `instructions`,
+/// following moves between registers. Returns `None` if the value is not set after the last branch in `instructions`.
+fn find_value_origin<'a>(instructions: &'a [Instruction], register: &Register) -> Option<&'a Instruction> {
+    let mut register = register.clone();
+    for instruction in instructions.iter().rev() {
+        // A call may overwrite the register, so stop at the last branch
+        if instruction.is_branch() {
+            return None;
+        }
+        if get_destination_register(instruction) != Some(&register) {
+            continue;
+        }
+        match &instruction.kind {
+            InstructionKind::RegisterMove {
+                source: Operand::Register(source),
+                ..
+            } => register = source.clone(),
+            _ => return Some(instruction),
+        }
+    }
+    None
+}
+
+/// Returns the value `instruction` loads into its destination register, if it can be determined statically.
+fn get_loaded_value(context: &Context, instruction: &Instruction) -> Option<u64> {
+    match &instruction.kind {
+        InstructionKind::AddressLoad { address, .. } => Some(*address),
+        InstructionKind::MemoryLoad { address, .. } => get_word_at_address(context, *address),
+        InstructionKind::RegisterMove {
+            source: Operand::Immediate(value),
+            ..
+        } => Some(*value),
+        _ => None,
+    }
+}
+
+/// Returns the size in bytes of a pointer in the binary of `context`.
+fn get_word_size(context: &Context) -> u64 {
+    if context.elf.elf().is_64 {
+        8
+    } else {
+        4
+    }
+}
+
+/// Reads the pointer sized word at `address`, in the byte order of the binary.
+fn get_word_at_address(context: &Context, address: u64) -> Option<u64> {
+    let word_size = get_word_size(context);
+    let bytes = get_bytes_at_address(context, address, word_size)?;
+    if word_size == 8 {
+        Some(context.endian().read_u64(bytes))
+    } else {
+        Some(u64::from(context.endian().read_u32(bytes)))
+    }
 }
 
 fn get_call_instruction(
     backtrace: &[BackTraceEntry],
     count: usize,
     fn_name: &str,
-) -> Option<Vec<Insn>> {
+) -> Option<Vec<Instruction>> {
+    // Compare the paths without generic arguments, which v0 symbols contain (`std::panicking::begin_panic::<&str>`)
+    let fn_path = SymbolPath::parse(fn_name);
     let panic_position = backtrace
         .iter()
-        .position(|x| x.procedure.borrow().linkage_name_demangled == fn_name)?;
+        .position(|x| x.procedure.borrow().symbol_path().segments() == fn_path.segments())?;
 
     // `core::panicking::panic` can never be the first entry in the stack trace.
     assert_ne!(panic_position, 0);
     let caller = backtrace[panic_position - 1].procedure.borrow();
+    // The operands of procedures of merged shared objects refer to the shared object instead of to the binary
+    if caller.is_in_shared_library() {
+        return None;
+    }
     let panic_invocation = backtrace[panic_position - 1]
         .outgoing_invocation
         .as_ref()
@@ -296,12 +259,12 @@ fn get_call_instruction(
     let panic_call_address = panic_invocation.borrow().instruction_address;
 
     // fetch last pair of instructions before the actual call, in order to find the loaded address
-    let instructions_vec = caller.disassembly.iter().collect::<Vec<_>>();
-    let mut target_instructions = instructions_vec.into_iter() // Implements DoubleEndedIterator
+    let mut target_instructions = caller.disassembly.iter()
         .rev()
-        .skip_while(|x| x.address() != panic_call_address)
+        .skip_while(|x| x.address != panic_call_address)
         .skip(1) // Get the instruction BEFORE call (since we reversed the order)
         .take(count)
+        .cloned()
         .collect::<Vec<_>>();
 
     // Undo reverse
@@ -309,15 +272,12 @@ fn get_call_instruction(
     Some(target_instructions)
 }
 
-fn get_mem_op_value(mem_operand: &X86OpMem, base_reg_value: u64) -> Option<u64> {
-    if mem_operand.index() == RegId(0) {
-        Some(base_reg_value + mem_operand.disp() as u64)
-    } else {
-        None
+fn get_bytes_at_address<'a>(context: &Context<'a>, address: u64, size: u64) -> Option<&'a [u8]> {
+    // The data sections of relocatable objects are not placed, so their addresses are not known
+    if !context.object_files.is_empty() {
+        return None;
     }
-}
 
-fn get_bytes_at_address<'a>(context: &Context<'a>, address: u64, size: u64) -> Option<&'a [u8]> {
     let section = context.elf.sections().find(|sec| {
         let sec_address = sec.address();
         let sec_size = sec.size();
@@ -338,22 +298,22 @@ pub fn get_panic_message_finders(_options: &AnalysisOptions) -> Vec<Box<dyn Pani
     vec![
         Box::new(CorePanickingPanicMessageFinder),
         Box::new(StdPanickingBeginPanicMessageFinder {
-            string_pointer_reg_id: REG_ID_RDI,
-            string_size_reg_id: REG_ID_ESI,
+            string_pointer_argument: 0,
+            string_size_argument: 1,
             function_name: "std::panicking::begin_panic",
         }),
         // Implementation to find messages for Result<T, E>::expect
         // Works on release builds only
         Box::new(StdPanickingBeginPanicMessageFinder {
-            string_pointer_reg_id: REG_ID_RDI,
-            string_size_reg_id: REG_ID_ESI,
+            string_pointer_argument: 0,
+            string_size_argument: 1,
             function_name: "core::option::expect_failed",
         }),
         // Implementation to find messages for Option<T>::expect
         // Works on release builds only
         Box::new(StdPanickingBeginPanicMessageFinder {
-            string_pointer_reg_id: REG_ID_RDI,
-            string_size_reg_id: REG_ID_ESI,
+            string_pointer_argument: 0,
+            string_size_argument: 1,
             function_name: "core::result::unwrap_failed",
         }),
     ]
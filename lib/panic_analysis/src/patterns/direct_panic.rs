@@ -68,8 +68,6 @@ mod test {
     use crate::BackTraceEntry;
     use crate::IntermediateBacktrace::NoTrace;
 
-    use self::capstone::arch::BuildsCapstone;
-
     use callgraph::Crate;
     use callgraph::InlineFunctionFrame;
     use callgraph::Invocation;
@@ -78,7 +76,7 @@ mod test {
     use callgraph::Procedure;
 
     use crate::PanicCall;
-    use PanicCallsCollection;
+    use crate::PanicCallsCollection;
     use crate::PanicPattern::Unrecognized;
     use crate::RDPInlineFrameMetaData;
 
@@ -102,6 +100,8 @@ mod test {
             },
             start_address: current_address,
             size: proc_size,
+            address_ranges: vec![],
+            language: None,
             location: None,
             attributes: RDPProcedureMetaData {
                 analysis_target: Cell::new(false),
@@ -113,13 +113,7 @@ mod test {
                 reachable_from_entry_point: Cell::new(true),
                 whitelisted: Cell::new(false),
             },
-            disassembly: capstone::Capstone::new()
-                .x86()
-                .mode(capstone::arch::x86::ArchMode::Mode64)
-                .build()
-                .unwrap()
-                .disasm_all(&vec![], 0)
-                .unwrap(),
+            disassembly: vec![],
         }
     }
 
@@ -132,6 +126,7 @@ mod test {
         let invocation_foo_bar = Rc::new(RefCell::new(Invocation {
             instruction_address: 0x144562,
             invocation_type: Direct,
+            trait_implementation: None,
             frames: vec![InlineFunctionFrame {
                 function_name: "crate::Foo".to_string(),
                 location: Location {
@@ -167,6 +162,7 @@ mod test {
             backtrace: vec![entry_foo, entry_bar],
             pattern: result.clone(),
             contains_dynamic_invocation: false,
+            entry_points: vec![],
             message: None,
         };
 
@@ -196,6 +192,7 @@ mod test {
         let invocation = Rc::new(RefCell::new(Invocation {
             instruction_address: 0x144562,
             invocation_type: Direct,
+            trait_implementation: None,
             frames: vec![InlineFunctionFrame {
                 function_name: "crate::Foo".to_string(),
                 location: Location {
@@ -234,6 +231,7 @@ mod test {
             backtrace: vec![entry_foo, entry_bar, entry_buz],
             pattern: result.clone(),
             contains_dynamic_invocation: false,
+            entry_points: vec![],
             message: None,
         };
 
@@ -262,6 +260,7 @@ mod test {
         let invocation_foo_bar = Rc::new(RefCell::new(Invocation {
             instruction_address: 0x144562,
             invocation_type: Direct,
+            trait_implementation: None,
             frames: vec![
                 InlineFunctionFrame {
                     function_name: "crate::Foo".to_string(),
@@ -313,6 +312,7 @@ mod test {
             backtrace: vec![entry_foo, entry_bar],
             pattern: result.clone(),
             contains_dynamic_invocation: false,
+            entry_points: vec![],
             message: None,
         };
 
@@ -341,6 +341,7 @@ mod test {
         let invocation_foo_bar = Rc::new(RefCell::new(Invocation {
             instruction_address: 0x144562,
             invocation_type: Direct,
+            trait_implementation: None,
             frames: vec![
                 InlineFunctionFrame {
                     function_name: "crate::Foo".to_string(),
@@ -392,6 +393,7 @@ mod test {
             backtrace: vec![entry_foo, entry_bar],
             pattern: result.clone(),
             contains_dynamic_invocation: false,
+            entry_points: vec![],
             message: None,
         };
 
@@ -6,6 +6,7 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+use callgraph::symbol_path::SymbolPath;
 use callgraph::Context;
 use crate::patterns::PatternFinder;
 
@@ -20,7 +21,7 @@ use crate::PanicPattern::Unwrap;
 
 /// Implementation of the `PatternFinder` to find unwrap calls causing a panic.
 struct FunctionPatternFinder<'a> {
-    // A hashmap that maps the name of function to the pattern the trace should be recognized as.
+    // A hashmap that maps the item name of a function (e.g. `unwrap`) to the pattern the trace should be recognized as.
     function_pattern_mapping: HashMap<&'a str, PanicPattern>,
 }
 
@@ -53,10 +54,9 @@ impl<'a> PatternFinder for FunctionPatternFinder<'a> {
                     names
                 })
                 .filter_map(|name| {
-                    self.function_pattern_mapping
-                        .iter()
-                        .find(|(function, _)| name.ends_with(*function))
-                        .map(|(_, pattern)| *pattern)
+                    // Compare the item only, as the generic arguments in v0 symbols follow the item name
+                    let item = SymbolPath::parse(&name).item;
+                    self.function_pattern_mapping.get(item.as_str()).copied()
                 })
                 .next()
                 .map(|pattern| call.pattern.replace(pattern));
@@ -66,9 +66,9 @@ impl<'a> PatternFinder for FunctionPatternFinder<'a> {
 
 pub fn get_function_names_pattern_finder(_options: &AnalysisOptions) -> Box<dyn PatternFinder> {
     let mut function_map = HashMap::new();
-    function_map.insert("::unwrap", Unwrap);
-    function_map.insert("::expect", Unwrap);
-    function_map.insert("::index", Indexing);
+    function_map.insert("unwrap", Unwrap);
+    function_map.insert("expect", Unwrap);
+    function_map.insert("index", Indexing);
     Box::new(FunctionPatternFinder {
         function_pattern_mapping: function_map,
     })
@@ -76,7 +76,6 @@ pub fn get_function_names_pattern_finder(_options: &AnalysisOptions) -> Box<dyn
 
 #[cfg(test)]
 mod tests {
-    use capstone::arch::BuildsCapstone;
     use test_common::*;
     use super::*;
 
@@ -84,7 +83,7 @@ mod tests {
     use crate::IntermediateBacktrace::NoTrace;
 
     use crate::PanicCall;
-    use PanicPattern;
+    use crate::PanicPattern;
     use crate::RDPInvocationMetaData;
     use crate::RDPProcedureMetaData;
 
@@ -117,6 +116,8 @@ mod tests {
                     },
                     start_address: 0x6450,
                     size: 0x200,
+                    address_ranges: vec![],
+                    language: None,
                     location: None,
                     attributes: RDPProcedureMetaData {
                         analysis_target: Cell::new(false),
@@ -128,17 +129,12 @@ mod tests {
                         reachable_from_entry_point: Cell::new(true),
                         whitelisted: Cell::new(false),
                     },
-                    disassembly: capstone::Capstone::new()
-                        .x86()
-                        .mode(capstone::arch::x86::ArchMode::Mode64)
-                        .build()
-                        .unwrap()
-                        .disasm_all(&vec![], 0)
-                        .unwrap(),
+                    disassembly: vec![],
                 })),
                 outgoing_invocation: Some(Rc::new(RefCell::new(Invocation {
                     instruction_address: 0x144562,
                     invocation_type: InvocationType::Direct,
+                    trait_implementation: None,
                     frames: inline_frames
                         .iter()
                         .map(|name| InlineFunctionFrame {
@@ -181,13 +177,14 @@ mod tests {
                 backtrace: proc_trace,
                 pattern: RefCell::new(PanicPattern::Unrecognized),
                 contains_dynamic_invocation: false,
+                entry_points: vec![],
                 message: None,
             }],
         };
 
         let mut function_map = HashMap::new();
-        function_map.insert("::unwrap", Unwrap);
-        function_map.insert("::expect", Unwrap);
+        function_map.insert("unwrap", Unwrap);
+        function_map.insert("expect", Unwrap);
 
         let finder = FunctionPatternFinder {
             function_pattern_mapping: function_map,
@@ -219,13 +216,14 @@ mod tests {
                 backtrace: proc_trace,
                 pattern: RefCell::new(PanicPattern::Unrecognized),
                 contains_dynamic_invocation: false,
+                entry_points: vec![],
                 message: None,
             }],
         };
 
         let mut function_map = HashMap::new();
-        function_map.insert("::unwrap", Unwrap);
-        function_map.insert("::expect", Unwrap);
+        function_map.insert("unwrap", Unwrap);
+        function_map.insert("expect", Unwrap);
 
         let finder = FunctionPatternFinder {
             function_pattern_mapping: function_map,
@@ -253,13 +251,14 @@ mod tests {
                 backtrace: proc_trace,
                 pattern: RefCell::new(PanicPattern::Unrecognized),
                 contains_dynamic_invocation: false,
+                entry_points: vec![],
                 message: None,
             }],
         };
 
         let mut function_map = HashMap::new();
-        function_map.insert("::unwrap", Unwrap);
-        function_map.insert("::expect", Unwrap);
+        function_map.insert("unwrap", Unwrap);
+        function_map.insert("expect", Unwrap);
 
         let finder = FunctionPatternFinder {
             function_pattern_mapping: function_map,
@@ -288,13 +287,14 @@ mod tests {
                 backtrace: proc_trace,
                 pattern: RefCell::new(PanicPattern::Unrecognized),
                 contains_dynamic_invocation: false,
+                entry_points: vec![],
                 message: None,
             }],
         };
 
         let mut function_map = HashMap::new();
-        function_map.insert("::unwrap", Unwrap);
-        function_map.insert("::expect", Unwrap);
+        function_map.insert("unwrap", Unwrap);
+        function_map.insert("expect", Unwrap);
 
         let finder = FunctionPatternFinder {
             function_pattern_mapping: function_map,
@@ -327,13 +327,14 @@ mod tests {
                 backtrace: proc_trace,
                 pattern: RefCell::new(PanicPattern::Unrecognized),
                 contains_dynamic_invocation: false,
+                entry_points: vec![],
                 message: None,
             }],
         };
 
         let mut function_map = HashMap::new();
-        function_map.insert("::unwrap", Unwrap);
-        function_map.insert("::expect", Unwrap);
+        function_map.insert("unwrap", Unwrap);
+        function_map.insert("expect", Unwrap);
 
         let finder = FunctionPatternFinder {
             function_pattern_mapping: function_map,
@@ -65,8 +65,8 @@ mod test {
     use super::*;
 
     use crate::PanicCall;
-    use PanicPattern::Indexing;
-    use PanicPattern::Unrecognized;
+    use crate::PanicPattern::Indexing;
+    use crate::PanicPattern::Unrecognized;
 
     use std::cell::RefCell;
 
@@ -92,6 +92,7 @@ mod test {
                 backtrace: Vec::new(),
                 pattern: RefCell::new(Unrecognized),
                 contains_dynamic_invocation: false,
+                entry_points: vec![],
                 message: Some("arith".to_string()),
             }],
         };
@@ -121,6 +122,7 @@ mod test {
                 backtrace: Vec::new(),
                 pattern: RefCell::new(Unrecognized),
                 contains_dynamic_invocation: false,
+                entry_points: vec![],
                 message: Some("index".to_string()),
             }],
         };
@@ -151,12 +153,14 @@ mod test {
                     backtrace: Vec::new(),
                     pattern: RefCell::new(Unrecognized),
                     contains_dynamic_invocation: false,
+                    entry_points: vec![],
                     message: Some("index".to_string()),
                 },
                 PanicCall {
                     backtrace: Vec::new(),
                     pattern: RefCell::new(Unrecognized),
                     contains_dynamic_invocation: false,
+                    entry_points: vec![],
                     message: Some("arith".to_string()),
                 },
             ],
@@ -189,24 +193,28 @@ mod test {
                     backtrace: Vec::new(),
                     pattern: RefCell::new(Unrecognized),
                     contains_dynamic_invocation: false,
+                    entry_points: vec![],
                     message: None,
                 },
                 PanicCall {
                     backtrace: Vec::new(),
                     pattern: RefCell::new(Unrecognized),
                     contains_dynamic_invocation: false,
+                    entry_points: vec![],
                     message: None,
                 },
                 PanicCall {
                     backtrace: Vec::new(),
                     pattern: RefCell::new(Unrecognized),
                     contains_dynamic_invocation: false,
+                    entry_points: vec![],
                     message: None,
                 },
                 PanicCall {
                     backtrace: Vec::new(),
                     pattern: RefCell::new(Unrecognized),
                     contains_dynamic_invocation: false,
+                    entry_points: vec![],
                     message: Some("index".to_string()),
                 },
             ],
@@ -237,6 +245,7 @@ mod test {
                 backtrace: Vec::new(),
                 pattern: RefCell::new(Unrecognized),
                 contains_dynamic_invocation: false,
+                entry_points: vec![],
                 message: Some("not present".to_string()),
             }],
         };
@@ -266,6 +275,7 @@ mod test {
                 backtrace: Vec::new(),
                 pattern: RefCell::new(Unrecognized),
                 contains_dynamic_invocation: false,
+                entry_points: vec![],
                 message: None,
             }],
         };
@@ -0,0 +1,36 @@
+// (C) COPYRIGHT 2018 TECHNOLUTION BV, GOUDA NL
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#[cfg(test)]
+mod test {
+    use panic_analysis::*;
+    use test_common::*;
+
+    /// In this integration test we look at the Cortex-M (thumbv7m) build of the 'firmware' test subject, which has no
+    /// main procedure. The reset handler in its vector table is the entry point, which calls `average` through `run`.
+    /// We test if the division by zero in `average` is found through the reset handler.
+    #[test]
+    pub fn test_panics_from_vector_table_thumbv7m() {
+        if test_common::skip_unbuilt(&TestSubjectType::DebugThumbv7m) {
+            return;
+        }
+        let path = test_common::get_test_subject_path("firmware", &TestSubjectType::DebugThumbv7m);
+        let options = AnalysisOptions {
+            binary_path: path.to_str().map(|x| x.to_string()),
+            crate_names: vec!["firmware".to_string()],
+            ..Default::default()
+        };
+
+        let calls = find_panics(&options).unwrap();
+
+        // Panics are only reported if they are reachable from an entry point
+        assert!(calls.calls.iter().any(|c| {
+            c.backtrace[0].procedure.borrow().linkage_name_demangled == "firmware::average"
+        }));
+    }
+}
@@ -24,7 +24,7 @@
 ///                      |  +---+  |    | +---------------+ |   |            |
 ///                      |         |    |                   |   |            |
 ///                      +---------+    +-------------------+   +------------+
-/// Here both the multi_dep and dep crates are in the test_subjects workspace, and hence have test_subjects as crate name
+/// Here both the multi_dep and dep crates are members of the test_subjects workspace
 /// Usually, these are seen as the analysis target, while simplelog and std are external code
 #[cfg(test)]
 mod test {
@@ -41,11 +41,8 @@ mod test {
 
         let options = AnalysisOptions {
             binary_path: Some(binary_path.to_str().unwrap().to_string()),
-            crate_names: vec!["test_subjects".to_string()],
-            whitelisted_functions: vec![],
-            output_full_callgraph: false,
-            output_filtered_callgraph: false,
-            full_crate_analysis: false,
+            crate_names: vec!["multi_dep".to_string(), "dep".to_string()],
+            ..Default::default()
         };
 
         let panic_calls = panic_analysis::find_panics(&options).unwrap();
@@ -84,15 +81,13 @@ mod test {
 
         let options = AnalysisOptions {
             binary_path: Some(binary_path.to_str().unwrap().to_string()),
-            crate_names: vec!["test_subjects".to_string()],
+            crate_names: vec!["multi_dep".to_string(), "dep".to_string()],
             whitelisted_functions: vec![FunctionWhiteListEntry {
                 function_name: "TermLogger::new".to_string(),
                 crate_name: Some("simplelog".to_string()),
                 crate_version: FunctionWhitelistCrateVersion::Strict("0.4.4".to_string()),
             }],
-            output_full_callgraph: false,
-            output_filtered_callgraph: false,
-            full_crate_analysis: false,
+            ..Default::default()
         };
 
         let panic_calls = panic_analysis::find_panics(&options).unwrap();
@@ -122,15 +117,13 @@ mod test {
 
         let options = AnalysisOptions {
             binary_path: Some(binary_path.to_str().unwrap().to_string()),
-            crate_names: vec!["test_subjects".to_string()],
+            crate_names: vec!["multi_dep".to_string(), "dep".to_string()],
             whitelisted_functions: vec![FunctionWhiteListEntry {
                 function_name: "TermLogger::new".to_string(),
                 crate_name: Some("simplelog".to_string()),
                 crate_version: FunctionWhitelistCrateVersion::None,
             }],
-            output_full_callgraph: false,
-            output_filtered_callgraph: false,
-            full_crate_analysis: false,
+            ..Default::default()
         };
 
         let panic_calls = panic_analysis::find_panics(&options).unwrap();
@@ -146,15 +139,13 @@ mod test {
 
         let options = AnalysisOptions {
             binary_path: Some(binary_path.to_str().unwrap().to_string()),
-            crate_names: vec!["test_subjects".to_string()],
+            crate_names: vec!["multi_dep".to_string(), "dep".to_string()],
             whitelisted_functions: vec![FunctionWhiteListEntry {
                 function_name: "multi_dep::bar".to_string(),
-                crate_name: Some("test_subjects".to_string()),
+                crate_name: Some("multi_dep".to_string()),
                 crate_version: FunctionWhitelistCrateVersion::None,
             }],
-            output_full_callgraph: false,
-            output_filtered_callgraph: false,
-            full_crate_analysis: false,
+            ..Default::default()
         };
 
         let panic_calls = panic_analysis::find_panics(&options).unwrap();
@@ -171,15 +162,13 @@ mod test {
 
         let options = AnalysisOptions {
             binary_path: Some(binary_path.to_str().unwrap().to_string()),
-            crate_names: vec!["test_subjects".to_string()],
+            crate_names: vec!["multi_dep".to_string(), "dep".to_string()],
             whitelisted_functions: vec![FunctionWhiteListEntry {
                 function_name: "multi_dep::baz".to_string(),
-                crate_name: Some("test_subjects".to_string()),
+                crate_name: Some("multi_dep".to_string()),
                 crate_version: FunctionWhitelistCrateVersion::None,
             }],
-            output_full_callgraph: false,
-            output_filtered_callgraph: false,
-            full_crate_analysis: false,
+            ..Default::default()
         };
 
         let panic_calls = panic_analysis::find_panics(&options).unwrap();
@@ -195,15 +184,14 @@ mod test {
 
         let options = AnalysisOptions {
             binary_path: Some(binary_path.to_str().unwrap().to_string()),
-            crate_names: vec!["test_subjects".to_string()],
+            crate_names: vec!["multi_dep".to_string(), "dep".to_string()],
             whitelisted_functions: vec![FunctionWhiteListEntry {
                 function_name: "multi_dep::bar".to_string(),
-                crate_name: Some("test_subjects".to_string()),
+                crate_name: Some("multi_dep".to_string()),
                 crate_version: FunctionWhitelistCrateVersion::None,
             }],
-            output_full_callgraph: false,
-            output_filtered_callgraph: false,
             full_crate_analysis: true,
+            ..Default::default()
         };
 
         let panic_calls = panic_analysis::find_panics(&options).unwrap();
@@ -242,15 +230,13 @@ mod test {
 
         let options = AnalysisOptions {
             binary_path: Some(binary_path.to_str().unwrap().to_string()),
-            crate_names: vec!["test_subjects".to_string()],
+            crate_names: vec!["multi_dep".to_string(), "dep".to_string()],
             whitelisted_functions: vec![FunctionWhiteListEntry {
                 function_name: "multi_dep::baz".to_string(),
-                crate_name: Some("test_subjects".to_string()),
+                crate_name: Some("multi_dep".to_string()),
                 crate_version: FunctionWhitelistCrateVersion::None,
             }],
-            output_full_callgraph: false,
-            output_filtered_callgraph: false,
-            full_crate_analysis: false,
+            ..Default::default()
         };
 
         let panic_calls = panic_analysis::find_panics(&options).unwrap();
@@ -278,11 +264,11 @@ mod test {
 
         let options = AnalysisOptions {
             binary_path: Some(binary_path.to_str().unwrap().to_string()),
-            crate_names: vec!["test_subjects".to_string()],
+            crate_names: vec!["multi_dep".to_string(), "dep".to_string()],
             whitelisted_functions: vec![
                 FunctionWhiteListEntry {
                     function_name: "multi_dep::baz".to_string(),
-                    crate_name: Some("test_subjects".to_string()),
+                    crate_name: Some("multi_dep".to_string()),
                     crate_version: FunctionWhitelistCrateVersion::None,
                 },
                 FunctionWhiteListEntry {
@@ -291,9 +277,7 @@ mod test {
                     crate_version: FunctionWhitelistCrateVersion::Strict("0.5.2".to_string()),
                 },
             ],
-            output_full_callgraph: false,
-            output_filtered_callgraph: false,
-            full_crate_analysis: false,
+            ..Default::default()
         };
 
         let panic_calls = panic_analysis::find_panics(&options).unwrap();
@@ -321,11 +305,11 @@ mod test {
 
         let options = AnalysisOptions {
             binary_path: Some(binary_path.to_str().unwrap().to_string()),
-            crate_names: vec!["test_subjects".to_string()],
+            crate_names: vec!["multi_dep".to_string(), "dep".to_string()],
             whitelisted_functions: vec![
                 FunctionWhiteListEntry {
                     function_name: "multi_dep::baz".to_string(),
-                    crate_name: Some("test_subjects".to_string()),
+                    crate_name: Some("multi_dep".to_string()),
                     crate_version: FunctionWhitelistCrateVersion::None,
                 },
                 FunctionWhiteListEntry {
@@ -334,9 +318,7 @@ mod test {
                     crate_version: FunctionWhitelistCrateVersion::Strict("0.4.4".to_string()),
                 },
             ],
-            output_full_callgraph: false,
-            output_filtered_callgraph: false,
-            full_crate_analysis: false,
+            ..Default::default()
         };
 
         let panic_calls = panic_analysis::find_panics(&options).unwrap();
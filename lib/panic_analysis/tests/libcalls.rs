@@ -31,6 +31,7 @@ mod test {
     ) -> bool {
         let name = crate_name;
         let options = create_options(
+            name,
             test_common::get_test_subject_path(name, subject_type)
                 .to_str()
                 .unwrap()
@@ -51,14 +52,11 @@ mod test {
         panic_count == count
     }
 
-    fn create_options(subject: String) -> AnalysisOptions {
+    fn create_options(crate_name: &str, subject: String) -> AnalysisOptions {
         AnalysisOptions {
             binary_path: Some(subject),
-            crate_names: vec!["test_subjects".to_string()],
-            output_full_callgraph: false,
-            output_filtered_callgraph: false,
-            whitelisted_functions: vec![],
-            full_crate_analysis: false,
+            crate_names: vec![crate_name.to_string()],
+            ..Default::default()
         }
     }
 
@@ -0,0 +1,63 @@
+// (C) COPYRIGHT 2018 TECHNOLUTION BV, GOUDA NL
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#[cfg(test)]
+mod test {
+    use self::panic_analysis::*;
+    use self::test_common::*;
+
+    fn create_options(binary: &str, library_mode: bool) -> AnalysisOptions {
+        let path = test_common::get_test_subject_path(binary, &TestSubjectType::Debug);
+        AnalysisOptions {
+            binary_path: path.to_str().map(|x| x.to_string()),
+            crate_names: vec!["ffi_lib".to_string()],
+            library_mode,
+            ..Default::default()
+        }
+    }
+
+    /// In this integration test we look at the 'ffi_lib' test subject, which is a cdylib
+    /// It exports checked_divide, which panics through check_divisor, and wrapping_sum, which does not panic.
+    /// We test if the panic is found, and reported for the checked_divide export only.
+    #[test]
+    pub fn test_panics_per_exported_function() {
+        let calls = find_panics(&create_options("libffi_lib.so", true)).unwrap();
+
+        assert!(calls.calls.iter().any(|call| {
+            call.backtrace[0].procedure.borrow().linkage_name_demangled == "ffi_lib::check_divisor"
+        }));
+        assert!(calls
+            .calls
+            .iter()
+            .all(|call| call.entry_points == vec!["checked_divide"]));
+    }
+
+    /// A shared library has no main procedure, so without library mode nothing is reachable and no panics are found
+    #[test]
+    pub fn test_no_entry_points_without_library_mode() {
+        let calls = find_panics(&create_options("libffi_lib.so", false)).unwrap();
+
+        assert!(calls.calls.is_empty());
+    }
+
+    /// The rlib of 'ffi_lib' is analyzed before it is linked: its global functions are the entry points, without
+    /// library mode. The standard library is not part of the rlib, so the panic is found through the external
+    /// `core::panicking` procedure that is called.
+    #[test]
+    pub fn test_panics_per_global_function_of_rlib() {
+        let calls = find_panics(&create_options("libffi_lib.rlib", false)).unwrap();
+
+        assert!(calls.calls.iter().any(|call| {
+            call.backtrace[0].procedure.borrow().linkage_name_demangled == "ffi_lib::check_divisor"
+        }));
+        assert!(calls.calls.iter().all(|call| {
+            call.entry_points.contains(&"checked_divide".to_string())
+                && !call.entry_points.contains(&"wrapping_sum".to_string())
+        }));
+    }
+}
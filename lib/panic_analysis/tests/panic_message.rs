@@ -20,11 +20,8 @@ mod test {
 
         let options = AnalysisOptions {
             binary_path,
-            crate_names: vec!["test_subjects".to_string()],
-            full_crate_analysis: false,
-            output_full_callgraph: false,
-            output_filtered_callgraph: false,
-            whitelisted_functions: vec![],
+            crate_names: vec!["unwrap".to_string()],
+            ..Default::default()
         };
 
         let calls = find_panics(&options).unwrap();
@@ -62,11 +59,8 @@ mod test {
 
         let options = AnalysisOptions {
             binary_path,
-            crate_names: vec!["test_subjects".to_string()],
-            full_crate_analysis: false,
-            output_full_callgraph: false,
-            output_filtered_callgraph: false,
-            whitelisted_functions: vec![],
+            crate_names: vec!["unwrap".to_string()],
+            ..Default::default()
         };
 
         let calls = find_panics(&options).unwrap();
@@ -103,11 +97,8 @@ mod test {
 
         let options = AnalysisOptions {
             binary_path,
-            crate_names: vec!["test_subjects".to_string()],
-            full_crate_analysis: false,
-            output_full_callgraph: false,
-            output_filtered_callgraph: false,
-            whitelisted_functions: vec![],
+            crate_names: vec!["direct".to_string()],
+            ..Default::default()
         };
 
         let calls = find_panics(&options).unwrap();
@@ -140,11 +131,8 @@ mod test {
 
         let options = AnalysisOptions {
             binary_path,
-            crate_names: vec!["test_subjects".to_string()],
-            full_crate_analysis: false,
-            output_full_callgraph: false,
-            output_filtered_callgraph: false,
-            whitelisted_functions: vec![],
+            crate_names: vec!["direct".to_string()],
+            ..Default::default()
         };
 
         let calls = find_panics(&options).unwrap();
@@ -178,11 +166,8 @@ mod test {
 
         let options = AnalysisOptions {
             binary_path,
-            crate_names: vec!["test_subjects".to_string()],
-            full_crate_analysis: false,
-            output_full_callgraph: false,
-            output_filtered_callgraph: false,
-            whitelisted_functions: vec![],
+            crate_names: vec!["unwrap".to_string()],
+            ..Default::default()
         };
 
         let calls = find_panics(&options).unwrap();
@@ -221,11 +206,8 @@ mod test {
 
         let options = AnalysisOptions {
             binary_path,
-            crate_names: vec!["test_subjects".to_string()],
-            full_crate_analysis: false,
-            output_full_callgraph: false,
-            output_filtered_callgraph: false,
-            whitelisted_functions: vec![],
+            crate_names: vec!["unwrap".to_string()],
+            ..Default::default()
         };
 
         let calls = find_panics(&options).unwrap();
@@ -252,4 +234,61 @@ mod test {
         );
     }
 
+    /// Test if we can retrieve a message on a call to `core::panicking::panic` in i686 builds, where the argument is
+    /// passed on the stack and the address is loaded relative to the GOT.
+    #[test]
+    pub fn test_find_core_message_i686() {
+        if test_common::skip_unbuilt(&TestSubjectType::DebugI686) {
+            return;
+        }
+        let path = test_common::get_test_subject_path("unwrap", &TestSubjectType::DebugI686);
+        let binary_path = path.to_str().map(|x| x.to_string());
+
+        let options = AnalysisOptions {
+            binary_path,
+            crate_names: vec!["unwrap".to_string()],
+            ..Default::default()
+        };
+
+        let calls = find_panics(&options).unwrap();
+        let unwrap_call = calls
+            .calls
+            .iter()
+            .find(|trace| {
+                trace.backtrace[0].procedure.borrow().linkage_name_demangled
+                    == "unwrap::call_unwrap"
+            })
+            .expect("No trace starting at unwrap::call_unwrap found");
+
+        assert_eq!(
+            unwrap_call.message,
+            Some("called `Option::unwrap()` on a `None` value".to_string())
+        );
+    }
+
+    /// Test if we can retrieve a message on a call to `std::panicking::begin_panic` in i686 builds, where the
+    /// arguments are passed on the stack.
+    #[test]
+    pub fn test_find_std_message_i686() {
+        if test_common::skip_unbuilt(&TestSubjectType::DebugI686) {
+            return;
+        }
+        let path = test_common::get_test_subject_path("direct", &TestSubjectType::DebugI686);
+        let binary_path = path.to_str().map(|x| x.to_string());
+
+        let options = AnalysisOptions {
+            binary_path,
+            crate_names: vec!["direct".to_string()],
+            ..Default::default()
+        };
+
+        let calls = find_panics(&options).unwrap();
+        assert_eq!(
+            calls.calls.len(),
+            1,
+            "Expected 1 trace from 'direct' test subject"
+        );
+
+        assert_eq!(calls.calls[0].message, Some("Panic from bar".to_string()));
+    }
 }
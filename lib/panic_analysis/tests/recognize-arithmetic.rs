@@ -24,11 +24,8 @@ mod test {
         let binary_path = path.to_str().map(|x| x.to_string());
         let options = AnalysisOptions {
             binary_path,
-            crate_names: vec!["test_subjects".to_string()],
-            full_crate_analysis: false,
-            output_full_callgraph: false,
-            output_filtered_callgraph: false,
-            whitelisted_functions: vec![],
+            crate_names: vec!["arithmetic".to_string()],
+            ..Default::default()
         };
 
         let calls = find_panics(&options).unwrap();
@@ -20,11 +20,8 @@ mod test {
         let binary_path = path.to_str().map(|x| x.to_string());
         let options = AnalysisOptions {
             binary_path,
-            crate_names: vec!["test_subjects".to_string()],
-            full_crate_analysis: false,
-            output_full_callgraph: false,
-            output_filtered_callgraph: false,
-            whitelisted_functions: vec![],
+            crate_names: vec!["direct".to_string()],
+            ..Default::default()
         };
 
         let calls = find_panics(&options).unwrap();
@@ -70,11 +67,8 @@ mod test {
         let binary_path = path.to_str().map(|x| x.to_string());
         let options = AnalysisOptions {
             binary_path,
-            crate_names: vec!["test_subjects".to_string()],
-            full_crate_analysis: false,
-            output_full_callgraph: false,
-            output_filtered_callgraph: false,
-            whitelisted_functions: vec![],
+            crate_names: vec!["indirect".to_string()],
+            ..Default::default()
         };
 
         let calls = find_panics(&options).unwrap();
@@ -124,11 +118,8 @@ mod test {
         let binary_path = path.to_str().map(|x| x.to_string());
         let options = AnalysisOptions {
             binary_path,
-            crate_names: vec!["test_subjects".to_string()],
-            full_crate_analysis: false,
-            output_full_callgraph: false,
-            output_filtered_callgraph: false,
-            whitelisted_functions: vec![],
+            crate_names: vec!["direct".to_string()],
+            ..Default::default()
         };
 
         let calls = find_panics(&options).unwrap();
@@ -174,11 +165,8 @@ mod test {
         let binary_path = path.to_str().map(|x| x.to_string());
         let options = AnalysisOptions {
             binary_path,
-            crate_names: vec!["test_subjects".to_string()],
-            full_crate_analysis: false,
-            output_full_callgraph: false,
-            output_filtered_callgraph: false,
-            whitelisted_functions: vec![],
+            crate_names: vec!["indirect".to_string()],
+            ..Default::default()
         };
 
         let calls = find_panics(&options).unwrap();
@@ -20,11 +20,8 @@ mod test {
         let binary_path = path.to_str().map(|x| x.to_string());
         let options = AnalysisOptions {
             binary_path,
-            crate_names: vec!["test_subjects".to_string()],
-            full_crate_analysis: false,
-            output_full_callgraph: false,
-            output_filtered_callgraph: false,
-            whitelisted_functions: vec![],
+            crate_names: vec!["unwrap".to_string()],
+            ..Default::default()
         };
 
         let calls = find_panics(&options).unwrap();
@@ -89,4 +86,34 @@ mod test {
             other_trace
         );
     }
+
+    /// The same binary is analyzed from memory, without a path, which finds the same traces
+    #[test]
+    pub fn test_recognize_unwraps_in_memory() {
+        let file_content = test_common::load_test_binary_as_bytes("unwrap", &TestSubjectType::Debug).unwrap();
+        let options = AnalysisOptions {
+            crate_names: vec!["unwrap".to_string()],
+            ..Default::default()
+        };
+
+        let calls = find_panics_in_memory(&file_content, &options).unwrap();
+
+        let unwrap_origins = calls
+            .calls
+            .iter()
+            .filter(|c| *c.pattern.borrow() == PanicPattern::Unwrap)
+            .map(|c| c.backtrace[0].procedure.borrow().linkage_name_demangled.to_owned())
+            .collect::<Vec<_>>();
+
+        assert_eq!(calls.calls.len(), 5);
+        [
+            "unwrap::call_unwrap",
+            "unwrap::call_expect",
+            "unwrap::call_option_expect",
+            "unwrap::call_unwrap_deep",
+        ].iter()
+            .for_each(|origin| {
+                assert!(unwrap_origins.iter().any(|x| x == origin), "No unwrap panic found in {}", origin);
+            });
+    }
 }
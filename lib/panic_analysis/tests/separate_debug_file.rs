@@ -0,0 +1,71 @@
+// (C) COPYRIGHT 2018 TECHNOLUTION BV, GOUDA NL
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#[cfg(test)]
+mod test {
+    use panic_analysis::*;
+    use test_common::*;
+
+    /// Runs panic_analysis on the 'unwrap' test subject `subject` of `subject_type`, with the debug files searched in
+    /// `debug_file_directories`, and returns the sorted traces with their pattern
+    fn get_sorted_traces(
+        subject: &str,
+        subject_type: &TestSubjectType,
+        debug_file_directories: Vec<String>,
+    ) -> Vec<(Vec<String>, PanicPattern)> {
+        let path = test_common::get_test_subject_path(subject, subject_type);
+        let options = AnalysisOptions {
+            binary_path: path.to_str().map(|x| x.to_string()),
+            crate_names: vec!["unwrap".to_string()],
+            debug_file_directories,
+            ..Default::default()
+        };
+
+        let calls = find_panics(&options).unwrap();
+
+        let mut traces = calls
+            .calls
+            .iter()
+            .map(|call| {
+                let trace = call
+                    .backtrace
+                    .iter()
+                    .map(|entry| entry.procedure.borrow().linkage_name_demangled.to_owned())
+                    .collect::<Vec<_>>();
+                (trace, *call.pattern.borrow())
+            })
+            .collect::<Vec<_>>();
+        traces.sort_by_key(|(trace, pattern)| (trace.clone(), format!("{:?}", pattern)));
+        traces
+    }
+
+    /// In this integration test we look at the release build of the 'unwrap' test subject, of which the debug
+    /// information is moved to a separate debug file.
+    /// We test if the same panics are found as in the unstripped binary, with the debug file found by its
+    /// `.gnu_debuglink`, and by its build ID.
+    #[test]
+    pub fn test_stripped_binary_with_separate_debug_file() {
+        let expected = get_sorted_traces("unwrap", &TestSubjectType::Release, vec![]);
+        assert!(!expected.is_empty());
+
+        assert_eq!(
+            get_sorted_traces("unwrap", &TestSubjectType::ReleaseSeparateDebug, vec![]),
+            expected
+        );
+
+        let debug_files = test_common::get_test_subject_path("debug-files", &TestSubjectType::ReleaseSeparateDebug);
+        assert_eq!(
+            get_sorted_traces(
+                "build-id/unwrap",
+                &TestSubjectType::ReleaseSeparateDebug,
+                vec![debug_files.to_str().unwrap().to_string()]
+            ),
+            expected
+        );
+    }
+}
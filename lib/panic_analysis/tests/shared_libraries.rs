@@ -0,0 +1,62 @@
+// (C) COPYRIGHT 2018 TECHNOLUTION BV, GOUDA NL
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#[cfg(test)]
+mod test {
+    use panic_analysis::*;
+    use test_common::*;
+
+    use std::process::Command;
+
+    /// Returns the directory of the standard library of the toolchain, which contains `libstd-*.so`
+    fn get_rust_library_dir() -> String {
+        let output = Command::new("rustc")
+            .arg("--print")
+            .arg("target-libdir")
+            .output()
+            .expect("Could not run rustc");
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    }
+
+    /// In this integration test we look at the build of the 'unwrap' test subject with `-C prefer-dynamic`, which calls
+    /// the panic functions of the standard library in `libstd-*.so`.
+    /// We test if the call graph of the shared object is merged, and the panics are found through its procedures.
+    #[test]
+    pub fn test_panics_through_shared_standard_library() {
+        if test_common::skip_unbuilt(&TestSubjectType::DebugPreferDynamic) {
+            return;
+        }
+        let path = test_common::get_test_subject_path("unwrap", &TestSubjectType::DebugPreferDynamic);
+        let options = AnalysisOptions {
+            binary_path: path.to_str().map(|x| x.to_string()),
+            crate_names: vec!["unwrap".to_string()],
+            library_paths: vec![get_rust_library_dir()],
+            ..Default::default()
+        };
+
+        let calls = find_panics(&options).unwrap();
+
+        // The panic functions are defined in the shared object, so the traces end in it
+        let shared_library_calls = calls
+            .calls
+            .iter()
+            .filter(|call| {
+                call.backtrace
+                    .iter()
+                    .any(|entry| entry.procedure.borrow().is_in_shared_library())
+            })
+            .collect::<Vec<_>>();
+        assert!(!shared_library_calls.is_empty());
+
+        // The unwrap in the binary is still recognized
+        assert!(shared_library_calls.iter().any(|call| {
+            *call.pattern.borrow() == PanicPattern::Unwrap
+                && call.backtrace[0].procedure.borrow().linkage_name_demangled == "unwrap::call_unwrap"
+        }));
+    }
+}
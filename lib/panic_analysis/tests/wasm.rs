@@ -0,0 +1,52 @@
+// (C) COPYRIGHT 2018 TECHNOLUTION BV, GOUDA NL
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#[cfg(test)]
+mod test {
+    use panic_analysis::*;
+    use test_common::*;
+
+    /// In this integration test we look at the `wasm32-wasip1` build of the 'unwrap' test subject, which is a
+    /// WebAssembly module with the debug information in custom sections.
+    /// We test if the `unwrap` and `expect` calls in test_subjects_lib are found from main, and categorized correctly.
+    #[test]
+    pub fn test_recognize_unwraps_in_wasm_module() {
+        if test_common::skip_unbuilt(&TestSubjectType::DebugWasm32) {
+            return;
+        }
+        let path = test_common::get_test_subject_path("unwrap.wasm", &TestSubjectType::DebugWasm32);
+        let options = AnalysisOptions {
+            binary_path: path.to_str().map(|x| x.to_string()),
+            crate_names: vec!["unwrap".to_string()],
+            ..Default::default()
+        };
+
+        let calls = find_panics(&options).unwrap();
+
+        let unwrap_origins = calls
+            .calls
+            .iter()
+            .filter(|c| *c.pattern.borrow() == PanicPattern::Unwrap)
+            .map(|c| c.backtrace[0].procedure.borrow().linkage_name_demangled.to_owned())
+            .collect::<Vec<_>>();
+
+        [
+            "unwrap::call_unwrap",
+            "unwrap::call_expect",
+            "unwrap::call_option_expect",
+            "unwrap::call_unwrap_deep",
+        ].iter()
+            .for_each(|origin| {
+                assert!(unwrap_origins.iter().any(|x| x == origin), "No unwrap panic found in {}", origin);
+            });
+
+        assert!(calls.calls.iter().any(|c| {
+            c.backtrace[0].procedure.borrow().linkage_name_demangled == "unwrap::panic_otherwise"
+        }));
+    }
+}
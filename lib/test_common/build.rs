@@ -7,16 +7,54 @@
 // except according to those terms.
 
 /// This build script ensures the binaries of the test subjects (programs that are used as input for the tests)
-/// are built for debug and release.
+/// are built for debug and release, for x86_64, i686, AArch64, RISC-V (rv64gc) and WebAssembly (WASI).
+/// The embedded test subjects (firmware without an operating system) are built for ARM Cortex-M (thumbv7m), RISC-V
+/// (rv32imac) and big endian ARM Cortex-R (armebv7r).
+/// Only the x86_64 subjects are required. The subjects of the other targets are skipped, with a warning, if the Rust
+/// target or the linker of the target is not installed, and so are the tests using them (see `TestSubjectType::is_built`).
+/// Variants of the x86_64 release binaries with compressed debug sections, and without debug sections, are created with
+/// `objcopy`, skipping with a warning the compression types `objcopy` does not support. Variants of the x86_64 debug
+/// binaries with split debug information, with DWARF 5, and dynamically linked against the standard library are built
+/// with the flags in `DEBUG_INFO_VARIANTS`, if supported.
 /// These subjects are used in tests that verify the tool works on a (new) particular Rust version.
 /// These tests perform regression testing on the tool itself as well as the Rust compiler.
 /// Changes in the Rust compiler that break the tool should be detected by tests
 /// on these projects.
+use std::env;
 use std::path::Path;
+use std::path::PathBuf;
 use std::process::Command;
 
 const RES_PATH: &str = "test_subjects";
+const EMBEDDED_RES_PATH: &str = "test_subjects_embedded";
 const BUILD_MODE_ARGS: &[Option<&str>] = &[None, Some("--release")];
+/// The first target is the host, for which the subjects must be built.
+/// The AArch64 and RISC-V subjects are cross compiled, the linkers are configured in `test_subjects/.cargo/config.toml`.
+/// The i686 subjects are linked by the host compiler, which needs 32-bit support (e.g. `gcc-multilib`).
+/// The WebAssembly subjects are linked by `rust-lld`, which is shipped with the target.
+const TARGETS: &[&str] = &[
+    "x86_64-unknown-linux-gnu",
+    "i686-unknown-linux-gnu",
+    "aarch64-unknown-linux-gnu",
+    "riscv64gc-unknown-linux-gnu",
+    "wasm32-wasip1",
+];
+/// Bare metal targets the embedded test subjects are built for, in debug mode. These are linked by `rust-lld`, which is
+/// shipped with the target.
+const EMBEDDED_TARGETS: &[&str] = &["thumbv7m-none-eabi", "riscv32imac-unknown-none-elf", "armebv7r-none-eabi"];
+/// Compression types passed to `objcopy --compress-debug-sections`, each written to `release-<type>`.
+/// The `zstd` type requires binutils 2.40 or later, and is skipped otherwise.
+const DEBUG_SECTION_COMPRESSIONS: &[&str] = &["zlib", "zlib-gnu", "zstd"];
+/// Subjects of which variants of the release builds are created with `objcopy`.
+const OBJCOPY_SUBJECTS: &[&str] = &["hello_world", "trait_invocation", "unwrap"];
+/// Names and `RUSTFLAGS` of the variants of the x86_64 debug builds, each built in `target/<name>`.
+/// `-C dwarf-version` requires Rust 1.88 or later, the variants of which the flags are not supported are skipped.
+const DEBUG_INFO_VARIANTS: &[(&str, &str)] = &[
+    ("split-unpacked", "-C split-debuginfo=unpacked"),
+    ("split-packed", "-C split-debuginfo=packed"),
+    ("dwarf5", "-C dwarf-version=5"),
+    ("prefer-dynamic", "-C prefer-dynamic"),
+];
 
 fn main() {
     let current_dir = Path::new(env!("CARGO_MANIFEST_DIR")).to_path_buf();
@@ -28,6 +66,13 @@ fn main() {
 
     let test_subjects_dir = Path::join(grandparent_dir, Path::new(RES_PATH));
 
+    let targets = TARGETS
+        .iter()
+        .enumerate()
+        .filter(|(index, target)| *index == 0 || is_installed(target))
+        .map(|(_, target)| *target)
+        .collect::<Vec<_>>();
+
     BUILD_MODE_ARGS.iter().for_each(|arg| {
         // clean the dir to force a fresh build
         let subjects_clean_status = Command::new("cargo")
@@ -40,25 +85,289 @@ fn main() {
             panic!("Could not clean test subjects, manual intervention needed");
         }
 
-        // rebuild the dir
-        let mut cargo = Command::new("cargo");
+        targets.iter().for_each(|target| {
+            // rebuild the dir
+            let mut cargo = Command::new("cargo");
 
-        cargo.current_dir(test_subjects_dir.clone());
+            cargo.current_dir(test_subjects_dir.clone());
 
-        cargo.arg("build");
-        cargo.arg("--target");
-        cargo.arg("x86_64-unknown-linux-gnu");
+            cargo.arg("build");
+            cargo.arg("--target");
+            cargo.arg(target);
 
-        if let Some(arg) = arg {
-            cargo.arg(arg);
-        }
+            if let Some(arg) = arg {
+                cargo.arg(arg);
+            }
+
+            let subjects_build_status = cargo
+                .status()
+                .expect("Building of test subjects did not produce any output");
+
+            if !subjects_build_status.success() {
+                panic!("Could not build test subjects for {}, manual intervention needed", target);
+            }
+        });
+    });
+
+    compress_debug_sections(&test_subjects_dir.join("target/x86_64-unknown-linux-gnu"));
+    strip_debug_sections(&test_subjects_dir.join("target/x86_64-unknown-linux-gnu"));
+    separate_debug_files(&test_subjects_dir.join("target/x86_64-unknown-linux-gnu"));
+
+    DEBUG_INFO_VARIANTS
+        .iter()
+        .filter(|(name, flags)| rustc_supports(name, flags))
+        .for_each(|(name, flags)| {
+            let variant_build_status = Command::new("cargo")
+                .current_dir(test_subjects_dir.clone())
+                .env("RUSTFLAGS", flags)
+                .arg("build")
+                .arg("--target")
+                .arg("x86_64-unknown-linux-gnu")
+                .arg("--target-dir")
+                .arg(Path::new("target").join(name))
+                .status()
+                .expect("Building of test subjects did not produce any output");
+
+            if !variant_build_status.success() {
+                panic!("Could not build test subjects with {}, manual intervention needed", flags);
+            }
+        });
+
+    build_embedded_subjects(&Path::join(grandparent_dir, Path::new(EMBEDDED_RES_PATH)));
+}
+
+/// Builds the embedded test subjects in `embedded_dir` for the `EMBEDDED_TARGETS` that are installed.
+fn build_embedded_subjects(embedded_dir: &Path) {
+    let clean_status = Command::new("cargo")
+        .current_dir(embedded_dir)
+        .arg("clean")
+        .status()
+        .expect("Cleaning embedded test subject dir did not produce any output");
+
+    if !clean_status.success() {
+        panic!("Could not clean embedded test subjects, manual intervention needed");
+    }
+
+    EMBEDDED_TARGETS
+        .iter()
+        .filter(|target| is_installed(target))
+        .for_each(|target| {
+            let build_status = Command::new("cargo")
+                .current_dir(embedded_dir)
+                .arg("build")
+                .arg("--target")
+                .arg(target)
+                .status()
+                .expect("Building of embedded test subjects did not produce any output");
+
+            if !build_status.success() {
+                panic!("Could not build embedded test subjects for {}, manual intervention needed", target);
+            }
+        });
+}
+
+/// Returns `true` if the Rust target `target` and its linker are installed. Prints a warning otherwise.
+fn is_installed(target: &str) -> bool {
+    let target_libdir = Command::new("rustc")
+        .arg("--print")
+        .arg("target-libdir")
+        .arg("--target")
+        .arg(target)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| PathBuf::from(String::from_utf8_lossy(&output.stdout).trim()));
+    if !target_libdir.map_or(false, |target_libdir| target_libdir.is_dir()) {
+        println!(
+            "cargo:warning=Skipping the {} test subjects, the target is not installed (rustup target add {})",
+            target, target
+        );
+        return false;
+    }
+
+    let linker_installed = match target {
+        "aarch64-unknown-linux-gnu" => succeeds(Command::new("aarch64-linux-gnu-gcc").arg("--version")),
+        "riscv64gc-unknown-linux-gnu" => succeeds(Command::new("riscv64-linux-gnu-gcc").arg("--version")),
+        "i686-unknown-linux-gnu" => links_32_bit(),
+        _ => true,
+    };
+    if !linker_installed {
+        println!("cargo:warning=Skipping the {} test subjects, the linker of the target is not installed", target);
+    }
+    linker_installed
+}
+
+/// Returns `true` if the host compiler can link 32-bit programs.
+fn links_32_bit() -> bool {
+    let out_dir = PathBuf::from(env::var("OUT_DIR").expect("OUT_DIR is not set"));
+    let source = out_dir.join("m32.c");
+    std::fs::write(&source, "int main(void) { return 0; }\n").expect("Could not write 32-bit test program");
+
+    succeeds(Command::new("cc").arg("-m32").arg(&source).arg("-o").arg(out_dir.join("m32")))
+}
+
+/// Returns `true` if `rustc` accepts the flags `flags` of the variant `name`. Prints a warning otherwise.
+fn rustc_supports(name: &str, flags: &str) -> bool {
+    let out_dir = PathBuf::from(env::var("OUT_DIR").expect("OUT_DIR is not set"));
+    let source = out_dir.join("variant.rs");
+    std::fs::write(&source, "fn main() {}\n").expect("Could not write variant test program");
 
-        let subjects_build_status = cargo
+    let supported = succeeds(
+        Command::new("rustc")
+            .args(flags.split_whitespace())
+            .arg("--target")
+            .arg("x86_64-unknown-linux-gnu")
+            .arg("--out-dir")
+            .arg(out_dir.join("variant"))
+            .arg(&source),
+    );
+    if !supported {
+        println!("cargo:warning=Skipping the {} test subjects, rustc does not support {}", name, flags);
+    }
+    supported
+}
+
+/// Returns `true` if `command` runs and exits successfully. Its output is discarded.
+fn succeeds(command: &mut Command) -> bool {
+    command.output().map_or(false, |output| output.status.success())
+}
+
+/// Writes copies of the release builds of `OBJCOPY_SUBJECTS` in `target_dir` with compressed debug sections.
+fn compress_debug_sections(target_dir: &Path) {
+    DEBUG_SECTION_COMPRESSIONS
+        .iter()
+        .filter(|compression| objcopy_supports(compression))
+        .for_each(|compression| {
+            let output_dir = target_dir.join(format!("release-{}", compression));
+            std::fs::create_dir_all(&output_dir).expect("Could not create directory for compressed test subjects");
+
+            OBJCOPY_SUBJECTS.iter().for_each(|subject| {
+                let objcopy_status = Command::new("objcopy")
+                    .arg(format!("--compress-debug-sections={}", compression))
+                    .arg(target_dir.join("release").join(subject))
+                    .arg(output_dir.join(subject))
+                    .status()
+                    .expect("Compressing debug sections of test subjects did not produce any output");
+
+                if !objcopy_status.success() {
+                    panic!("Could not compress debug sections of {} with {}, manual intervention needed", subject, compression);
+                }
+            });
+        });
+}
+
+/// Returns `true` if `objcopy` supports the compression type `compression`. Prints a warning otherwise.
+fn objcopy_supports(compression: &str) -> bool {
+    // The supported types are listed with `--compress-debug-sections`
+    let supported = Command::new("objcopy")
+        .arg("--help")
+        .output()
+        .map_or(false, |output| String::from_utf8_lossy(&output.stdout).contains(compression));
+    if !supported {
+        println!(
+            "cargo:warning=Skipping the {} compressed test subjects, objcopy does not support it",
+            compression
+        );
+    }
+    supported
+}
+
+/// Writes copies of the release builds of `OBJCOPY_SUBJECTS` in `target_dir` without debug sections, to `release-nodebug`.
+fn strip_debug_sections(target_dir: &Path) {
+    let output_dir = target_dir.join("release-nodebug");
+    std::fs::create_dir_all(&output_dir).expect("Could not create directory for stripped test subjects");
+
+    OBJCOPY_SUBJECTS.iter().for_each(|subject| {
+        let objcopy_status = Command::new("objcopy")
+            .arg("--strip-debug")
+            .arg(target_dir.join("release").join(subject))
+            .arg(output_dir.join(subject))
             .status()
-            .expect("Building of test subjects did not produce any output");
+            .expect("Stripping debug sections of test subjects did not produce any output");
 
-        if !subjects_build_status.success() {
-            panic!("Could not build test subjects, manual intervention needed");
+        if !objcopy_status.success() {
+            panic!("Could not strip debug sections of {}, manual intervention needed", subject);
         }
-    })
-}
\ No newline at end of file
+    });
+}
+
+/// Writes copies of the release builds of `OBJCOPY_SUBJECTS` in `target_dir` of which the debug information is moved to
+/// a separate debug file, to `release-separate-debug`:
+/// * `<subject>` refers to its debug file `<subject>.debug` next to it with a `.gnu_debuglink` section.
+/// * `build-id/<subject>` does not refer to its debug file, which is found by the build ID of the binary in
+///   `debug-files/.build-id`.
+/// * `wrong-crc/<subject>` refers to `wrong-crc/<subject>.debug`, which is the debug file of another subject, so its
+///   checksum does not match.
+fn separate_debug_files(target_dir: &Path) {
+    let output_dir = target_dir.join("release-separate-debug");
+    ["build-id", "wrong-crc"].iter().for_each(|directory| {
+        std::fs::create_dir_all(output_dir.join(directory)).expect("Could not create directory for separate debug files");
+    });
+
+    OBJCOPY_SUBJECTS.iter().for_each(|subject| {
+        let binary = target_dir.join("release").join(subject);
+        let debug_file = output_dir.join(format!("{}.debug", subject));
+
+        run_objcopy(&["--only-keep-debug"], &binary, &debug_file);
+        // The checksum of the debug file is stored in the debuglink, next to its file name
+        run_objcopy(
+            &["--strip-debug", &format!("--add-gnu-debuglink={}", debug_file.display())],
+            &binary,
+            &output_dir.join(subject),
+        );
+        run_objcopy(&["--strip-debug"], &binary, &output_dir.join("build-id").join(subject));
+
+        let build_id = read_build_id(&binary);
+        let build_id_dir = output_dir.join("debug-files/.build-id").join(&build_id[..2]);
+        std::fs::create_dir_all(&build_id_dir).expect("Could not create build ID directory for debug files");
+        std::fs::copy(&debug_file, build_id_dir.join(format!("{}.debug", &build_id[2..])))
+            .expect("Could not copy debug file to build ID directory");
+    });
+
+    // The debug files of the subjects are rotated, so each debuglink refers to the debug file of another subject
+    OBJCOPY_SUBJECTS.iter().zip(OBJCOPY_SUBJECTS.iter().cycle().skip(1)).for_each(|(subject, other)| {
+        let wrong_crc_dir = output_dir.join("wrong-crc");
+        std::fs::copy(output_dir.join(subject), wrong_crc_dir.join(subject))
+            .expect("Could not copy stripped test subject");
+        std::fs::copy(
+            output_dir.join(format!("{}.debug", other)),
+            wrong_crc_dir.join(format!("{}.debug", subject)),
+        ).expect("Could not copy debug file of other test subject");
+    });
+}
+
+/// Runs `objcopy` with the arguments `args` on `input`, writing `output`.
+fn run_objcopy(args: &[&str], input: &Path, output: &Path) {
+    let objcopy_status = Command::new("objcopy")
+        .args(args)
+        .arg(input)
+        .arg(output)
+        .status()
+        .expect("Running objcopy on test subjects did not produce any output");
+
+    if !objcopy_status.success() {
+        panic!("Could not run objcopy {:?} on {}, manual intervention needed", args, input.display());
+    }
+}
+
+/// Returns the build ID of the binary at `binary` as a hexadecimal string. The x86_64 subjects are linked with
+/// `--build-id` (see `test_subjects/.cargo/config.toml`).
+fn read_build_id(binary: &Path) -> String {
+    let out_dir = PathBuf::from(env::var("OUT_DIR").expect("OUT_DIR is not set"));
+    let note_path = out_dir.join("build-id-note");
+    run_objcopy(
+        &["--dump-section", &format!(".note.gnu.build-id={}", note_path.display())],
+        binary,
+        &out_dir.join("build-id-binary"),
+    );
+
+    // The note header consists of the sizes of the name (`GNU\0`) and of the build ID, and the note type
+    let note = std::fs::read(&note_path).expect("Could not read build ID note");
+    let word = |offset: usize| u32::from_le_bytes([note[offset], note[offset + 1], note[offset + 2], note[offset + 3]]);
+    let build_id_start = 12 + (word(0) as usize + 3) / 4 * 4;
+
+    note[build_id_start..build_id_start + word(4) as usize]
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
@@ -6,32 +6,107 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+use std::env;
 use std::fs::File;
 use std::io::Error;
 use std::io::Read;
 use std::path::Path;
 use std::path::PathBuf;
 
+#[derive(Debug)]
 pub enum TestSubjectType {
     Debug,
     Release,
     DebugStableRustc,
     ReleaseStableRustc,
+    DebugAArch64,
+    ReleaseAArch64,
+    DebugRiscV64,
+    ReleaseRiscV64,
+    DebugI686,
+    ReleaseI686,
+    /// WebAssembly modules, of which the file names end with `.wasm`
+    DebugWasm32,
+    ReleaseWasm32,
+    /// x86_64 release builds with debug sections compressed by `objcopy --compress-debug-sections`
+    ReleaseZlib,
+    ReleaseZlibGnu,
+    ReleaseZstd,
+    /// x86_64 release builds of which the debug sections are removed (`objcopy --strip-debug`)
+    ReleaseNoDebugInfo,
+    /// x86_64 release builds of which the debug information is moved to a separate debug file, which is found by its
+    /// `.gnu_debuglink` or build ID. See `separate_debug_files` in `build.rs` for the layout.
+    ReleaseSeparateDebug,
+    /// x86_64 debug builds with `-C split-debuginfo=unpacked` (`.dwo` files), `-C split-debuginfo=packed` (a `.dwp`
+    /// package) and DWARF 5
+    DebugSplitUnpacked,
+    DebugSplitPacked,
+    DebugDwarf5,
+    /// x86_64 debug builds with `-C prefer-dynamic`, which are dynamically linked against `libstd-*.so`
+    DebugPreferDynamic,
+    /// Firmware in `test_subjects_embedded` for ARM Cortex-M cores, which runs without an operating system
+    DebugThumbv7m,
+    /// Firmware in `test_subjects_embedded` for rv32imac cores
+    DebugRiscV32,
+    /// Firmware in `test_subjects_embedded` for big endian ARM Cortex-R cores
+    DebugArmebv7r,
 }
 
 impl TestSubjectType {
+    /// Returns `true` if the test subjects of this type are built. Only the x86_64 debug and release subjects are
+    /// always built, the build script skips the others if their toolchain is not installed (see `build.rs`).
+    pub fn is_built(&self) -> bool {
+        get_test_subjects_dir(self).is_dir()
+    }
+
+    /// Returns `true` if the test subjects of this type must be built on CI, where the toolchains of all targets are
+    /// installed. The zstd compressed subjects depend on the version of binutils of the CI image instead.
+    fn is_required_on_ci(&self) -> bool {
+        match *self {
+            TestSubjectType::ReleaseZstd => false,
+            _ => true,
+        }
+    }
+
     fn get_test_subject_path(&self) -> &str {
         match *self {
             TestSubjectType::Debug => "test_subjects/target/x86_64-unknown-linux-gnu/debug",
             TestSubjectType::Release => "test_subjects/target/x86_64-unknown-linux-gnu/release",
             TestSubjectType::DebugStableRustc => "test_subjects_stable_rustc/target/x86_64-unknown-linux-gnu/debug",
             TestSubjectType::ReleaseStableRustc => "test_subjects_stable_rustc/target/x86_64-unknown-linux-gnu/release",
+            TestSubjectType::DebugAArch64 => "test_subjects/target/aarch64-unknown-linux-gnu/debug",
+            TestSubjectType::ReleaseAArch64 => "test_subjects/target/aarch64-unknown-linux-gnu/release",
+            TestSubjectType::DebugRiscV64 => "test_subjects/target/riscv64gc-unknown-linux-gnu/debug",
+            TestSubjectType::ReleaseRiscV64 => "test_subjects/target/riscv64gc-unknown-linux-gnu/release",
+            TestSubjectType::DebugI686 => "test_subjects/target/i686-unknown-linux-gnu/debug",
+            TestSubjectType::ReleaseI686 => "test_subjects/target/i686-unknown-linux-gnu/release",
+            TestSubjectType::DebugWasm32 => "test_subjects/target/wasm32-wasip1/debug",
+            TestSubjectType::ReleaseWasm32 => "test_subjects/target/wasm32-wasip1/release",
+            TestSubjectType::ReleaseZlib => "test_subjects/target/x86_64-unknown-linux-gnu/release-zlib",
+            TestSubjectType::ReleaseZlibGnu => "test_subjects/target/x86_64-unknown-linux-gnu/release-zlib-gnu",
+            TestSubjectType::ReleaseZstd => "test_subjects/target/x86_64-unknown-linux-gnu/release-zstd",
+            TestSubjectType::ReleaseNoDebugInfo => "test_subjects/target/x86_64-unknown-linux-gnu/release-nodebug",
+            TestSubjectType::ReleaseSeparateDebug => {
+                "test_subjects/target/x86_64-unknown-linux-gnu/release-separate-debug"
+            }
+            TestSubjectType::DebugSplitUnpacked => "test_subjects/target/split-unpacked/x86_64-unknown-linux-gnu/debug",
+            TestSubjectType::DebugSplitPacked => "test_subjects/target/split-packed/x86_64-unknown-linux-gnu/debug",
+            TestSubjectType::DebugDwarf5 => "test_subjects/target/dwarf5/x86_64-unknown-linux-gnu/debug",
+            TestSubjectType::DebugPreferDynamic => "test_subjects/target/prefer-dynamic/x86_64-unknown-linux-gnu/debug",
+            TestSubjectType::DebugThumbv7m => "test_subjects_embedded/target/thumbv7m-none-eabi/debug",
+            TestSubjectType::DebugRiscV32 => "test_subjects_embedded/target/riscv32imac-unknown-none-elf/debug",
+            TestSubjectType::DebugArmebv7r => "test_subjects_embedded/target/armebv7r-none-eabi/debug",
         }
     }
 }
 
 /// Prepare the path of the executable of test subject `subject`
 pub fn get_test_subject_path(subject: &str, subject_type: &TestSubjectType) -> PathBuf {
+    Path::join(get_test_subjects_dir(subject_type).as_path(), subject)
+}
+
+/// Returns the directory containing the test subjects of `subject_type`
+fn get_test_subjects_dir(subject_type: &TestSubjectType) -> PathBuf {
     let current_dir = Path::new(env!("CARGO_MANIFEST_DIR")).to_path_buf();
     let current_grandparent = current_dir
         .parent()
@@ -39,9 +114,24 @@ pub fn get_test_subject_path(subject: &str, subject_type: &TestSubjectType) -> P
         .parent()
         .expect("Current executable path has no grandparent");
 
-    let executable_path = Path::join(current_grandparent, subject_type.get_test_subject_path());
+    Path::join(current_grandparent, subject_type.get_test_subject_path())
+}
 
-    Path::join(executable_path.as_path(), subject)
+/// Returns `true`, after printing a warning, if the test subjects of `subject_type` were skipped by the build script
+/// because their toolchain is not installed. Tests of these subjects should return without testing anything.
+///
+/// # Panics
+/// If the `CI` environment variable is set and the subjects are required on CI, so a broken cross toolchain fails the
+/// tests instead of skipping them.
+pub fn skip_unbuilt(subject_type: &TestSubjectType) -> bool {
+    if subject_type.is_built() {
+        return false;
+    }
+    if env::var_os("CI").is_some() && subject_type.is_required_on_ci() {
+        panic!("The {:?} test subjects are not built, which is required on CI", subject_type);
+    }
+    eprintln!("Skipping test, the {:?} test subjects are not built", subject_type);
+    true
 }
 
 /// Load a test subject executable. Must be one of the crates in `test_subjects`
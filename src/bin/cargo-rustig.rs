@@ -0,0 +1,77 @@
+// (C) COPYRIGHT 2018 TECHNOLUTION BV, GOUDA NL
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! This is the `cargo rustig` subcommand, which builds the binaries of a Cargo project and checks each of them for
+//! paths to panic. It accepts the package and target selection arguments of `cargo build`, and the options of
+//! `rustig`, except `--binary`.
+
+use rustig::cmd_args;
+
+use std::process;
+use std::result::Result::Ok;
+
+/// Cargo subcommand entrypoint
+pub fn main() {
+    // Parse cmd arguments, and build the project
+    let (cmd_args, output_options) = match cmd_args::get_cargo_args() {
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(101);
+        }
+        Ok(r) => r,
+    };
+
+    // Execute analysis of each binary
+    process::exit(rustig::run_analyses(&cmd_args, &output_options));
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    /// Test that the subcommand is only accepted as `cargo rustig`, as cargo passes the subcommand name
+    #[test]
+    fn test_subcommand_name_required() {
+        assert_cli::Assert::cargo_binary("cargo-rustig")
+            .stderr().contains("USAGE:")
+            .fails_with(101)
+            .unwrap();
+
+        assert_cli::Assert::cargo_binary("cargo-rustig")
+            .with_args(&["rustig", "--help"])
+            .stderr().contains("--release")
+            .succeeds()
+            .unwrap();
+    }
+
+    /// Test that each binary of a workspace is built, and analyzed in its own section of the output
+    #[test]
+    fn test_workspace_binaries_analyzed() {
+        let workspace_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("test_subjects");
+        let manifest_path = workspace_dir.join("Cargo.toml");
+        let binary_dir = workspace_dir.join("target/debug");
+        let header = |binary: &str| format!("Analyzing {}\n", binary_dir.join(binary).display());
+
+        assert_cli::Assert::cargo_binary("cargo-rustig")
+            .with_args(&[
+                "rustig",
+                "--manifest-path",
+                &manifest_path.to_string_lossy(),
+                "-p",
+                "hello_world",
+                "-p",
+                "unwrap",
+                "-c",
+                "unwrap",
+            ])
+            .stdout().contains(header("hello_world"))
+            .stdout().contains(header("unwrap"))
+            .fails_with(1)
+            .unwrap();
+    }
+}
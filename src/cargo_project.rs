@@ -0,0 +1,222 @@
+// (C) COPYRIGHT 2018 TECHNOLUTION BV, GOUDA NL
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Building the binaries of a Cargo project (`--manifest-path`, or `cargo rustig`), which are analyzed instead of a
+//! single binary.
+//!
+//! The project is built with `cargo build`, with profile overrides that guarantee the binaries contain debug
+//! information. The executables (or, in library mode, the shared libraries) are read from the JSON messages of the
+//! build, and the crates of the workspace members from `cargo metadata`.
+
+use crate::errors::*;
+
+use serde_derive::Deserialize;
+
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+use std::process::Stdio;
+
+/// Configuration overrides passed to `cargo build`, so the binaries contain full debug information, even if the
+/// project disables it or strips its binaries.
+const DEBUG_INFO_OVERRIDES: &[&str] = &[
+    "profile.dev.debug=true",
+    "profile.dev.strip=false",
+    "profile.release.debug=true",
+    "profile.release.strip=false",
+];
+
+/// Binaries and workspace members of a Cargo project that was built.
+#[derive(Debug, Clone)]
+pub struct CargoProject {
+    /// Paths of the executables produced by the build, or of the shared libraries in library mode
+    pub binaries: Vec<PathBuf>,
+    /// Names of the crates of the workspace members, which are the default analysis target
+    pub crate_names: Vec<String>,
+    /// Path of the `Cargo.lock` file of the workspace, if it exists
+    pub cargo_lock_path: Option<PathBuf>,
+}
+
+/// Output of `cargo metadata`, of which only the used fields are deserialized
+#[derive(Deserialize)]
+struct Metadata {
+    packages: Vec<Package>,
+    workspace_members: Vec<String>,
+    workspace_root: PathBuf,
+}
+
+#[derive(Deserialize)]
+struct Package {
+    id: String,
+    name: String,
+    manifest_path: PathBuf,
+}
+
+/// JSON message of `cargo build --message-format=json`
+#[derive(Deserialize)]
+struct BuildMessage {
+    reason: String,
+    target: Option<BuildTarget>,
+    #[serde(default)]
+    filenames: Vec<PathBuf>,
+    executable: Option<PathBuf>,
+}
+
+#[derive(Deserialize)]
+struct BuildTarget {
+    kind: Vec<String>,
+}
+
+/// Builds the Cargo project of the manifest at `manifest_path` (or of the current directory), and returns its
+/// executables and workspace members. `build_args` are passed to `cargo build` to select the packages and targets to
+/// build (e.g. `--bin`, `--release`). If `libraries` is `true`, the shared libraries (`cdylib` and `dylib` targets)
+/// are returned instead of the executables.
+pub fn build_project(manifest_path: Option<&Path>, build_args: &[String], libraries: bool) -> Result<CargoProject> {
+    let mut metadata = cargo();
+    metadata.args(["metadata", "--format-version", "1", "--no-deps"]);
+    let mut build = cargo();
+    // Diagnostics are rendered to the standard error by cargo itself
+    build.args(["build", "--message-format=json-render-diagnostics"]).args(build_args);
+    DEBUG_INFO_OVERRIDES.iter().for_each(|config| {
+        build.arg("--config").arg(config);
+    });
+    if let Some(manifest_path) = manifest_path {
+        metadata.arg("--manifest-path").arg(manifest_path);
+        build.arg("--manifest-path").arg(manifest_path);
+    }
+
+    let metadata: Metadata = serde_json::from_slice(&run_cargo(&mut metadata, "metadata")?)
+        .chain_err(|| ErrorKind::Cargo("metadata".to_string(), Some("Invalid output".to_string())))?;
+    let binaries = parse_build_messages(&run_cargo(&mut build, "build")?, libraries);
+
+    if binaries.is_empty() {
+        let kind = if libraries { "shared libraries" } else { "executables" };
+        bail!(ErrorKind::Cargo("build".to_string(), Some(format!("No {} were built", kind))));
+    }
+
+    let cargo_lock_path = metadata.workspace_root.join("Cargo.lock");
+    Ok(CargoProject {
+        binaries,
+        crate_names: workspace_crate_names(&metadata),
+        cargo_lock_path: Some(cargo_lock_path).filter(|path| path.is_file()),
+    })
+}
+
+/// Returns the cargo command, which is the cargo that runs rustig if it is run by cargo.
+fn cargo() -> Command {
+    Command::new(std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into()))
+}
+
+/// Runs `command`, of which the standard error is shown to the user, and returns its standard output.
+fn run_cargo(command: &mut Command, name: &str) -> Result<Vec<u8>> {
+    let output = command
+        .stderr(Stdio::inherit())
+        .output()
+        .chain_err(|| ErrorKind::Cargo(name.to_string(), None))?;
+
+    if !output.status.success() {
+        bail!(ErrorKind::Cargo(name.to_string(), Some(output.status.to_string())));
+    }
+    Ok(output.stdout)
+}
+
+/// Returns the paths of the executables in the output of `cargo build --message-format=json`. These are the binaries,
+/// and with `--all-targets` also the examples, tests, benchmarks and unit test harnesses.
+/// If `libraries` is `true`, the paths of the shared objects of `cdylib` and `dylib` targets are returned instead.
+fn parse_build_messages(output: &[u8], libraries: bool) -> Vec<PathBuf> {
+    output
+        .split(|byte| *byte == b'\n')
+        .filter_map(|line| serde_json::from_slice::<BuildMessage>(line).ok())
+        .filter(|message| message.reason == "compiler-artifact")
+        .flat_map(|message| match libraries {
+            true => {
+                let is_shared_library = message
+                    .target
+                    .map(|target| target.kind.iter().any(|kind| kind == "cdylib" || kind == "dylib"))
+                    .unwrap_or(false);
+                message
+                    .filenames
+                    .into_iter()
+                    .filter(|filename| is_shared_library && filename.extension().is_some_and(|ext| ext == "so"))
+                    .collect::<Vec<_>>()
+            }
+            false => message.executable.into_iter().collect(),
+        })
+        .collect()
+}
+
+/// Returns the names of the crates of the workspace members.
+///
+/// The crate of a workspace member is named after its directory if it is read from the debug information, and after
+/// its crate name (with underscores) if it is read from the symbol table, so all of these names are returned.
+fn workspace_crate_names(metadata: &Metadata) -> Vec<String> {
+    metadata
+        .packages
+        .iter()
+        .filter(|package| metadata.workspace_members.contains(&package.id))
+        .flat_map(|package| {
+            let directory_name = package
+                .manifest_path
+                .parent()
+                .and_then(Path::file_name)
+                .map(|name| name.to_string_lossy().to_string());
+
+            vec![package.name.clone(), package.name.replace('-', "_")]
+                .into_iter()
+                .chain(directory_name)
+        })
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Verify the executables, or the shared libraries, are read from the build messages
+    #[test]
+    fn test_parse_build_messages() {
+        let output = br#"{"reason":"compiler-artifact","package_id":"path+file:///ws/tool#0.1.0","target":{"kind":["lib"],"name":"tool"},"profile":{"test":false},"executable":null,"fresh":false}
+{"reason":"compiler-artifact","package_id":"path+file:///ws/tool#0.1.0","target":{"kind":["bin"],"name":"tool-cli"},"profile":{"test":false},"executable":"/ws/target/debug/tool-cli","fresh":false}
+{"reason":"compiler-artifact","package_id":"path+file:///ws/tool#0.1.0","target":{"kind":["lib"],"name":"tool"},"profile":{"test":true},"executable":"/ws/target/debug/deps/tool-0123456789abcdef","fresh":false}
+{"reason":"compiler-artifact","package_id":"path+file:///ws/tool-ffi#0.1.0","target":{"kind":["cdylib","rlib"],"name":"tool_ffi"},"profile":{"test":false},"filenames":["/ws/target/debug/libtool_ffi.so","/ws/target/debug/libtool_ffi.rlib"],"executable":null,"fresh":false}
+{"reason":"build-finished","success":true}
+"#;
+
+        assert_eq!(
+            parse_build_messages(output, false),
+            vec![
+                PathBuf::from("/ws/target/debug/tool-cli"),
+                PathBuf::from("/ws/target/debug/deps/tool-0123456789abcdef"),
+            ]
+        );
+        assert_eq!(
+            parse_build_messages(output, true),
+            vec![PathBuf::from("/ws/target/debug/libtool_ffi.so")]
+        );
+    }
+
+    /// Verify the crates of the workspace members are named after their package and directory
+    #[test]
+    fn test_workspace_crate_names() {
+        let metadata: Metadata = serde_json::from_str(
+            r#"{
+                "packages": [
+                    {"id": "path+file:///ws/crates/tool-core#0.1.0", "name": "tool-core", "manifest_path": "/ws/crates/tool-core/Cargo.toml"},
+                    {"id": "path+file:///ws#tool@0.1.0", "name": "tool", "manifest_path": "/ws/Cargo.toml"}
+                ],
+                "workspace_members": ["path+file:///ws/crates/tool-core#0.1.0", "path+file:///ws#tool@0.1.0"],
+                "workspace_root": "/ws"
+            }"#,
+        ).unwrap();
+
+        assert_eq!(workspace_crate_names(&metadata), vec!["tool", "tool-core", "tool_core", "ws"]);
+    }
+}
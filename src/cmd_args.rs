@@ -6,19 +6,24 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+use crate::cargo_project;
+use crate::cargo_project::CargoProject;
 use crate::config_file::parse_config;
 use crate::errors::*;
 
 use panic_analysis::AnalysisOptions;
 
 use clap::App;
+use clap::AppSettings;
 use clap::Arg;
 use clap::ArgMatches;
 use clap::ErrorKind;
+use clap::SubCommand;
 
 use crate::output::OutputOptions;
 
 use std::option::Option::Some;
+use std::path::Path;
 
 static CALL_GRAPH_BUILD_MODES: [&str; 2] = ["full", "filtered"];
 
@@ -29,10 +34,46 @@ fn parse_multiple_args(cmd_matches: &ArgMatches, name: &str) -> Vec<String> {
     }
 }
 
-pub fn get_args() -> Result<(AnalysisOptions, OutputOptions)> {
-    let cmd_matches_opt = get_app_definition().get_matches_safe();
+/// Returns the options of the analyses to run, one for each binary, and the output options.
+pub fn get_args() -> Result<(Vec<AnalysisOptions>, OutputOptions)> {
+    let cmd_matches = get_matches(get_app_definition());
+    let (rustig_options, output_options) = get_analysis_options(&cmd_matches)?;
 
-    let cmd_matches = match cmd_matches_opt {
+    // Either `binary` or `manifest_path` is required by clap
+    let rustig_options = match cmd_matches.value_of("manifest_path") {
+        Some(manifest_path) => {
+            let project =
+                cargo_project::build_project(Some(Path::new(manifest_path)), &[], rustig_options.library_mode)?;
+            project_analysis_options(&rustig_options, &project)
+        }
+        None => vec![rustig_options],
+    };
+
+    Ok((rustig_options, output_options))
+}
+
+/// Returns the options of the analyses of `cargo rustig`, one for each binary of the project that was built, and the
+/// output options.
+pub fn get_cargo_args() -> Result<(Vec<AnalysisOptions>, OutputOptions)> {
+    let cmd_matches = get_matches(get_cargo_app_definition());
+    // Cargo passes the name of the subcommand as first argument, which is required by clap
+    let cmd_matches = cmd_matches
+        .subcommand_matches("rustig")
+        .expect("No rustig subcommand");
+    let (rustig_options, output_options) = get_analysis_options(cmd_matches)?;
+
+    let project = cargo_project::build_project(
+        cmd_matches.value_of("manifest_path").map(Path::new),
+        &get_cargo_build_args(cmd_matches),
+        rustig_options.library_mode,
+    )?;
+
+    Ok((project_analysis_options(&rustig_options, &project), output_options))
+}
+
+/// Parses the command line arguments of `app`. Exits if they are invalid, or if the help or version is displayed.
+fn get_matches<'a>(app: App<'a, '_>) -> ArgMatches<'a> {
+    match app.get_matches_safe() {
         Ok(matches) => matches,
         Err(error) => {
             eprintln!("{}", error.message);
@@ -42,11 +83,18 @@ pub fn get_args() -> Result<(AnalysisOptions, OutputOptions)> {
                 _ => std::process::exit(101),
             }
         }
-    };
+    }
+}
+
+/// Returns the analysis and output options of the arguments in `analysis_args`.
+fn get_analysis_options(cmd_matches: &ArgMatches) -> Result<(AnalysisOptions, OutputOptions)> {
+    let crate_names = parse_multiple_args(cmd_matches, "crates");
 
-    let crate_names = parse_multiple_args(&cmd_matches, "crates");
+    let callgraph_outputs = parse_multiple_args(cmd_matches, "callgraph");
 
-    let callgraph_outputs = parse_multiple_args(&cmd_matches, "callgraph");
+    let library_paths = parse_multiple_args(cmd_matches, "library_path");
+
+    let debug_file_directories = parse_multiple_args(cmd_matches, "debug_file_directory");
 
     let config_opt = cmd_matches.value_of("config");
     let required = config_opt.is_some();
@@ -54,12 +102,17 @@ pub fn get_args() -> Result<(AnalysisOptions, OutputOptions)> {
     let file_options = parse_config(config_opt.unwrap_or("rustig.toml"), required)?;
 
     let rustig_options = AnalysisOptions {
-        binary_path: Some(cmd_matches.value_of("binary").unwrap().to_string()), // Required by clap, can safely be unwrapped.
+        binary_path: cmd_matches.value_of("binary").map(str::to_string),
         crate_names,
         whitelisted_functions: file_options.function_whitelists,
         output_filtered_callgraph: callgraph_outputs.iter().any(|output| output == "filtered"),
         output_full_callgraph: callgraph_outputs.iter().any(|output| output == "full"),
         full_crate_analysis: cmd_matches.is_present("full_crate_analysis"),
+        precise_dynamic_dispatch: cmd_matches.is_present("precise_dynamic_dispatch"),
+        library_paths,
+        debug_file_directories,
+        cargo_lock_path: cmd_matches.value_of("cargo_lock").map(str::to_string),
+        library_mode: cmd_matches.is_present("library"),
     };
 
     let output_options = OutputOptions {
@@ -71,74 +124,196 @@ pub fn get_args() -> Result<(AnalysisOptions, OutputOptions)> {
     Ok((rustig_options, output_options))
 }
 
+/// Returns the arguments of `cargo build` for the package and target selection arguments of `cargo rustig`.
+fn get_cargo_build_args(cmd_matches: &ArgMatches) -> Vec<String> {
+    let mut build_args = vec![];
+
+    ["package", "bin", "features", "target"].iter().for_each(|name| {
+        parse_multiple_args(cmd_matches, name).into_iter().for_each(|value| {
+            build_args.push(format!("--{}", name));
+            build_args.push(value);
+        })
+    });
+    ["all_targets", "release"]
+        .iter()
+        .filter(|name| cmd_matches.is_present(name))
+        .for_each(|name| build_args.push(format!("--{}", name.replace('_', "-"))));
+
+    build_args
+}
+
+/// Returns the options of the analysis of each binary of `project`, based on `options`.
+/// The workspace members are the analysis target, unless crates are passed explicitly, and the versions of crates are
+/// read from the `Cargo.lock` file of the workspace, unless another lock file is passed.
+fn project_analysis_options(options: &AnalysisOptions, project: &CargoProject) -> Vec<AnalysisOptions> {
+    project
+        .binaries
+        .iter()
+        .map(|binary| AnalysisOptions {
+            binary_path: Some(binary.to_string_lossy().to_string()),
+            crate_names: match options.crate_names.is_empty() {
+                true => project.crate_names.clone(),
+                false => options.crate_names.clone(),
+            },
+            cargo_lock_path: options.cargo_lock_path.clone().or_else(|| {
+                project
+                    .cargo_lock_path
+                    .as_ref()
+                    .map(|path| path.to_string_lossy().to_string())
+            }),
+            ..options.clone()
+        })
+        .collect()
+}
+
 fn get_app_definition<'a, 'b>() -> App<'a, 'b> {
     App::new("rustic - a checking tool for sources of panic in rust programs")
         // Argument accepting the path to the binary to analyze
-        // Note that this is a parameter, since we might build from Cargo projects in the future
-        // In that case this argument will not be required anymore.
-        // To maintain compatibility, we decided to make it an parameter immediately
+        // Instead of a binary, a Cargo project can be passed, of which all binaries are built and analyzed
         .arg(
             Arg::with_name("binary")
                 .short("b")
                 .long("binary")
                 .value_name("FILE")
                 .help("Path to binary file to analyze")
-                .required(true) // Needs to be removed if we decide to compile our own binaries in the future.
+                .required_unless("manifest_path")
+                .conflicts_with("manifest_path")
                 .takes_value(true),
         )
+        .arg(manifest_path_arg())
+        .args(&analysis_args())
+}
+
+/// Definition of `cargo rustig`, which is run by cargo as `cargo-rustig rustig [ARGS]`.
+fn get_cargo_app_definition<'a, 'b>() -> App<'a, 'b> {
+    App::new("cargo-rustig")
+        .bin_name("cargo")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(
+            SubCommand::with_name("rustig")
+                .about("Builds the binaries of a Cargo project, and checks them for sources of panic")
+                .arg(manifest_path_arg())
+                .arg(
+                    Arg::with_name("package")
+                        .multiple(true)
+                        .number_of_values(1)
+                        .short("p")
+                        .long("package")
+                        .value_name("SPEC")
+                        .help("Package to build and analyze"),
+                )
+                .arg(
+                    Arg::with_name("bin")
+                        .multiple(true)
+                        .number_of_values(1)
+                        .long("bin")
+                        .value_name("NAME")
+                        .help("Binary to build and analyze"),
+                )
+                .arg(
+                    Arg::with_name("all_targets")
+                        .long("all-targets")
+                        .help("Build and analyze all targets, including examples, tests and benchmarks"),
+                )
+                .arg(
+                    Arg::with_name("features")
+                        .multiple(true)
+                        .number_of_values(1)
+                        .long("features")
+                        .value_name("FEATURES")
+                        .help("Space or comma separated list of features to activate"),
+                )
+                .arg(
+                    Arg::with_name("release")
+                        .long("release")
+                        .help("Build and analyze the binaries of the release profile"),
+                )
+                .arg(
+                    Arg::with_name("target")
+                        .long("target")
+                        .value_name("TRIPLE")
+                        .help("Build and analyze the binaries for the target triple")
+                        .takes_value(true),
+                )
+                .args(&analysis_args()),
+        )
+}
+
+fn manifest_path_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("manifest_path")
+        .long("manifest-path")
+        .value_name("PATH")
+        .help("Path to the Cargo.toml of a project to build and analyze. All binaries of the project are built with debug information and analyzed, with the crates of the workspace members as analysis target")
+        .takes_value(true)
+}
+
+/// Arguments of the analysis, which are shared by `rustig` and `cargo rustig`
+fn analysis_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
         // Right now, crates are printed in the output
         // Maybe we should once make a subcommand that prints all crates and versions
-        .arg(
-            Arg::with_name("crates")
-                .multiple(true)
-                .short("c")
-                .long("crates")
-                .value_name("CRATES")
-                .help("Names of the compilation unit which should be analyzed. If not provided, the crate of the entry points will be used"),
-        )
-        .arg(
-            Arg::with_name("verbose")
-                .short("v")
-                .long("verbose")
-                .conflicts_with("silent")
-                .help("Turn on verbose mode for full stack traces of panic calls"),
-        )
-        .arg(
-            Arg::with_name("json-stream")
-                .long("json-stream")
-                .conflicts_with("silent")
-                .help("Output full stack traces of panic calls into JSON"),
-        )
-        .arg(
-            Arg::with_name("config")
-                .long("config")
-                .help("Path to rustig! configuration file (default: rustig.toml)")
-                .takes_value(true),
-        )
-        .arg(
-            Arg::with_name("full_crate_analysis")
-                .short("f")
-                .long("full-crate-analysis")
-                .help("Analyze all functions in analysis target, instead of entry points only"),
-        )
-        .arg(
-            Arg::with_name("silent")
-                .short("s")
-                .long("silent")
-                .conflicts_with("verbose")
-                .conflicts_with("json")
-                .help("Turn on silent mode to not print anything"),
-        )
-        .arg(
-            Arg::with_name("callgraph")
-                .multiple(true)
-                .min_values(1)
-                .value_name("CALLGRAPH")
-                .long("callgraph")
-                .short("g")
-                .help("Write a callgraph of the given binary to a file. The output filename will be: `rustig-callgraph-{projectname}-{type}`, where `type` is either `full` or `filtered`. The full callgraph contains all function calls that are detected by rustig!, while filtered callgraph only contains paths that possibly lead to panic calls")
-                .possible_values(&CALL_GRAPH_BUILD_MODES),
-        )
+        Arg::with_name("crates")
+            .multiple(true)
+            .short("c")
+            .long("crates")
+            .value_name("CRATES")
+            .help("Names of the compilation unit which should be analyzed. If not provided, the crate of the entry points will be used"),
+        Arg::with_name("verbose")
+            .short("v")
+            .long("verbose")
+            .conflicts_with("silent")
+            .help("Turn on verbose mode for full stack traces of panic calls"),
+        Arg::with_name("json-stream")
+            .long("json-stream")
+            .conflicts_with("silent")
+            .help("Output full stack traces of panic calls into JSON"),
+        Arg::with_name("config")
+            .long("config")
+            .help("Path to rustig! configuration file (default: rustig.toml)")
+            .takes_value(true),
+        Arg::with_name("full_crate_analysis")
+            .short("f")
+            .long("full-crate-analysis")
+            .help("Analyze all functions in analysis target, instead of entry points only"),
+        Arg::with_name("library")
+            .long("library")
+            .help("Analyze a shared library (e.g. a cdylib) instead of an executable. The exported functions are the entry points, and panics are reported per exported function"),
+        Arg::with_name("precise_dynamic_dispatch")
+            .long("precise-dynamic-dispatch")
+            .help("Resolve calls through a vtable to the called function where possible, instead of assuming all functions in the vtable are called"),
+        Arg::with_name("library_path")
+            .multiple(true)
+            .number_of_values(1)
+            .short("L")
+            .long("library-path")
+            .value_name("DIR")
+            .help("Directory in which the shared libraries the binary is dynamically linked against are searched. The call graphs of the libraries that are found are joined with the call graph of the binary"),
+        Arg::with_name("debug_file_directory")
+            .multiple(true)
+            .number_of_values(1)
+            .long("debug-file-directory")
+            .value_name("DIR")
+            .help("Directory in which the separate debug files of stripped binaries are searched, by build ID or .gnu_debuglink, before /usr/lib/debug"),
+        Arg::with_name("cargo_lock")
+            .long("cargo-lock")
+            .value_name("FILE")
+            .help("Path to the Cargo.lock file of the project the binary is built from. Used to determine the versions of crates of which the version is not known from their source directory (e.g. workspace members and git dependencies)")
+            .takes_value(true),
+        Arg::with_name("silent")
+            .short("s")
+            .long("silent")
+            .conflicts_with("verbose")
+            .conflicts_with("json")
+            .help("Turn on silent mode to not print anything"),
+        Arg::with_name("callgraph")
+            .multiple(true)
+            .min_values(1)
+            .value_name("CALLGRAPH")
+            .long("callgraph")
+            .short("g")
+            .help("Write a callgraph of the given binary to a file. The output filename will be: `rustig-callgraph-{projectname}-{type}`, where `type` is either `full` or `filtered`. The full callgraph contains all function calls that are detected by rustig!, while filtered callgraph only contains paths that possibly lead to panic calls")
+            .possible_values(&CALL_GRAPH_BUILD_MODES),
+    ]
 }
 
 #[cfg(test)]
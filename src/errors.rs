@@ -1,6 +1,7 @@
 #![allow(unexpected_cfgs)]
 
 use error_chain::error_chain;
+pub use error_chain::bail;
 
 error_chain!{
     types {
@@ -12,5 +13,9 @@ error_chain!{
             description("Config file not found")
             display("Unable to read config file `{}`{}", path, reason.as_ref().map(|x| format!(": {}", x)).unwrap_or_else(|| "".to_string()))
         }
+        Cargo(command: String, reason: Option<String>) {
+            description("Cargo command failed")
+            display("`cargo {}` failed{}", command, reason.as_ref().map(|x| format!(": {}", x)).unwrap_or_else(|| "".to_string()))
+        }
     }
 }
\ No newline at end of file
@@ -0,0 +1,90 @@
+// (C) COPYRIGHT 2018 TECHNOLUTION BV, GOUDA NL
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Implementation of the `rustig` and `cargo rustig` command-line interfaces to the panic_analysis crate.
+
+#![recursion_limit="128"]
+
+pub mod cargo_project;
+pub mod cmd_args;
+mod config_file;
+pub mod output;
+
+pub mod errors;
+
+use crate::output::OutputOptions;
+
+use panic_analysis::AnalysisOptions;
+
+/// Analyzes each binary in `analyses`, and prints the results in the format specified by `output_options`.
+///
+/// Returns the exit code of the command-line interfaces: 1 if a panic path is found in any of the binaries, which
+/// enables integration with CI tools, or 101 if a binary could not be analyzed. The other binaries are still analyzed
+/// if one of them could not be, of which the error is printed under its header.
+pub fn run_analyses(analyses: &[AnalysisOptions], output_options: &OutputOptions) -> i32 {
+    let mut panics_found = false;
+    let mut analysis_failed = false;
+    for options in analyses {
+        if analyses.len() > 1 {
+            output::print_binary_header(output_options, options.binary_path.as_deref().unwrap_or_default());
+        }
+
+        match panic_analysis::find_panics(options) {
+            Err(e) => {
+                eprintln!("{}", e);
+                analysis_failed = true;
+            }
+            Ok(collection) => {
+                output::print_results(output_options, &collection);
+                panics_found |= !collection.calls.is_empty();
+            }
+        }
+    }
+
+    if analysis_failed {
+        101
+    } else if panics_found {
+        1
+    } else {
+        0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Test that a binary that could not be analyzed determines the exit code, also if panics are found in the binaries
+    /// analyzed after it
+    #[test]
+    fn test_analysis_failure_exit_code() {
+        let binary_path = |subject: &str| {
+            let path = test_common::get_test_subject_path(subject, &test_common::TestSubjectType::Debug);
+            Some(path.to_string_lossy().to_string())
+        };
+        let analyses = vec![
+            AnalysisOptions {
+                binary_path: binary_path("nonexistent_subject"),
+                ..Default::default()
+            },
+            AnalysisOptions {
+                binary_path: binary_path("lib_calls"),
+                crate_names: vec!["lib_calls".to_string()],
+                ..Default::default()
+            },
+        ];
+        let output_options = OutputOptions {
+            silent: true,
+            verbose: false,
+            json: false,
+        };
+
+        assert_eq!(run_analyses(&analyses, &output_options), 101);
+        assert_eq!(run_analyses(&analyses[1..], &output_options), 1);
+    }
+}
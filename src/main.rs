@@ -11,13 +11,7 @@
 //! [GitHub documentation](https://github.com/Technolution/rustig).
 //! 
 
-#![recursion_limit="128"]
-
-mod cmd_args;
-mod config_file;
-mod output;
-
-pub mod errors;
+use rustig::cmd_args;
 
 use std::process;
 use std::result::Result::Ok;
@@ -33,22 +27,8 @@ pub fn main() {
         Ok(r) => r,
     };
 
-    // Execute analysis
-    match panic_analysis::find_panics(&cmd_args) {
-        Err(e) => {
-            eprintln!("{}", e);
-            process::exit(101);
-        }
-        Ok(collection) => {
-            output::print_results(&output_options, &collection);
-
-            // If a panic path is found, we exit with code 1
-            // This enables integration with CI tools
-            if !collection.calls.is_empty() {
-                process::exit(1);
-            }
-        }
-    }
+    // Execute analysis of each binary
+    process::exit(rustig::run_analyses(&cmd_args, &output_options));
 }
 
 #[cfg(test)]
@@ -60,7 +40,7 @@ mod test {
             test_common::get_test_subject_path("lib_calls", &test_common::TestSubjectType::Debug);
 
         assert_cli::Assert::main_binary()
-            .with_args(&["-b", &path.to_string_lossy(), "-c", "test_subjects", "-s"])
+            .with_args(&["-b", &path.to_string_lossy(), "-c", "lib_calls", "-s"])
             .fails_with(1)
             .unwrap();
     }
@@ -72,7 +52,7 @@ mod test {
             test_common::get_test_subject_path("empty", &test_common::TestSubjectType::Debug);
 
         assert_cli::Assert::main_binary()
-            .with_args(&["-b", &path.to_string_lossy(), "-c", "test_subjects", "-s"])
+            .with_args(&["-b", &path.to_string_lossy(), "-c", "empty", "-s"])
             .succeeds()
             .unwrap();
     }
@@ -23,6 +23,9 @@
 //! <core::result::Result<T, E> as cli::errors::ResultExt<T>>::chain_err calls core::panicking::panic (stdlib@1.26.2) at /home/pc/rustig/<impl_error_chain_processed macros>:141
 //! ```
 //!
+//! When a shared library is analyzed (`--library`), the traces are listed per exported function they are reachable
+//! from. A trace that is reachable from multiple exported functions is listed for each of them.
+//!
 //! ### 2. Verbose.
 //! The verbose output mode prints a full trace from the last function in the analysis target to the _panic_ handler, including inlined functions.
 //! Furthermore, the cause for the _panic_ is given, if it could be determined (See [PanicPattern](../panic_analysis/enum.PanicPattern.html)). Also, a message is printed if the trace contains dynamic invocations, since then it could be a false positive.
@@ -50,6 +53,7 @@
 //! ```
 
 use std::cell::RefCell;
+use std::collections::BTreeSet;
 use panic_analysis::{PanicCallsCollection, PanicPattern};
 use serde_json as json;
 use std::io;
@@ -83,8 +87,27 @@ struct SimpleConsoleOutputStream {}
 
 impl OutputStream for SimpleConsoleOutputStream {
     fn print_output(&self, panic_calls: &PanicCallsCollection) {
-        for trace in &panic_calls.calls {
-            println!("{}", trace)
+        let entry_points = panic_calls
+            .calls
+            .iter()
+            .flat_map(|trace| trace.entry_points.iter())
+            .collect::<BTreeSet<_>>();
+
+        // Entry points are only reported in library mode
+        if entry_points.is_empty() {
+            for trace in &panic_calls.calls {
+                println!("{}", trace)
+            }
+            return;
+        }
+
+        for entry_point in entry_points {
+            println!("{}:", entry_point);
+            panic_calls
+                .calls
+                .iter()
+                .filter(|trace| trace.entry_points.contains(entry_point))
+                .for_each(|trace| println!("    {}", trace));
         }
     }
 }
@@ -129,6 +152,8 @@ impl OutputStream for JsonConsoleOutputStream {
                 },
                 "message" : if let Some(message) = &trace.message { message.clone().into() } else { json::Value::Null },
                 "dynamic_invocation" : trace.contains_dynamic_invocation,
+                "language_boundary" : trace.crosses_language_boundary(),
+                "entry_points" : trace.entry_points.clone(),
                 "backtrace" : json::Value::Array(
                     trace.backtrace.iter().enumerate().map(|(i, backtrace)| {
                             let procedure = backtrace.procedure.deref().borrow();
@@ -139,6 +164,7 @@ impl OutputStream for JsonConsoleOutputStream {
                                     "name" : procedure.name.clone(),
                                     "linkage_name" : procedure.linkage_name.clone(),
                                     "linkage_name_demangled" : procedure.linkage_name_demangled.clone(),
+                                    "language" : if let Some(language) = procedure.language { language.to_string().into() } else { json::Value::Null },
                                     "crate" : json!({
                                         "name" : procedure.defining_crate.name.clone(),
                                         "version" : if let Some(version) = &procedure.defining_crate.version { version.clone().into() } else { json::Value::Null },
@@ -164,8 +190,17 @@ impl OutputStream for JsonConsoleOutputStream {
                                                 callgraph::InvocationType::Direct => "direct",
                                                 callgraph::InvocationType::ProcedureReference => "procedure",
                                                 callgraph::InvocationType::VTable => "vtable",
+                                                callgraph::InvocationType::VTableSlot => "vtable_slot",
                                                 callgraph::InvocationType::Jump => "jump",
                                             },
+                                            "trait_implementation" : if let Some(implementation) = &invocation.trait_implementation {
+                                                    json!({
+                                                        "trait" : if let Some(trait_name) = &implementation.trait_name { trait_name.clone().into() } else { json::Value::Null },
+                                                        "type" : implementation.type_name.clone(),
+                                                    })
+                                                } else {
+                                                    json::Value::Null
+                                                },
                                             "is_whitelisted" : invocation.attributes.whitelisted,
                                             "frames" : json::Value::Array(
                                                 invocation.frames.iter().enumerate().map(|(i, frame)| {
@@ -220,6 +255,14 @@ fn get_output_streams(options: &OutputOptions) -> Box<OutputStreamsCollection> {
     })
 }
 
+/// Print the path of the analyzed binary to the standard output, before its results, if multiple binaries are
+/// analyzed. Nothing is printed in silent mode, or in JSON mode, in which the output only consists of JSON objects.
+pub fn print_binary_header(options: &OutputOptions, binary_path: &str) {
+    if !options.silent && !options.json {
+        println!("Analyzing {}", binary_path);
+    }
+}
+
 /// Print the results to the standard output, in the format specified by the [options] parameter.
 pub fn print_results(options: &OutputOptions, results: &PanicCallsCollection) {
     let output_streams = get_output_streams(options);
@@ -0,0 +1,31 @@
+// (C) COPYRIGHT 2018 TECHNOLUTION BV, GOUDA NL
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! This test subject is a shared library without a main procedure, of which the exported functions are the entry
+//! points. It is used to test library mode in /panic_analysis/tests/library_mode.rs
+//! It is built as an rlib as well, to test the analysis of relocatable objects.
+
+/// Panics if `divisor` is 0, which would cross the FFI boundary.
+#[unsafe(no_mangle)]
+pub extern "C" fn checked_divide(dividend: i32, divisor: i32) -> i32 {
+    check_divisor(divisor);
+    dividend.wrapping_div(divisor)
+}
+
+/// Does not panic.
+#[unsafe(no_mangle)]
+pub extern "C" fn wrapping_sum(a: i32, b: i32) -> i32 {
+    a.wrapping_add(b)
+}
+
+#[inline(never)]
+fn check_divisor(divisor: i32) {
+    if divisor == 0 {
+        panic!("Division by zero");
+    }
+}
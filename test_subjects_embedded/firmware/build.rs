@@ -0,0 +1,19 @@
+// (C) COPYRIGHT 2018 TECHNOLUTION BV, GOUDA NL
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/// Links the firmware with the linker script of the target architecture (e.g. `arm.ld`), which places the code in
+/// flash, and the vector table at its start.
+use std::env;
+
+fn main() {
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR is not set");
+    let arch = env::var("CARGO_CFG_TARGET_ARCH").expect("CARGO_CFG_TARGET_ARCH is not set");
+
+    println!("cargo:rustc-link-arg=-T{}/{}.ld", manifest_dir, arch);
+    println!("cargo:rerun-if-changed={}.ld", arch);
+}
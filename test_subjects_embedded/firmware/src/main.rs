@@ -0,0 +1,96 @@
+// (C) COPYRIGHT 2018 TECHNOLUTION BV, GOUDA NL
+
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Firmware without a main procedure: the hardware calls the reset handler in the vector table (ARM), or starts
+//! executing `_start` at the start of flash, which calls the reset handler (RISC-V).
+
+#![no_std]
+#![no_main]
+
+use core::panic::PanicInfo;
+
+trait Sensor {
+    fn read(&self) -> u32;
+}
+
+struct Thermometer {
+    offset: u32,
+}
+
+struct Hygrometer {
+    scale: u32,
+}
+
+impl Sensor for Thermometer {
+    fn read(&self) -> u32 {
+        self.offset + 20
+    }
+}
+
+impl Sensor for Hygrometer {
+    fn read(&self) -> u32 {
+        self.scale * 40
+    }
+}
+
+/// Panics if there are no samples
+#[inline(never)]
+fn average(samples: &[u32]) -> u32 {
+    samples.iter().sum::<u32>() / samples.len() as u32
+}
+
+#[inline(never)]
+fn sample(sensors: &[&dyn Sensor], samples: &mut [u32]) {
+    for (sensor, sample) in sensors.iter().zip(samples.iter_mut()) {
+        *sample = sensor.read();
+    }
+}
+
+#[inline(never)]
+fn run() -> u32 {
+    let thermometer = Thermometer { offset: 2 };
+    let hygrometer = Hygrometer { scale: 1 };
+    let sensors: [&dyn Sensor; 2] = [&thermometer, &hygrometer];
+
+    let mut samples = [0; 2];
+    sample(&sensors, &mut samples);
+    average(&samples)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn reset() -> ! {
+    loop {
+        core::hint::black_box(run());
+    }
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn hard_fault() -> ! {
+    loop {}
+}
+
+/// Handlers after the initial stack pointer, which is placed before them by the linker script
+#[cfg(target_arch = "arm")]
+#[unsafe(link_section = ".vector_table")]
+#[unsafe(no_mangle)]
+pub static VECTOR_TABLE: [extern "C" fn() -> !; 3] = [reset, hard_fault, hard_fault];
+
+// The stack pointer is not initialized by the hardware
+#[cfg(target_arch = "riscv32")]
+core::arch::global_asm!(
+    ".section .text.start, \"ax\"",
+    ".global _start",
+    "_start:",
+    "la sp, _stack_top",
+    "j reset",
+);
+
+#[panic_handler]
+fn panic(_info: &PanicInfo) -> ! {
+    loop {}
+}